@@ -0,0 +1,131 @@
+//! 下单热路径的纯 CPU 部分基准：报价换算（滑点 + tick 取整 + 数量取整）、订单构建、EIP-712 签名。
+//!
+//! 执行器在二进制 crate 中，且 SDK 构建订单需要联网查询 tick size，因此这里按 CTF Exchange 的
+//! Order 结构直接用 alloy 复现同样的计算：构建 = 价格/数量换算为 maker/taker 金额并填充 Order，
+//! 签名 = EIP-712 哈希 + secp256k1 签名（与 SDK `sign` 的工作量一致）。
+//!
+//! ```text
+//! cargo bench --bench hot_path
+//! ```
+//!
+//! 结果按组件分组输出：`hot_path/price`、`hot_path/build`、`hot_path/sign`、`hot_path/build_sign_pair`。
+
+use alloy::primitives::{address, Address, U256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use alloy::sol;
+use alloy::sol_types::{eip712_domain, Eip712Domain, SolStruct};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+sol! {
+    /// CTF Exchange 订单结构（EIP-712 typed data）
+    struct Order {
+        uint256 salt;
+        address maker;
+        address signer;
+        address taker;
+        uint256 tokenId;
+        uint256 makerAmount;
+        uint256 takerAmount;
+        uint256 expiration;
+        uint256 nonce;
+        uint256 feeRateBps;
+        uint8 side;
+        uint8 signatureType;
+    }
+}
+
+const EXCHANGE: Address = address!("0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E");
+const TICK_SIZE: Decimal = dec!(0.01);
+const SIZE_INCREMENT: Decimal = dec!(0.01);
+/// 固定测试私钥（仅用于基准，不持有资金）
+const BENCH_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+fn domain() -> Eip712Domain {
+    eip712_domain! {
+        name: "Polymarket CTF Exchange",
+        version: "1",
+        chain_id: 137,
+        verifying_contract: EXCHANGE,
+    }
+}
+
+/// 含滑点报价：卖一 + 滑点，按 tick 向上取整；数量按预算与深度取小并按最小变动单位向下取整
+fn price_and_size(ask: Decimal, slippage: Decimal, depth: Decimal, max_size: Decimal) -> (Decimal, Decimal) {
+    let price = ((ask + slippage) / TICK_SIZE).ceil() * TICK_SIZE;
+    let size = (depth.min(max_size) / SIZE_INCREMENT).floor() * SIZE_INCREMENT;
+    (price.min(dec!(0.99)), size)
+}
+
+/// USDC / 份数（6 位小数）转最小单位
+fn to_units(v: Decimal) -> U256 {
+    U256::from((v * dec!(1000000)).trunc().to_u128().unwrap_or(0))
+}
+
+/// 买单：maker 付出 price×size USDC，taker 收到 size 份
+fn build_order(maker: Address, token_id: U256, price: Decimal, size: Decimal, salt: u64) -> Order {
+    Order {
+        salt: U256::from(salt),
+        maker,
+        signer: maker,
+        taker: Address::ZERO,
+        tokenId: token_id,
+        makerAmount: to_units(price * size),
+        takerAmount: to_units(size),
+        expiration: U256::ZERO,
+        nonce: U256::ZERO,
+        feeRateBps: U256::ZERO,
+        side: 0,
+        signatureType: 0,
+    }
+}
+
+fn hot_path(c: &mut Criterion) {
+    let signer: PrivateKeySigner = BENCH_KEY.parse().expect("bench key");
+    let maker = signer.address();
+    let domain = domain();
+    let yes_token = U256::from(0x1234_5678u64);
+    let no_token = U256::from(0x8765_4321u64);
+
+    let mut group = c.benchmark_group("hot_path");
+
+    group.bench_function("price", |b| {
+        b.iter(|| price_and_size(black_box(dec!(0.47)), black_box(dec!(0.01)), black_box(dec!(153.27)), black_box(dec!(100))))
+    });
+
+    group.bench_function("build", |b| {
+        b.iter(|| build_order(maker, black_box(yes_token), black_box(dec!(0.48)), black_box(dec!(100)), 1))
+    });
+
+    let order = build_order(maker, yes_token, dec!(0.48), dec!(100), 1);
+    group.bench_function("sign", |b| {
+        b.iter(|| {
+            let hash = black_box(&order).eip712_signing_hash(&domain);
+            signer.sign_hash_sync(&hash).expect("sign")
+        })
+    });
+
+    // 一个订单对的完整 CPU 部分：两腿报价换算 + 构建 + 签名
+    group.bench_function("build_sign_pair", |b| {
+        let mut salt = 0u64;
+        b.iter(|| {
+            salt += 1;
+            let (yes_price, yes_size) = price_and_size(dec!(0.47), dec!(0.01), dec!(153.27), dec!(100));
+            let (no_price, no_size) = price_and_size(dec!(0.50), dec!(0), dec!(88.5), dec!(100));
+            let yes = build_order(maker, yes_token, yes_price, yes_size, salt);
+            let no = build_order(maker, no_token, no_price, no_size, salt);
+            (
+                signer.sign_hash_sync(&yes.eip712_signing_hash(&domain)).expect("sign"),
+                signer.sign_hash_sync(&no.eip712_signing_hash(&domain)).expect("sign"),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, hot_path);
+criterion_main!(benches);
@@ -0,0 +1,164 @@
+//! 管理端口：本地 TCP 文本命令（每行一条，回复一行），用于运行中人工干预。
+//!
+//! ```text
+//! $ nc 127.0.0.1 9099
+//! flatten
+//! 取消挂单:已撤销 3/3 | 卖出:2笔(失败0) | Merge:1个市场(失败0)
+//! ```
+//!
+//! 命令：
+//! - `flatten`：紧急平仓（取消全部挂单 → 激进卖出净持仓 → Merge 双边平衡部分）
+//! - `disable <condition_id>` / `enable <condition_id>`：运行时禁用/启用单个市场的套利
+//! - `disabled`：列出当前禁用的市场
+//! - `pnl`：按市场与策略标签汇总成交台账的现金流盈亏
+//! - `pause` / `resume`：暂停/恢复下单，行情订阅与监控保持运行，恢复后立即生效
+//! - `balance`：当前 USDC 余额与下限状态（余额不足时自动暂停，与 `pause` 相互独立）
+//!
+//! Unix 下向进程发送 SIGUSR1 同样触发 `flatten`。命令由主程序的处理任务串行执行。
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+/// 管理命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminCommand {
+    Flatten,
+    DisableMarket(String),
+    EnableMarket(String),
+    ListDisabled,
+    Pnl,
+    Pause,
+    Resume,
+    Balance,
+}
+
+impl AdminCommand {
+    pub fn parse(line: &str) -> std::result::Result<Self, String> {
+        let mut parts = line.split_whitespace();
+        match parts.next().map(|s| s.to_lowercase()).as_deref() {
+            Some("flatten") => Ok(AdminCommand::Flatten),
+            Some(cmd @ ("disable" | "enable")) => {
+                let id = parts
+                    .next()
+                    .ok_or_else(|| format!("用法: {} <condition_id>", cmd))?
+                    .to_string();
+                Ok(if cmd == "disable" {
+                    AdminCommand::DisableMarket(id)
+                } else {
+                    AdminCommand::EnableMarket(id)
+                })
+            }
+            Some("disabled") => Ok(AdminCommand::ListDisabled),
+            Some("pnl") => Ok(AdminCommand::Pnl),
+            Some("pause") => Ok(AdminCommand::Pause),
+            Some("resume") => Ok(AdminCommand::Resume),
+            Some("balance") => Ok(AdminCommand::Balance),
+            Some(other) => Err(format!(
+                "未知命令: {}（可用: flatten, disable, enable, disabled, pnl, pause, resume, balance）",
+                other
+            )),
+            None => Err("空命令".to_string()),
+        }
+    }
+}
+
+/// 命令与回复通道：处理任务执行完后通过 oneshot 返回一行文本
+pub type AdminRequest = (AdminCommand, oneshot::Sender<String>);
+
+/// 校验管理端口监听地址：端口无认证，默认只允许回环地址（127.0.0.1、::1、localhost）；
+/// `allow_remote` 为 true 时只校验地址可解析
+pub fn check_bind_addr(addr: &str, allow_remote: bool) -> Result<()> {
+    use std::net::ToSocketAddrs;
+    let resolved: Vec<_> = addr
+        .to_socket_addrs()
+        .with_context(|| format!("ADMIN_ADDR 无效（应为 IP:端口）: {}", addr))?
+        .collect();
+    if allow_remote {
+        return Ok(());
+    }
+    if let Some(a) = resolved.iter().find(|a| !a.ip().is_loopback()) {
+        anyhow::bail!(
+            "ADMIN_ADDR={} 监听非回环地址 {}：管理端口无认证，只允许 127.0.0.1 / ::1；确需对外监听请设置 ADMIN_ALLOW_REMOTE=true",
+            addr,
+            a.ip()
+        );
+    }
+    Ok(())
+}
+
+/// 监听管理端口，将每行命令转发给处理任务并把回复写回连接
+pub async fn serve(addr: String, tx: mpsc::Sender<AdminRequest>) -> Result<()> {
+    let listener = TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("管理端口监听失败: {}", addr))?;
+    info!(addr = %addr, "🛠️ 管理端口已启动");
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let reply = match AdminCommand::parse(&line) {
+                    Ok(cmd) => {
+                        info!(peer = %peer, command = ?cmd, "🛠️ 收到管理命令");
+                        dispatch(&tx, cmd).await
+                    }
+                    Err(e) => e,
+                };
+                if writer.write_all(format!("{}\n", reply).as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// 发送命令并等待处理结果
+pub async fn dispatch(tx: &mpsc::Sender<AdminRequest>, cmd: AdminCommand) -> String {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    if tx.send((cmd, reply_tx)).await.is_err() {
+        return "命令处理任务已退出".to_string();
+    }
+    reply_rx.await.unwrap_or_else(|_| "命令处理中断".to_string())
+}
+
+/// SIGUSR1 → flatten（仅 Unix）
+#[cfg(unix)]
+pub async fn forward_sigusr1(tx: mpsc::Sender<AdminRequest>) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut sig = signal(SignalKind::user_defined1())?;
+    while sig.recv().await.is_some() {
+        warn!("🚨 收到 SIGUSR1，触发紧急平仓");
+        let reply = dispatch(&tx, AdminCommand::Flatten).await;
+        info!("SIGUSR1 紧急平仓结果: {}", reply);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_addr_allows_only_loopback_by_default() {
+        assert!(check_bind_addr("127.0.0.1:9099", false).is_ok());
+        assert!(check_bind_addr("[::1]:9099", false).is_ok());
+        assert!(check_bind_addr("0.0.0.0:9099", false).is_err());
+        assert!(check_bind_addr("[::]:9099", false).is_err());
+        assert!(check_bind_addr("10.0.0.5:9099", false).is_err());
+    }
+
+    #[test]
+    fn bind_addr_remote_requires_opt_in() {
+        assert!(check_bind_addr("0.0.0.0:9099", true).is_ok());
+        assert!(check_bind_addr("9099", true).is_err());
+        assert!(check_bind_addr("127.0.0.1", false).is_err());
+    }
+}
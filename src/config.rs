@@ -1,6 +1,8 @@
 use anyhow::Result;
 use polymarket_client_sdk::clob::types::OrderType;
+use rust_decimal::Decimal;
 use std::env;
+use std::str::FromStr;
 
 use polymarket_client_sdk::types::Address;
 
@@ -15,39 +17,184 @@ fn parse_arbitrage_order_type(s: &str) -> OrderType {
     }
 }
 
+/// 把 f64 配置转为 Decimal 并校验范围 [min, max]（max 为 None 时不设上限）；
+/// NaN、无穷或越界直接报错，避免手误的配置被静默替换为默认值后继续交易
+pub(crate) fn decimal_setting(name: &str, value: f64, min: Decimal, max: Option<Decimal>) -> Result<Decimal> {
+    let d = Decimal::try_from(value).map_err(|_| anyhow::anyhow!("{} 无效: {}（须为有限数值）", name, value))?;
+    if d < min || max.is_some_and(|m| d > m) {
+        match max {
+            Some(m) => anyhow::bail!("{} 超出范围: {}（须在 {} ~ {} 之间）", name, value, min, m),
+            None => anyhow::bail!("{} 超出范围: {}（须 ≥ {}）", name, value, min),
+        }
+    }
+    Ok(d)
+}
+
+/// 解析可选配置：未设置或为空时用默认值，格式错误直接报错，避免手误的配置被静默替换为默认值
+fn parse_setting<T: FromStr>(name: &str, raw: Option<&str>, default: T) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    match raw.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse().map_err(|e| anyhow::anyhow!("{} 无效: {:?}（{}）", name, v, e)),
+        None => Ok(default),
+    }
+}
+
+/// 读取环境变量 `name` 并按 [`parse_setting`] 解析
+fn env_parse<T: FromStr>(name: &str, default: T) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    parse_setting(name, env::var(name).ok().as_deref(), default)
+}
+
 /// 解析滑点数组：逗号分隔，如 "-0.02,0.0"。
 /// 索引 0=上涨/持平侧滑点，1=仅下降侧滑点。只写一个值时用于两项。默认 "0,0.01"。
-fn parse_slippage(s: &str) -> [f64; 2] {
+fn parse_slippage(s: &str) -> Result<[f64; 2]> {
     let parts: Vec<f64> = s
         .split(',')
-        .map(|x| x.trim().parse().unwrap_or(0.0))
-        .collect();
-    match parts.len() {
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| x.parse().map_err(|_| anyhow::anyhow!("SLIPPAGE 无效: {:?} 不是数字", x)))
+        .collect::<Result<_>>()?;
+    Ok(match parts.len() {
         0 => [0.0, 0.01],
         1 => [parts[0], parts[0]],
         _ => [parts[0], parts[1]],
-    }
+    })
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub private_key: String,
     pub proxy_address: Option<Address>, // Polymarket Proxy地址（如果使用Email/Magic或Browser Wallet登录）
+    /// 签名（EIP-712 domain）与 Merge 使用的链 ID，默认 137（Polygon 主网）；测试网/分叉链按需覆盖
+    pub chain_id: u64,
+    /// 私钥与代理地址不匹配时拒绝启动，默认false（只告警）
+    pub strict_proxy_check: bool,
     pub min_profit_threshold: f64,
+    /// 扣费后每份净利下限（基点），监控发出机会与执行端含滑点复核共用，默认0（不限制）
+    pub min_net_edge_bps: u32,
     pub max_order_size_usdc: f64,
+    /// MAX_ORDER_SIZE_USDC 的口径：shares（默认，份数上限）| usdc（每腿美元预算）
+    pub size_mode: crate::trading::SizeMode,
+    /// 两腿涨跌方向相同时的处理：off | warn（默认，告警后照常下单）| skip（跳过）
+    pub direction_mismatch: crate::trading::DirectionMismatchAction,
+    /// 一腿提交失败、另一腿有成交时的处理：leave_for_risk（默认）| cancel_working | unwind_filled
+    pub on_partial_error: crate::trading::OnPartialError,
+    /// 自成交保护：off（默认）| cancel_resting（先撤会被吃到的本账户卖单）| skip_order（放弃本次下单）
+    pub self_trade_prevention: crate::trading::SelfTradePrevention,
     pub crypto_symbols: Vec<String>,
     pub market_refresh_advance_secs: u64,
     pub risk_max_exposure_usdc: f64,
     pub risk_imbalance_threshold: f64,
     pub hedge_take_profit_pct: f64, // 对冲止盈百分比（例如0.05表示5%）
     pub hedge_stop_loss_pct: f64,   // 对冲止损百分比（例如0.05表示5%）
+    /// 单边/不平衡成交后卖出多余腿的方式，None=不处理（默认）
+    pub unwind_style: Option<crate::trading::UnwindStyle>,
+    /// 双边都有成交但数量不等时的处理（ignore | unwind | top_up）
+    pub on_imbalance: crate::risk::recovery::OnImbalance,
     pub arbitrage_execution_spread: f64, // 套利执行价差：yes+no <= 1 - 套利执行价差时，执行套利
-    /// 滑点 [first, second]：仅下降侧用 second，上涨与持平用 first。如 "-0.02,0.0"
-    pub slippage: [f64; 2],
+    /// 手续费（基点），用于扣费后净利润判定，默认0
+    pub fee_bps: f64,
+    /// 按市场类别的手续费表：类别:maker_bps:taker_bps,...（如 btc:0:20,xrp:0:50），未列出的类别用 FEE_BPS，默认空
+    pub fee_schedule: String,
+    /// 两腿卖一互补偏差上限（tick 数）：|(1 - YES卖一) - NO卖一| 超过 N×0.01 视为快照不一致而拒绝，默认0（不检查）
+    pub max_complement_ticks: u32,
+    /// 监控端逐档累计可成交数量时最多读取的订单簿档数（防止异常深的订单簿拖慢热路径），默认10
+    pub max_book_levels: usize,
+    /// 任一腿订单簿超过此时长（毫秒）未更新则不交易（监控与下单前各查一次），默认0（不检查）
+    pub max_book_staleness_ms: u64,
+    /// 结算前禁入：窗口结束（结算）前最后 N 秒不提交新订单对，与 GTD 过期缓冲无关，默认0（不限制）
+    pub resolution_blackout_secs: u64,
+    /// 监控与执行器共享盘口缓存的 TTL（毫秒）：加价重试、卖出前 TTL 内复用监控推送的买一/卖一，默认500，0=不缓存
+    pub book_cache_ttl_ms: u64,
+    /// 市场白名单（condition_id 或 slug，逗号分隔；slug 可省略窗口时间戳，如 btc-updown-5m），非空时只交易名单内市场，默认空
+    pub market_allowlist: Vec<String>,
+    /// 市场黑名单（格式同白名单），白名单优先，默认空
+    pub market_denylist: Vec<String>,
+    /// 机会去重窗口（毫秒）：同一市场取整后价格相同的机会在窗口内只发出一次，默认0（不去重）
+    pub opportunity_dedup_ttl_ms: u64,
+    /// 机会去重的价格取整粒度，默认0.01（一个 tick）
+    pub opportunity_dedup_granularity: f64,
+    /// 机会打分模型：spread（价差百分比，默认）| guaranteed_return（扣费后保证收益率）
+    pub profit_model: String,
+    /// 含滑点的 YES+NO 价格之和上限，超过则执行端拒绝下单，默认1.0
+    pub max_slipped_sum: f64,
+    /// 按涨跌方向的滑点表：由 SLIPPAGE [first, second] 生成（仅下降侧用 second，上涨与持平用 first），
+    /// 再按 SLIPPAGE_BY_DIRECTION 覆盖单个方向
+    pub slippage: crate::trading::SlippageTable,
+    /// 滑点下限：按方向取得的滑点低于此值时抬到此值，默认0（负滑点会被抬到 0；设为 -1 保留负滑点）
+    pub min_slippage: f64,
+    /// 价格改善：卖一档深度覆盖整腿数量时以卖一价报单（不加滑点），默认false
+    pub capture_improvement: bool,
+    /// 滑点随窗口剩余时间放大的曲线：off（默认）| linear | quadratic | cubic
+    pub slippage_time_ramp: String,
+    /// 窗口结束时滑点的最大倍数，默认3.0
+    pub slippage_time_ramp_max_mult: f64,
+    /// 单腿最大成交价 = 卖一 + 此值（不超过1），含滑点价格超过时放弃机会；默认0（不限制）
+    pub max_edge_giveback: f64,
+    /// 涨跌方向的采样来源（决定按方向分配的滑点），默认 BestAsk
+    pub direction_source: crate::monitor::DirectionSource,
     pub gtd_expiration_secs: u64, // GTD订单过期时间（秒），默认300秒（5分钟）；仅当 arbitrage_order_type=GTD 时有效
     /// 套利下单时的订单类型：GTC（一直有效）、GTD（配合 gtd_expiration_secs）、FOK（立即全部成交否则取消）、FAK（立即部分成交其余取消）
     pub arbitrage_order_type: OrderType,
+    /// 双边均报 "no orders found to match" 时按最新卖一价逐 tick 加价重试的最大 tick 数，0=不重试
+    pub reprice_max_ticks: u32,
+    /// 双边均挂单未成交时撤单并按最新卖一重报一次（改价后仍须盈利），默认false
+    pub reprice_resting_pair: bool,
+    /// 批量下单后按订单 ID 核对返回结果所属 token 与提交顺序一致，默认true
+    pub verify_order_responses: bool,
+    /// 两腿都因余额不足被拒时，按最新 USDC 余额缩小数量重试一次（仍须满足 $1 最小金额），默认true
+    pub balance_reject_retry: bool,
+    /// 一对订单构建/签名失败时的重试次数（两腿共用，纯本地操作，不含批量提交），0=不重试，默认2
+    pub build_sign_retries: u32,
+    /// 提交前 USDC 余额核对：off（默认）| serial（核对通过再构建签名）| concurrent（与构建签名并行，不足则丢弃已签名订单）
+    pub balance_check: crate::trading::balance_guard::BalanceCheckMode,
+    /// 提交后多久按账户成交记录（trades）对账两腿成交数量（毫秒），补上 GTD/GTC 随后成交的部分，默认0（不对账）
+    pub reconcile_fills_delay_ms: u64,
+    /// 订阅用户频道（认证 WS）实时接收本账户订单的成交/撤单推送，更新持仓与订单对成交，默认false
+    pub order_updates_ws: bool,
+    /// 同时在途的套利订单对上限（执行端信号量），0=不限制，默认4
+    pub max_concurrent_pairs: usize,
+    /// 使用 SDK 市价单（按 USDC 金额、FOK/FAK 立即成交）代替限价+滑点，默认false
+    pub use_market_orders: bool,
+    /// 下单数量最小变动单位，数量按此向下取整后再检查 $1 最小金额，默认0.01；0=不取整
+    pub size_increment: f64,
+    /// 每个5分钟窗口最多提交的套利订单对数（控制交易次数与手续费），0=不限制
+    pub max_pairs_per_window: u32,
+    /// 下单返回成功但成交 0 且无错误时，按订单 ID 轮询确认的次数，默认2；0=不轮询
+    pub fill_poll_count: u32,
+    /// 上述轮询间隔（毫秒），默认500
+    pub fill_poll_interval_ms: u64,
+    /// 认证验证（api_keys）成功结果缓存秒数，缓存期内 verify_authentication 不调用 API，默认30，0=不缓存
+    pub auth_cache_ttl_secs: u64,
+    /// CLOB REST 连接超时（毫秒），默认5000
+    pub clob_connect_timeout_ms: u64,
+    /// CLOB 请求与单次认证的总超时（毫秒），默认15000
+    pub clob_request_timeout_ms: u64,
+    /// CLOB 认证超时后的重试次数，默认2
+    pub clob_auth_retries: u32,
+    /// 已持有单边多余库存时只补缺口腿（缺口不足 $1 的腿不下单），默认false
+    pub use_existing_inventory: bool,
+    /// 同一 token 两次提交的最小间隔（毫秒），间隔内再次出现的订单对跳过，默认0（不限制）
+    pub min_interval_per_token_ms: u64,
+    /// GTC 订单客户端逻辑过期时间（秒），到期后台撤单，默认0（不过期）；仅 ARBITRAGE_ORDER_TYPE=GTC 时有效
+    pub gtc_ttl_secs: u64,
+    /// GTC 过期清扫间隔（秒），默认5
+    pub expiry_sweep_interval_secs: u64,
+    /// GTD 过期挂单清理间隔（秒），默认60，0=不清理；仅 ARBITRAGE_ORDER_TYPE=GTD 时有效
+    pub gtd_cleanup_interval_secs: u64,
+    /// USDC 余额下限：低于时自动暂停下单，回升后自动恢复，默认0（不启用）
+    pub min_trading_balance: f64,
+    /// USDC 余额检查间隔（秒），默认30；仅 min_trading_balance > 0 时有效
+    pub balance_check_interval_secs: u64,
     pub stop_arbitrage_before_end_minutes: u64, // 市场结束前N分钟停止执行套利，默认0（不停止）
+    /// 单市场未 merge 的平衡持仓上限（对，YES+NO 各 1 份为 1 对），默认0（不限制）
+    pub max_open_pairs_per_market: f64,
+    /// 当前5分钟窗口剩余秒数少于此值时不再下单（订单来不及成交），默认0（不限制）
+    pub min_seconds_remaining: u64,
     /// 定时 Merge 间隔（分钟），0 表示不启用。CONDITION_ID 与订单簿一样由当前窗口市场获取。
     pub merge_interval_minutes: u64,
     /// YES 价格阈值：只有当 YES 价格 >= 此阈值时才执行套利，默认 0.0（不限制）
@@ -56,6 +203,10 @@ pub struct Config {
     pub min_no_price_threshold: f64,
     /// 持仓同步间隔（秒），默认10秒（从API获取最新持仓覆盖本地缓存）
     pub position_sync_interval_secs: u64,
+    /// 持仓快照文件路径（JSON），为空表示不持久化；启动时加载并与链上余额核对
+    pub position_snapshot_path: String,
+    /// 持仓快照落盘去抖间隔（毫秒），默认1000
+    pub position_snapshot_debounce_ms: u64,
     /// 仓位平衡检查间隔（秒），默认60秒
     pub position_balance_interval_secs: u64,
     /// 不平衡阈值，只有当持仓差异 >= 此阈值时才取消挂单，默认2.0
@@ -66,106 +217,392 @@ pub struct Config {
     pub wind_down_before_window_end_minutes: u64,
     /// 收尾时单腿卖出的限价单价格（尽量快速成交），默认0.01
     pub wind_down_sell_price: f64,
+    /// 收尾时双边持仓的退出方式：merge_first（默认）| sell_first | sell_if_bid_above_merge_value
+    pub close_strategy: crate::trading::CloseStrategy,
+    /// 盘中 Merge 触发阈值（USDC）：某市场 min(YES, NO) 持仓 >= 此值时立即 merge 回收资金。0=不启用（仅收尾/定时 merge）
+    pub merge_trigger_usdc: f64,
+    /// 管理端口监听地址（如 127.0.0.1:9099），为空表示不启用；非回环地址需 ADMIN_ALLOW_REMOTE=true
+    pub admin_addr: Option<String>,
+    /// 紧急平仓时卖单相对买一的最大让价，默认0.05
+    pub flatten_max_slippage: f64,
+    /// 成交台账后端：null（默认）| jsonl | sqlite
+    pub ledger_backend: String,
+    /// 台账文件路径（jsonl 文件或 sqlite 数据库），默认 ledger.jsonl
+    pub ledger_path: String,
+    /// 策略变体标签，写入订单对结果与台账，便于按标签对比盈亏，默认 default
+    pub strategy_tag: String,
+    /// 订单簿快照记录目录，为空表示不记录
+    pub book_recorder_dir: Option<String>,
+    /// 订单簿快照单文件大小上限（MB），超过后轮转新文件，默认64
+    pub book_recorder_max_file_mb: u64,
+    /// 外部机会队列：none（默认）| redis（需 --features redis-queue），监控端发布、`consume` 子命令订阅下单
+    pub opportunity_sink: String,
+    /// 队列地址，默认 redis://127.0.0.1/
+    pub opportunity_queue_url: String,
+    /// 队列名（Redis 列表 key），默认 poly_5min_bot:opportunities
+    pub opportunity_queue_key: String,
+    /// 只发布不在本进程下单（监控进程），默认false
+    pub opportunity_sink_only: bool,
+    /// 主备选举的租约锁：none（默认，单实例）| file | redis
+    pub leader_lock: String,
+    /// LEADER_LOCK=file 时的租约文件路径
+    pub leader_lock_path: String,
+    /// LEADER_LOCK=redis 时的 Redis 地址
+    pub leader_lock_url: String,
+    /// LEADER_LOCK=redis 时的租约键
+    pub leader_lock_key: String,
+    /// 租约时长（秒）：leader 每 1/3 时长续约一次，超过此时长未续约即由备机接管，默认15
+    pub leader_lease_secs: u64,
+    /// 影子配置：SHADOW_CONFIG 指向的 env 文件覆盖当前配置后生成；每个机会按它再做一次下单前决策并记录与实盘的差异，
+    /// 不会下单。未设置时为 None
+    pub shadow_config: Option<Box<Config>>,
 }
 
 impl Config {
+    /// 监控与执行共用的盈利判定参数；FEE_BPS、MIN_PROFIT_THRESHOLD、MAX_SLIPPED_SUM 无效或越界时报错
+    pub fn profit_config(&self) -> Result<crate::monitor::ProfitConfig> {
+        let cfg = crate::monitor::ProfitConfig::new(self.fee_bps, self.min_profit_threshold, self.max_slipped_sum)?
+            .with_min_net_edge_bps(self.min_net_edge_bps);
+        let schedule = crate::monitor::FeeSchedule::parse(&self.fee_schedule, cfg.fee_bps)
+            .map_err(|e| anyhow::anyhow!("FEE_SCHEDULE 无效: {}", e))?;
+        Ok(cfg.with_fee_schedule(schedule))
+    }
+
+    /// CLOB 连接参数（执行器与风险管理客户端共用）
+    pub fn connect_options(&self) -> crate::trading::clob_api::ConnectOptions {
+        crate::trading::clob_api::ConnectOptions {
+            connect_timeout: std::time::Duration::from_millis(self.clob_connect_timeout_ms),
+            request_timeout: std::time::Duration::from_millis(self.clob_request_timeout_ms),
+            auth_retries: self.clob_auth_retries,
+        }
+    }
+
+    /// 交易执行器参数
+    pub fn executor_settings(&self) -> Result<crate::trading::executor::ExecutorSettings> {
+        Ok(crate::trading::executor::ExecutorSettings {
+            max_order_size_usdc: self.max_order_size_usdc,
+            size_mode: self.size_mode,
+            direction_mismatch: self.direction_mismatch,
+            on_partial_error: self.on_partial_error,
+            self_trade_prevention: self.self_trade_prevention,
+            slippage: self.slippage,
+            min_slippage: self.min_slippage,
+            capture_improvement: self.capture_improvement,
+            gtd_expiration_secs: self.gtd_expiration_secs,
+            arbitrage_order_type: self.arbitrage_order_type.clone(),
+            profit_config: self.profit_config()?,
+            reprice_max_ticks: self.reprice_max_ticks,
+            reprice_resting_pair: self.reprice_resting_pair,
+            verify_order_responses: self.verify_order_responses,
+            balance_reject_retry: self.balance_reject_retry,
+            build_sign_retries: self.build_sign_retries,
+            balance_check: self.balance_check,
+            reconcile_fills_delay_ms: self.reconcile_fills_delay_ms,
+            max_concurrent_pairs: self.max_concurrent_pairs,
+            use_market_orders: self.use_market_orders,
+            size_increment: self.size_increment,
+            max_pairs_per_window: self.max_pairs_per_window,
+            fill_poll_count: self.fill_poll_count,
+            fill_poll_interval_ms: self.fill_poll_interval_ms,
+            auth_cache_ttl_secs: self.auth_cache_ttl_secs,
+            connect: self.connect_options(),
+            use_existing_inventory: self.use_existing_inventory,
+            min_interval_per_token_ms: self.min_interval_per_token_ms,
+            gtc_ttl_secs: self.gtc_ttl_secs,
+            slippage_ramp: crate::trading::slippage::SlippageRamp::parse(
+                &self.slippage_time_ramp,
+                self.slippage_time_ramp_max_mult,
+            )?,
+            strategy_tag: self.strategy_tag.clone(),
+            max_edge_giveback: self.max_edge_giveback,
+            strict_proxy_check: self.strict_proxy_check,
+            max_book_staleness_ms: self.max_book_staleness_ms,
+            resolution_blackout_secs: self.resolution_blackout_secs,
+            max_open_pairs_per_market: self.max_open_pairs_per_market,
+            min_trading_balance: self.min_trading_balance,
+            book_cache_ttl_ms: self.book_cache_ttl_ms,
+            market_allowlist: self.market_allowlist.clone(),
+            market_denylist: self.market_denylist.clone(),
+            shadow: self
+                .shadow_config
+                .as_ref()
+                .map(|c| c.executor_settings().map(Box::new))
+                .transpose()?,
+        })
+    }
+
+    /// 读取 SHADOW_CONFIG 指向的 env 文件，把其中的键临时覆盖到进程环境后重新解析配置，随后还原环境。
+    /// 只在启动时（from_env 内）调用；影子配置本身不再读取 SHADOW_CONFIG
+    fn shadow_from_env() -> Result<Option<Config>> {
+        let Some(path) = env::var("SHADOW_CONFIG").ok().filter(|p| !p.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let overrides = dotenvy::from_path_iter(path.trim())
+            .map_err(|e| anyhow::anyhow!("SHADOW_CONFIG 无法读取 {}: {}", path, e))?
+            .collect::<std::result::Result<Vec<(String, String)>, _>>()
+            .map_err(|e| anyhow::anyhow!("SHADOW_CONFIG 格式无效 {}: {}", path, e))?;
+        let mut saved: Vec<(String, Option<String>)> = vec![("SHADOW_CONFIG".to_string(), Some(path.clone()))];
+        env::remove_var("SHADOW_CONFIG");
+        for (key, value) in &overrides {
+            saved.push((key.clone(), env::var(key).ok()));
+            env::set_var(key, value);
+        }
+        let shadow = Self::from_env();
+        for (key, value) in saved.into_iter().rev() {
+            match value {
+                Some(v) => env::set_var(&key, v),
+                None => env::remove_var(&key),
+            }
+        }
+        let shadow = shadow.map_err(|e| anyhow::anyhow!("SHADOW_CONFIG 配置无效 {}: {}", path, e))?;
+        Ok(Some(shadow))
+    }
+
     pub fn from_env() -> Result<Self> {
         dotenvy::dotenv().ok();
 
+        // 影子配置须在读取本配置前解析完（期间会临时改写环境变量）
+        let shadow_config = Self::shadow_from_env()?.map(Box::new);
+
         // 解析proxy_address（可选）
         let proxy_address: Option<Address> = env::var("POLYMARKET_PROXY_ADDRESS")
             .ok()
             .and_then(|addr| addr.parse().ok());
 
+        // 滑点表：SLIPPAGE 给出默认 [first, second]，SLIPPAGE_BY_DIRECTION 覆盖单个方向，格式错误直接报错
+        // 每项须为 [-1, 1] 内的有限数值（负值 = 挂在卖一之下等待成交），NaN/越界直接报错而非静默按 0 处理
+        let [first, second] = parse_slippage(&env::var("SLIPPAGE").unwrap_or_else(|_| "0,0.01".to_string()))?;
+        let slippage_range = (rust_decimal_macros::dec!(-1), Some(rust_decimal_macros::dec!(1)));
+        let slippage = crate::trading::SlippageTable::from_pair(
+            decimal_setting("SLIPPAGE", first, slippage_range.0, slippage_range.1)?,
+            decimal_setting("SLIPPAGE", second, slippage_range.0, slippage_range.1)?,
+        )
+        .with_overrides(&env::var("SLIPPAGE_BY_DIRECTION").unwrap_or_default())
+        .map_err(|e| anyhow::anyhow!("SLIPPAGE_BY_DIRECTION 无效: {}", e))?;
+
+        // 管理端口没有认证：只允许监听回环地址，ADMIN_ALLOW_REMOTE=true 时才放开
+        let admin_addr = env::var("ADMIN_ADDR")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        if let Some(addr) = &admin_addr {
+            let allow_remote = env::var("ADMIN_ALLOW_REMOTE")
+                .map(|s| s.trim() == "1" || s.trim().eq_ignore_ascii_case("true"))
+                .unwrap_or(false);
+            crate::admin::check_bind_addr(addr, allow_remote)?;
+        }
+
+        // 涨跌方向采样来源：未知值直接报错，避免静默按卖一价判定方向
+        let direction_source = crate::monitor::DirectionSource::parse(
+            &env::var("DIRECTION_SOURCE").unwrap_or_else(|_| "best_ask".to_string()),
+        )
+        .map_err(|e| anyhow::anyhow!("DIRECTION_SOURCE 无效: {}", e))?;
+
+        // 按类别手续费表：格式错误直接报错，避免静默按默认费率判定盈利
+        let fee_schedule = env::var("FEE_SCHEDULE").unwrap_or_default();
+        crate::monitor::FeeSchedule::parse(&fee_schedule, rust_decimal::Decimal::ZERO)
+            .map_err(|e| anyhow::anyhow!("FEE_SCHEDULE 无效: {}", e))?;
+
+        // 单边卖出的最大滑点（买一之下），UNWIND_STYLE=market_with_max_slippage 与 ON_PARTIAL_ERROR=unwind_filled 共用
+        let unwind_max_slippage = env_parse("UNWIND_MAX_SLIPPAGE", rust_decimal_macros::dec!(0.05))?; // 默认0.05
+
+        // 签名链 ID：默认 Polygon 主网，0 或非数字直接报错（签名会全部无效）
+        let chain_id = match env::var("CHAIN_ID").ok().filter(|v| !v.trim().is_empty()) {
+            Some(v) => poly_5min_bot::merge::parse_chain_id(&v).map_err(|e| anyhow::anyhow!("CHAIN_ID 无效: {}", e))?,
+            None => polymarket_client_sdk::POLYGON,
+        };
+
         Ok(Config {
             private_key: env::var("POLYMARKET_PRIVATE_KEY")
                 .expect("POLYMARKET_PRIVATE_KEY must be set"),
             proxy_address,
-            min_profit_threshold: env::var("MIN_PROFIT_THRESHOLD")
-                .unwrap_or_else(|_| "0.001".to_string())
-                .parse()
-                .unwrap_or(0.001),
-            max_order_size_usdc: env::var("MAX_ORDER_SIZE_USDC")
-                .unwrap_or_else(|_| "100.0".to_string())
-                .parse()
-                .unwrap_or(100.0),
+            chain_id,
+            strict_proxy_check: env_parse("STRICT_PROXY_CHECK", false)?, // 默认false（只告警）
+            min_profit_threshold: env_parse("MIN_PROFIT_THRESHOLD", 0.001)?,
+            min_net_edge_bps: env_parse("MIN_NET_EDGE_BPS", 0)?, // 默认0（不限制）
+            max_order_size_usdc: env_parse("MAX_ORDER_SIZE_USDC", 100.0)?,
+            size_mode: crate::trading::SizeMode::parse(
+                &env::var("SIZE_MODE").unwrap_or_else(|_| "shares".to_string()),
+            ),
+            direction_mismatch: crate::trading::DirectionMismatchAction::parse(
+                &env::var("DIRECTION_MISMATCH").unwrap_or_else(|_| "warn".to_string()),
+            ),
+            on_partial_error: crate::trading::OnPartialError::parse(
+                &env::var("ON_PARTIAL_ERROR").unwrap_or_else(|_| "leave_for_risk".to_string()),
+                unwind_max_slippage,
+            ),
+            self_trade_prevention: crate::trading::SelfTradePrevention::parse(
+                &env::var("SELF_TRADE_PREVENTION").unwrap_or_else(|_| "off".to_string()),
+            ),
             crypto_symbols: env::var("CRYPTO_SYMBOLS")
                 .unwrap_or_else(|_| "btc,eth,xrp,sol".to_string())
                 .split(',')
                 .map(|s| s.trim().to_lowercase())
                 .collect(),
-            market_refresh_advance_secs: env::var("MARKET_REFRESH_ADVANCE_SECS")
-                .unwrap_or_else(|_| "5".to_string())
-                .parse()
-                .unwrap_or(5),
-            risk_max_exposure_usdc: env::var("RISK_MAX_EXPOSURE_USDC")
-                .unwrap_or_else(|_| "1000.0".to_string())
-                .parse()
-                .unwrap_or(1000.0),
-            risk_imbalance_threshold: env::var("RISK_IMBALANCE_THRESHOLD")
-                .unwrap_or_else(|_| "0.1".to_string())
-                .parse()
-                .unwrap_or(0.1),
-            hedge_take_profit_pct: env::var("HEDGE_TAKE_PROFIT_PCT")
-                .unwrap_or_else(|_| "0.05".to_string())
-                .parse()
-                .unwrap_or(0.05), // 默认5%止盈
-            hedge_stop_loss_pct: env::var("HEDGE_STOP_LOSS_PCT")
-                .unwrap_or_else(|_| "0.05".to_string())
-                .parse()
-                .unwrap_or(0.05), // 默认5%止损
-            arbitrage_execution_spread: env::var("ARBITRAGE_EXECUTION_SPREAD")
-                .unwrap_or_else(|_| "0.01".to_string())
-                .parse()
-                .unwrap_or(0.01), // 默认0.01
-            slippage: parse_slippage(&env::var("SLIPPAGE").unwrap_or_else(|_| "0,0.01".to_string())),
-            gtd_expiration_secs: env::var("GTD_EXPIRATION_SECS")
-                .unwrap_or_else(|_| "300".to_string())
-                .parse()
-                .unwrap_or(300), // 默认300秒（5分钟）
+            market_refresh_advance_secs: env_parse("MARKET_REFRESH_ADVANCE_SECS", 5)?,
+            risk_max_exposure_usdc: env_parse("RISK_MAX_EXPOSURE_USDC", 1000.0)?,
+            risk_imbalance_threshold: env_parse("RISK_IMBALANCE_THRESHOLD", 0.1)?,
+            hedge_take_profit_pct: env_parse("HEDGE_TAKE_PROFIT_PCT", 0.05)?, // 默认5%止盈
+            hedge_stop_loss_pct: env_parse("HEDGE_STOP_LOSS_PCT", 0.05)?, // 默认5%止损
+            unwind_style: crate::trading::UnwindStyle::parse(
+                &env::var("UNWIND_STYLE").unwrap_or_else(|_| "off".to_string()),
+                unwind_max_slippage,
+                env_parse("UNWIND_LADDER_STEPS", 3)?, // 默认3档
+                env_parse("UNWIND_LADDER_STEP", rust_decimal_macros::dec!(0.01))?, // 默认每档降 0.01
+            ),
+            on_imbalance: crate::risk::recovery::OnImbalance::parse(
+                &env::var("ON_IMBALANCE").unwrap_or_else(|_| "ignore".to_string()),
+            ),
+            arbitrage_execution_spread: env_parse("ARBITRAGE_EXECUTION_SPREAD", 0.01)?, // 默认0.01
+            fee_bps: env_parse("FEE_BPS", 0.0)?, // 默认0
+            fee_schedule,
+            max_complement_ticks: env_parse("MAX_COMPLEMENT_TICKS", 0)?, // 默认0（不检查）
+            max_book_levels: env_parse("MAX_BOOK_LEVELS", 10)?, // 默认10档
+            max_book_staleness_ms: env_parse("MAX_BOOK_STALENESS_MS", 0)?, // 默认0（不检查）
+            resolution_blackout_secs: env_parse("RESOLUTION_BLACKOUT_SECS", 0)?, // 默认0（不限制）
+            book_cache_ttl_ms: env_parse("BOOK_CACHE_TTL_MS", 500)?, // 默认500ms
+            market_allowlist: env::var("MARKET_ALLOWLIST")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            market_denylist: env::var("MARKET_DENYLIST")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            opportunity_dedup_ttl_ms: env_parse("OPPORTUNITY_DEDUP_TTL_MS", 0)?, // 默认0（不去重）
+            opportunity_dedup_granularity: env_parse("OPPORTUNITY_DEDUP_GRANULARITY", 0.01)?, // 默认一个 tick
+            profit_model: env::var("PROFIT_MODEL")
+                .unwrap_or_else(|_| "spread".to_string())
+                .trim()
+                .to_lowercase(),
+            max_slipped_sum: env_parse("MAX_SLIPPED_SUM", 1.0)?, // 默认1.0
+            slippage,
+            min_slippage: env_parse("MIN_SLIPPAGE", 0.0)?, // 默认0（不抬高非负滑点）
+            capture_improvement: env_parse("CAPTURE_IMPROVEMENT", false)?, // 默认false
+            slippage_time_ramp: env::var("SLIPPAGE_TIME_RAMP").unwrap_or_else(|_| "off".to_string()),
+            slippage_time_ramp_max_mult: env_parse("SLIPPAGE_TIME_RAMP_MAX_MULT", 3.0)?, // 默认3倍
+            max_edge_giveback: env_parse("MAX_EDGE_GIVEBACK", 0.0)?, // 默认0（不限制）
+            direction_source,
+            gtd_expiration_secs: env_parse("GTD_EXPIRATION_SECS", 300)?, // 默认300秒（5分钟）
             arbitrage_order_type: parse_arbitrage_order_type(
                 &env::var("ARBITRAGE_ORDER_TYPE").unwrap_or_else(|_| "GTD".to_string()),
             ),
-            stop_arbitrage_before_end_minutes: env::var("STOP_ARBITRAGE_BEFORE_END_MINUTES")
-                .unwrap_or_else(|_| "0".to_string())
-                .parse()
-                .unwrap_or(0), // 默认0（不停止）
-            merge_interval_minutes: env::var("MERGE_INTERVAL_MINUTES")
-                .unwrap_or_else(|_| "0".to_string())
-                .parse()
-                .unwrap_or(0), // 0=不启用
-            min_yes_price_threshold: env::var("MIN_YES_PRICE_THRESHOLD")
-                .unwrap_or_else(|_| "0.0".to_string())
-                .parse()
-                .unwrap_or(0.0), // 默认0.0（不限制）
-            min_no_price_threshold: env::var("MIN_NO_PRICE_THRESHOLD")
-                .unwrap_or_else(|_| "0.0".to_string())
-                .parse()
-                .unwrap_or(0.0), // 默认0.0（不限制）
-            position_sync_interval_secs: env::var("POSITION_SYNC_INTERVAL_SECS")
-                .unwrap_or_else(|_| "10".to_string())
-                .parse()
-                .unwrap_or(10), // 默认10秒
-            position_balance_interval_secs: env::var("POSITION_BALANCE_INTERVAL_SECS")
-                .unwrap_or_else(|_| "60".to_string())
-                .parse()
-                .unwrap_or(60), // 默认60秒
-            position_balance_threshold: env::var("POSITION_BALANCE_THRESHOLD")
-                .unwrap_or_else(|_| "2.0".to_string())
-                .parse()
-                .unwrap_or(2.0), // 默认2.0
-            position_balance_min_total: env::var("POSITION_BALANCE_MIN_TOTAL")
-                .unwrap_or_else(|_| "5.0".to_string())
-                .parse()
-                .unwrap_or(5.0), // 默认5.0
-            wind_down_before_window_end_minutes: env::var("WIND_DOWN_BEFORE_WINDOW_END_MINUTES")
-                .unwrap_or_else(|_| "0".to_string())
-                .parse()
-                .unwrap_or(0), // 0=不启用
-            wind_down_sell_price: env::var("WIND_DOWN_SELL_PRICE")
-                .unwrap_or_else(|_| "0.01".to_string())
-                .parse()
-                .unwrap_or(0.01), // 默认0.01
+            reprice_max_ticks: env_parse("REPRICE_MAX_TICKS", 0)?, // 0=不重试
+            reprice_resting_pair: env_parse("REPRICE_RESTING_PAIR", false)?, // 默认false（挂单等待）
+            verify_order_responses: env_parse("VERIFY_ORDER_RESPONSES", true)?, // 默认true
+            balance_reject_retry: env_parse("BALANCE_REJECT_RETRY", true)?, // 默认true
+            build_sign_retries: env_parse("BUILD_SIGN_RETRIES", 2)?, // 默认2
+            balance_check: crate::trading::balance_guard::BalanceCheckMode::parse(
+                &env::var("PRE_SUBMIT_BALANCE_CHECK").unwrap_or_else(|_| "off".to_string()),
+            ),
+            reconcile_fills_delay_ms: env_parse("RECONCILE_FILLS_DELAY_MS", 0)?, // 默认0（不对账）
+            order_updates_ws: env_parse("ORDER_UPDATES_WS", false)?, // 默认false（不订阅用户频道）
+            max_concurrent_pairs: env_parse("MAX_CONCURRENT_PAIRS", 4)?, // 默认4
+            use_market_orders: env_parse("USE_MARKET_ORDERS", false)?, // 默认false（限价+滑点）
+            size_increment: env_parse("SIZE_INCREMENT", 0.01)?, // 默认0.01
+            max_pairs_per_window: env_parse("MAX_PAIRS_PER_WINDOW", 0)?, // 0=不限制
+            fill_poll_count: env_parse("FILL_POLL_COUNT", 2)?, // 默认2次
+            fill_poll_interval_ms: env_parse("FILL_POLL_INTERVAL_MS", 500)?, // 默认500毫秒
+            auth_cache_ttl_secs: env_parse("AUTH_CACHE_TTL_SECS", 30)?, // 默认30秒
+            clob_connect_timeout_ms: env_parse("CLOB_CONNECT_TIMEOUT_MS", 5000)?, // 默认5秒
+            clob_request_timeout_ms: env_parse("CLOB_REQUEST_TIMEOUT_MS", 15000)?, // 默认15秒
+            clob_auth_retries: env_parse("CLOB_AUTH_RETRIES", 2)?, // 默认重试2次
+            use_existing_inventory: env_parse("USE_EXISTING_INVENTORY", false)?, // 默认false
+            min_interval_per_token_ms: env_parse("MIN_INTERVAL_PER_TOKEN_MS", 0)?, // 默认0（不限制）
+            gtc_ttl_secs: env_parse("GTC_TTL_SECS", 0)?, // 默认0（不过期）
+            expiry_sweep_interval_secs: env_parse("EXPIRY_SWEEP_INTERVAL_SECS", 5)?, // 默认5秒
+            gtd_cleanup_interval_secs: env_parse("GTD_CLEANUP_INTERVAL_SECS", 60)?, // 默认60秒
+            min_trading_balance: env_parse("MIN_TRADING_BALANCE", 0.0)?, // 默认0（不启用）
+            balance_check_interval_secs: env_parse("BALANCE_CHECK_INTERVAL_SECS", 30)?, // 默认30秒
+            stop_arbitrage_before_end_minutes: env_parse("STOP_ARBITRAGE_BEFORE_END_MINUTES", 0)?, // 默认0（不停止）
+            max_open_pairs_per_market: env_parse("MAX_OPEN_PAIRS_PER_MARKET", 0.0)?, // 默认0（不限制）
+            min_seconds_remaining: env_parse("MIN_SECONDS_REMAINING", 0)?, // 默认0（不限制）
+            merge_interval_minutes: env_parse("MERGE_INTERVAL_MINUTES", 0)?, // 0=不启用
+            min_yes_price_threshold: env_parse("MIN_YES_PRICE_THRESHOLD", 0.0)?, // 默认0.0（不限制）
+            min_no_price_threshold: env_parse("MIN_NO_PRICE_THRESHOLD", 0.0)?, // 默认0.0（不限制）
+            position_sync_interval_secs: env_parse("POSITION_SYNC_INTERVAL_SECS", 10)?, // 默认10秒
+            position_snapshot_path: env::var("POSITION_SNAPSHOT_PATH")
+                .unwrap_or_default()
+                .trim()
+                .to_string(), // 默认不启用
+            position_snapshot_debounce_ms: env_parse("POSITION_SNAPSHOT_DEBOUNCE_MS", 1000)?, // 默认1秒
+            position_balance_interval_secs: env_parse("POSITION_BALANCE_INTERVAL_SECS", 60)?, // 默认60秒
+            position_balance_threshold: env_parse("POSITION_BALANCE_THRESHOLD", 2.0)?, // 默认2.0
+            position_balance_min_total: env_parse("POSITION_BALANCE_MIN_TOTAL", 5.0)?, // 默认5.0
+            wind_down_before_window_end_minutes: env_parse("WIND_DOWN_BEFORE_WINDOW_END_MINUTES", 0)?, // 0=不启用
+            close_strategy: crate::trading::CloseStrategy::parse(
+                &env::var("CLOSE_STRATEGY").unwrap_or_else(|_| "merge_first".to_string()),
+            ),
+            wind_down_sell_price: env_parse("WIND_DOWN_SELL_PRICE", 0.01)?, // 默认0.01
+            merge_trigger_usdc: env_parse("MERGE_TRIGGER_USDC", 0.0)?, // 0=不启用
+            admin_addr,
+            flatten_max_slippage: env_parse("FLATTEN_MAX_SLIPPAGE", 0.05)?, // 默认0.05
+            ledger_backend: env::var("LEDGER_BACKEND").unwrap_or_else(|_| "null".to_string()),
+            ledger_path: env::var("LEDGER_PATH").unwrap_or_else(|_| "ledger.jsonl".to_string()),
+            strategy_tag: env::var("STRATEGY_TAG")
+                .map(|s| s.trim().to_string())
+                .ok()
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "default".to_string()),
+            book_recorder_dir: env::var("BOOK_RECORDER_DIR")
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            book_recorder_max_file_mb: env_parse("BOOK_RECORDER_MAX_FILE_MB", 64)?, // 默认64MB
+            opportunity_sink: env::var("OPPORTUNITY_SINK").unwrap_or_else(|_| "none".to_string()),
+            opportunity_queue_url: env::var("OPPORTUNITY_QUEUE_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string()),
+            opportunity_queue_key: env::var("OPPORTUNITY_QUEUE_KEY")
+                .unwrap_or_else(|_| "poly_5min_bot:opportunities".to_string()),
+            opportunity_sink_only: env_parse("OPPORTUNITY_SINK_ONLY", false)?, // 默认false
+            leader_lock: env::var("LEADER_LOCK").unwrap_or_else(|_| "none".to_string()),
+            leader_lock_path: env::var("LEADER_LOCK_PATH").unwrap_or_else(|_| "./poly_5min_bot.leader".to_string()),
+            leader_lock_url: env::var("LEADER_LOCK_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string()),
+            leader_lock_key: env::var("LEADER_LOCK_KEY").unwrap_or_else(|_| "poly_5min_bot:leader".to_string()),
+            leader_lease_secs: env_parse("LEADER_LEASE_SECS", 15)?, // 默认15秒
+            shadow_config,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn decimal_setting_rejects_nan_and_infinite() {
+        assert!(decimal_setting("X", f64::NAN, dec!(0), None).is_err());
+        assert!(decimal_setting("X", f64::INFINITY, dec!(0), None).is_err());
+        assert!(decimal_setting("X", f64::NEG_INFINITY, dec!(-1), Some(dec!(1))).is_err());
+    }
+
+    #[test]
+    fn decimal_setting_checks_bounds_inclusively() {
+        assert_eq!(decimal_setting("X", 0.0, dec!(0), Some(dec!(1))).unwrap(), dec!(0));
+        assert_eq!(decimal_setting("X", 1.0, dec!(0), Some(dec!(1))).unwrap(), dec!(1));
+        assert!(decimal_setting("X", -0.01, dec!(0), None).is_err());
+        assert!(decimal_setting("X", 1.01, dec!(0), Some(dec!(1))).is_err());
+    }
+
+    #[test]
+    fn negative_slippage_is_accepted_within_range() {
+        let range = (dec!(-1), Some(dec!(1)));
+        assert_eq!(decimal_setting("SLIPPAGE", -0.02, range.0, range.1).unwrap(), dec!(-0.02));
+        assert!(decimal_setting("SLIPPAGE", -1.5, range.0, range.1).is_err());
+        assert_eq!(parse_slippage("-0.02,0").unwrap(), [-0.02, 0.0]);
+    }
+
+    #[test]
+    fn parse_setting_defaults_only_when_unset() {
+        assert_eq!(parse_setting("X", None, 500u64).unwrap(), 500);
+        assert_eq!(parse_setting("X", Some("  "), 500u64).unwrap(), 500);
+        assert_eq!(parse_setting("X", Some(" 250 "), 500u64).unwrap(), 250);
+        assert!(parse_setting("X", Some("5s"), 500u64).is_err());
+        assert!(parse_setting("X", Some("1"), false).is_err());
+        assert_eq!(parse_setting("X", Some("0.2"), 0.1f64).unwrap(), 0.2);
+    }
+}
@@ -0,0 +1,273 @@
+//! 主备选举：冗余部署多个实例时，只有持有租约的 leader 下单；备机照常订阅行情并定期校验认证，
+//! leader 停止续约（进程退出或卡死）超过租约时长后由备机接管。
+//!
+//! | 实现 | LEADER_LOCK | 说明 |
+//! |------|-------------|------|
+//! | [`FileLease`] | `file` | 共享文件中的租约（持有者 ID + 到期时间），适合同机或共享盘部署 |
+//! | `RedisLease` | `redis` | `SET NX PX` 租约，续约时校验持有者；需 `--features redis-queue` |
+//!
+//! 租约按 TTL 过期而非依赖进程存活，因此 leader 卡死（不再续约）同样会被接管。
+//! 本实例也按同一到期时刻自行判定（[`LeaderState`]）：续约任务卡住或迟到时，租约一到期即停止下单，
+//! 不会与接管的备机同时交易。
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 本实例的持有者 ID：进程号 + 随机后缀，重启后不会误认旧租约
+pub fn new_owner_id() -> String {
+    format!("{}-{}", std::process::id(), uuid::Uuid::new_v4().simple())
+}
+
+/// 本实例的 leader 状态：记录租约到期时刻（单调时钟），`now < 到期` 才视为 leader
+pub struct LeaderState {
+    base: Instant,
+    /// 到期时刻（相对 base 的毫秒）；u64::MAX 表示未启用选举、始终为 leader，0 表示未持有
+    expires_ms: AtomicU64,
+}
+
+impl LeaderState {
+    /// 未启用选举：始终为 leader
+    pub fn always() -> Self {
+        Self {
+            base: Instant::now(),
+            expires_ms: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        (self.base.elapsed().as_millis() as u64) < self.expires_ms.load(Ordering::SeqCst)
+    }
+
+    /// 记录一次竞选/续约结果，返回此前是否为 leader。持有时到期时刻按发起请求的时刻 + ttl 计，
+    /// 早于锁端的实际到期，请求耗时不会让本实例多持有租约
+    pub fn renew(&self, held: bool, requested_at: Instant, ttl: Duration) -> bool {
+        let was = self.is_leader();
+        let expires = if held {
+            (requested_at.saturating_duration_since(self.base) + ttl).as_millis() as u64
+        } else {
+            0
+        };
+        self.expires_ms.store(expires, Ordering::SeqCst);
+        was
+    }
+}
+
+/// 租约锁
+pub trait LeaderLock: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 获取或续约租约（ttl 内未再次调用即失效），返回本实例是否持有租约
+    fn acquire(&self, ttl: Duration) -> BoxFuture<'_, Result<bool>>;
+}
+
+/// 文件租约：内容为 `<owner> <到期 Unix 毫秒>`。
+/// 读取、判定与写入都在租约文件的排他锁（flock）内完成，两个备机同时接管时只有一个能写入；
+/// 文件 IO 在阻塞线程池执行，不占用 tokio 工作线程
+pub struct FileLease {
+    path: PathBuf,
+    owner: String,
+}
+
+impl FileLease {
+    pub fn new(path: impl Into<PathBuf>, owner: &str) -> Self {
+        Self {
+            path: path.into(),
+            owner: owner.to_string(),
+        }
+    }
+
+    /// 解析租约内容 (持有者, 到期毫秒)；内容为空或无效时为 None（视为无人持有）
+    fn parse(content: &str) -> Option<(String, i64)> {
+        let mut parts = content.split_whitespace();
+        match (parts.next(), parts.next().and_then(|v| v.parse().ok())) {
+            (Some(owner), Some(expires_ms)) => Some((owner.to_string(), expires_ms)),
+            _ => None,
+        }
+    }
+
+    /// 持排他锁完成一次竞选/续约（阻塞）：他人持有未过期的租约时返回 false，否则写入本实例的租约
+    fn acquire_blocking(path: &Path, owner: &str, ttl: Duration) -> Result<bool> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("打开租约文件失败: {}", path.display()))?;
+        file.lock().with_context(|| format!("锁定租约文件失败: {}", path.display()))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .with_context(|| format!("读取租约文件失败: {}", path.display()))?;
+        let now = Utc::now().timestamp_millis();
+        if let Some((holder, expires_ms)) = Self::parse(&content) {
+            if holder != owner && expires_ms > now {
+                return Ok(false);
+            }
+        }
+        let lease = format!("{} {}", owner, now + ttl.as_millis() as i64);
+        file.set_len(0)
+            .and_then(|_| file.rewind())
+            .and_then(|_| file.write_all(lease.as_bytes()))
+            .and_then(|_| file.sync_data())
+            .with_context(|| format!("写入租约文件失败: {}", path.display()))?;
+        // 关闭文件时释放锁
+        Ok(true)
+    }
+}
+
+impl LeaderLock for FileLease {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn acquire(&self, ttl: Duration) -> BoxFuture<'_, Result<bool>> {
+        let (path, owner) = (self.path.clone(), self.owner.clone());
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || Self::acquire_blocking(&path, &owner, ttl))
+                .await
+                .context("租约文件任务异常退出")?
+        })
+    }
+}
+
+#[cfg(feature = "redis-queue")]
+pub use redis_lease::RedisLease;
+
+#[cfg(feature = "redis-queue")]
+mod redis_lease {
+    use super::*;
+    use redis::aio::MultiplexedConnection;
+
+    /// 持有者为自己则续期，否则仅在无人持有时 SET NX 获取；返回 1 表示持有
+    const ACQUIRE_SCRIPT: &str = "if redis.call('GET', KEYS[1]) == ARGV[1] then \
+        return redis.call('PEXPIRE', KEYS[1], ARGV[2]) \
+        elseif redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then return 1 else return 0 end";
+
+    /// Redis 租约
+    pub struct RedisLease {
+        conn: MultiplexedConnection,
+        key: String,
+        owner: String,
+    }
+
+    impl RedisLease {
+        pub async fn connect(url: &str, key: &str, owner: &str) -> Result<Self> {
+            let conn = redis::Client::open(url)
+                .with_context(|| format!("Redis 地址无效: {}", url))?
+                .get_multiplexed_async_connection()
+                .await
+                .with_context(|| format!("连接 Redis 失败: {}", url))?;
+            Ok(Self {
+                conn,
+                key: key.to_string(),
+                owner: owner.to_string(),
+            })
+        }
+    }
+
+    impl LeaderLock for RedisLease {
+        fn name(&self) -> &'static str {
+            "redis"
+        }
+
+        fn acquire(&self, ttl: Duration) -> BoxFuture<'_, Result<bool>> {
+            Box::pin(async move {
+                let mut conn = self.conn.clone();
+                let held: i64 = redis::cmd("EVAL")
+                    .arg(ACQUIRE_SCRIPT)
+                    .arg(1)
+                    .arg(&self.key)
+                    .arg(&self.owner)
+                    .arg(ttl.as_millis() as u64)
+                    .query_async(&mut conn)
+                    .await?;
+                Ok(held == 1)
+            })
+        }
+    }
+}
+
+/// 按配置构建租约锁：none（不选举，单实例直接下单）| file | redis
+pub async fn lock_from_config(
+    backend: &str,
+    path: &str,
+    url: &str,
+    key: &str,
+    owner: &str,
+) -> Result<Option<Box<dyn LeaderLock>>> {
+    match backend.trim().to_lowercase().as_str() {
+        "" | "none" | "off" => Ok(None),
+        "file" => Ok(Some(Box::new(FileLease::new(path, owner)))),
+        #[cfg(feature = "redis-queue")]
+        "redis" => Ok(Some(Box::new(RedisLease::connect(url, key, owner).await?))),
+        #[cfg(not(feature = "redis-queue"))]
+        "redis" => {
+            let _ = (url, key);
+            anyhow::bail!("LEADER_LOCK=redis 需以 --features redis-queue 构建")
+        }
+        other => anyhow::bail!("未知 LEADER_LOCK: {}（可用: none, file, redis）", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lease_path() -> PathBuf {
+        std::env::temp_dir().join(format!("leader-test-{}.lease", uuid::Uuid::new_v4().simple()))
+    }
+
+    #[test]
+    fn leader_state_expires_without_renewal() {
+        let state = LeaderState::always();
+        assert!(state.is_leader());
+        let ttl = Duration::from_millis(50);
+        assert!(state.renew(true, Instant::now(), ttl));
+        assert!(state.is_leader());
+        std::thread::sleep(ttl * 2);
+        assert!(!state.is_leader());
+        assert!(!state.renew(false, Instant::now(), ttl));
+        assert!(!state.is_leader());
+    }
+
+    #[test]
+    fn leader_state_counts_from_request_time() {
+        let state = LeaderState::always();
+        let ttl = Duration::from_millis(50);
+        let requested_at = Instant::now();
+        std::thread::sleep(ttl * 2);
+        // 请求耗时超过租约：锁端的租约已到期，本实例不得视为 leader
+        state.renew(true, requested_at, ttl);
+        assert!(!state.is_leader());
+    }
+
+    #[tokio::test]
+    async fn file_lease_is_exclusive_until_expiry() {
+        let path = lease_path();
+        let (a, b) = (FileLease::new(&path, "a"), FileLease::new(&path, "b"));
+        let ttl = Duration::from_millis(200);
+        assert!(a.acquire(ttl).await.unwrap());
+        assert!(!b.acquire(ttl).await.unwrap());
+        // 持有者续约不受影响
+        assert!(a.acquire(ttl).await.unwrap());
+        tokio::time::sleep(ttl * 2).await;
+        assert!(b.acquire(ttl).await.unwrap());
+        assert!(!a.acquire(ttl).await.unwrap());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn concurrent_takeover_has_one_winner() {
+        let path = lease_path();
+        let leases: Vec<FileLease> = (0..8).map(|i| FileLease::new(&path, &format!("owner-{}", i))).collect();
+        let ttl = Duration::from_secs(30);
+        let held = futures::future::join_all(leases.iter().map(|l| l.acquire(ttl))).await;
+        assert_eq!(held.into_iter().filter(|h| *h.as_ref().unwrap()).count(), 1);
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -1,5 +1,8 @@
 //! poly_15min_bot 库：供主程序和 binaries 复用的模块。
 
+pub mod leader;
 pub mod merge;
+pub mod opportunity_queue;
 pub mod positions;
+pub mod recorder;
 pub mod trial;
\ No newline at end of file
@@ -1,3 +1,4 @@
+mod admin;
 mod config;
 mod market;
 mod monitor;
@@ -6,7 +7,8 @@ mod trading;
 mod utils;
 
 use poly_5min_bot::merge;
-use poly_5min_bot::positions::{get_positions, Position};
+use poly_5min_bot::positions::{get_positions, Position, PositionBook};
+use poly_5min_bot::recorder::BookRecorder;
 
 use anyhow::Result;
 use dashmap::DashMap;
@@ -18,15 +20,16 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 use polymarket_client_sdk::types::{Address, B256, U256};
 
-use crate::config::Config;
+use crate::config::{decimal_setting, Config};
 use crate::market::{MarketDiscoverer, MarketInfo, MarketScheduler};
-use crate::monitor::{ArbitrageDetector, OrderBookMonitor};
+use crate::monitor::{compute_direction, profit_model_from_name, ArbitrageDetector, ArbitrageOpportunity, OrderBookMonitor};
 use crate::risk::positions::PositionTracker;
 use crate::risk::{HedgeMonitor, PositionBalancer, RiskManager};
-use crate::trading::TradingExecutor;
+use crate::trading::{PriceDirection, TradingExecutor};
+use crate::trading::clock::short_id;
 
 /// 从持仓中筛出 **YES 和 NO 都持仓** 的 condition_id，仅这些市场才能 merge；单边持仓直接跳过。
 /// Data API 可能返回 outcome_index 0/1（0=Yes, 1=No）或 1/2（与 CTF index_set 一致），两种都支持。
@@ -90,8 +93,10 @@ async fn run_merge_task(
     interval_minutes: u64,
     proxy: Address,
     private_key: String,
+    chain_id: u64,
     position_tracker: Arc<PositionTracker>,
     wind_down_in_progress: Arc<AtomicBool>,
+    executor: Arc<TradingExecutor>,
 ) {
     let interval = Duration::from_secs(interval_minutes * 60);
     /// 每笔 merge 之间间隔，降低 RPC  bursts
@@ -110,6 +115,11 @@ async fn run_merge_task(
             sleep(interval).await;
             continue;
         }
+        if !executor.is_leader() {
+            debug!("备机不执行定时 merge，本轮跳过");
+            sleep(interval).await;
+            continue;
+        }
         let (condition_ids, merge_info) = match get_positions().await {
             Ok(positions) => (
                 condition_ids_with_both_sides(&positions),
@@ -138,19 +148,25 @@ async fn run_merge_task(
                 info!("本轮回 merge: 等待 30 秒后合并下一市场 (第 {}/{} 个)", i + 1, condition_ids.len());
                 sleep(DELAY_BETWEEN_MERGES).await;
             }
-            let mut result = merge::merge_max(condition_id, proxy, &private_key, None).await;
+            let merge_guard = executor.merge_lock().lock().await;
+            let mut result = merge::merge_max(condition_id, proxy, &private_key, None, chain_id).await;
             if result.is_err() {
                 let msg = result.as_ref().unwrap_err().to_string();
                 if msg.contains("rate limit") || msg.contains("retry in") {
                     warn!(condition_id = %condition_id, "⏳ RPC 限速，等待 {}s 后重试一次", RATE_LIMIT_BACKOFF.as_secs());
                     sleep(RATE_LIMIT_BACKOFF).await;
-                    result = merge::merge_max(condition_id, proxy, &private_key, None).await;
+                    result = merge::merge_max(condition_id, proxy, &private_key, None, chain_id).await;
                 }
             }
+            drop(merge_guard);
             match result {
-                Ok(tx) => {
-                    info!("✅ Merge 完成 | condition_id={:#x}", condition_id);
-                    info!("  📝 tx={}", tx);
+                Ok(skipped @ (merge::MergeOutcome::SkippedUneconomical { .. } | merge::MergeOutcome::BelowThreshold { .. })) => {
+                    info!(condition_id = %condition_id, "⏭️ 跳过 merge: {}", skipped);
+                }
+                Ok(merge::MergeOutcome::Merged(res)) => {
+                    info!("✅ Merge 完成 | condition_id={:#x} | 路径:{}", condition_id, res.path);
+                    info!("  📝 tx={}", res.tx_hash);
+                    executor.record_merge(condition_id, merge::units_to_usdc(res.merge_amount)).await;
                     // Merge 成功：扣减持仓与风险敞口（先扣敞口再扣持仓，保证 update_exposure_cost 读到的是合并前持仓）
                     if let Some((yes_token, no_token, merge_amt)) = merge_info.get(&condition_id) {
                         position_tracker.update_exposure_cost(*yes_token, dec!(0), -*merge_amt);
@@ -179,6 +195,267 @@ async fn run_merge_task(
     }
 }
 
+/// 盘中阈值 Merge：成交登记后检查该市场 min(YES, NO) 持仓（每份合并回 1 USDC），
+/// 达到 `trigger` 即执行 merge_max 回收资金。proxy 的 merge 锁被占用（其他 merge 进行中）或收尾进行中时跳过。
+async fn merge_if_over_threshold(
+    trigger: Decimal,
+    condition_id: B256,
+    yes_token: U256,
+    no_token: U256,
+    proxy: Address,
+    private_key: &str,
+    chain_id: u64,
+    position_tracker: Arc<PositionTracker>,
+    wind_down_in_progress: Arc<AtomicBool>,
+    executor: &TradingExecutor,
+) {
+    let (yes_pos, no_pos) = position_tracker.get_pair_positions(yes_token, no_token);
+    let balanced = yes_pos.min(no_pos);
+    if balanced < trigger {
+        return;
+    }
+    if wind_down_in_progress.load(Ordering::Relaxed) {
+        debug!(condition_id = %condition_id, "收尾进行中，跳过盘中阈值 merge");
+        return;
+    }
+    // Safe nonce 按 proxy 唯一：任一市场的 merge 进行中都不并发提交，下一笔成交后再检查
+    let Ok(_merge_guard) = executor.merge_lock().try_lock() else {
+        debug!(condition_id = %condition_id, "已有 merge 进行中，跳过盘中阈值 merge");
+        return;
+    };
+
+    info!(
+        "🔄 盘中阈值 Merge | condition_id={:#x} | YES:{} NO:{} | 阈值:{} USDC",
+        condition_id, yes_pos, no_pos, trigger
+    );
+    match merge::merge_max(condition_id, proxy, private_key, None, chain_id).await {
+        Ok(skipped @ (merge::MergeOutcome::SkippedUneconomical { .. } | merge::MergeOutcome::BelowThreshold { .. })) => {
+            info!(condition_id = %condition_id, "⏭️ 盘中阈值 Merge 跳过: {}", skipped);
+        }
+        Ok(merge::MergeOutcome::Merged(res)) => {
+            let merged = merge::units_to_usdc(res.merge_amount);
+            info!(
+                "✅ 盘中 Merge 完成 | condition_id={:#x} | 路径:{} | 数量:{} | tx={}",
+                condition_id, res.path, merged, res.tx_hash
+            );
+            executor.record_merge(condition_id, merged).await;
+            position_tracker.update_exposure_cost(yes_token, dec!(0), -merged);
+            position_tracker.update_exposure_cost(no_token, dec!(0), -merged);
+            position_tracker.update_position(yes_token, -merged);
+            position_tracker.update_position(no_token, -merged);
+        }
+        Err(e) => {
+            warn!(condition_id = %condition_id, error = %e, "❌ 盘中阈值 Merge 失败");
+        }
+    }
+}
+
+/// 主备选举：配置 LEADER_LOCK 时先同步竞选一次（leader 立即可下单），之后后台每 1/3 租约时长续约或竞选；
+/// 备机每轮校验认证以保持凭证可用，接管后即可下单。续约出错时按失去租约处理（宁可漏单不可重复下单）。
+/// 未配置时不选举，执行器始终为 leader
+async fn start_leader_election(config: &Config, executor: Arc<TradingExecutor>) -> Result<()> {
+    let owner = poly_5min_bot::leader::new_owner_id();
+    let Some(lock) = poly_5min_bot::leader::lock_from_config(
+        &config.leader_lock,
+        &config.leader_lock_path,
+        &config.leader_lock_url,
+        &config.leader_lock_key,
+        &owner,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    let ttl = Duration::from_secs(config.leader_lease_secs.max(3));
+    let requested_at = Instant::now();
+    let held = lock.acquire(ttl).await?;
+    executor.renew_leader(held, requested_at, ttl);
+    if held {
+        info!(lock = lock.name(), owner = %owner, lease_secs = ttl.as_secs(), "👑 已获得 leader 租约，开始交易");
+    } else {
+        info!(lock = lock.name(), owner = %owner, lease_secs = ttl.as_secs(), "🕒 备机模式：已有 leader，只监控行情并校验认证");
+    }
+    tokio::spawn(async move {
+        loop {
+            sleep(ttl / 3).await;
+            let requested_at = Instant::now();
+            let held = match lock.acquire(ttl).await {
+                Ok(held) => held,
+                Err(e) => {
+                    warn!(error = %e, "leader 租约续约失败，按失去租约处理");
+                    false
+                }
+            };
+            match (executor.renew_leader(held, requested_at, ttl), held) {
+                (false, true) => info!(owner = %owner, "👑 已接管 leader 租约，开始交易"),
+                (true, false) => warn!(owner = %owner, "⚠️ 已失去 leader 租约，停止下单"),
+                _ => {}
+            }
+            if !held {
+                if let Err(e) = executor.verify_authentication().await {
+                    warn!(error = %e, "备机认证校验失败");
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// `--show-proxy` 子命令：读取 POLYMARKET_PRIVATE_KEY，打印 EOA、推导的 proxy 地址，并与已配置的 POLYMARKET_PROXY_ADDRESS 比对
+fn show_proxy() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let private_key = std::env::var("POLYMARKET_PRIVATE_KEY")
+        .map_err(|_| anyhow::anyhow!("POLYMARKET_PRIVATE_KEY 未设置"))?;
+    let (eoa, derived) = merge::derived_proxy_for_key(&private_key)?;
+    println!("EOA:            {}", eoa);
+    println!("Derived proxy:  {}", derived);
+    match std::env::var("POLYMARKET_PROXY_ADDRESS").ok().filter(|s| !s.trim().is_empty()) {
+        Some(configured) => match configured.trim().parse::<Address>() {
+            Ok(addr) if addr == derived => println!("✅ POLYMARKET_PROXY_ADDRESS 与推导结果一致"),
+            Ok(addr) => println!(
+                "⚠️ POLYMARKET_PROXY_ADDRESS={} 与推导结果不一致（Browser Wallet/Gnosis Safe 账户的 proxy 不由此方式推导，可忽略）",
+                addr
+            ),
+            Err(e) => println!("❌ POLYMARKET_PROXY_ADDRESS 格式无效: {}", e),
+        },
+        None => println!("未配置 POLYMARKET_PROXY_ADDRESS（Email/Magic 账户可填入上面的 Derived proxy）"),
+    }
+    Ok(())
+}
+
+/// `consume` 子命令：从外部机会队列（OPPORTUNITY_SINK）订阅机会并逐条下单，与 OPPORTUNITY_SINK_ONLY=true 的监控进程配合。
+/// 阈值、剩余时间、敞口等过滤已在监控端发布前完成；这里只保留执行器自身的复核（暂停、陈旧度、含滑点盈利）。
+async fn run_consume_command(config: &Config) -> Result<()> {
+    let executor = TradingExecutor::new(
+        config.private_key.clone(),
+        config.proxy_address,
+        config.chain_id,
+        config.executor_settings()?,
+    )
+    .await?;
+    let ledger = trading::ledger::ledger_from_config(&config.ledger_backend, &config.ledger_path)?;
+    let executor = Arc::new(executor.with_ledger(ledger));
+    start_leader_election(config, executor.clone()).await?;
+    let mut source = poly_5min_bot::opportunity_queue::source_from_config(
+        &config.opportunity_sink,
+        &config.opportunity_queue_url,
+        &config.opportunity_queue_key,
+    )
+    .await?;
+    info!(source = source.name(), key = %config.opportunity_queue_key, "📥 开始订阅机会队列");
+    loop {
+        // 备机不取消息，留给 leader 消费
+        if !executor.is_leader() {
+            sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+        let Some(msg) = source.next().await? else {
+            break;
+        };
+        let (opp, yes_dir, no_dir) = match ArbitrageOpportunity::from_message(&msg) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(error = %e, "机会消息无效，跳过");
+                continue;
+            }
+        };
+        let executor = executor.clone();
+        let span = info_span!("opportunity", trace_id = %opp.trace_id, market_id = %opp.market_id, source = source.name());
+        tokio::spawn(
+            async move {
+                match executor.execute_arbitrage_pair(&opp, yes_dir, no_dir).await {
+                    Ok(r) => info!(
+                        "📥 队列机会已执行 | {} | YES成交:{}份 | NO成交:{}份",
+                        short_id(&r.pair_id), r.yes_filled, r.no_filled
+                    ),
+                    Err(e) => warn!(market_id = %opp.market_id, error = %e, "队列机会执行失败"),
+                }
+            }
+            .instrument(span),
+        );
+    }
+    info!("机会队列已关闭，退出");
+    Ok(())
+}
+
+/// `merge` 子命令：单次 merge 后退出。私钥取 POLYMARKET_PRIVATE_KEY
+#[derive(clap::Parser, Debug)]
+#[command(name = "merge", bin_name = "poly_5min_bot merge", about = "对单个市场执行一次 merge_max 后退出")]
+#[command(group(clap::ArgGroup::new("market").required(true).args(["condition", "slug"])))]
+struct MergeArgs {
+    /// 市场 condition ID（0x...），与 --slug 二选一
+    #[arg(long)]
+    condition: Option<B256>,
+    /// 市场 slug，经 Gamma API 解析为 condition ID
+    #[arg(long)]
+    slug: Option<String>,
+    /// 持仓所在 proxy 地址，缺省取 POLYMARKET_PROXY_ADDRESS
+    #[arg(long)]
+    proxy: Option<Address>,
+    /// 写交易使用的 RPC 节点
+    #[arg(long)]
+    rpc: Option<String>,
+    /// 只检查可合并数量与路径，不发送交易
+    #[arg(long)]
+    dry_run: bool,
+    /// gas 成本不低于回收金额时仍执行
+    #[arg(long)]
+    force: bool,
+}
+
+async fn run_merge_command(args: MergeArgs) -> Result<()> {
+    dotenvy::dotenv().ok();
+    let MergeArgs { condition, slug, proxy, rpc, dry_run, force } = args;
+
+    // clap 已保证 --condition 与 --slug 恰有其一
+    let condition = match (condition, slug) {
+        (Some(c), _) => c,
+        (None, Some(s)) => crate::market::condition_id_from_slug(&s).await?,
+        (None, None) => anyhow::bail!("缺少 --condition 或 --slug"),
+    };
+    let proxy = match proxy {
+        Some(p) => p,
+        None => std::env::var("POLYMARKET_PROXY_ADDRESS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("缺少 --proxy 且未配置有效的 POLYMARKET_PROXY_ADDRESS"))?,
+    };
+    let private_key = std::env::var("POLYMARKET_PRIVATE_KEY")
+        .map_err(|_| anyhow::anyhow!("POLYMARKET_PRIVATE_KEY 未设置"))?;
+    let base = merge::MergeOptions::from_env()?;
+    let opts = merge::MergeOptions {
+        dry_run,
+        force: force || base.force,
+        ..base
+    };
+
+    info!(condition_id = %condition, proxy = %proxy, dry_run, "执行单次 Merge");
+    let res = match merge::merge_max_with(condition, proxy, &private_key, rpc.as_deref(), &opts).await? {
+        merge::MergeOutcome::Merged(res) => res,
+        merge::MergeOutcome::BelowThreshold { merge_amount, min_merge_amount } => {
+            println!("merge_amount:  {} ({} USDC)", merge_amount, merge::units_to_usdc(merge_amount));
+            println!("skipped:       低于最小合并数量 {} USDC（MERGE_MIN_AMOUNT）", merge::units_to_usdc(min_merge_amount));
+            return Ok(());
+        }
+        merge::MergeOutcome::SkippedUneconomical { merge_amount, merged_usdc, gas_cost_usdc } => {
+            println!("merge_amount:  {} ({} USDC)", merge_amount, merged_usdc);
+            println!("skipped:       gas ≈ {:.4} USD ≥ 回收金额（--force 强制执行）", gas_cost_usdc);
+            return Ok(());
+        }
+    };
+    println!("path:          {}", res.path);
+    println!("merge_amount:  {} ({} USDC)", res.merge_amount, merge::units_to_usdc(res.merge_amount));
+    if dry_run {
+        println!("tx_hash:       (dry-run，未发送)");
+    } else {
+        println!("tx_hash:       {}", res.tx_hash);
+    }
+    if let Some(observed) = res.merged_usdc_observed {
+        println!("usdc_observed: {}", observed);
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
 
@@ -187,6 +464,11 @@ async fn main() -> Result<()> {
         .install_default()
         .expect("Failed to install rustls crypto provider");
 
+    // --show-proxy：打印私钥对应的 EOA 与推导出的 proxy 地址后退出（配置 POLYMARKET_PROXY_ADDRESS 前核对用）
+    if std::env::args().any(|a| a == "--show-proxy") {
+        return show_proxy();
+    }
+
     // 初始化日志
     utils::logger::init_logger()?;
 
@@ -195,10 +477,28 @@ async fn main() -> Result<()> {
     // 许可证校验：须存在有效 license.key，删除许可证将无法运行
     poly_5min_bot::trial::check_license()?;
 
+    // merge 子命令：单次 merge 后退出，不启动交易循环；--help 与未知参数由 clap 处理
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("merge") {
+        return run_merge_command(<MergeArgs as clap::Parser>::parse_from(&args[1..])).await;
+    }
+
     // 加载配置
     let config = Config::from_env()?;
     tracing::info!("配置加载完成");
 
+    // consume 子命令：只从外部机会队列取机会下单，不启动行情监控
+    if args.get(1).map(String::as_str) == Some("consume") {
+        return run_consume_command(&config).await;
+    }
+    // Relayer（Magic/Email）merge 凭证：设置了任一项则三项都须存在且格式正确
+    match merge::BuilderCreds::from_env() {
+        Ok(Some(_)) => tracing::info!("Builder 凭证校验通过（Relayer merge 可用）"),
+        Ok(None) => tracing::info!("未配置 POLY_BUILDER_*，Relayer merge 不可用（Gnosis Safe 不受影响）"),
+        Err(e) => return Err(anyhow::anyhow!("Builder 凭证无效: {}", e)),
+    }
+    tracing::info!(source = config.direction_source.as_str(), "涨跌方向采样来源");
+
     // 运行时间校准流水线（本地时钟与业务窗口对齐，可选）
     {
         use time_calibrator::{pipeline::PipelineOptions, CalibrationConfig};
@@ -210,12 +510,22 @@ async fn main() -> Result<()> {
     // 初始化组件（暂时不使用，主循环已禁用）
     let _discoverer = MarketDiscoverer::new(config.crypto_symbols.clone());
     let _scheduler = MarketScheduler::new(_discoverer, config.market_refresh_advance_secs);
-    let _detector = ArbitrageDetector::new(config.min_profit_threshold);
+    let profit_config = config.profit_config()?;
+    info!("手续费表（maker/taker bps）: {}", profit_config.fee_schedule.describe());
+    let fee_bps = profit_config.fee_bps;
+    let _detector = ArbitrageDetector::new(profit_config)
+        .with_profit_model(profit_model_from_name(&config.profit_model, fee_bps))
+        .with_max_complement_ticks(config.max_complement_ticks)
+        .with_max_book_levels(config.max_book_levels)
+        .with_max_book_staleness(Duration::from_millis(config.max_book_staleness_ms))
+        .with_dedup(
+            Duration::from_millis(config.opportunity_dedup_ttl_ms),
+            decimal_setting("OPPORTUNITY_DEDUP_GRANULARITY", config.opportunity_dedup_granularity, dec!(0), Some(dec!(1)))?,
+        );
     
     // 验证私钥格式
     info!("正在验证私钥格式...");
     use alloy::signers::local::LocalSigner;
-    use polymarket_client_sdk::POLYGON;
     use std::str::FromStr;
     
     let _signer_test = LocalSigner::from_str(&config.private_key)
@@ -232,15 +542,15 @@ async fn main() -> Result<()> {
     info!("注意：如果看到'Could not create api key'警告，这是正常的。SDK会先尝试创建新API key，失败后会自动使用派生方式，认证仍然会成功。");
     let executor = match TradingExecutor::new(
         config.private_key.clone(),
-        config.max_order_size_usdc,
         config.proxy_address,
-        config.slippage,
-        config.gtd_expiration_secs,
-        config.arbitrage_order_type.clone(),
+        config.chain_id,
+        config.executor_settings()?,
     ).await {
         Ok(exec) => {
             info!("交易执行器认证成功（可能使用了派生API key）");
-            Arc::new(exec)
+            let ledger = trading::ledger::ledger_from_config(&config.ledger_backend, &config.ledger_path)?;
+            info!(backend = ledger.name(), path = %config.ledger_path, "成交台账已配置");
+            Arc::new(exec.with_ledger(ledger))
         }
         Err(e) => {
             error!(error = %e, "交易执行器认证失败！无法继续运行。");
@@ -253,6 +563,31 @@ async fn main() -> Result<()> {
         }
     };
 
+    // 启动校验：当前窗口（临近结束时取下一窗口）的全部配置市场，token、可交易状态与交易参数有误时集中报告并退出
+    {
+        let now = chrono::Utc::now();
+        let next_window = MarketDiscoverer::calculate_next_window_timestamp(now);
+        let window = if next_window - now.timestamp() <= config.market_refresh_advance_secs as i64 {
+            next_window
+        } else {
+            MarketDiscoverer::calculate_current_window_timestamp(now)
+        };
+        let slugs = _scheduler.discoverer().generate_market_slugs(window);
+        let validated = executor
+            .validate_markets(_scheduler.discoverer(), &slugs)
+            .await
+            .map_err(|e| anyhow::anyhow!("市场校验失败，程序退出: {}", e))?;
+        for m in &validated {
+            info!(
+                "✅ 市场校验通过 | {} | tick:{} | 最小数量:{}",
+                m.info.slug, m.meta.tick_size, m.meta.min_order_size
+            );
+        }
+    }
+
+    // 主备选举（LEADER_LOCK）：备机照常监控行情，但不下单、不收尾、不 merge
+    start_leader_election(&config, executor.clone()).await?;
+
     // 创建CLOB客户端用于风险管理（需要认证）
     info!("正在初始化风险管理客户端（需要API认证）...");
     use alloy::signers::Signer;
@@ -260,7 +595,7 @@ async fn main() -> Result<()> {
     use polymarket_client_sdk::clob::types::SignatureType;
 
     let signer_for_risk = LocalSigner::from_str(&config.private_key)?
-        .with_chain_id(Some(POLYGON));
+        .with_chain_id(Some(config.chain_id));
     let clob_config = ClobConfig::builder().use_server_time(true).build();
     let mut auth_builder_risk = Client::new("https://clob.polymarket.com", clob_config)?
         .authentication_builder(&signer_for_risk);
@@ -272,7 +607,12 @@ async fn main() -> Result<()> {
             .signature_type(SignatureType::Proxy);
     }
     
-    let clob_client = match auth_builder_risk.authenticate().await {
+    let risk_timeout = config.connect_options().request_timeout;
+    let risk_auth = tokio::time::timeout(risk_timeout, auth_builder_risk.authenticate())
+        .await
+        .map_err(|_| anyhow::anyhow!("认证超时（{}ms，CLOB_REQUEST_TIMEOUT_MS）", risk_timeout.as_millis()))
+        .and_then(|r| r.map_err(anyhow::Error::from));
+    let clob_client = match risk_auth {
         Ok(client) => {
             info!("风险管理客户端认证成功（可能使用了派生API key）");
             client
@@ -288,15 +628,84 @@ async fn main() -> Result<()> {
         }
     };
     
-    let _risk_manager = Arc::new(RiskManager::new(clob_client.clone(), &config));
+    // GTC 客户端过期：后台按 ID 撤销超过 GTC_TTL_SECS 的挂单
+    if config.gtc_ttl_secs > 0 {
+        if matches!(config.arbitrage_order_type, polymarket_client_sdk::clob::types::OrderType::GTC) {
+            trading::expiry_sweeper::spawn(
+                executor.clone(),
+                Duration::from_secs(config.expiry_sweep_interval_secs.max(1)),
+            );
+        } else {
+            warn!("GTC_TTL_SECS 仅在 ARBITRAGE_ORDER_TYPE=GTC 时生效，当前订单类型: {:?}", config.arbitrage_order_type);
+        }
+    }
+
+    // USDC 余额下限：低于时自动暂停下单
+    if executor.balance_guard().is_enabled() {
+        let owner = match config.proxy_address {
+            Some(proxy) => proxy,
+            None => _signer_test.address(),
+        };
+        trading::balance_guard::spawn(
+            executor.clone(),
+            owner,
+            Duration::from_secs(config.balance_check_interval_secs.max(1)),
+        );
+    }
+
+    // GTD 过期清理：只撤销已过期但交易所尚未清扫的 GTD 挂单
+    if config.gtd_cleanup_interval_secs > 0
+        && matches!(config.arbitrage_order_type, polymarket_client_sdk::clob::types::OrderType::GTD)
+    {
+        trading::expiry_sweeper::spawn_gtd_cleanup(
+            executor.clone(),
+            Duration::from_secs(config.gtd_cleanup_interval_secs),
+        );
+    }
+
+    let _risk_manager = Arc::new(RiskManager::new(clob_client.clone(), &config)?.with_order_fills(executor.order_fills()));
+
+    // 用户频道：挂单成交/撤单实时推送，更新持仓与订单对成交（替代按 trades 轮询的延迟）
+    if config.order_updates_ws {
+        let risk_manager_ws = _risk_manager.clone();
+        monitor::ws::spawn_order_updates(
+            clob_client.credentials().clone(),
+            clob_client.address(),
+            executor.order_fills(),
+            move |event| risk_manager_ws.apply_order_event(event),
+        );
+    }
     
     // 创建对冲监测器（传入PositionTracker的Arc引用以更新风险敞口）
     // 对冲策略已暂时关闭，但保留hedge_monitor变量以备将来使用
     let position_tracker = _risk_manager.position_tracker();
+    if !config.position_snapshot_path.is_empty() {
+        position_tracker
+            .enable_snapshot(config.position_snapshot_path.clone().into(), config.proxy_address)
+            .map_err(|e| anyhow::anyhow!("持仓快照加载失败: {}", e))?;
+        if config.proxy_address.is_some() {
+            if let Err(e) = position_tracker.reconcile_with_chain(None, config.chain_id).await {
+                warn!(error = %e, "持仓链上核对失败，沿用快照中的持仓");
+            }
+        }
+        position_tracker
+            .clone()
+            .spawn_snapshot_writer(Duration::from_millis(config.position_snapshot_debounce_ms.max(1)));
+    }
+    if config.use_existing_inventory || config.max_open_pairs_per_market > 0.0 {
+        executor.attach_inventory(position_tracker.clone());
+    }
+    if config.use_existing_inventory {
+        info!("已启用库存扣减：单边多余持仓时只补缺口腿");
+    }
+    if config.max_open_pairs_per_market > 0.0 {
+        info!("已启用单市场平衡持仓上限：{} 对（merge 减仓后恢复下单）", config.max_open_pairs_per_market);
+    }
     let _hedge_monitor = HedgeMonitor::new(
         clob_client.clone(),
         config.private_key.clone(),
         config.proxy_address.clone(),
+        config.chain_id,
         position_tracker,
     );
 
@@ -325,7 +734,7 @@ async fn main() -> Result<()> {
         clob_client.clone(),
         _risk_manager.position_tracker(),
         &config,
-    ));
+    )?);
 
     // 定时持仓同步任务：每N秒从API获取最新持仓，覆盖本地缓存
     let position_sync_interval = config.position_sync_interval_secs;
@@ -370,6 +779,113 @@ async fn main() -> Result<()> {
     // 收尾进行中标志：定时 merge 会检查并跳过，避免与收尾 merge 竞争
     let wind_down_in_progress = Arc::new(AtomicBool::new(false));
 
+    // 管理命令处理任务：管理端口与 SIGUSR1 的命令都在此串行执行
+    let (admin_tx, mut admin_rx) = tokio::sync::mpsc::channel::<admin::AdminRequest>(8);
+    {
+        let executor_admin = executor.clone();
+        let config_admin = config.clone();
+        let flatten_slippage = decimal_setting("FLATTEN_MAX_SLIPPAGE", config.flatten_max_slippage, dec!(0), Some(dec!(1)))?;
+        tokio::spawn(async move {
+            while let Some((cmd, reply)) = admin_rx.recv().await {
+                let text = match cmd {
+                    admin::AdminCommand::Flatten => match get_positions().await {
+                        Ok(positions) => {
+                            let merge_with = config_admin
+                                .proxy_address
+                                .map(|proxy| (proxy, config_admin.private_key.as_str()));
+                            let book = PositionBook::from_positions(&positions);
+                            match executor_admin.panic_flatten(&book, flatten_slippage, merge_with).await {
+                                Ok(report) => report.to_string(),
+                                Err(e) => format!("紧急平仓失败: {}", e),
+                            }
+                        }
+                        Err(e) => format!("获取持仓失败，未平仓: {}", e),
+                    },
+                    admin::AdminCommand::DisableMarket(id) => {
+                        executor_admin.market_gate().disable_market(&id);
+                        warn!("🚫 已禁用市场 | condition_id={}", id);
+                        format!("已禁用: {}", id)
+                    }
+                    admin::AdminCommand::EnableMarket(id) => {
+                        if executor_admin.market_gate().enable_market(&id) {
+                            info!("✅ 已重新启用市场 | condition_id={}", id);
+                            format!("已启用: {}", id)
+                        } else {
+                            format!("未处于禁用状态: {}", id)
+                        }
+                    }
+                    admin::AdminCommand::ListDisabled => {
+                        let list = executor_admin.market_gate().disabled_markets();
+                        if list.is_empty() {
+                            "无禁用市场".to_string()
+                        } else {
+                            list.join(", ")
+                        }
+                    }
+                    admin::AdminCommand::Pnl => match executor_admin.ledger().pnl_by_market_and_tag().await {
+                        Ok(pnl) if pnl.is_empty() => format!("台账({})无记录", executor_admin.ledger().name()),
+                        Ok(pnl) => {
+                            let total: Decimal = pnl.values().sum();
+                            let mut by_tag: HashMap<&str, Decimal> = HashMap::new();
+                            for ((_, tag), v) in &pnl {
+                                *by_tag.entry(if tag.is_empty() { "-" } else { tag.as_str() }).or_insert(dec!(0)) += *v;
+                            }
+                            let mut tags: Vec<String> = by_tag.iter().map(|(t, v)| format!("{}:{:.4}", t, v)).collect();
+                            tags.sort();
+                            let mut parts: Vec<String> = pnl
+                                .iter()
+                                .map(|((market, tag), v)| format!("{:#x}[{}]:{:.4}", market, tag, v))
+                                .collect();
+                            parts.sort();
+                            format!("合计:{:.4} USDC | 按标签 {} | {}", total, tags.join(", "), parts.join(", "))
+                        }
+                        Err(e) => format!("读取台账失败: {}", e),
+                    },
+                    admin::AdminCommand::Pause => {
+                        if executor_admin.pause() {
+                            "已处于暂停状态".to_string()
+                        } else {
+                            warn!("⏸️ 已暂停下单（行情订阅保持运行）");
+                            "已暂停下单".to_string()
+                        }
+                    }
+                    admin::AdminCommand::Resume => {
+                        let reply = if executor_admin.resume() {
+                            info!("▶️ 已恢复下单");
+                            "已恢复下单".to_string()
+                        } else {
+                            "未处于暂停状态".to_string()
+                        };
+                        if executor_admin.balance_guard().is_low() {
+                            format!("{}（但{}）", reply, executor_admin.balance_guard().state())
+                        } else {
+                            reply
+                        }
+                    }
+                    admin::AdminCommand::Balance => executor_admin.balance_guard().state().to_string(),
+                };
+                let _ = reply.send(text);
+            }
+        });
+    }
+    if let Some(addr) = config.admin_addr.clone() {
+        let tx = admin_tx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = admin::serve(addr, tx).await {
+                error!(error = %e, "管理端口退出");
+            }
+        });
+    }
+    #[cfg(unix)]
+    {
+        let tx = admin_tx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = admin::forward_sigusr1(tx).await {
+                warn!(error = %e, "SIGUSR1 监听失败");
+            }
+        });
+    }
+
     // 两次套利交易之间的最小间隔
     const MIN_TRADE_INTERVAL: Duration = Duration::from_secs(3);
     let last_trade_time: Arc<tokio::sync::Mutex<Option<Instant>>> = Arc::new(tokio::sync::Mutex::new(None));
@@ -379,10 +895,12 @@ async fn main() -> Result<()> {
     if merge_interval > 0 {
         if let Some(proxy) = config.proxy_address {
             let private_key = config.private_key.clone();
+            let chain_id = config.chain_id;
             let position_tracker = _risk_manager.position_tracker().clone();
             let wind_down_flag = wind_down_in_progress.clone();
+            let executor_merge = executor.clone();
             tokio::spawn(async move {
-                run_merge_task(merge_interval, proxy, private_key, position_tracker, wind_down_flag).await;
+                run_merge_task(merge_interval, proxy, private_key, chain_id, position_tracker, wind_down_flag, executor_merge).await;
             });
             info!(
                 interval_minutes = merge_interval,
@@ -396,6 +914,54 @@ async fn main() -> Result<()> {
         info!("定时 Merge 未启用（MERGE_INTERVAL_MINUTES=0），如需启用请在 .env 中设置 MERGE_INTERVAL_MINUTES 为正数，例如 5 或 15");
     }
 
+    // 盘中阈值 Merge：需要 proxy 地址，0 表示不启用
+    let merge_trigger = decimal_setting("MERGE_TRIGGER_USDC", config.merge_trigger_usdc, dec!(0), None)?;
+    // 收尾卖出单腿持仓的限价
+    let wind_down_sell_price = decimal_setting("WIND_DOWN_SELL_PRICE", config.wind_down_sell_price, dec!(0), Some(dec!(1)))?;
+    // 监控阶段执行门槛：总价 <= 1 - 套利执行价差；YES/NO 卖一低于最低价时不执行（0 表示不限制）
+    let execution_threshold =
+        dec!(1) - decimal_setting("ARBITRAGE_EXECUTION_SPREAD", config.arbitrage_execution_spread, dec!(0), Some(dec!(1)))?;
+    let min_yes_price_decimal =
+        decimal_setting("MIN_YES_PRICE_THRESHOLD", config.min_yes_price_threshold, dec!(0), Some(dec!(1)))?;
+    let min_no_price_decimal = decimal_setting("MIN_NO_PRICE_THRESHOLD", config.min_no_price_threshold, dec!(0), Some(dec!(1)))?;
+    if merge_trigger > dec!(0) {
+        if config.proxy_address.is_some() {
+            info!("盘中阈值 Merge 已启用：min(YES, NO) >= {} USDC 时立即 merge", merge_trigger);
+        } else {
+            warn!("MERGE_TRIGGER_USDC={} 但未设置 POLYMARKET_PROXY_ADDRESS，盘中阈值 Merge 已禁用", merge_trigger);
+        }
+    }
+
+    // 订单簿快照记录（独立任务，不阻塞交易）
+    let book_recorder = match config.book_recorder_dir.as_deref() {
+        Some(dir) => match BookRecorder::spawn(dir, config.book_recorder_max_file_mb * 1024 * 1024) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                warn!(error = %e, "订单簿记录器启动失败，继续运行但不记录");
+                None
+            }
+        },
+        None => None,
+    };
+
+    // 外部机会队列：发布给独立的执行进程（consume 子命令）
+    let opportunity_sink = poly_5min_bot::opportunity_queue::sink_from_config(
+        &config.opportunity_sink,
+        &config.opportunity_queue_url,
+        &config.opportunity_queue_key,
+    )
+    .await?;
+    match &opportunity_sink {
+        Some(sink) => info!(
+            sink = sink.name(),
+            key = %config.opportunity_queue_key,
+            publish_only = config.opportunity_sink_only,
+            "📮 机会队列已启用"
+        ),
+        None if config.opportunity_sink_only => warn!("OPPORTUNITY_SINK_ONLY=true 但未配置 OPPORTUNITY_SINK，忽略"),
+        None => {}
+    }
+
     // 主循环已启用，开始监控和交易
     #[allow(unreachable_code)]
     loop {
@@ -417,8 +983,9 @@ async fn main() -> Result<()> {
         // 新一轮开始：重置风险敞口，使本轮从 0 敞口重新累计
         _risk_manager.position_tracker().reset_exposure();
 
-        // 初始化订单簿监控器
-        let mut monitor = OrderBookMonitor::new();
+        // 初始化订单簿监控器（推送写入与执行器共享的盘口缓存；上一轮的 token 不再使用，先清空）
+        executor.book_cache().clear();
+        let mut monitor = OrderBookMonitor::new().with_book_cache(executor.book_cache());
 
         // 订阅所有市场
         for market in &markets {
@@ -446,6 +1013,11 @@ async fn main() -> Result<()> {
             .unwrap_or_else(|| Utc::now());
         let mut wind_down_done = false;
 
+        // 登记本窗口市场 slug，白名单/黑名单的 slug 条目按 condition_id 匹配
+        for m in &markets {
+            executor.market_filter().remember_slug(&format!("{:#x}", m.market_id), &m.slug);
+        }
+
         // 创建市场ID到市场信息的映射
         let market_map: HashMap<B256, &MarketInfo> = markets.iter()
             .map(|m| (m.market_id, m))
@@ -467,14 +1039,15 @@ async fn main() -> Result<()> {
             None
         };
 
-        // 按市场记录上一拍卖一价，用于计算涨跌方向（仅一次 HashMap 读写，不影响监控性能）
+        // 按市场记录上一拍方向采样值（由 DIRECTION_SOURCE 决定），用于计算涨跌方向（仅一次 HashMap 读写，不影响监控性能）
         let last_prices: DashMap<B256, (Decimal, Decimal)> = DashMap::new();
 
         // 监控订单簿更新
         loop {
             // 收尾检查：距窗口结束 <= N 分钟时执行一次收尾（不跳出，继续监控直到窗口结束由下方「新窗口检测」自然切换）
             // 使用秒级精度，5分钟窗口下 num_minutes() 截断可能导致漏检
-            if config.wind_down_before_window_end_minutes > 0 && !wind_down_done {
+            // 备机不收尾（撤单、卖出、merge 都作用于同一账户，由 leader 负责）
+            if config.wind_down_before_window_end_minutes > 0 && !wind_down_done && executor.is_leader() {
                 let now = Utc::now();
                 let seconds_until_end = (window_end - now).num_seconds();
                 let threshold_seconds = config.wind_down_before_window_end_minutes as i64 * 60;
@@ -486,16 +1059,19 @@ async fn main() -> Result<()> {
                     // 收尾在独立任务中执行，不阻塞订单簿；各市场 merge 之间间隔 30 秒
                     let executor_wd = executor.clone();
                     let config_wd = config.clone();
+                    // 市场类别（crypto_symbol），收尾策略按类别取 taker 费率
+                    let categories_wd: HashMap<B256, String> =
+                        market_map.iter().map(|(id, m)| (*id, m.crypto_symbol.clone())).collect();
                     let risk_manager_wd = _risk_manager.clone();
                     let wind_down_flag = wind_down_in_progress.clone();
                     tokio::spawn(async move {
                         const MERGE_INTERVAL: Duration = Duration::from_secs(30);
 
                         // 1. 取消所有挂单
-                        if let Err(e) = executor_wd.cancel_all_orders().await {
-                            warn!(error = %e, "收尾：取消所有挂单失败，继续执行 Merge 与卖出");
-                        } else {
-                            info!("✅ 收尾：已取消所有挂单");
+                        match executor_wd.cancel_all_orders().await {
+                            Ok(summary) if summary.failed.is_empty() => info!("✅ 收尾：取消所有挂单 | {}", summary),
+                            Ok(summary) => warn!("收尾：部分挂单取消失败，继续执行 Merge 与卖出 | {}", summary),
+                            Err(e) => warn!(error = %e, "收尾：取消所有挂单失败，继续执行 Merge 与卖出"),
                         }
 
                         // 取消后等 10 秒再 Merge，避免取消前刚成交的订单尚未上链更新持仓
@@ -512,10 +1088,26 @@ async fn main() -> Result<()> {
                                     let merge_info = merge_info_with_both_sides(&positions);
                                     let n = condition_ids.len();
                                     for (i, condition_id) in condition_ids.iter().enumerate() {
-                                        match merge::merge_max(*condition_id, proxy, &config_wd.private_key, None).await {
-                                            Ok(tx) => {
+                                        // 按 CLOSE_STRATEGY 决定该市场 Merge 还是留给第 3 步卖出
+                                        if let Some((yes_token, no_token, _)) = merge_info.get(condition_id) {
+                                            let category = categories_wd.get(condition_id).map(String::as_str);
+                                            if !executor_wd.close_should_merge(config_wd.close_strategy, *yes_token, *no_token, category).await {
+                                                info!("收尾：按 CLOSE_STRATEGY={} 卖出双边持仓，跳过 Merge | condition_id={:#x}", config_wd.close_strategy.as_str(), condition_id);
+                                                continue;
+                                            }
+                                        }
+                                        let merge_result = {
+                                            let _merge_guard = executor_wd.merge_lock().lock().await;
+                                            merge::merge_max(*condition_id, proxy, &config_wd.private_key, None, config_wd.chain_id).await
+                                        };
+                                        match merge_result {
+                                            Ok(skipped @ (merge::MergeOutcome::SkippedUneconomical { .. } | merge::MergeOutcome::BelowThreshold { .. })) => {
+                                                info!(condition_id = %condition_id, "收尾：Merge 跳过: {}", skipped);
+                                            }
+                                            Ok(merge::MergeOutcome::Merged(res)) => {
                                                 did_any_merge = true;
-                                                info!("✅ 收尾：Merge 完成 | condition_id={:#x} | tx={}", condition_id, tx);
+                                                info!("✅ 收尾：Merge 完成 | condition_id={:#x} | 路径:{} | tx={}", condition_id, res.path, res.tx_hash);
+                                                executor_wd.record_merge(*condition_id, merge::units_to_usdc(res.merge_amount)).await;
                                                 if let Some((yes_token, no_token, merge_amt)) = merge_info.get(condition_id) {
                                                     position_tracker.update_exposure_cost(*yes_token, dec!(0), -*merge_amt);
                                                     position_tracker.update_exposure_cost(*no_token, dec!(0), -*merge_amt);
@@ -547,7 +1139,6 @@ async fn main() -> Result<()> {
                         }
 
                         // 3. 市价卖出剩余单腿持仓
-                        let wind_down_sell_price = Decimal::try_from(config_wd.wind_down_sell_price).unwrap_or(dec!(0.01));
                         match get_positions().await {
                             Ok(positions) => {
                                 for pos in positions.iter().filter(|p| p.size > dec!(0)) {
@@ -556,7 +1147,7 @@ async fn main() -> Result<()> {
                                         debug!(token_id = %pos.asset, size = %pos.size, "收尾：持仓过小，跳过卖出");
                                         continue;
                                     }
-                                    if let Err(e) = executor_wd.sell_at_price(pos.asset, wind_down_sell_price, size_floor).await {
+                                    if let Err(e) = executor_wd.sell_at_price(pos.condition_id, pos.asset, wind_down_sell_price, size_floor).await {
                                         warn!(token_id = %pos.asset, size = %pos.size, error = %e, "收尾：卖出单腿失败");
                                     } else {
                                         info!("✅ 收尾：已下卖单 | token_id={:#x} | 数量:{} | 价格:{:.4}", pos.asset, size_floor, wind_down_sell_price);
@@ -577,6 +1168,9 @@ async fn main() -> Result<()> {
                 book_result = stream.next() => {
                     match book_result {
                         Some(Ok(book)) => {
+                            if let Some(ref recorder) = book_recorder {
+                                recorder.record(&book);
+                            }
                             // 然后处理订单簿更新（book会被move）
                             if let Some(pair) = monitor.handle_book_update(book) {
                                 // 注意：asks 最后一个为卖一价
@@ -585,20 +1179,21 @@ async fn main() -> Result<()> {
                                 let total_ask_price = yes_best_ask.and_then(|(p, _)| no_best_ask.map(|(np, _)| p + np));
 
                                 let market_id = pair.market_id;
-                                // 与上一拍比较得到涨跌方向（↑涨 ↓跌 −平），首拍无箭头
-                                let (yes_dir, no_dir) = match (yes_best_ask, no_best_ask) {
-                                    (Some((yp, _)), Some((np, _))) => {
+                                // 按 DIRECTION_SOURCE 采样并与上一拍比较得到涨跌方向（↑涨 ↓跌 −平），首拍无箭头
+                                let direction_source = config.direction_source;
+                                let (yes_dir, no_dir) = match (
+                                    direction_source.sample(&pair.yes_book),
+                                    direction_source.sample(&pair.no_book),
+                                ) {
+                                    (Some(ys), Some(ns)) => {
                                         let prev = last_prices.get(&market_id).map(|r| (r.0, r.1));
-                                        let (y_dir, n_dir) = prev
-                                            .map(|(ly, ln)| (
-                                                if yp > ly { "↑" } else if yp < ly { "↓" } else { "−" },
-                                                if np > ln { "↑" } else if np < ln { "↓" } else { "−" },
-                                            ))
-                                            .unwrap_or(("", ""));
-                                        last_prices.insert(market_id, (yp, np));
-                                        (y_dir, n_dir)
+                                        last_prices.insert(market_id, (ys, ns));
+                                        (
+                                            compute_direction(prev.map(|p| p.0), Some(ys), direction_source),
+                                            compute_direction(prev.map(|p| p.1), Some(ns), direction_source),
+                                        )
                                     }
-                                    _ => ("", ""),
+                                    _ => (PriceDirection::Unknown, PriceDirection::Unknown),
                                 };
 
                                 let market_info = market_map.get(&pair.market_id);
@@ -625,7 +1220,7 @@ async fn main() -> Result<()> {
                                 let is_arbitrage = prefix == "🚨套利机会";
                                 let yes_info = yes_best_ask
                                     .map(|(p, s)| {
-                                        if is_arbitrage && !yes_dir.is_empty() {
+                                        if is_arbitrage && yes_dir != PriceDirection::Unknown {
                                             format!("Yes:{:.4} 份额:{} {}", p, s, yes_dir)
                                         } else {
                                             format!("Yes:{:.4} 份额:{}", p, s)
@@ -634,7 +1229,7 @@ async fn main() -> Result<()> {
                                     .unwrap_or_else(|| "Yes:无".to_string());
                                 let no_info = no_best_ask
                                     .map(|(p, s)| {
-                                        if is_arbitrage && !no_dir.is_empty() {
+                                        if is_arbitrage && no_dir != PriceDirection::Unknown {
                                             format!("No:{:.4} 份额:{} {}", p, s, no_dir)
                                         } else {
                                             format!("No:{:.4} 份额:{}", p, s)
@@ -660,46 +1255,40 @@ async fn main() -> Result<()> {
                                 );
 
                                 // 检测套利机会（监控阶段：只有当总价 <= 1 - 套利执行价差 时才执行套利）
-                                use rust_decimal::Decimal;
-                                let execution_threshold = dec!(1.0) - Decimal::try_from(config.arbitrage_execution_spread)
-                                    .unwrap_or(dec!(0.01));
                                 if let Some(total_price) = total_ask_price {
                                     if total_price <= execution_threshold {
                                         if let Some(opp) = _detector.check_arbitrage(
                                             &pair.yes_book,
                                             &pair.no_book,
                                             &pair.market_id,
+                                            Some(market_symbol).filter(|s| !s.is_empty()),
+                                            pair.oldest_update,
                                         ) {
+                                            // 白名单/黑名单：不在允许范围的市场不发出机会
+                                            if let Some(reason) = executor.market_filter().rejection(&format!("{:#x}", opp.market_id)) {
+                                                debug!("🚫 {}，跳过套利机会 | 市场:{}", reason, market_display);
+                                                continue;
+                                            }
                                             // 检查 YES 价格是否达到阈值
-                                            if config.min_yes_price_threshold > 0.0 {
-                                                use rust_decimal::Decimal;
-                                                let min_yes_price_decimal = Decimal::try_from(config.min_yes_price_threshold)
-                                                    .unwrap_or(dec!(0.0));
-                                                if opp.yes_ask_price < min_yes_price_decimal {
-                                                    debug!(
-                                                        "⏸️ YES价格未达到阈值，跳过套利执行 | 市场:{} | YES价格:{:.4} | 阈值:{:.4}",
-                                                        market_display,
-                                                        opp.yes_ask_price,
-                                                        config.min_yes_price_threshold
-                                                    );
-                                                    continue; // 跳过这个套利机会
-                                                }
+                                            if opp.yes_ask_price < min_yes_price_decimal {
+                                                debug!(
+                                                    "⏸️ YES价格未达到阈值，跳过套利执行 | 市场:{} | YES价格:{:.4} | 阈值:{:.4}",
+                                                    market_display,
+                                                    opp.yes_ask_price,
+                                                    min_yes_price_decimal
+                                                );
+                                                continue; // 跳过这个套利机会
                                             }
                                             
                                             // 检查 NO 价格是否达到阈值
-                                            if config.min_no_price_threshold > 0.0 {
-                                                use rust_decimal::Decimal;
-                                                let min_no_price_decimal = Decimal::try_from(config.min_no_price_threshold)
-                                                    .unwrap_or(dec!(0.0));
-                                                if opp.no_ask_price < min_no_price_decimal {
-                                                    debug!(
-                                                        "⏸️ NO价格未达到阈值，跳过套利执行 | 市场:{} | NO价格:{:.4} | 阈值:{:.4}",
-                                                        market_display,
-                                                        opp.no_ask_price,
-                                                        config.min_no_price_threshold
-                                                    );
-                                                    continue; // 跳过这个套利机会
-                                                }
+                                            if opp.no_ask_price < min_no_price_decimal {
+                                                debug!(
+                                                    "⏸️ NO价格未达到阈值，跳过套利执行 | 市场:{} | NO价格:{:.4} | 阈值:{:.4}",
+                                                    market_display,
+                                                    opp.no_ask_price,
+                                                    min_no_price_decimal
+                                                );
+                                                continue; // 跳过这个套利机会
                                             }
                                             
                                             // 检查是否接近市场结束时间（如果配置了停止时间）
@@ -724,13 +1313,56 @@ async fn main() -> Result<()> {
                                                 }
                                             }
                                             
+                                            // 窗口剩余时间过短：订单来不及成交，跳过
+                                            if config.min_seconds_remaining > 0 {
+                                                let seconds_remaining = (window_end - Utc::now()).num_seconds();
+                                                if seconds_remaining < config.min_seconds_remaining as i64 {
+                                                    info!(
+                                                        "⏰ 窗口剩余时间不足，跳过套利执行 | 市场:{} | 剩余:{}秒 | 最少:{}秒",
+                                                        market_display,
+                                                        seconds_remaining,
+                                                        config.min_seconds_remaining
+                                                    );
+                                                    continue; // 跳过这个套利机会
+                                                }
+                                            }
+
+                                            // 与执行端同一口径：加滑点后不满足盈利条件的机会执行端必然跳过，不发布也不执行
+                                            if !executor.admits_after_slippage(&opp, yes_dir, no_dir) {
+                                                debug!(
+                                                    "📉 含滑点后不满足盈利条件，跳过套利机会 | 市场:{} | 总价:{:.4}",
+                                                    market_display,
+                                                    opp.yes_ask_price + opp.no_ask_price
+                                                );
+                                                continue;
+                                            }
+
+                                            // 发布到外部队列；仅发布模式下由执行进程下单，本进程不再继续
+                                            if let Some(ref sink) = opportunity_sink {
+                                                if let Err(e) = sink.publish(&opp.to_message(yes_dir, no_dir)).await {
+                                                    warn!(sink = sink.name(), error = %e, "发布机会失败 | 市场:{}", market_display);
+                                                }
+                                                if config.opportunity_sink_only {
+                                                    continue;
+                                                }
+                                            }
+
+                                            // 已暂停下单：不计敞口、不占交易间隔，行情继续更新
+                                            if executor.is_paused() {
+                                                debug!("⏸️ 已暂停下单，跳过套利执行 | 市场:{}", market_display);
+                                                continue;
+                                            }
+                                            if !executor.is_leader() {
+                                                debug!("🕒 备机不下单，跳过套利执行 | 市场:{}", market_display);
+                                                continue;
+                                            }
+
                                             // 计算订单成本（USD）
                                             // 使用套利机会中的实际可用数量，但不超过配置的最大订单大小
-                                            use rust_decimal::Decimal;
-                                            let max_order_size = Decimal::try_from(config.max_order_size_usdc).unwrap_or(dec!(100.0));
-                                            let order_size = opp.yes_size.min(opp.no_size).min(max_order_size);
-                                            let yes_cost = opp.yes_ask_price * order_size;
-                                            let no_cost = opp.no_ask_price * order_size;
+                                            // 与执行端一致：受最大订单限制并按 SIZE_INCREMENT 向下取整
+                                            let (yes_order_size, no_order_size) = executor.leg_sizes(&opp);
+                                            let yes_cost = opp.yes_ask_price * yes_order_size;
+                                            let no_cost = opp.no_ask_price * no_order_size;
                                             let total_cost = yes_cost + no_cost;
                                             
                                             // 检查风险敞口限制
@@ -776,31 +1408,50 @@ async fn main() -> Result<()> {
                                             }
 
                                             info!(
-                                                "⚡ 执行套利交易 | 市场:{} | 利润:{:.2}% | 下单数量:{}份 | 订单成本:{:.2} USD | 当前敞口:{:.2} USD",
+                                                "⚡ 执行套利交易 | 市场:{} | 利润:{:.2}% | 下单数量:YES {}份 NO {}份 | 订单成本:{:.2} USD | 当前敞口:{:.2} USD",
                                                 market_display,
                                                 opp.profit_percentage,
-                                                order_size,
+                                                yes_order_size,
+                                                no_order_size,
                                                 total_cost,
                                                 current_exposure
                                             );
                                             // 简化敞口：只要执行套利就增加敞口，不管是否成交
                                             let _pt = _risk_manager.position_tracker();
-                                            _pt.update_exposure_cost(opp.yes_token_id, opp.yes_ask_price, order_size);
-                                            _pt.update_exposure_cost(opp.no_token_id, opp.no_ask_price, order_size);
+                                            _pt.update_exposure_cost(opp.yes_token_id, opp.yes_ask_price, yes_order_size);
+                                            _pt.update_exposure_cost(opp.no_token_id, opp.no_ask_price, no_order_size);
                                             
                                             // 套利执行：只要总价 <= 阈值即执行，不因涨跌组合跳过；涨跌仅用于滑点分配（仅下降=second，上涨与持平=first）
                                             // 克隆需要的变量到独立任务中（涨跌方向用于按方向分配滑点）
                                             let executor_clone = executor.clone();
                                             let risk_manager_clone = _risk_manager.clone();
                                             let opp_clone = opp.clone();
-                                            let yes_dir_s = yes_dir.to_string();
-                                            let no_dir_s = no_dir.to_string();
+                                            let merge_proxy = config.proxy_address.filter(|_| merge_trigger > dec!(0));
+                                            let merge_private_key = config.private_key.clone();
+                                            let merge_chain_id = config.chain_id;
+                                            let wind_down_flag = wind_down_in_progress.clone();
+                                            // 机会级 span：下单、风控与 merge 都在其下，按 trace_id 串联端到端耗时
+                                            let opp_span = info_span!("opportunity", trace_id = %opp.trace_id, market_id = %opp.market_id);
                                             
                                             // 使用 tokio::spawn 异步执行套利交易，不阻塞订单簿更新处理
                                             tokio::spawn(async move {
                                                 // 执行套利交易（滑点：仅下降=second，上涨与持平=first）
-                                                match executor_clone.execute_arbitrage_pair(&opp_clone, &yes_dir_s, &no_dir_s).await {
+                                                match executor_clone.execute_arbitrage_pair(&opp_clone, yes_dir, no_dir).await {
                                                     Ok(result) => {
+                                                        // 按成交记录对账：挂单随后成交的部分计入后再交风险管理器
+                                                        let result = match executor_clone.reconcile_fills_delay() {
+                                                            Some(delay) => {
+                                                                sleep(delay).await;
+                                                                match executor_clone.reconcile_fills(&result).await {
+                                                                    Ok(reconciled) => reconciled,
+                                                                    Err(e) => {
+                                                                        warn!(pair_id = %result.pair_id, error = %e, "成交对账失败，沿用提交时的成交数量");
+                                                                        result
+                                                                    }
+                                                                }
+                                                            }
+                                                            None => result,
+                                                        };
                                                         // 先保存 pair_id，因为 result 会被移动
                                                         let pair_id = result.pair_id.clone();
                                                         
@@ -832,14 +1483,87 @@ async fn main() -> Result<()> {
                                                                     crate::risk::recovery::RecoveryAction::ManualIntervention { reason } => {
                                                                         warn!("需要手动干预: {}", reason);
                                                                     }
+                                                                    crate::risk::recovery::RecoveryAction::Unwind { market_id, token_id, amount, entry_price, style, pair_id, cancel_order_ids } => {
+                                                                        for id in &cancel_order_ids {
+                                                                            if let Err(e) = executor_clone.cancel_order(id).await {
+                                                                                warn!(order_id = %id, error = %e, "撤销挂单余量失败");
+                                                                            }
+                                                                        }
+                                                                        match executor_clone.unwind(market_id, token_id, amount, entry_price, style).await {
+                                                                            Ok(results) => {
+                                                                                // 卖单付出的份数（making），taking 为收到的 USDC
+                                                                                let sold: Decimal = results.iter().map(|r| r.making_amount).sum();
+                                                                                if sold > dec!(0) {
+                                                                                    risk_manager_clone.position_tracker().update_position(token_id, -sold);
+                                                                                }
+                                                                                info!(
+                                                                                    "🔻 单边卖出已提交 | {} | 方式:{} | 数量:{} | 立即成交:{} | 卖单:{}笔",
+                                                                                    short_id(&pair_id), style.as_str(), amount, sold, results.len()
+                                                                                );
+                                                                            }
+                                                                            Err(e) => warn!(pair_id = %pair_id, error = %e, "单边卖出失败"),
+                                                                        }
+                                                                    }
+                                                                    crate::risk::recovery::RecoveryAction::TopUp { market_id, token_id, amount, max_price, pair_id, cancel_order_ids } => {
+                                                                        for id in &cancel_order_ids {
+                                                                            if let Err(e) = executor_clone.cancel_order(id).await {
+                                                                                warn!(order_id = %id, error = %e, "撤销挂单余量失败");
+                                                                            }
+                                                                        }
+                                                                        match executor_clone.top_up(market_id, token_id, amount, max_price).await {
+                                                                            Ok(Some(r)) => {
+                                                                                if r.taking_amount > dec!(0) {
+                                                                                    risk_manager_clone.position_tracker().update_position(token_id, r.taking_amount);
+                                                                                }
+                                                                                info!(
+                                                                                    "🔺 补买已提交 | {} | 差额:{} | 立即成交:{}",
+                                                                                    short_id(&pair_id), amount, r.taking_amount
+                                                                                );
+                                                                            }
+                                                                            Ok(None) => {}
+                                                                            Err(e) => warn!(pair_id = %pair_id, error = %e, "补买失败"),
+                                                                        }
+                                                                    }
                                                                 }
                                                             }
                                                             Err(e) => {
                                                                 error!("风险处理失败: {}", e);
                                                             }
                                                         }
+
+                                                        // 持仓已更新：检查是否达到盘中 Merge 阈值
+                                                        if let Some(proxy) = merge_proxy {
+                                                            merge_if_over_threshold(
+                                                                merge_trigger,
+                                                                opp_clone.market_id,
+                                                                opp_clone.yes_token_id,
+                                                                opp_clone.no_token_id,
+                                                                proxy,
+                                                                &merge_private_key,
+                                                                merge_chain_id,
+                                                                risk_manager_clone.position_tracker(),
+                                                                wind_down_flag,
+                                                                &executor_clone,
+                                                            ).await;
+                                                        }
                                                     }
                                                     Err(e) => {
+                                                        if e.downcast_ref::<crate::utils::errors::AuthExpired>().is_some() {
+                                                            error!("🔑 API凭证已过期且重新认证失败，请检查私钥/网络后重启: {}", e);
+                                                            return;
+                                                        }
+                                                        if e.downcast_ref::<crate::utils::errors::Paused>().is_some() {
+                                                            debug!("⏸️ 已暂停下单，跳过机会");
+                                                            return;
+                                                        }
+                                                        if e.downcast_ref::<crate::utils::errors::NotLeader>().is_some() {
+                                                            debug!("🕒 已失去 leader 租约，跳过机会");
+                                                            return;
+                                                        }
+                                                        if let Some(loss) = e.downcast_ref::<crate::utils::errors::GuaranteedLoss>() {
+                                                            debug!(sum = %loss.sum, "硬止损已拒绝该机会");
+                                                            return;
+                                                        }
                                                         // 错误详情已在executor中记录，这里只记录简要信息
                                                         let error_msg = e.to_string();
                                                         // 提取简化的错误信息
@@ -851,7 +1575,7 @@ async fn main() -> Result<()> {
                                                         }
                                                     }
                                                 }
-                                            });
+                                            }.instrument(opp_span));
                                         }
                                     }
                                 }
@@ -873,7 +1597,9 @@ async fn main() -> Result<()> {
                 _ = async {
                     if let Some(ref mut timer) = balance_timer {
                         timer.tick().await;
-                        if let Err(e) = position_balancer.check_and_balance_positions(&market_token_map).await {
+                        if !executor.is_leader() {
+                            debug!("备机不做仓位平衡，跳过");
+                        } else if let Err(e) = position_balancer.check_and_balance_positions(&market_token_map).await {
                             warn!(error = %e, "仓位平衡检查失败");
                         }
                     } else {
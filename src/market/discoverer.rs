@@ -18,6 +18,52 @@ pub struct MarketInfo {
     pub crypto_symbol: String,
 }
 
+/// 按 slug 查到的市场标识（不要求市场仍在交易，已关闭的市场同样可用于 merge）
+#[derive(Debug, Clone)]
+pub struct SlugMarket {
+    pub condition_id: B256,
+    /// clobTokenIds，顺序与 outcomes 一致
+    pub token_ids: Vec<U256>,
+    pub question: String,
+}
+
+/// 通过 Gamma API 按 slug 查询市场，返回 condition ID 与 token IDs
+pub async fn resolve_slug(slug: &str) -> Result<SlugMarket> {
+    let slug = slug.trim();
+    anyhow::ensure!(!slug.is_empty(), "slug 为空");
+    let request = MarketsRequest::builder().slug(vec![slug.to_string()]).build();
+    let markets = Client::default()
+        .markets(&request)
+        .await
+        .map_err(|e| anyhow::anyhow!("Gamma 查询市场失败 slug={}: {}", slug, e))?;
+    let market = markets
+        .into_iter()
+        .find(|m| m.slug.as_deref() == Some(slug))
+        .ok_or_else(|| anyhow::anyhow!("未找到 slug 为 {} 的市场", slug))?;
+    let condition_id = market
+        .condition_id
+        .ok_or_else(|| anyhow::anyhow!("市场 {} 缺少 conditionId", slug))?;
+    Ok(SlugMarket {
+        condition_id,
+        token_ids: market.clob_token_ids.unwrap_or_default(),
+        question: market.question.unwrap_or_default(),
+    })
+}
+
+/// 通过 Gamma API 按 slug 解析 condition ID（同时记录问题与 token IDs，便于核对）
+pub async fn condition_id_from_slug(slug: &str) -> Result<B256> {
+    let market = resolve_slug(slug).await?;
+    let token_ids: Vec<String> = market.token_ids.iter().map(|t| t.to_string()).collect();
+    info!(
+        slug = %slug,
+        condition_id = %market.condition_id,
+        token_ids = %token_ids.join(","),
+        "按 slug 解析市场: {}",
+        market.question
+    );
+    Ok(market.condition_id)
+}
+
 pub struct MarketDiscoverer {
     gamma_client: Client,
     crypto_symbols: Vec<String>,
@@ -70,7 +116,7 @@ impl MarketDiscoverer {
                 // 过滤并解析市场
                 let valid_markets: Vec<MarketInfo> = markets
                     .into_iter()
-                    .filter_map(|market| self.parse_market(market))
+                    .filter_map(|market| Self::check_market(market).ok())
                     .collect();
 
                 info!(count = valid_markets.len(), "找到符合条件的市场");
@@ -83,40 +129,71 @@ impl MarketDiscoverer {
         }
     }
 
-    /// 解析市场信息，提取YES和NO的token_id
-    fn parse_market(&self, market: polymarket_client_sdk::gamma::types::response::Market) -> Option<MarketInfo> {
+    /// 逐个 slug 查询市场并检查是否可交易，返回 (slug, 市场或不可交易原因)；
+    /// 与 get_markets_for_timestamp 不同，未找到或不可交易的市场也会返回原因，用于启动时集中报告
+    pub async fn probe_markets(&self, slugs: &[String]) -> Result<Vec<(String, std::result::Result<MarketInfo, String>)>> {
+        let request = MarketsRequest::builder().slug(slugs.to_vec()).build();
+        let mut markets = self
+            .gamma_client
+            .markets(&request)
+            .await
+            .map_err(|e| anyhow::anyhow!("Gamma 查询市场失败: {}", e))?;
+        Ok(slugs
+            .iter()
+            .map(|slug| {
+                let found = markets
+                    .iter()
+                    .position(|m| m.slug.as_deref() == Some(slug.as_str()))
+                    .map(|i| markets.swap_remove(i));
+                let checked = match found {
+                    Some(market) => Self::check_market(market),
+                    None => Err("未找到市场".to_string()),
+                };
+                (slug.clone(), checked)
+            })
+            .collect())
+    }
+
+    /// 解析市场信息，提取YES和NO的token_id；不可交易或字段缺失时返回原因
+    fn check_market(market: polymarket_client_sdk::gamma::types::response::Market) -> std::result::Result<MarketInfo, String> {
         // 检查市场是否活跃、启用订单簿且接受订单
+        if market.closed.unwrap_or(false) {
+            return Err("市场已关闭（已结算）".to_string());
+        }
         if !market.active.unwrap_or(false) 
            || !market.enable_order_book.unwrap_or(false)
            || !market.accepting_orders.unwrap_or(false) {
-            return None;
+            return Err("市场未激活、未启用订单簿或不接受订单".to_string());
         }
 
         // 检查outcomes是否为["Up", "Down"]
-        let outcomes = market.outcomes.as_ref()?;
+        let outcomes = market.outcomes.as_ref().ok_or("缺少 outcomes")?;
 
         if outcomes.len() != 2 
            || !outcomes.contains(&"Up".to_string()) 
            || !outcomes.contains(&"Down".to_string()) {
-            return None;
+            return Err(format!("outcomes 不是 Up/Down: {:?}", outcomes));
         }
 
         // 获取clobTokenIds
-        let token_ids = market.clob_token_ids.as_ref()?;
+        let token_ids = market.clob_token_ids.as_ref().ok_or("缺少 clobTokenIds")?;
 
         if token_ids.len() != 2 {
-            return None;
+            return Err(format!("clobTokenIds 数量为 {}（应为 2）", token_ids.len()));
         }
 
         // 第一个是"Up"的token_id，第二个是"Down"的token_id
         let yes_token_id = token_ids[0];
         let no_token_id = token_ids[1];
+        if yes_token_id == no_token_id {
+            return Err("YES 与 NO 的 token_id 相同".to_string());
+        }
 
         // 获取conditionId
-        let market_id = market.condition_id?;
+        let market_id = market.condition_id.ok_or("缺少 conditionId")?;
 
         // 从slug中提取加密货币符号
-        let slug = market.slug.as_ref()?;
+        let slug = market.slug.as_ref().ok_or("缺少 slug")?;
         let crypto_symbol = slug
             .split('-')
             .next()
@@ -124,9 +201,12 @@ impl MarketDiscoverer {
             .to_string();
 
         // 获取endDate
-        let end_date = market.end_date?;
+        let end_date = market.end_date.ok_or("缺少 endDate")?;
+        if end_date <= Utc::now() {
+            return Err(format!("市场已于 {} 结束", end_date));
+        }
 
-        Some(MarketInfo {
+        Ok(MarketInfo {
             market_id,
             slug: slug.clone(),
             yes_token_id,
@@ -19,6 +19,10 @@ impl MarketScheduler {
         }
     }
 
+    pub fn discoverer(&self) -> &MarketDiscoverer {
+        &self.discoverer
+    }
+
     /// 计算到下一个5分钟窗口的等待时间
     pub fn calculate_wait_time(&self, now: DateTime<Utc>) -> Duration {
         let next_window_ts = MarketDiscoverer::calculate_next_window_timestamp(now);
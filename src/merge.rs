@@ -9,12 +9,14 @@
 //! use alloy::primitives::B256;
 //! use polymarket_client_sdk::types::Address;
 //!
-//! let tx = poly_15min_bot::merge::merge_max(
+//! let res = poly_15min_bot::merge::merge_max(
 //!     condition_id,
 //!     proxy,
 //!     &private_key,
 //!     Some("https://polygon-rpc.com"),
+//!     polymarket_client_sdk::POLYGON,
 //! ).await?;
+//! println!("{}", res); // MergeOutcome：Merged(..)、BelowThreshold { .. } 或 SkippedUneconomical { .. }
 //! ```
 
 use std::env;
@@ -25,12 +27,13 @@ use alloy::signers::local::LocalSigner;
 use alloy::signers::Signer as _;
 use alloy::sol_types::SolCall;
 use anyhow::Result;
+use rust_decimal::Decimal;
 use polymarket_client_sdk::ctf::types::{CollectionIdRequest, MergePositionsRequest, PositionIdRequest};
 use polymarket_client_sdk::ctf::Client;
 use polymarket_client_sdk::types::address;
 use polymarket_client_sdk::{contract_config, POLYGON};
 use std::str::FromStr as _;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 use alloy::sol;
 sol! {
@@ -39,9 +42,16 @@ sol! {
         function balanceOf(address account, uint256 id) external view returns (uint256);
     }
 
+    #[sol(rpc)]
+    interface IERC20Balance {
+        function balanceOf(address account) external view returns (uint256);
+    }
+
     #[sol(rpc)]
     interface IGnosisSafe {
         function nonce() external view returns (uint256);
+        function isOwner(address owner) external view returns (bool);
+        function getOwners() external view returns (address[] memory);
         function encodeTransactionData(
             address to,
             uint256 value,
@@ -81,7 +91,89 @@ sol! {
 
 const RPC_URL_DEFAULT: &str = "https://polygon-bor-rpc.publicnode.com";
 const RELAYER_URL_DEFAULT: &str = "https://relayer-v2.polymarket.com";
-const USDC_POLYGON: Address = address!("0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174");
+
+/// 只读 RPC 池：余额与 position-ID 推导等读请求在多个节点间轮询，写交易固定走单一节点。
+/// 读节点取 `RPC_READ_URLS`（逗号分隔），未设置时与写节点相同；写节点取调用方传入的 rpc_url、
+/// 其次 `RPC_WRITE_URL`，都没有时用默认节点。
+pub struct ReadProviderPool {
+    urls: Vec<String>,
+    next: std::sync::atomic::AtomicUsize,
+}
+
+impl ReadProviderPool {
+    /// urls 不能为空
+    pub fn new(urls: Vec<String>) -> Result<Self> {
+        if urls.is_empty() {
+            anyhow::bail!("只读 RPC 列表为空");
+        }
+        Ok(Self {
+            urls,
+            next: std::sync::atomic::AtomicUsize::new(0),
+        })
+    }
+
+    /// 从 `RPC_READ_URLS` 构建，未设置或为空时返回 None
+    pub fn from_env() -> Option<Self> {
+        let urls: Vec<String> = env::var("RPC_READ_URLS")
+            .ok()?
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        Self::new(urls).ok()
+    }
+
+    /// 轮询取下一个读节点
+    pub fn next_url(&self) -> &str {
+        let i = self.next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        &self.urls[i % self.urls.len()]
+    }
+
+    /// 连接下一个读节点
+    pub async fn connect(&self) -> Result<impl Provider + Clone> {
+        let url = self.next_url();
+        ProviderBuilder::new()
+            .connect(url)
+            .await
+            .map_err(|e| anyhow::anyhow!("连接只读 RPC 失败 {}: {}", url, e))
+    }
+
+    pub fn len(&self) -> usize {
+        self.urls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+}
+
+/// 进程内共享的只读 RPC 池（来自 RPC_READ_URLS），保证轮询在多次 merge 间延续
+static SHARED_READ_POOL: std::sync::OnceLock<Option<std::sync::Arc<ReadProviderPool>>> = std::sync::OnceLock::new();
+
+/// 写节点：调用方传入 > RPC_WRITE_URL > 默认节点
+fn write_rpc_url(rpc_url: Option<&str>) -> String {
+    rpc_url
+        .map(str::to_string)
+        .or_else(|| env::var("RPC_WRITE_URL").ok().filter(|s| !s.trim().is_empty()).map(|s| s.trim().to_string()))
+        .unwrap_or_else(|| RPC_URL_DEFAULT.to_string())
+}
+
+/// 读节点池：配置了 RPC_READ_URLS 时用共享池，否则只有写节点
+fn read_pool(write_url: &str) -> std::sync::Arc<ReadProviderPool> {
+    SHARED_READ_POOL
+        .get_or_init(|| {
+            let pool = ReadProviderPool::from_env()?;
+            info!(read_rpcs = pool.len(), "Merge 只读 RPC 池已启用");
+            Some(std::sync::Arc::new(pool))
+        })
+        .clone()
+        .unwrap_or_else(|| {
+            std::sync::Arc::new(ReadProviderPool {
+                urls: vec![write_url.to_string()],
+                next: std::sync::atomic::AtomicUsize::new(0),
+            })
+        })
+}
 
 const RELAYER_GET_RELAY_PAYLOAD: &str = "/relay-payload";
 const RELAYER_SUBMIT: &str = "/submit";
@@ -93,13 +185,425 @@ const PROXY_INIT_CODE_HASH: [u8; 32] = [
     0x86, 0x92, 0x87, 0xab, 0x0b, 0x05, 0x8b, 0xe0, 0x5a, 0xa9, 0xe8, 0xaf, 0x63, 0x30, 0xa0, 0x0b,
 ];
 const PROXY_DEFAULT_GAS: u64 = 160_000;
+/// Relayer 提交的默认最大尝试次数（MERGE_RELAYER_MAX_ATTEMPTS 可覆盖）
+const RELAYER_MAX_ATTEMPTS_DEFAULT: u32 = 3;
+/// Relayer 重试的初始退避，每次翻倍
+const RELAYER_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+/// collection/position ID 推导的默认最大尝试次数（MERGE_READ_MAX_ATTEMPTS 可覆盖）
+const READ_MAX_ATTEMPTS_DEFAULT: u32 = 3;
+/// 推导读取重试的初始退避，每次翻倍
+const READ_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+/// Safe execTransaction 估算 gas 失败时使用的 gas 上限
+const SAFE_MERGE_GAS_FALLBACK: u64 = 250_000;
+/// 估算 gas 成本用的默认 POL 价格（USD），偏高取值使判定保守
+const POL_PRICE_USD_DEFAULT: Decimal = Decimal::from_parts(5, 0, 0, false, 1);
+/// 默认最小合并数量：0.01 USDC（6 位小数），避免为粉尘份额发送交易
+const MIN_MERGE_AMOUNT_DEFAULT: u64 = 10_000;
+/// proxy 合约代码长度低于此值视为 EIP-1167 最小代理（Magic/Email），走 Relayer；否则按 Gnosis Safe 处理
+const SAFE_MIN_CODE_LEN: usize = 150;
+
+/// Merge 可调参数：ProxyFactory 地址与 proxy 合约 init-code-hash（用于 CREATE2 推导 proxy 钱包）。
+/// 默认取当前 Polymarket 部署的常量；协议升级后可通过环境变量覆盖而无需重新编译：
+/// `MERGE_PROXY_FACTORY`、`MERGE_PROXY_INIT_CODE_HASH`（32 字节 hex）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub proxy_factory: Address,
+    pub proxy_init_code_hash: [u8; 32],
+    /// 只查询余额与路径决策，不发送交易（tx_hash 为空）
+    pub dry_run: bool,
+    /// Safe execTransaction 的签名格式
+    pub signature_type: SignatureType,
+    /// Safe 路径预估 gas 成本不低于回收金额时仍强制 merge
+    pub force: bool,
+    /// 估算 gas 成本用的 POL(MATIC) 价格（USD）
+    pub pol_price_usd: Decimal,
+    /// 签名与合约地址使用的链 ID（默认 Polygon 主网；测试网/分叉链通过 `CHAIN_ID` 覆盖）
+    pub chain_id: u64,
+    /// Safe 路径广播前先 `eth_call` 模拟 execTransaction，回滚时直接返回解码后的原因而不发送交易
+    pub simulate_first: bool,
+    /// Relayer 路径的签名方式（`MERGE_RELAYER_SIGNING`），默认 EIP-191
+    pub relayer_signing: RelayerSigning,
+    /// 最小合并数量（CTF 最小单位）：min(YES, NO) 低于此值时返回 `BelowThreshold` 而不发送交易（`MERGE_MIN_AMOUNT`，USDC）
+    pub min_merge_amount: U256,
+}
+
+/// Relayer 请求签名方式：
+/// - `Eip191`：当前 Relayer 使用的 `rlx:` 前缀拼接哈希 + personal-sign（`\x19Ethereum Signed Message`）
+/// - `Eip712`：按 `RelayRequest` 结构化数据签名（domain 见 [`RELAYER_EIP712_NAME`]），供 Relayer 改用 EIP-712 后切换
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayerSigning {
+    Eip191,
+    Eip712,
+}
+
+impl RelayerSigning {
+    /// 解析 `MERGE_RELAYER_SIGNING`：eip191（或 personal）| eip712（或 typed），大小写不敏感
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "").as_str() {
+            "" | "eip191" | "personal" => Ok(RelayerSigning::Eip191),
+            "eip712" | "typed" | "typeddata" => Ok(RelayerSigning::Eip712),
+            other => anyhow::bail!("未知 Relayer 签名方式: {}（可用: eip191, eip712）", other),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelayerSigning::Eip191 => "eip191",
+            RelayerSigning::Eip712 => "eip712",
+        }
+    }
+}
+
+/// Safe 签名格式：
+/// - `Eoa`：EOA 直接是 Safe owner，65 字节 ECDSA 签名（v 修正为 27/28）
+/// - `Contract(owner)`：Safe owner 是智能合约钱包（由该 EOA 控制），按 EIP-1271 合约签名格式编码，
+///   由 Safe 调用 owner 的 isValidSignature 校验
+/// - `Auto`：发送前读取 Safe owner 判断——EOA 是 owner 则用 `Eoa`，否则取第一个有代码的 owner 用 `Contract`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureType {
+    Auto,
+    Eoa,
+    Contract(Address),
+}
+
+impl SignatureType {
+    /// 解析 `MERGE_SIGNATURE_TYPE`（auto|eoa|contract）与 `MERGE_SIGNATURE_OWNER`（contract 时必填）
+    pub fn parse(kind: &str, owner: Option<&str>) -> Result<Self> {
+        match kind.trim().to_lowercase().as_str() {
+            "" | "auto" => Ok(SignatureType::Auto),
+            "eoa" => Ok(SignatureType::Eoa),
+            "contract" | "eip1271" => {
+                let owner = owner
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("contract 签名需设置 MERGE_SIGNATURE_OWNER（owner 合约钱包地址）"))?;
+                let owner = owner
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("MERGE_SIGNATURE_OWNER 地址无效: {}", e))?;
+                Ok(SignatureType::Contract(owner))
+            }
+            other => anyhow::bail!("未知签名类型: {}（可用: auto, eoa, contract）", other),
+        }
+    }
+}
+
+/// v 为 0/1 时修正为 27/28（ecrecover 约定）
+fn fix_v(mut sig: Vec<u8>) -> Vec<u8> {
+    if sig.len() == 65 && (sig[64] == 0 || sig[64] == 1) {
+        sig[64] += 27;
+    }
+    sig
+}
+
+/// 按签名格式编码 Safe signatures 字段。EIP-1271 格式：
+/// r = owner 地址（左补零到 32 字节），s = 动态数据偏移（65），v = 0，随后是 长度(32 字节) + 内层 EOA 签名
+fn encode_safe_signature(sig: Vec<u8>, signature_type: SignatureType) -> Vec<u8> {
+    let inner = fix_v(sig);
+    match signature_type {
+        SignatureType::Contract(owner) => {
+            let mut out = Vec::with_capacity(65 + 32 + inner.len());
+            out.extend_from_slice(&[0u8; 12]);
+            out.extend_from_slice(owner.as_slice());
+            out.extend_from_slice(&U256::from(65u64).to_be_bytes::<32>());
+            out.push(0u8);
+            out.extend_from_slice(&U256::from(inner.len()).to_be_bytes::<32>());
+            out.extend_from_slice(&inner);
+            out
+        }
+        SignatureType::Auto | SignatureType::Eoa => inner,
+    }
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            proxy_factory: PROXY_FACTORY,
+            proxy_init_code_hash: PROXY_INIT_CODE_HASH,
+            dry_run: false,
+            signature_type: SignatureType::Auto,
+            force: false,
+            pol_price_usd: POL_PRICE_USD_DEFAULT,
+            chain_id: POLYGON,
+            simulate_first: true,
+            relayer_signing: RelayerSigning::Eip191,
+            min_merge_amount: U256::from(MIN_MERGE_AMOUNT_DEFAULT),
+        }
+    }
+}
+
+impl MergeOptions {
+    /// 解析 init-code-hash：可带 0x 前缀，须恰好 32 字节（64 位 hex）
+    pub fn parse_init_code_hash(s: &str) -> Result<[u8; 32]> {
+        let hex = s.trim();
+        let hex = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")).unwrap_or(hex);
+        if !hex.is_ascii() {
+            anyhow::bail!("init-code-hash 含非 ASCII 字符");
+        }
+        if hex.len() != 64 {
+            anyhow::bail!("init-code-hash 长度须为 32 字节（64 位 hex），实际 {} 位", hex.len());
+        }
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|_| anyhow::anyhow!("init-code-hash 含非法 hex 字符: {}", &hex[i * 2..i * 2 + 2]))?;
+        }
+        Ok(out)
+    }
+
+    /// 从环境变量覆盖默认值；未设置的项保持默认，格式错误时报错并指明变量名
+    pub fn from_env() -> Result<Self> {
+        let mut opts = Self::default();
+        if let Some(v) = env::var("MERGE_PROXY_FACTORY").ok().filter(|v| !v.trim().is_empty()) {
+            opts.proxy_factory = v
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("MERGE_PROXY_FACTORY 地址无效: {}", e))?;
+        }
+        if let Some(v) = env::var("MERGE_PROXY_INIT_CODE_HASH").ok().filter(|v| !v.trim().is_empty()) {
+            opts.proxy_init_code_hash = Self::parse_init_code_hash(&v)
+                .map_err(|e| anyhow::anyhow!("MERGE_PROXY_INIT_CODE_HASH 无效: {}", e))?;
+        }
+        if let Ok(v) = env::var("MERGE_FORCE") {
+            opts.force = v.trim() == "1" || v.trim().eq_ignore_ascii_case("true");
+        }
+        if let Some(v) = env::var("MERGE_POL_PRICE_USD").ok().filter(|v| !v.trim().is_empty()) {
+            opts.pol_price_usd = v
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("MERGE_POL_PRICE_USD 无效: {}", e))?;
+        }
+        if let Ok(kind) = env::var("MERGE_SIGNATURE_TYPE") {
+            opts.signature_type = SignatureType::parse(&kind, env::var("MERGE_SIGNATURE_OWNER").ok().as_deref())?;
+        }
+        if let Ok(v) = env::var("MERGE_RELAYER_SIGNING") {
+            opts.relayer_signing = RelayerSigning::parse(&v).map_err(|e| anyhow::anyhow!("MERGE_RELAYER_SIGNING 无效: {}", e))?;
+        }
+        if let Some(v) = env::var("MERGE_MIN_AMOUNT").ok().filter(|v| !v.trim().is_empty()) {
+            let usdc: Decimal = v
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("MERGE_MIN_AMOUNT 无效: {}", e))?;
+            anyhow::ensure!(!usdc.is_sign_negative(), "MERGE_MIN_AMOUNT 不能为负: {}", usdc);
+            let units = (usdc * Decimal::from(1_000_000u64)).trunc();
+            opts.min_merge_amount = U256::from(
+                u64::try_from(units).map_err(|_| anyhow::anyhow!("MERGE_MIN_AMOUNT 超出范围: {}", usdc))?,
+            );
+        }
+        if let Ok(v) = env::var("MERGE_SIMULATE_FIRST") {
+            opts.simulate_first = !(v.trim() == "0" || v.trim().eq_ignore_ascii_case("false"));
+        }
+        if let Some(v) = env::var("CHAIN_ID").ok().filter(|v| !v.trim().is_empty()) {
+            opts.chain_id = parse_chain_id(&v).map_err(|e| anyhow::anyhow!("CHAIN_ID 无效: {}", e))?;
+        }
+        Ok(opts)
+    }
+}
+
+/// 解析签名用链 ID：须为非零整数（Polygon 主网 137，Amoy 测试网 80002）
+pub fn parse_chain_id(s: &str) -> Result<u64> {
+    let chain_id: u64 = s.trim().parse().map_err(|e| anyhow::anyhow!("{}: {}", s.trim(), e))?;
+    anyhow::ensure!(chain_id != 0, "chain_id 不能为 0");
+    Ok(chain_id)
+}
+
+/// Merge 实际走的执行路径
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePath {
+    /// Gnosis Safe：EOA 签名后直接发送 execTransaction
+    GnosisSafe,
+    /// Magic/Email EIP-1167：通过 Polymarket Relayer 提交
+    Relayer,
+}
+
+impl MergePath {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergePath::GnosisSafe => "gnosis_safe",
+            MergePath::Relayer => "relayer",
+        }
+    }
+}
+
+impl std::fmt::Display for MergePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
-/// 将 0x 开头的长 hex 缩短为 `0x` + 前 8 位 + `..` + 后 6 位，便于日志。
+/// Auto 时按 Safe owner 判断签名格式：EOA 是 owner → Eoa；否则第一个有代码的 owner → Contract；
+/// 读取失败或找不到合约 owner 时回退 Eoa
+async fn resolve_safe_signature_type<P: Provider>(
+    safe: &IGnosisSafe::IGnosisSafeInstance<P>,
+    eoa: Address,
+    configured: SignatureType,
+) -> SignatureType {
+    if configured != SignatureType::Auto {
+        return configured;
+    }
+    match safe.isOwner(eoa).call().await {
+        Ok(true) => return SignatureType::Eoa,
+        Ok(false) => {}
+        Err(e) => {
+            warn!(error = %e, "读取 Safe.isOwner 失败，按 EOA 签名");
+            return SignatureType::Eoa;
+        }
+    }
+    let owners = match safe.getOwners().call().await {
+        Ok(owners) => owners,
+        Err(e) => {
+            warn!(error = %e, "读取 Safe.getOwners 失败，按 EOA 签名");
+            return SignatureType::Eoa;
+        }
+    };
+    for owner in owners {
+        if !safe.provider().get_code_at(owner).await.unwrap_or_default().is_empty() {
+            return SignatureType::Contract(owner);
+        }
+    }
+    warn!("EOA 不是 Safe owner 且未找到合约 owner，按 EOA 签名（交易可能失败）");
+    SignatureType::Eoa
+}
+
+/// `merge_max` 的返回结果
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    /// 交易哈希（Relayer 路径下若响应无哈希，则为 Relayer 原始响应体）
+    pub tx_hash: String,
+    /// 实际走的执行路径
+    pub path: MergePath,
+    /// 合并数量（CTF 最小单位，1e6 = 1 USDC）
+    pub merge_amount: U256,
+    /// Safe 路径：merge 前后 proxy 的 USDC 余额实际增量（USDC）；Relayer 路径异步上链，为 None
+    pub merged_usdc_observed: Option<Decimal>,
+    /// Safe 路径：本笔 execTransaction 使用的 Safe nonce；Relayer 路径为 None
+    pub safe_nonce: Option<U256>,
+}
+
+/// merge 的结果：已执行；因可合并数量低于阈值而跳过；或因 gas 成本不低于回收金额而跳过（仅 Safe 路径；Relayer 无 gas 成本总是执行）
+#[derive(Debug, Clone)]
+pub enum MergeOutcome {
+    Merged(MergeResult),
+    BelowThreshold {
+        /// 可合并数量 min(YES, NO)（CTF 最小单位）
+        merge_amount: U256,
+        /// 配置的最小合并数量（CTF 最小单位）
+        min_merge_amount: U256,
+    },
+    SkippedUneconomical {
+        /// 可合并数量（CTF 最小单位）
+        merge_amount: U256,
+        /// 预估回收 USDC
+        merged_usdc: Decimal,
+        /// 预估 gas 成本（USD）
+        gas_cost_usdc: Decimal,
+    },
+}
+
+impl std::fmt::Display for MergeOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeOutcome::Merged(res) => write!(f, "merged via {} tx={}", res.path, res.tx_hash),
+            MergeOutcome::BelowThreshold { merge_amount, min_merge_amount } => write!(
+                f,
+                "skipped: 可合并 {} USDC < 最小合并数量 {} USDC",
+                units_to_usdc(*merge_amount),
+                units_to_usdc(*min_merge_amount)
+            ),
+            MergeOutcome::SkippedUneconomical { merged_usdc, gas_cost_usdc, .. } => write!(
+                f,
+                "skipped: gas ≈ {:.4} USD ≥ 回收 {} USDC",
+                gas_cost_usdc, merged_usdc
+            ),
+        }
+    }
+}
+
+/// CTF/USDC 最小单位（6 位小数）转 Decimal
+pub fn units_to_usdc(units: U256) -> Decimal {
+    Decimal::from_i128_with_scale(u64::try_from(units).unwrap_or(u64::MAX) as i128, 6)
+}
+
+/// Safe 路径 gas price 默认倍数（在 eth_gasPrice 基础上上浮，避免拥堵时卡单）
+const GAS_PRICE_MULTIPLIER_DEFAULT: f64 = 1.2;
+/// Safe 路径 gas price 默认上限（gwei），防止极端行情下高价成交
+const GAS_PRICE_CAP_GWEI_DEFAULT: f64 = 1000.0;
+const GWEI: f64 = 1_000_000_000.0;
+
+/// Gnosis Safe 常见回滚码（GSxxx）的含义，便于直接定位模拟失败原因
+fn safe_revert_hint(reason: &str) -> Option<&'static str> {
+    let code = reason.trim();
+    Some(match code {
+        "GS013" => "内部调用失败（CTF mergePositions 回滚：余额不足或 calldata 错误）",
+        "GS020" | "GS021" | "GS022" | "GS023" | "GS024" | "GS025" | "GS026" => "签名无效（签名格式或 owner 不匹配，检查 MERGE_SIGNATURE_TYPE）",
+        "GS010" | "GS011" => "gas 不足",
+        _ if code.starts_with("GS") => "Safe 校验失败",
+        _ => return None,
+    })
+}
+
+/// 把 execTransaction 的 `eth_call` 错误解码为可读原因：优先取 revert 数据中的 `Error(string)`，
+/// 否则回退为原始错误文本
+fn decode_simulation_error(e: &alloy::contract::Error) -> String {
+    let reason = e
+        .as_revert_data()
+        .and_then(|data| alloy::sol_types::decode_revert_reason(&data))
+        .unwrap_or_else(|| e.to_string());
+    match safe_revert_hint(&reason) {
+        Some(hint) => format!("{}（{}）", reason, hint),
+        None => reason,
+    }
+}
+
+/// 根据 `eth_gasPrice` 给出 Safe execTransaction 使用的 gas price（wei）。
+///
+/// 结果 = 节点报价 × `MERGE_GAS_PRICE_MULTIPLIER`（默认 1.2），
+/// 且不超过 `MERGE_GAS_PRICE_CAP_GWEI`（默认 1000 gwei，0 表示不设上限）。
+pub async fn suggest_gas_price<P: Provider>(provider: &P) -> Result<u128> {
+    let node_price = provider
+        .get_gas_price()
+        .await
+        .map_err(|e| anyhow::anyhow!("读取 eth_gasPrice 失败: {}", e))?;
+    let multiplier: f64 = env::var("MERGE_GAS_PRICE_MULTIPLIER")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .filter(|m: &f64| *m > 0.0)
+        .unwrap_or(GAS_PRICE_MULTIPLIER_DEFAULT);
+    let cap_gwei: f64 = env::var("MERGE_GAS_PRICE_CAP_GWEI")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(GAS_PRICE_CAP_GWEI_DEFAULT);
+
+    let mut price = (node_price as f64 * multiplier) as u128;
+    let capped = cap_gwei > 0.0 && price as f64 > cap_gwei * GWEI;
+    if capped {
+        price = (cap_gwei * GWEI) as u128;
+    }
+    info!(
+        node_gwei = node_price as f64 / GWEI,
+        multiplier,
+        chosen_gwei = price as f64 / GWEI,
+        cap_gwei,
+        capped,
+        "⛽ Safe merge gas price: {:.2} gwei",
+        price as f64 / GWEI
+    );
+    Ok(price)
+}
+
+/// 将 hex 缩短为便于日志的形式（输入可带或不带 `0x`/`0X` 前缀）：
+///
+/// - 去前缀后长度 > 16：`0x` + 前 8 位 + `..` + 后 6 位（首尾两段不重叠，省略至少 3 位）
+/// - 长度 ≤ 16：`0x` + 完整内容（缩写省不了几位，反而会误导）
+/// - 含非 ASCII 字符（不是合法 hex）：`0x` + 原样内容，不做切片，避免在多字节字符中间切分
 pub fn short_hex(s: &str) -> String {
-    let hex = s.strip_prefix("0x").unwrap_or(s);
-    if hex.len() > 14 {
-        let lo = hex.len().saturating_sub(6);
-        format!("0x{}..{}", &hex[..8.min(hex.len())], &hex[lo..])
+    const HEAD: usize = 8;
+    const TAIL: usize = 6;
+    let hex = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    if hex.is_ascii() && hex.len() > HEAD + TAIL + 2 {
+        format!("0x{}..{}", &hex[..HEAD], &hex[hex.len() - TAIL..])
     } else {
         format!("0x{}", hex)
     }
@@ -110,8 +614,11 @@ use hmac::{Hmac, Mac};
 use sha2::Sha256;
 type HmacSha256 = Hmac<Sha256>;
 
-fn encode_merge_calldata(req: &MergePositionsRequest) -> Vec<u8> {
-    let sel = &keccak256(b"mergePositions(address,bytes32,bytes32,uint256[],uint256)")[..4];
+const MERGE_POSITIONS_SIG: &[u8] = b"mergePositions(address,bytes32,bytes32,uint256[],uint256)";
+
+/// 手写 ABI 编码 CTF `mergePositions` 调用：selector + 4 个静态参数头（partition 为偏移 160）+ partition 数组
+pub fn encode_merge_calldata(req: &MergePositionsRequest) -> Vec<u8> {
+    let sel = &keccak256(MERGE_POSITIONS_SIG)[..4];
     let mut out = Vec::from(sel);
     out.extend_from_slice(&[0u8; 12]);
     out.extend_from_slice(req.collateral_token.as_slice());
@@ -126,13 +633,93 @@ fn encode_merge_calldata(req: &MergePositionsRequest) -> Vec<u8> {
     out
 }
 
-fn derive_proxy_wallet(eoa: Address, proxy_factory: Address) -> Address {
+/// `encode_merge_calldata` 的逆过程：校验 selector、地址高位补零、partition 偏移与数组长度后还原请求。
+/// 本程序只 merge 二元市场，还原后的 parent collection 与 partition 须与 `for_binary_market` 一致，否则报错。
+pub fn decode_merge_calldata(bytes: &[u8]) -> Result<MergePositionsRequest> {
+    let word = |i: usize| -> Result<&[u8]> {
+        bytes
+            .get(4 + i * 32..4 + (i + 1) * 32)
+            .ok_or_else(|| anyhow::anyhow!("calldata 过短：缺少第 {} 个 32 字节字", i))
+    };
+    if bytes.len() < 4 || bytes[..4] != keccak256(MERGE_POSITIONS_SIG)[..4] {
+        anyhow::bail!("selector 不是 mergePositions");
+    }
+    if (bytes.len() - 4) % 32 != 0 {
+        anyhow::bail!("calldata 长度 {} 不是 4 + 32×N", bytes.len());
+    }
+    let collateral_word = word(0)?;
+    if collateral_word[..12].iter().any(|&b| b != 0) {
+        anyhow::bail!("collateral 地址高 12 字节非零");
+    }
+    let collateral = Address::from_slice(&collateral_word[12..]);
+    let parent = B256::from_slice(word(1)?);
+    let condition_id = B256::from_slice(word(2)?);
+    let offset = U256::from_be_slice(word(3)?);
+    if offset != U256::from(160u64) {
+        anyhow::bail!("partition 偏移应为 160，实际 {}", offset);
+    }
+    let amount = U256::from_be_slice(word(4)?);
+    let len = U256::from_be_slice(word(5)?);
+    let len = usize::try_from(len).map_err(|_| anyhow::anyhow!("partition 长度过大: {}", len))?;
+    if bytes.len() != 4 + (6 + len) * 32 {
+        anyhow::bail!("calldata 长度 {} 与 partition 长度 {} 不符", bytes.len(), len);
+    }
+    let partition = (0..len)
+        .map(|i| word(6 + i).map(U256::from_be_slice))
+        .collect::<Result<Vec<_>>>()?;
+
+    let req = MergePositionsRequest::for_binary_market(collateral, condition_id, amount);
+    if req.parent_collection_id != parent || req.partition != partition {
+        anyhow::bail!(
+            "非二元市场 merge：parent={} partition={:?}（期望 parent={} partition={:?}）",
+            parent,
+            partition,
+            req.parent_collection_id,
+            req.partition
+        );
+    }
+    Ok(req)
+}
+
+/// merge 请求的可读形式，用于日志与排查
+pub fn describe_merge_request(req: &MergePositionsRequest) -> String {
+    let partition: Vec<String> = req.partition.iter().map(|p| p.to_string()).collect();
+    format!(
+        "mergePositions(collateral={}, parent={}, condition={}, partition=[{}], amount={})",
+        req.collateral_token,
+        req.parent_collection_id,
+        req.condition_id,
+        partition.join(","),
+        req.amount
+    )
+}
+
+/// calldata 的 0x 前缀小写 hex
+pub fn merge_calldata_hex(bytes: &[u8]) -> String {
+    to_hex_0x(bytes)
+}
+
+/// 用默认工厂与 init code hash 推导 EOA 对应的 proxy 钱包地址（Email/Magic 账户），
+/// 用于启动前核对 `POLYMARKET_PROXY_ADDRESS`。
+pub fn derived_proxy_for(eoa: Address) -> Address {
+    derive_proxy_wallet(eoa, PROXY_FACTORY, &PROXY_INIT_CODE_HASH)
+}
+
+/// 从私钥得到 (EOA, 推导的 proxy 地址)
+pub fn derived_proxy_for_key(private_key: &str) -> Result<(Address, Address)> {
+    let eoa = LocalSigner::from_str(private_key)
+        .map_err(|e| anyhow::anyhow!("私钥格式无效: {}", e))?
+        .address();
+    Ok((eoa, derived_proxy_for(eoa)))
+}
+
+fn derive_proxy_wallet(eoa: Address, proxy_factory: Address, init_code_hash: &[u8; 32]) -> Address {
     let salt = keccak256(eoa.as_slice());
     let mut buf = [0u8; 1 + 20 + 32 + 32];
     buf[0] = 0xff;
     buf[1..21].copy_from_slice(proxy_factory.as_slice());
     buf[21..53].copy_from_slice(salt.as_slice());
-    buf[53..85].copy_from_slice(&PROXY_INIT_CODE_HASH);
+    buf[53..85].copy_from_slice(init_code_hash);
     let h = keccak256(buf);
     Address::from_slice(&h.as_slice()[12..32])
 }
@@ -148,6 +735,118 @@ fn to_hex_0x(b: &[u8]) -> String {
     s
 }
 
+/// 转为标准 Base64：URL-safe 字符（-_）替换为 +/，并补齐 '=' 填充
+fn normalize_b64_standard(s: &str) -> String {
+    let mut out = s.trim().trim_end_matches('=').replace('-', "+").replace('_', "/");
+    while out.len() % 4 != 0 {
+        out.push('=');
+    }
+    out
+}
+
+/// 转为 Base64URL：+/ 替换为 -_，保留 '=' 填充
+fn normalize_b64_urlsafe(s: &str) -> String {
+    normalize_b64_standard(s).replace('+', "-").replace('/', "_")
+}
+
+/// 只允许可直接放进 HTTP 头的可见 ASCII 字符
+fn is_header_safe(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| (0x21..=0x7e).contains(&b))
+}
+
+/// Relayer 的 Builder 凭证（POLY_BUILDER_API_KEY / SECRET / PASSPHRASE），已校验并规范化。
+///
+/// - SECRET：接受标准 Base64 与 Base64URL（可缺省填充），解码为 HMAC 密钥
+/// - PASSPHRASE：默认原样发送；`POLY_BUILDER_PASSPHRASE_ENCODING=standard|urlsafe`
+///   时按与 SECRET 相同的规则规范为标准 Base64 / Base64URL 后发送（须能正确解码）
+#[derive(Clone)]
+pub struct BuilderCreds {
+    pub api_key: String,
+    pub secret: Vec<u8>,
+    pub passphrase: String,
+}
+
+impl std::fmt::Debug for BuilderCreds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BuilderCreds")
+            .field("api_key", &self.api_key)
+            .field("secret", &"***")
+            .field("passphrase", &"***")
+            .finish()
+    }
+}
+
+impl BuilderCreds {
+    /// 校验并规范化三项凭证；错误信息指明具体是哪一项有问题
+    pub fn parse(api_key: &str, secret: &str, passphrase: &str, passphrase_encoding: &str) -> Result<Self> {
+        let api_key = api_key.trim();
+        if !is_header_safe(api_key) {
+            anyhow::bail!("POLY_BUILDER_API_KEY 格式无效：不能为空，且只能包含可见 ASCII 字符（检查是否有空格或引号）");
+        }
+        let secret = base64::engine::general_purpose::STANDARD
+            .decode(normalize_b64_standard(secret))
+            .map_err(|e| anyhow::anyhow!("POLY_BUILDER_SECRET base64 解码失败: {}", e))?;
+        if secret.is_empty() {
+            anyhow::bail!("POLY_BUILDER_SECRET 为空");
+        }
+        let passphrase = passphrase.trim();
+        let passphrase = match passphrase_encoding.trim().to_lowercase().as_str() {
+            "" | "raw" => passphrase.to_string(),
+            enc @ ("standard" | "urlsafe") => {
+                let standard = normalize_b64_standard(passphrase);
+                base64::engine::general_purpose::STANDARD.decode(&standard).map_err(|e| {
+                    anyhow::anyhow!("POLY_BUILDER_PASSPHRASE 不是有效的 base64（POLY_BUILDER_PASSPHRASE_ENCODING={}）: {}", enc, e)
+                })?;
+                if enc == "standard" {
+                    standard
+                } else {
+                    normalize_b64_urlsafe(passphrase)
+                }
+            }
+            other => anyhow::bail!(
+                "POLY_BUILDER_PASSPHRASE_ENCODING 无效: {}（可选 raw | standard | urlsafe）",
+                other
+            ),
+        };
+        if !is_header_safe(&passphrase) {
+            anyhow::bail!("POLY_BUILDER_PASSPHRASE 格式无效：不能为空，且只能包含可见 ASCII 字符（检查是否有空格或引号）");
+        }
+        Ok(Self {
+            api_key: api_key.to_string(),
+            secret,
+            passphrase,
+        })
+    }
+
+    /// 从环境变量读取。三项都未设置时返回 Ok(None)（不使用 Relayer）；
+    /// 只设置了部分或格式错误时返回指明具体变量的错误。
+    pub fn from_env() -> Result<Option<Self>> {
+        let read = |k: &str| env::var(k).ok().filter(|v| !v.trim().is_empty());
+        let key = read("POLY_BUILDER_API_KEY");
+        let secret = read("POLY_BUILDER_SECRET");
+        let passphrase = read("POLY_BUILDER_PASSPHRASE");
+        match (key, secret, passphrase) {
+            (None, None, None) => Ok(None),
+            (Some(k), Some(s), Some(p)) => {
+                let enc = env::var("POLY_BUILDER_PASSPHRASE_ENCODING").unwrap_or_default();
+                Self::parse(&k, &s, &p, &enc).map(Some)
+            }
+            (k, s, p) => {
+                let missing: Vec<&str> = [
+                    ("POLY_BUILDER_API_KEY", k.is_none()),
+                    ("POLY_BUILDER_SECRET", s.is_none()),
+                    ("POLY_BUILDER_PASSPHRASE", p.is_none()),
+                ]
+                .into_iter()
+                .filter(|(_, m)| *m)
+                .map(|(n, _)| n)
+                .collect();
+                anyhow::bail!("Builder 凭证不完整，缺少: {}", missing.join("、"))
+            }
+        }
+    }
+}
+
 fn build_hmac_signature(secret: &[u8], timestamp: u64, method: &str, path: &str, body: &str) -> String {
     let msg = format!("{}{}{}{}", timestamp, method, path, body);
     let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC key");
@@ -203,7 +902,8 @@ fn create_struct_hash(
     nonce: &str,
     relay_hub: Address,
     relay: Address,
-) -> B256 {
+) -> Result<B256> {
+    let n = parse_relay_nonce(nonce)?;
     let mut buf = Vec::new();
     buf.extend_from_slice(b"rlx:");
     buf.extend_from_slice(from.as_slice());
@@ -212,11 +912,18 @@ fn create_struct_hash(
     buf.extend_from_slice(&U256::from(tx_fee).to_be_bytes::<32>());
     buf.extend_from_slice(&U256::from(gas_price).to_be_bytes::<32>());
     buf.extend_from_slice(&U256::from(gas_limit).to_be_bytes::<32>());
-    let n: u64 = nonce.parse().unwrap_or(0);
     buf.extend_from_slice(&U256::from(n).to_be_bytes::<32>());
     buf.extend_from_slice(relay_hub.as_slice());
     buf.extend_from_slice(relay.as_slice());
-    keccak256(buf)
+    Ok(keccak256(buf))
+}
+
+/// Relayer 返回的 nonce 必须是十进制整数；按 0 签名会被 Relayer 拒绝或重放旧 nonce，直接报错
+fn parse_relay_nonce(nonce: &str) -> Result<u64> {
+    nonce
+        .trim()
+        .parse()
+        .map_err(|e| anyhow::anyhow!("relayer nonce 无效 {:?}: {}", nonce, e))
 }
 
 fn eip191_hash(struct_hash: B256) -> B256 {
@@ -225,17 +932,139 @@ fn eip191_hash(struct_hash: B256) -> B256 {
     keccak256(msg)
 }
 
+/// EIP-712 domain 名称与版本，verifyingContract 为 RelayHub；Relayer 公布的 domain 不同时改这里
+pub const RELAYER_EIP712_NAME: &str = "Polymarket Relayer";
+pub const RELAYER_EIP712_VERSION: &str = "1";
+const RELAY_REQUEST_TYPE: &[u8] = b"RelayRequest(address from,address to,bytes data,uint256 txFee,uint256 gasPrice,uint256 gasLimit,uint256 nonce,address relayHub,address relay)";
+const EIP712_DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+
+/// 地址左补零到 32 字节（ABI 编码）
+fn word_address(a: Address) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[12..].copy_from_slice(a.as_slice());
+    w
+}
+
+/// 与 [`create_struct_hash`] 字段相同的 EIP-712 结构哈希：
+/// keccak256(typeHash ‖ from ‖ to ‖ keccak256(data) ‖ txFee ‖ gasPrice ‖ gasLimit ‖ nonce ‖ relayHub ‖ relay)
+fn create_struct_hash_712(
+    from: Address,
+    to: Address,
+    data: &[u8],
+    tx_fee: u64,
+    gas_price: u64,
+    gas_limit: u64,
+    nonce: &str,
+    relay_hub: Address,
+    relay: Address,
+) -> Result<B256> {
+    let n = parse_relay_nonce(nonce)?;
+    let mut buf = Vec::with_capacity(32 * 10);
+    buf.extend_from_slice(keccak256(RELAY_REQUEST_TYPE).as_slice());
+    buf.extend_from_slice(&word_address(from));
+    buf.extend_from_slice(&word_address(to));
+    buf.extend_from_slice(keccak256(data).as_slice());
+    for v in [tx_fee, gas_price, gas_limit, n] {
+        buf.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+    }
+    buf.extend_from_slice(&word_address(relay_hub));
+    buf.extend_from_slice(&word_address(relay));
+    Ok(keccak256(buf))
+}
+
+/// EIP-712 domain separator（name/version 见常量，verifyingContract = RelayHub）
+fn relayer_domain_separator(chain_id: u64) -> B256 {
+    let mut buf = Vec::with_capacity(32 * 5);
+    buf.extend_from_slice(keccak256(EIP712_DOMAIN_TYPE).as_slice());
+    buf.extend_from_slice(keccak256(RELAYER_EIP712_NAME.as_bytes()).as_slice());
+    buf.extend_from_slice(keccak256(RELAYER_EIP712_VERSION.as_bytes()).as_slice());
+    buf.extend_from_slice(&U256::from(chain_id).to_be_bytes::<32>());
+    buf.extend_from_slice(&word_address(RELAY_HUB));
+    keccak256(buf)
+}
+
+/// 待签名哈希：keccak256(0x19 0x01 ‖ domainSeparator ‖ structHash)
+fn eip712_hash(domain_separator: B256, struct_hash: B256) -> B256 {
+    let mut msg = Vec::with_capacity(66);
+    msg.extend_from_slice(b"\x19\x01");
+    msg.extend_from_slice(domain_separator.as_slice());
+    msg.extend_from_slice(struct_hash.as_slice());
+    keccak256(msg)
+}
+
+/// 只读 RPC 调用失败时按 500ms、1s、2s… 退避重试，最多 MERGE_READ_MAX_ATTEMPTS 次（默认3）。
+/// 仅用于纯读（collection/position ID 推导），重试总是安全的。
+async fn retry_read<T, E, F, Fut>(what: &str, mut call: F) -> Result<T>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = std::result::Result<T, E>>,
+{
+    let max_attempts: u32 = env::var("MERGE_READ_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .filter(|n: &u32| *n > 0)
+        .unwrap_or(READ_MAX_ATTEMPTS_DEFAULT);
+    let mut backoff = READ_RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt < max_attempts => {
+                warn!(attempt, max_attempts, error = %e, "{} 读取失败，{}ms 后重试", what, backoff.as_millis());
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => anyhow::bail!("{} 读取失败（已尝试 {} 次）: {}", what, max_attempts, e),
+        }
+    }
+}
+
+/// Relayer 请求用的 HTTP 客户端：连接/总超时取 CLOB_CONNECT_TIMEOUT_MS / CLOB_REQUEST_TIMEOUT_MS（默认 5s / 15s）
+fn http_client() -> Result<reqwest::Client> {
+    let ms = |key: &str, default: u64| {
+        env::var(key).ok().and_then(|s| s.trim().parse().ok()).unwrap_or(default)
+    };
+    reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_millis(ms("CLOB_CONNECT_TIMEOUT_MS", 5_000)))
+        .timeout(std::time::Duration::from_millis(ms("CLOB_REQUEST_TIMEOUT_MS", 15_000)))
+        .build()
+        .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))
+}
+
+/// Relayer 响应是否表示该签名载荷已提交过（重试时前一次请求实际已被接收）
+fn relayer_already_submitted(body: &str) -> bool {
+    let lower = body.to_lowercase();
+    ["already submitted", "already exists", "already known", "duplicate"]
+        .iter()
+        .any(|p| lower.contains(p))
+}
+
+/// 从 Relayer 响应体解析交易哈希
+fn relayer_tx_hash(body: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(body).ok()?;
+    json.get("transactionHash")
+        .or_else(|| json.get("transaction_hash"))
+        .and_then(|v| v.as_str())
+        .map(String::from)
+}
+
+/// 提交 Relayer merge。签名载荷只生成一次，重试复用同一 body（同一 nonce + 签名），
+/// 因此 Relayer 至多执行一次；5xx / 429 / 网络错误 / 无响应体的 4xx 按指数退避重试，
+/// 带响应体的 4xx 视为明确的客户端错误直接返回；"already submitted" 类响应视为成功。
 async fn relayer_execute_merge(
     merge_calldata: &[u8],
     ctf_address: Address,
     proxy_wallet: Address,
     signer: &impl alloy::signers::Signer,
-    builder_key: &str,
-    builder_secret: &str,
-    builder_passphrase: &str,
+    creds: &BuilderCreds,
     relayer_url: &str,
+    proxy_factory: Address,
+    signing: RelayerSigning,
+    chain_id: u64,
 ) -> Result<String> {
-    let client = reqwest::Client::new();
+    let client = http_client()?;
     let eoa = signer.address();
     let base = relayer_url.trim_end_matches('/');
 
@@ -247,16 +1076,21 @@ async fn relayer_execute_merge(
         .unwrap_or(PROXY_DEFAULT_GAS);
 
     if env::var("MERGE_PROXY_TO").map(|s| s.trim().eq_ignore_ascii_case("PROXY_WALLET")).unwrap_or(false) {
-        info!("ℹ️ MERGE_PROXY_TO=PROXY_WALLET 已忽略，使用 to=PROXY_FACTORY");
+        info!("ℹ️ MERGE_PROXY_TO=PROXY_WALLET 已忽略，使用 to=ProxyFactory ({:#x})", proxy_factory);
     }
-    let to = PROXY_FACTORY;
-    let struct_hash = create_struct_hash(eoa, to, &proxy_data, 0, 0, gas_limit, &nonce, RELAY_HUB, relay);
-    let to_sign = eip191_hash(struct_hash);
+    let to = proxy_factory;
+    let to_sign = match signing {
+        RelayerSigning::Eip191 => {
+            eip191_hash(create_struct_hash(eoa, to, &proxy_data, 0, 0, gas_limit, &nonce, RELAY_HUB, relay)?)
+        }
+        RelayerSigning::Eip712 => eip712_hash(
+            relayer_domain_separator(chain_id),
+            create_struct_hash_712(eoa, to, &proxy_data, 0, 0, gas_limit, &nonce, RELAY_HUB, relay)?,
+        ),
+    };
+    debug!(signing = signing.as_str(), "Relayer 请求签名");
     let sig = signer.sign_hash(&to_sign).await.map_err(|e| anyhow::anyhow!("EOA 签名失败: {}", e))?;
-    let mut sig_bytes = sig.as_bytes().to_vec();
-    if sig_bytes.len() == 65 && (sig_bytes[64] == 0 || sig_bytes[64] == 1) {
-        sig_bytes[64] += 27;
-    }
+    let sig_bytes = fix_v(sig.as_bytes().to_vec());
     let signature_hex = to_hex_0x(&sig_bytes);
 
     let signature_params = serde_json::json!({
@@ -281,40 +1115,67 @@ async fn relayer_execute_merge(
 
     let path = RELAYER_SUBMIT;
     let method = "POST";
-    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_millis() as u64;
-    // 支持标准 Base64 (+/) 与 Base64URL (-_) 两种格式
-    let secret_b64 = builder_secret
-        .trim()
-        .replace('-', "+")
-        .replace('_', "/");
-    let secret_bytes = base64::engine::general_purpose::STANDARD
-        .decode(&secret_b64)
-        .map_err(|e| anyhow::anyhow!("POLY_BUILDER_SECRET base64 解码失败: {}", e))?;
-    let sig_hmac = build_hmac_signature(&secret_bytes, timestamp, method, path, &body_str);
-
     let url = format!("{}{}", base, path);
-    let resp = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .header("POLY_BUILDER_API_KEY", builder_key)
-        .header("POLY_BUILDER_TIMESTAMP", timestamp.to_string())
-        .header("POLY_BUILDER_PASSPHRASE", builder_passphrase)
-        .header("POLY_BUILDER_SIGNATURE", sig_hmac)
-        .body(body_str)
-        .send()
-        .await?;
-    let status = resp.status();
-    let text = resp.text().await?;
-    if !status.is_success() {
-        anyhow::bail!("Relayer 请求失败 status={} body={}", status, text);
+    let max_attempts: u32 = env::var("MERGE_RELAYER_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .filter(|n: &u32| *n > 0)
+        .unwrap_or(RELAYER_MAX_ATTEMPTS_DEFAULT);
+    let mut backoff = RELAYER_RETRY_BACKOFF;
+    let mut last_err = String::new();
+
+    for attempt in 1..=max_attempts {
+        if attempt > 1 {
+            warn!(attempt, max_attempts, "Relayer 提交重试（同一签名载荷），{}ms 后发送", backoff.as_millis());
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        // 仅 HMAC 头随时间戳更新，body（含 EOA 签名与 nonce）保持不变
+        let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_millis() as u64;
+        // SECRET 已在 BuilderCreds::parse 中按标准 Base64 / Base64URL 解码
+        let sig_hmac = build_hmac_signature(&creds.secret, timestamp, method, path, &body_str);
+
+        let sent = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .header("POLY_BUILDER_API_KEY", &creds.api_key)
+            .header("POLY_BUILDER_TIMESTAMP", timestamp.to_string())
+            .header("POLY_BUILDER_PASSPHRASE", &creds.passphrase)
+            .header("POLY_BUILDER_SIGNATURE", sig_hmac)
+            .body(body_str.clone())
+            .send()
+            .await;
+        let resp = match sent {
+            Ok(resp) => resp,
+            Err(e) => {
+                last_err = format!("Relayer 请求发送失败: {}", e);
+                continue;
+            }
+        };
+        let status = resp.status();
+        let text = match resp.text().await {
+            Ok(t) => t,
+            Err(e) => {
+                last_err = format!("Relayer 响应读取失败 status={}: {}", status, e);
+                continue;
+            }
+        };
+        if status.is_success() {
+            return Ok(relayer_tx_hash(&text).unwrap_or(text));
+        }
+        if attempt > 1 && relayer_already_submitted(&text) {
+            info!(status = %status, "Relayer 返回已提交，视为前一次请求成功: {}", text);
+            return Ok(relayer_tx_hash(&text).unwrap_or(text));
+        }
+        let transient = status.is_server_error()
+            || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || text.trim().is_empty();
+        if !transient {
+            anyhow::bail!("Relayer 请求失败 status={} body={}", status, text);
+        }
+        last_err = format!("Relayer 请求失败 status={} body={}", status, text);
     }
-    let json: serde_json::Value = serde_json::from_str(&text)?;
-    let hash = json
-        .get("transactionHash")
-        .or_else(|| json.get("transaction_hash"))
-        .and_then(|v| v.as_str())
-        .map(String::from);
-    Ok(hash.unwrap_or_else(|| text))
+    anyhow::bail!("Relayer 提交 {} 次均失败，最后错误: {}", max_attempts, last_err)
 }
 
 /// 对指定 `condition_id` 在 `proxy` 上合并最大可用 YES+NO 为 USDC。
@@ -324,38 +1185,175 @@ async fn relayer_execute_merge(
 /// - `condition_id`: 市场的 condition ID（32 字节十六进制）
 /// - `proxy`: Proxy 地址（Gnosis Safe 或 EIP-1167）
 /// - `private_key`: EOA 私钥
-/// - `rpc_url`: Polygon RPC，`None` 时用 `https://polygon-rpc.com`
+/// - `rpc_url`: Polygon 写交易 RPC，`None` 时取 `RPC_WRITE_URL` 或默认节点；读请求见 [`ReadProviderPool`]
+/// - `chain_id`: 签名与合约地址使用的链 ID，主网传 `POLYGON`（覆盖 [`MergeOptions::chain_id`]）
 ///
 /// Magic/Email 路径会从环境变量读取：`POLY_BUILDER_API_KEY`、`POLY_BUILDER_SECRET`、`POLY_BUILDER_PASSPHRASE`、`RELAYER_URL`（可选）。
 ///
-/// 返回交易哈希（十六进制字符串）。
+/// ProxyFactory 与 init-code-hash 取 [`MergeOptions::from_env`]（未设置时为内置默认值）。
+///
+/// 返回 [`MergeOutcome`]：`Merged` 含交易哈希与实际执行路径（Safe / Relayer）；Safe 路径 gas 成本不低于回收金额且未设置 force 时返回 `SkippedUneconomical`。
+#[tracing::instrument(name = "merge_max", skip_all, fields(condition_id = %condition_id, proxy = %proxy))]
 pub async fn merge_max(
     condition_id: B256,
     proxy: Address,
     private_key: &str,
     rpc_url: Option<&str>,
-) -> Result<String> {
-    let rpc = rpc_url.unwrap_or(RPC_URL_DEFAULT);
-    let chain = POLYGON;
+    chain_id: u64,
+) -> Result<MergeOutcome> {
+    let opts = MergeOptions {
+        chain_id,
+        ..MergeOptions::from_env()?
+    };
+    merge_max_with(condition_id, proxy, private_key, rpc_url, &opts).await
+}
+
+/// 同 [`merge_max`]，显式传入 [`MergeOptions`]。
+pub async fn merge_max_with(
+    condition_id: B256,
+    proxy: Address,
+    private_key: &str,
+    rpc_url: Option<&str>,
+    opts: &MergeOptions,
+) -> Result<MergeOutcome> {
+    merge_max_inner(condition_id, proxy, private_key, rpc_url, opts, None).await
+}
+
+/// 批量 merge 同一 proxy 下的多个市场，按输入顺序返回每个 condition 的结果。
+///
+/// - Gnosis Safe 路径：串行执行，首笔从链上读取 nonce，之后本地递增（避免节点延迟返回旧 nonce 导致冲突）；
+///   任一笔失败后下一笔重新从链上读取 nonce。
+/// - Relayer 路径：Relayer 自行排队，最多 `concurrency` 笔并发（0 视为 1）。
+pub async fn merge_many(
+    condition_ids: &[B256],
+    proxy: Address,
+    private_key: &str,
+    rpc_url: Option<&str>,
+    opts: &MergeOptions,
+    concurrency: usize,
+) -> Vec<(B256, Result<MergeOutcome>)> {
+    use futures::StreamExt as _;
+
+    if condition_ids.is_empty() {
+        return Vec::new();
+    }
+    let code = match read_pool(&write_rpc_url(rpc_url)).connect().await {
+        Ok(p) => p.get_code_at(proxy).await.map_err(|e| format!("读取 proxy 合约代码失败 {}: {}", proxy, e)),
+        Err(e) => Err(format!("连接 RPC 失败: {}", e)),
+    };
+    let is_safe = match code {
+        Ok(code) => code.len() >= SAFE_MIN_CODE_LEN,
+        Err(msg) => return condition_ids.iter().map(|c| (*c, Err(anyhow::anyhow!("{}", msg)))).collect(),
+    };
+
+    if is_safe {
+        let out = merge_safe_sequential(condition_ids, |condition_id, nonce| {
+            merge_max_inner(condition_id, proxy, private_key, rpc_url, opts, nonce)
+        })
+        .await;
+        info!(merge.path = MergePath::GnosisSafe.as_str(), "批量 Merge 完成（Safe 串行）| 市场数:{}", out.len());
+        out
+    } else {
+        let out: Vec<_> = futures::stream::iter(condition_ids.iter().copied())
+            .map(|condition_id| async move {
+                (condition_id, merge_max_inner(condition_id, proxy, private_key, rpc_url, opts, None).await)
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+        info!(merge.path = MergePath::Relayer.as_str(), concurrency = concurrency.max(1), "批量 Merge 完成（Relayer）| 市场数:{}", out.len());
+        out
+    }
+}
+
+/// Safe 串行 merge：`merge_one(condition_id, nonce)` 的 nonce 为 None 时从链上读取。
+/// 成功后下一笔用本笔 nonce + 1；跳过（未发送交易）时沿用；失败后重置为 None。
+async fn merge_safe_sequential<F, Fut>(condition_ids: &[B256], mut merge_one: F) -> Vec<(B256, Result<MergeOutcome>)>
+where
+    F: FnMut(B256, Option<U256>) -> Fut,
+    Fut: std::future::Future<Output = Result<MergeOutcome>>,
+{
+    let mut next_nonce: Option<U256> = None;
+    let mut out = Vec::with_capacity(condition_ids.len());
+    for &condition_id in condition_ids {
+        let result = merge_one(condition_id, next_nonce).await;
+        next_nonce = match &result {
+            Ok(MergeOutcome::Merged(res)) => res.safe_nonce.map(|n| n + U256::from(1)),
+            // 跳过未发送交易，nonce 未消耗
+            Ok(MergeOutcome::SkippedUneconomical { .. } | MergeOutcome::BelowThreshold { .. }) => next_nonce,
+            Err(_) => None,
+        };
+        out.push((condition_id, result));
+    }
+    out
+}
+
+/// 读取 `owner` 在 CTF 合约上各 token 的 ERC1155 余额（最小单位，1e6 = 1 份），顺序与 `token_ids` 一致。
+/// CTF 合约地址按 `chain_id` 取自 SDK 合约配置；读请求走只读 RPC 池。
+pub async fn ctf_balances(owner: Address, token_ids: &[U256], rpc_url: Option<&str>, chain_id: u64) -> Result<Vec<U256>> {
+    let reads = read_pool(&write_rpc_url(rpc_url));
+    let config = contract_config(chain_id, false).ok_or_else(|| anyhow::anyhow!("不支持的 chain_id: {}", chain_id))?;
+    let erc1155 = IERC1155Balance::new(config.conditional_tokens, reads.connect().await?);
+    let mut out = Vec::with_capacity(token_ids.len());
+    for &id in token_ids {
+        let bal = erc1155
+            .balanceOf(owner, id)
+            .call()
+            .await
+            .map_err(|e| anyhow::anyhow!("读取 ERC1155 余额失败 token_id={}: {}", id, e))?;
+        out.push(bal);
+    }
+    Ok(out)
+}
+
+/// 读取 `owner` 的抵押品（USDC.e）余额（USDC）；抵押品地址按 `chain_id` 取自 SDK 合约配置，读请求走只读 RPC 池
+pub async fn usdc_balance(owner: Address, rpc_url: Option<&str>, chain_id: u64) -> Result<Decimal> {
+    let reads = read_pool(&write_rpc_url(rpc_url));
+    let config = contract_config(chain_id, false).ok_or_else(|| anyhow::anyhow!("不支持的 chain_id: {}", chain_id))?;
+    let erc20 = IERC20Balance::new(config.collateral, reads.connect().await?);
+    let units = erc20
+        .balanceOf(owner)
+        .call()
+        .await
+        .map_err(|e| anyhow::anyhow!("读取 USDC 余额失败 owner={}: {}", owner, e))?;
+    Ok(units_to_usdc(units))
+}
+
+/// merge 主流程；`safe_nonce` 为 Some 时 Safe 路径使用该 nonce 而不从链上读取
+async fn merge_max_inner(
+    condition_id: B256,
+    proxy: Address,
+    private_key: &str,
+    rpc_url: Option<&str>,
+    opts: &MergeOptions,
+    safe_nonce: Option<U256>,
+) -> Result<MergeOutcome> {
+    let write_url = write_rpc_url(rpc_url);
+    let reads = read_pool(&write_url);
+    let chain = opts.chain_id;
+    anyhow::ensure!(chain != 0, "chain_id 不能为 0");
     let signer = LocalSigner::from_str(private_key)?.with_chain_id(Some(chain));
     let wallet = signer.address();
 
-    let provider = ProviderBuilder::new().wallet(signer.clone()).connect(rpc).await?;
-    let client = Client::new(provider.clone(), chain)?;
+    // 写交易固定走 write_url；余额与 position-ID 读取在只读池中轮询
+    let provider = ProviderBuilder::new().wallet(signer.clone()).connect(&write_url).await?;
     let config = contract_config(chain, false).ok_or_else(|| anyhow::anyhow!("不支持的 chain_id: {}", chain))?;
-    let prov_read = ProviderBuilder::new().connect(rpc).await?;
-    let erc1155 = IERC1155Balance::new(config.conditional_tokens, prov_read);
+    let collateral = config.collateral;
+    let erc20 = IERC20Balance::new(collateral, reads.connect().await?);
+    let erc1155 = IERC1155Balance::new(config.conditional_tokens, reads.connect().await?);
     let ctf = config.conditional_tokens;
+    let yes_client = Client::new(reads.connect().await?, chain)?;
+    let no_client = Client::new(reads.connect().await?, chain)?;
 
     let req_col_yes = CollectionIdRequest::builder().parent_collection_id(B256::ZERO).condition_id(condition_id).index_set(U256::from(1)).build();
     let req_col_no = CollectionIdRequest::builder().parent_collection_id(B256::ZERO).condition_id(condition_id).index_set(U256::from(2)).build();
-    let col_yes = client.collection_id(&req_col_yes).await?;
-    let col_no = client.collection_id(&req_col_no).await?;
+    let col_yes = retry_read("YES collection_id", || yes_client.collection_id(&req_col_yes)).await?;
+    let col_no = retry_read("NO collection_id", || no_client.collection_id(&req_col_no)).await?;
 
-    let req_pos_yes = PositionIdRequest::builder().collateral_token(USDC_POLYGON).collection_id(col_yes.collection_id).build();
-    let req_pos_no = PositionIdRequest::builder().collateral_token(USDC_POLYGON).collection_id(col_no.collection_id).build();
-    let pos_yes = client.position_id(&req_pos_yes).await?;
-    let pos_no = client.position_id(&req_pos_no).await?;
+    let req_pos_yes = PositionIdRequest::builder().collateral_token(collateral).collection_id(col_yes.collection_id).build();
+    let req_pos_no = PositionIdRequest::builder().collateral_token(collateral).collection_id(col_no.collection_id).build();
+    let pos_yes = retry_read("YES position_id", || yes_client.position_id(&req_pos_yes)).await?;
+    let pos_no = retry_read("NO position_id", || no_client.position_id(&req_pos_no)).await?;
 
     let b_yes: U256 = erc1155.balanceOf(proxy, pos_yes.position_id).call().await.unwrap_or(U256::ZERO);
     let b_no: U256 = erc1155.balanceOf(proxy, pos_no.position_id).call().await.unwrap_or(U256::ZERO);
@@ -364,15 +1362,46 @@ pub async fn merge_max(
     if merge_amount == U256::ZERO {
         anyhow::bail!("无可用份额可 merge：YES={} NO={}，至少一方为 0。", b_yes, b_no);
     }
+    if merge_amount < opts.min_merge_amount {
+        info!("⏭️ 可合并数量低于阈值：YES={} NO={} | 最小合并数量:{}", b_yes, b_no, opts.min_merge_amount);
+        return Ok(MergeOutcome::BelowThreshold { merge_amount, min_merge_amount: opts.min_merge_amount });
+    }
     info!("🔄 合并数量: {} ({} USDC)", merge_amount, merge_amount / U256::from(1_000_000));
 
-    let merge_req = MergePositionsRequest::for_binary_market(USDC_POLYGON, condition_id, merge_amount);
+    let merge_req = MergePositionsRequest::for_binary_market(collateral, condition_id, merge_amount);
     let merge_calldata = encode_merge_calldata(&merge_req);
-    let code = provider.get_code_at(proxy).await.unwrap_or_default();
+    debug!("merge calldata | {} | {}", describe_merge_request(&merge_req), merge_calldata_hex(&merge_calldata));
+    let code = provider
+        .get_code_at(proxy)
+        .await
+        .map_err(|e| anyhow::anyhow!("读取 proxy 合约代码失败 {}: {}", proxy, e))?;
 
-    if code.len() < 150 {
-        let derived = derive_proxy_wallet(wallet, PROXY_FACTORY);
+    if opts.dry_run {
+        let path = if code.len() < SAFE_MIN_CODE_LEN { MergePath::Relayer } else { MergePath::GnosisSafe };
+        info!(merge.path = path.as_str(), proxy_code_len = code.len(), "🧪 Dry-run：YES={} NO={}，不发送交易", b_yes, b_no);
+        return Ok(MergeOutcome::Merged(MergeResult {
+            tx_hash: String::new(),
+            path,
+            merge_amount,
+            merged_usdc_observed: None,
+            safe_nonce: None,
+        }));
+    }
+
+    if code.len() < SAFE_MIN_CODE_LEN {
+        let derived = derive_proxy_wallet(wallet, opts.proxy_factory, &opts.proxy_init_code_hash);
         let try_anyway = env::var("MERGE_TRY_ANYWAY").map(|s| s.trim() == "1" || s.trim().eq_ignore_ascii_case("true")).unwrap_or(false);
+        info!(
+            merge.path = MergePath::Relayer.as_str(),
+            proxy_code_len = code.len(),
+            safe_min_code_len = SAFE_MIN_CODE_LEN,
+            configured_proxy = %proxy,
+            derived_proxy = %derived,
+            derived_matches = derived == proxy,
+            try_anyway,
+            "Merge 路径决策：proxy 代码长度 < {}，按 EIP-1167 走 Relayer",
+            SAFE_MIN_CODE_LEN
+        );
         if derived != proxy {
             if !try_anyway {
                 anyhow::bail!(
@@ -383,30 +1412,61 @@ pub async fn merge_max(
             }
             warn!("MERGE_TRY_ANYWAY=1：derive != proxy，仍发 Relayer 请求。");
         }
-        let builder_key = env::var("POLY_BUILDER_API_KEY").ok();
-        let builder_secret = env::var("POLY_BUILDER_SECRET").ok();
-        let builder_passphrase = env::var("POLY_BUILDER_PASSPHRASE").ok();
+        if let SignatureType::Contract(owner) = opts.signature_type {
+            anyhow::bail!(
+                "Relayer 路径由 Relay Hub 校验 EOA 签名，不支持 EIP-1271 合约签名（MERGE_SIGNATURE_OWNER={:?}）",
+                owner
+            );
+        }
         let relayer_url = env::var("RELAYER_URL").unwrap_or_else(|_| RELAYER_URL_DEFAULT.to_string());
-        match (builder_key.as_deref(), builder_secret.as_deref(), builder_passphrase.as_deref()) {
-            (Some(k), Some(s), Some(p)) => {
-                let out = relayer_execute_merge(&merge_calldata, ctf, proxy, &signer, k, s, p, &relayer_url).await?;
-                info!("✅ Relayer 已提交 tx: {}", out);
-                return Ok(out);
+        match BuilderCreds::from_env()? {
+            Some(creds) => {
+                let out = relayer_execute_merge(
+                    &merge_calldata,
+                    ctf,
+                    proxy,
+                    &signer,
+                    &creds,
+                    &relayer_url,
+                    opts.proxy_factory,
+                    opts.relayer_signing,
+                    opts.chain_id,
+                )
+                .await?;
+                info!(merge.path = MergePath::Relayer.as_str(), "✅ Relayer 已提交 tx: {}", out);
+                return Ok(MergeOutcome::Merged(MergeResult {
+                    tx_hash: out,
+                    path: MergePath::Relayer,
+                    merge_amount,
+                    merged_usdc_observed: None,
+                    safe_nonce: None,
+                }));
             }
-            _ => anyhow::bail!(
+            None => anyhow::bail!(
                 "Magic/Email 需配置 POLY_BUILDER_API_KEY、POLY_BUILDER_SECRET、POLY_BUILDER_PASSPHRASE；或改用网页 merge。",
             ),
         }
     }
 
+    info!(
+        merge.path = MergePath::GnosisSafe.as_str(),
+        proxy_code_len = code.len(),
+        safe_min_code_len = SAFE_MIN_CODE_LEN,
+        configured_proxy = %proxy,
+        "Merge 路径决策：proxy 代码长度 >= {}，按 Gnosis Safe 发送 execTransaction",
+        SAFE_MIN_CODE_LEN
+    );
     let safe = IGnosisSafe::new(proxy, provider);
-    let nonce: U256 = safe.nonce().call().await.map_err(|e| {
-        let msg = e.to_string();
-        let hint = if msg.contains("revert") || msg.contains("reverted") {
-            " 该地址可能不是 Gnosis Safe；Magic/Email 请用 Relayer 或网页 merge。"
-        } else { "" };
-        anyhow::anyhow!("读取 Safe nonce 失败: {}{}", msg, hint)
-    })?;
+    let nonce: U256 = match safe_nonce {
+        Some(n) => n,
+        None => safe.nonce().call().await.map_err(|e| {
+            let msg = e.to_string();
+            let hint = if msg.contains("revert") || msg.contains("reverted") {
+                " 该地址可能不是 Gnosis Safe；Magic/Email 请用 Relayer 或网页 merge。"
+            } else { "" };
+            anyhow::anyhow!("读取 Safe nonce 失败: {}{}", msg, hint)
+        })?,
+    };
 
     let tx_hash_data = safe
         .encodeTransactionData(ctf, U256::ZERO, merge_calldata.clone().into(), 0u8, U256::ZERO, U256::ZERO, U256::ZERO, Address::ZERO, Address::ZERO, nonce)
@@ -414,17 +1474,287 @@ pub async fn merge_max(
 
     let tx_hash = keccak256(tx_hash_data.as_ref());
     let sig = signer.sign_hash(&tx_hash).await.map_err(|e| anyhow::anyhow!("签名失败: {}", e))?;
-    let mut sig_bytes = sig.as_bytes().to_vec();
-    if sig_bytes.len() == 65 && (sig_bytes[64] == 0 || sig_bytes[64] == 1) {
-        sig_bytes[64] += 27;
-    }
+    let signature_type = resolve_safe_signature_type(&safe, wallet, opts.signature_type).await;
+    info!(merge.path = MergePath::GnosisSafe.as_str(), signature_type = ?signature_type, "Safe 签名格式");
+    let sig_bytes = encode_safe_signature(sig.as_bytes().to_vec(), signature_type);
 
-    let pending = safe
+    let gas_price = suggest_gas_price(safe.provider()).await?;
+    let exec = safe
         .execTransaction(ctf, U256::ZERO, merge_calldata.into(), 0u8, U256::ZERO, U256::ZERO, U256::ZERO, Address::ZERO, Address::ZERO, sig_bytes.into())
+        .gas_price(gas_price);
+
+    // 广播前模拟：回滚（nonce 错误、余额不足、签名/calldata 错误）时直接返回，不消耗 gas
+    if opts.simulate_first {
+        match exec.call().await {
+            Ok(true) => debug!(nonce = %nonce, "Safe execTransaction 模拟通过"),
+            Ok(false) => anyhow::bail!("Safe.execTransaction 模拟返回 false（内部调用失败），未广播 | nonce={}", nonce),
+            Err(e) => anyhow::bail!("Safe.execTransaction 模拟回滚，未广播 | nonce={} | 原因: {}", nonce, decode_simulation_error(&e)),
+        }
+    }
+
+    // 经济性检查：gas 上限 × gas price × POL 价格 ≥ 回收 USDC 时跳过（force 时仍执行）
+    let gas_limit = exec.estimate_gas().await.unwrap_or_else(|e| {
+        warn!(error = %e, "估算 Safe merge gas 失败，按 {} 计算", SAFE_MERGE_GAS_FALLBACK);
+        SAFE_MERGE_GAS_FALLBACK
+    });
+    // gas_price 单位为 wei，按 18 位小数转为 POL
+    let gas_cost_pol = Decimal::from(gas_limit) * Decimal::from_i128_with_scale(gas_price as i128, 18);
+    let gas_cost_usdc = gas_cost_pol * opts.pol_price_usd;
+    let merged_usdc = units_to_usdc(merge_amount);
+    info!(
+        gas_limit,
+        gas_cost_usdc = %gas_cost_usdc,
+        merged_usdc = %merged_usdc,
+        pol_price_usd = %opts.pol_price_usd,
+        "⛽ Safe merge 经济性: gas ≈ {:.4} USD，回收 {} USDC",
+        gas_cost_usdc,
+        merged_usdc
+    );
+    if gas_cost_usdc >= merged_usdc {
+        if !opts.force {
+            warn!("⏭️ Merge 不经济，跳过（gas ≈ {:.4} USD ≥ 回收 {} USDC；MERGE_FORCE=1 可强制）", gas_cost_usdc, merged_usdc);
+            return Ok(MergeOutcome::SkippedUneconomical { merge_amount, merged_usdc, gas_cost_usdc });
+        }
+        warn!("MERGE_FORCE=1：gas 成本不低于回收金额，仍执行 merge");
+    }
+
+    // merge 前 USDC 余额，用于上链后核对实际回收金额；读取失败不阻断 merge
+    let usdc_before = erc20.balanceOf(proxy).call().await.ok();
+    let pending = exec
+        .gas(gas_limit)
         .send().await.map_err(|e| anyhow::anyhow!("Safe.execTransaction 失败: {}", e))?;
 
     let tx_hash_out = *pending.tx_hash();
-    let _receipt = pending.get_receipt().await.map_err(|e| anyhow::anyhow!("等待 receipt 失败: {}", e))?;
-    info!("✅ Merge 成功（Safe）tx: {:#x}", tx_hash_out);
-    Ok(format!("{:#x}", tx_hash_out))
+    let receipt = pending.get_receipt().await.map_err(|e| anyhow::anyhow!("等待 receipt 失败: {}", e))?;
+    // 上链不等于成功：回滚的交易同样有 receipt，status=false 时未 merge，不能按成功扣减持仓
+    if !receipt.status() {
+        anyhow::bail!("Safe.execTransaction 已上链但执行回滚，未 merge（tx {:#x}）", tx_hash_out);
+    }
+
+    let merged_usdc_observed = match (usdc_before, erc20.balanceOf(proxy).call().await.ok()) {
+        (Some(before), Some(after)) => {
+            let observed = units_to_usdc(after.saturating_sub(before));
+            let expected = units_to_usdc(merge_amount);
+            if observed != expected {
+                warn!(
+                    observed_usdc = %observed,
+                    expected_usdc = %expected,
+                    "⚠️ Merge 后 USDC 余额增量与预期不符（可能交易回滚或部分合并），请核对 tx {:#x}",
+                    tx_hash_out
+                );
+            } else {
+                info!("💵 Merge 后 USDC 余额增加 {}，与预期一致", observed);
+            }
+            Some(observed)
+        }
+        _ => {
+            warn!("读取 USDC 余额失败，跳过 Merge 回收金额核对");
+            None
+        }
+    };
+    info!(merge.path = MergePath::GnosisSafe.as_str(), "✅ Merge 成功（Safe）tx: {:#x}", tx_hash_out);
+    Ok(MergeOutcome::Merged(MergeResult {
+        tx_hash: format!("{:#x}", tx_hash_out),
+        path: MergePath::GnosisSafe,
+        merge_amount,
+        merged_usdc_observed,
+        safe_nonce: Some(nonce),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::sol_types::SolCall;
+
+    sol! {
+        function mergePositions(
+            address collateralToken,
+            bytes32 parentCollectionId,
+            bytes32 conditionId,
+            uint256[] partition,
+            uint256 amount
+        ) external;
+
+        struct RelayRequest {
+            address from;
+            address to;
+            bytes data;
+            uint256 txFee;
+            uint256 gasPrice;
+            uint256 gasLimit;
+            uint256 nonce;
+            address relayHub;
+            address relay;
+        }
+    }
+
+    fn binary_merge() -> MergePositionsRequest {
+        let condition_id: B256 = "0x5eed00000000000000000000000000000000000000000000000000000000c0de".parse().unwrap();
+        let usdc = contract_config(POLYGON, false).unwrap().collateral;
+        MergePositionsRequest::for_binary_market(usdc, condition_id, U256::from(12_345_678u64))
+    }
+
+    #[test]
+    fn polygon_collateral_is_usdc_e() {
+        let config = contract_config(POLYGON, false).unwrap();
+        assert_eq!(config.collateral, address!("0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174"));
+    }
+
+    #[test]
+    fn merge_calldata_matches_sol_abi_encoding() {
+        let req = binary_merge();
+        let expected = mergePositionsCall {
+            collateralToken: req.collateral_token,
+            parentCollectionId: req.parent_collection_id,
+            conditionId: req.condition_id,
+            partition: req.partition.clone(),
+            amount: req.amount,
+        }
+        .abi_encode();
+        assert_eq!(encode_merge_calldata(&req), expected);
+    }
+
+    #[test]
+    fn merge_calldata_round_trips() {
+        let req = binary_merge();
+        let decoded = decode_merge_calldata(&encode_merge_calldata(&req)).unwrap();
+        assert_eq!(decoded.collateral_token, req.collateral_token);
+        assert_eq!(decoded.parent_collection_id, req.parent_collection_id);
+        assert_eq!(decoded.condition_id, req.condition_id);
+        assert_eq!(decoded.partition, req.partition);
+        assert_eq!(decoded.amount, req.amount);
+    }
+
+    #[test]
+    fn decode_rejects_truncated_calldata() {
+        let calldata = encode_merge_calldata(&binary_merge());
+        assert!(decode_merge_calldata(&calldata[..calldata.len() - 32]).is_err());
+        assert!(decode_merge_calldata(&calldata[..3]).is_err());
+    }
+
+    #[test]
+    fn short_hex_keeps_short_inputs_whole() {
+        assert_eq!(short_hex(""), "0x");
+        assert_eq!(short_hex("0x"), "0x");
+        assert_eq!(short_hex("0xabc"), "0xabc");
+        assert_eq!(short_hex("abc"), "0xabc");
+        // 恰好 14 位（首尾两段之和）与 16 位都不缩写
+        assert_eq!(short_hex("0x0123456789abcd"), "0x0123456789abcd");
+        assert_eq!(short_hex("0x0123456789abcdef"), "0x0123456789abcdef");
+    }
+
+    #[test]
+    fn short_hex_abbreviates_long_inputs() {
+        assert_eq!(short_hex("0x0123456789abcdef0"), "0x01234567..bcdef0");
+        let tx = format!("0X{}", "ab".repeat(32));
+        assert_eq!(short_hex(&tx), "0xabababab..ababab");
+    }
+
+    #[test]
+    fn short_hex_does_not_slice_non_ascii() {
+        assert_eq!(short_hex("0x交易哈希交易哈希交易哈希交易哈希"), "0x交易哈希交易哈希交易哈希交易哈希");
+    }
+
+    #[test]
+    fn eip712_hash_matches_alloy_signing_hash() {
+        use alloy::sol_types::{eip712_domain, SolStruct};
+
+        let from = Address::repeat_byte(0x11);
+        let to = Address::repeat_byte(0x22);
+        let relay = Address::repeat_byte(0x33);
+        let data = encode_proxy_call(Address::repeat_byte(0x44), &encode_merge_calldata(&binary_merge()));
+        let ours = eip712_hash(
+            relayer_domain_separator(137),
+            create_struct_hash_712(from, to, &data, 0, 0, PROXY_DEFAULT_GAS, "42", RELAY_HUB, relay).unwrap(),
+        );
+
+        let req = RelayRequest {
+            from,
+            to,
+            data: data.clone().into(),
+            txFee: U256::ZERO,
+            gasPrice: U256::ZERO,
+            gasLimit: U256::from(PROXY_DEFAULT_GAS),
+            nonce: U256::from(42u64),
+            relayHub: RELAY_HUB,
+            relay,
+        };
+        let domain = eip712_domain! {
+            name: RELAYER_EIP712_NAME,
+            version: RELAYER_EIP712_VERSION,
+            chain_id: 137,
+            verifying_contract: RELAY_HUB,
+        };
+        assert_eq!(ours, req.eip712_signing_hash(&domain));
+    }
+
+    #[test]
+    fn struct_hash_rejects_invalid_nonce() {
+        let a = Address::repeat_byte(0x11);
+        for nonce in ["", "abc", "-1", "0x10"] {
+            assert!(create_struct_hash(a, a, &[], 0, 0, 0, nonce, RELAY_HUB, a).is_err(), "{nonce:?}");
+            assert!(create_struct_hash_712(a, a, &[], 0, 0, 0, nonce, RELAY_HUB, a).is_err(), "{nonce:?}");
+        }
+        assert!(create_struct_hash_712(a, a, &[], 0, 0, 0, "7", RELAY_HUB, a).is_ok());
+    }
+
+    #[test]
+    fn derived_proxy_matches_published_vector() {
+        // anvil/hardhat 默认账户 #0（私钥公开），proxy 地址按 CREATE2(ProxyFactory, keccak256(eoa), initCodeHash) 独立计算
+        let eoa = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let proxy = address!("0x365f0ca36ae1f641e02fe3b7743673da42a13a70");
+        assert_eq!(derived_proxy_for(eoa), proxy);
+        // 与 SDK 的推导一致（下单侧 proxy_mismatch 用的是 SDK 版本）
+        assert_eq!(polymarket_client_sdk::derive_proxy_wallet(eoa, POLYGON), Some(proxy));
+
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        assert_eq!(derived_proxy_for_key(key).unwrap(), (eoa, proxy));
+        assert!(derived_proxy_for_key("0x1234").is_err());
+    }
+
+    fn merged_at(nonce: U256) -> Result<MergeOutcome> {
+        Ok(MergeOutcome::Merged(MergeResult {
+            tx_hash: format!("0x{:x}", nonce),
+            path: MergePath::GnosisSafe,
+            merge_amount: U256::from(1_000_000u64),
+            merged_usdc_observed: None,
+            safe_nonce: Some(nonce),
+        }))
+    }
+
+    /// 模拟 Safe：nonce 为 None 时按链上值 `chain_nonce` 执行；`fail_at` 中的市场返回错误（不消耗 nonce）
+    async fn run_sequential(ids: &[B256], chain_nonce: u64, fail_at: &[B256], skip_at: &[B256]) -> Vec<Option<U256>> {
+        let seen = std::cell::RefCell::new(Vec::new());
+        merge_safe_sequential(ids, |condition_id, nonce| {
+            seen.borrow_mut().push(nonce);
+            let result = if fail_at.contains(&condition_id) {
+                Err(anyhow::anyhow!("模拟回滚"))
+            } else if skip_at.contains(&condition_id) {
+                Ok(MergeOutcome::BelowThreshold { merge_amount: U256::ZERO, min_merge_amount: U256::from(1) })
+            } else {
+                merged_at(nonce.unwrap_or(U256::from(chain_nonce)))
+            };
+            async move { result }
+        })
+        .await;
+        seen.into_inner()
+    }
+
+    #[tokio::test]
+    async fn safe_merges_use_sequential_nonces() {
+        let ids: Vec<B256> = (1..=3u8).map(B256::repeat_byte).collect();
+        let seen = run_sequential(&ids, 5, &[], &[]).await;
+        assert_eq!(seen, vec![None, Some(U256::from(6)), Some(U256::from(7))]);
+    }
+
+    #[tokio::test]
+    async fn safe_merge_failure_rereads_nonce_and_skip_keeps_it() {
+        let ids: Vec<B256> = (1..=5u8).map(B256::repeat_byte).collect();
+        let seen = run_sequential(&ids, 5, &[ids[1]], &[ids[3]]).await;
+        assert_eq!(
+            seen,
+            vec![None, Some(U256::from(6)), None, Some(U256::from(6)), Some(U256::from(6))]
+        );
+    }
 }
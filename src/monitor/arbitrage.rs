@@ -1,7 +1,16 @@
+use dashmap::DashMap;
 use polymarket_client_sdk::clob::ws::types::response::BookUpdate;
 use polymarket_client_sdk::types::{B256, Decimal, U256};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
-use tracing::debug;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use super::fees::FeeSchedule;
+use crate::config::decimal_setting;
+use super::profit_model::{ProfitModel, RawBook, SpreadModel};
+use crate::trading::PriceDirection;
+use poly_5min_bot::opportunity_queue::OpportunityMessage;
 
 #[derive(Debug, Clone)]
 pub struct ArbitrageOpportunity {
@@ -12,26 +21,258 @@ pub struct ArbitrageOpportunity {
     pub no_ask_price: Decimal,
     pub total_cost: Decimal,
     pub profit_percentage: Decimal,
+    /// YES 腿目标数量；executor 按腿独立下单（各自受 max_order_size 与 $1 最小金额约束）
     pub yes_size: Decimal,
+    /// NO 腿目标数量；默认与 yes_size 相同（对称套利）
     pub no_size: Decimal,
+    /// 市场类别（crypto_symbol，如 btc），用于按类别查手续费；None 用默认费率
+    pub category: Option<String>,
+    /// 两腿订单簿中较旧一侧的更新时间，executor 下单前据此复核陈旧度
+    pub book_updated_at: Instant,
+    /// 机会追踪 ID（发现时生成，经队列传递），作为 span 字段串联监控→下单→merge
+    pub trace_id: String,
+}
+
+impl ArbitrageOpportunity {
+    /// 较旧一侧订单簿距今的时长
+    pub fn book_age(&self) -> Duration {
+        self.book_updated_at.elapsed()
+    }
+
+    /// 转为队列消息（附带涨跌方向，执行端据此分配滑点）
+    pub fn to_message(&self, yes_dir: PriceDirection, no_dir: PriceDirection) -> OpportunityMessage {
+        OpportunityMessage {
+            ts_ms: chrono::Utc::now().timestamp_millis(),
+            market_id: self.market_id.to_string(),
+            yes_token_id: self.yes_token_id.to_string(),
+            no_token_id: self.no_token_id.to_string(),
+            yes_ask_price: self.yes_ask_price.to_string(),
+            no_ask_price: self.no_ask_price.to_string(),
+            total_cost: self.total_cost.to_string(),
+            profit_percentage: self.profit_percentage.to_string(),
+            yes_size: self.yes_size.to_string(),
+            no_size: self.no_size.to_string(),
+            category: self.category.clone(),
+            book_age_ms: self.book_age().as_millis() as u64,
+            yes_dir: yes_dir.symbol().to_string(),
+            no_dir: no_dir.symbol().to_string(),
+            trace_id: self.trace_id.clone(),
+        }
+    }
+
+    /// 从队列消息还原机会与两腿涨跌方向；订单簿更新时间按消息中的陈旧度回推
+    pub fn from_message(msg: &OpportunityMessage) -> anyhow::Result<(Self, PriceDirection, PriceDirection)> {
+        let num = |field: &str, v: &str| -> anyhow::Result<Decimal> {
+            v.parse().map_err(|e| anyhow::anyhow!("{} 无效: {} ({})", field, v, e))
+        };
+        let token = |field: &str, v: &str| -> anyhow::Result<U256> {
+            v.parse().map_err(|e| anyhow::anyhow!("{} 无效: {} ({})", field, v, e))
+        };
+        let age = Duration::from_millis(msg.age_ms_now());
+        let opp = Self {
+            market_id: msg
+                .market_id
+                .parse()
+                .map_err(|e| anyhow::anyhow!("market_id 无效: {} ({})", msg.market_id, e))?,
+            yes_token_id: token("yes_token_id", &msg.yes_token_id)?,
+            no_token_id: token("no_token_id", &msg.no_token_id)?,
+            yes_ask_price: num("yes_ask_price", &msg.yes_ask_price)?,
+            no_ask_price: num("no_ask_price", &msg.no_ask_price)?,
+            total_cost: num("total_cost", &msg.total_cost)?,
+            profit_percentage: num("profit_percentage", &msg.profit_percentage)?,
+            yes_size: num("yes_size", &msg.yes_size)?,
+            no_size: num("no_size", &msg.no_size)?,
+            category: msg.category.clone(),
+            book_updated_at: Instant::now().checked_sub(age).unwrap_or_else(Instant::now),
+            trace_id: if msg.trace_id.is_empty() { new_trace_id() } else { msg.trace_id.clone() },
+        };
+        Ok((opp, PriceDirection::from(msg.yes_dir.as_str()), PriceDirection::from(msg.no_dir.as_str())))
+    }
+}
+
+/// 新的机会追踪 ID（32 位十六进制，与 OpenTelemetry trace ID 等长）
+fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// 盈利判定参数：监控端发出机会与执行端下单前共用同一套判定，避免两处口径不一致
+#[derive(Debug, Clone)]
+pub struct ProfitConfig {
+    /// 默认手续费（基点，按 YES+NO 总价计），如 20 表示 0.2%
+    pub fee_bps: Decimal,
+    /// 按市场类别的手续费表，未配置的类别用 fee_bps
+    pub fee_schedule: FeeSchedule,
+    /// 每份最小净利润（扣费后，1 - 总价 - 手续费），如 0.001
+    pub min_edge: Decimal,
+    /// YES+NO 价格之和上限（执行端传入含滑点价格），超过则不执行
+    pub max_slipped_sum: Decimal,
+    /// 扣费后每份净利下限（基点，1 bp = 0.0001 USDC/份），0 表示不限制；执行端按含滑点价格判定
+    pub min_net_edge_bps: Decimal,
+}
+
+impl ProfitConfig {
+    /// 参数为 NaN、无穷或越界时报错（手续费 0~10000 bps，最小净利 0~1，价格之和上限 0~2），不静默替换为默认值
+    pub fn new(fee_bps: f64, min_edge: f64, max_slipped_sum: f64) -> anyhow::Result<Self> {
+        let fee_bps = decimal_setting("FEE_BPS", fee_bps, dec!(0), Some(dec!(10000)))?;
+        Ok(Self {
+            fee_bps,
+            fee_schedule: FeeSchedule::flat(fee_bps),
+            min_edge: decimal_setting("MIN_PROFIT_THRESHOLD", min_edge, dec!(0), Some(dec!(1)))?,
+            max_slipped_sum: decimal_setting("MAX_SLIPPED_SUM", max_slipped_sum, dec!(0), Some(dec!(2)))?,
+            min_net_edge_bps: dec!(0),
+        })
+    }
+
+    /// 设置净利下限（基点）
+    pub fn with_min_net_edge_bps(mut self, bps: u32) -> Self {
+        self.min_net_edge_bps = Decimal::from(bps);
+        self
+    }
+
+    /// 替换按类别的手续费表
+    pub fn with_fee_schedule(mut self, fee_schedule: FeeSchedule) -> Self {
+        self.fee_schedule = fee_schedule;
+        self
+    }
+
+    /// 该类别适用的 taker 费率（基点）
+    pub fn taker_fee_bps(&self, category: Option<&str>) -> Decimal {
+        self.fee_schedule.taker_bps(category)
+    }
+}
+
+impl ArbitrageOpportunity {
+    /// 每份净利润：1 - (YES + NO) - 手续费（按机会所属类别的 taker 费率）
+    pub fn net_edge(&self, cfg: &ProfitConfig) -> Decimal {
+        let sum = self.yes_ask_price + self.no_ask_price;
+        let fees = sum * cfg.taker_fee_bps(self.category.as_deref()) / dec!(10000);
+        dec!(1.0) - sum - fees
+    }
+
+    /// 扣费后每份净利（基点）
+    pub fn net_edge_bps(&self, cfg: &ProfitConfig) -> Decimal {
+        self.net_edge(cfg) * dec!(10000)
+    }
+
+    /// 是否值得执行：价格之和不超过 max_slipped_sum，且扣费后净利润 > 0、>= min_edge 且 >= min_net_edge_bps
+    pub fn is_profitable(&self, cfg: &ProfitConfig) -> bool {
+        let sum = self.yes_ask_price + self.no_ask_price;
+        if sum > cfg.max_slipped_sum {
+            return false;
+        }
+        let net = self.net_edge(cfg);
+        net > dec!(0) && net >= cfg.min_edge && self.net_edge_bps(cfg) >= cfg.min_net_edge_bps
+    }
+}
+
+/// 去重表超过此条目数时清理过期项
+const DEDUP_PRUNE_THRESHOLD: usize = 4096;
+
+/// 机会去重：(condition_id, 取整后的 YES 卖一, 取整后的 NO 卖一) 在 TTL 内只发出一次，
+/// 抑制订单簿未实质变化时的重复机会
+pub struct OpportunityDedup {
+    ttl: Duration,
+    granularity: Decimal,
+    seen: DashMap<(B256, i64, i64), Instant>,
+}
+
+impl OpportunityDedup {
+    /// granularity ≤ 0 时按 0.01（一个 tick）取整
+    pub fn new(ttl: Duration, granularity: Decimal) -> Self {
+        Self {
+            ttl,
+            granularity: if granularity > dec!(0) { granularity } else { dec!(0.01) },
+            seen: DashMap::new(),
+        }
+    }
+
+    fn bucket(&self, price: Decimal) -> i64 {
+        (price / self.granularity).round().to_i64().unwrap_or(i64::MAX)
+    }
+
+    /// TTL 内已发出过相同机会时返回 true（不刷新时间）；否则登记并返回 false
+    pub fn is_duplicate(&self, market_id: B256, yes_ask: Decimal, no_ask: Decimal) -> bool {
+        let now = Instant::now();
+        let key = (market_id, self.bucket(yes_ask), self.bucket(no_ask));
+        if let Some(at) = self.seen.get(&key) {
+            if now.duration_since(*at) < self.ttl {
+                return true;
+            }
+        }
+        self.seen.insert(key, now);
+        if self.seen.len() > DEDUP_PRUNE_THRESHOLD {
+            self.seen.retain(|_, at| now.duration_since(*at) < self.ttl);
+        }
+        false
+    }
 }
 
 pub struct ArbitrageDetector {
-    min_profit_threshold: Decimal,
-    max_depth: usize, // 最大探测深度
+    profit_config: ProfitConfig,
+    dedup: Option<OpportunityDedup>, // 重复机会抑制，None=不去重
+    profit_model: Box<dyn ProfitModel>, // profit_percentage 的打分方式，默认价差百分比
+    max_complement_ticks: u32, // |(1 - YES卖一) - NO卖一| 允许的最大 tick 数，超过视为快照不一致，0=不检查
+    max_book_staleness: Duration, // 任一腿订单簿超过此时长未更新则不发出机会，0=不检查
+    max_book_levels: usize, // 逐档累计可成交数量时最多读取的档数，防止异常订单簿（上千档）拖慢热路径
     min_order_value_usd: Decimal, // 最小订单金额（USD）
 }
 
 impl ArbitrageDetector {
-    pub fn new(min_profit_threshold: f64) -> Self {
+    pub fn new(profit_config: ProfitConfig) -> Self {
         Self {
-            min_profit_threshold: Decimal::try_from(min_profit_threshold)
-                .unwrap_or(dec!(0.001)),
-            max_depth: 10, // 默认最多探测10档
+            profit_config,
+            profit_model: Box::new(SpreadModel),
+            dedup: None,
+            max_complement_ticks: 0,
+            max_book_staleness: Duration::ZERO,
+            max_book_levels: 10, // 默认最多探测10档
             min_order_value_usd: dec!(1.0), // 最小订单金额$1
         }
     }
 
+    /// 替换打分模型（影响 profit_percentage，不影响是否执行）
+    pub fn with_profit_model(mut self, model: Box<dyn ProfitModel>) -> Self {
+        self.profit_model = model;
+        self
+    }
+
+    /// 启用机会去重：ttl 为 0 时不启用
+    pub fn with_dedup(mut self, ttl: Duration, granularity: Decimal) -> Self {
+        self.dedup = (!ttl.is_zero()).then(|| OpportunityDedup::new(ttl, granularity));
+        self
+    }
+
+    /// 设置两腿互补偏差上限（tick 数，0=不检查）
+    pub fn with_max_complement_ticks(mut self, ticks: u32) -> Self {
+        self.max_complement_ticks = ticks;
+        self
+    }
+
+    /// 设置订单簿最大陈旧时长（0=不检查）
+    pub fn with_max_book_staleness(mut self, staleness: Duration) -> Self {
+        self.max_book_staleness = staleness;
+        self
+    }
+
+    /// 设置逐档累计时最多读取的档数（至少 1 档）
+    pub fn with_max_book_levels(mut self, levels: usize) -> Self {
+        self.max_book_levels = levels.max(1);
+        self
+    }
+
+    /// 从卖一开始逐档累计报价 ≤ limit 的可成交数量，最多读取 max_book_levels 档；
+    /// 遇到高于 limit 的档或累计已覆盖 target 时提前停止（asks 最后一个为卖一）
+    fn fillable_size(&self, book: &BookUpdate, limit: Decimal, target: Option<Decimal>) -> Decimal {
+        let mut filled = dec!(0);
+        for level in book.asks.iter().rev().take(self.max_book_levels) {
+            if level.price > limit || target.is_some_and(|t| filled >= t) {
+                break;
+            }
+            filled += level.size;
+        }
+        filled
+    }
+
     /// 选中价格：仅用卖一价。返回 (yes_ask, no_ask, size, profit_pct, total_price)。
     /// 后续在 executor 中：比较哪个价格高 → 加滑点 → 放入订单创建。
     fn find_best_opportunity(
@@ -51,8 +292,10 @@ impl ArbitrageDetector {
             return None; // 卖一总价 > 1，无套利
         }
 
-        // 卖一档的可用份额取两者较小值，向下取整到 2 位小数
-        let raw_size = yes_best.size.min(no_best.size);
+        // 按卖一报价可成交的份额（逐档累计，受 max_book_levels 限制）取两者较小值，向下取整到 2 位小数；
+        // 与取整前的卖一价比较，卖一档总会计入；NO 腿累计到覆盖 YES 腿即停
+        let yes_fillable = self.fillable_size(yes_book, yes_price.max(yes_best.price), None);
+        let raw_size = yes_fillable.min(self.fillable_size(no_book, no_price.max(no_best.price), Some(yes_fillable)));
         let final_size = if raw_size.is_zero() {
             dec!(0.01)
         } else {
@@ -65,7 +308,11 @@ impl ArbitrageDetector {
             return None;
         }
 
-        let profit_pct = (dec!(1.0) - total_price) * dec!(100.0);
+        let profit_pct = self.profit_model.score(&RawBook {
+            yes_ask: yes_price,
+            no_ask: no_price,
+            size: final_size,
+        });
         Some((yes_price, no_price, final_size, profit_pct, total_price))
     }
 
@@ -108,12 +355,15 @@ impl ArbitrageDetector {
         // 选档日志已移至 executor 中，在执行套利时打印加滑点后的价格
     }
 
-    /// 检查订单簿是否存在套利机会
+    /// 检查订单簿是否存在套利机会；category 为市场类别（crypto_symbol），用于按类别查手续费，
+    /// oldest_update 为两腿中较旧一侧订单簿的更新时间
     pub fn check_arbitrage(
         &self,
         yes_book: &BookUpdate,
         no_book: &BookUpdate,
         market_id: &B256,
+        category: Option<&str>,
+        oldest_update: Instant,
     ) -> Option<ArbitrageOpportunity> {
         // 先选卖一价；executor 中再：比较谁高 → 加滑点 → 放入订单创建
         let (yes_ask, no_ask, final_size, net_profit_pct, total_price) =
@@ -121,17 +371,49 @@ impl ArbitrageDetector {
 
         self.print_orderbook_depth(yes_book, no_book, yes_ask, no_ask, final_size, final_size);
 
+        // 推送中断时另一腿的快照可能早已过时，按此下单等于交易已移动的市场
+        if !self.max_book_staleness.is_zero() {
+            let age = oldest_update.elapsed();
+            if age > self.max_book_staleness {
+                info!(
+                    market_id = %market_id,
+                    book_age_ms = age.as_millis() as u64,
+                    max_ms = self.max_book_staleness.as_millis() as u64,
+                    "⏳ 订单簿快照陈旧，不发出套利机会"
+                );
+                return None;
+            }
+        }
+
+        // 两腿卖一应大致互补：偏差过大通常是某一侧快照陈旧，按此下单容易单边成交亏损
+        if self.max_complement_ticks > 0 {
+            let inconsistency = ((dec!(1.0) - yes_ask) - no_ask).abs();
+            let tolerance = dec!(0.01) * Decimal::from(self.max_complement_ticks);
+            if inconsistency > tolerance {
+                debug!(
+                    market_id = %market_id,
+                    yes_price = %yes_ask,
+                    no_price = %no_ask,
+                    inconsistency = %inconsistency,
+                    tolerance = %tolerance,
+                    "两腿卖一偏离互补超过容差，疑似快照不一致，不发出套利机会"
+                );
+                return None;
+            }
+        }
+
         debug!(
             market_id = %market_id,
             yes_price = %yes_ask,
             no_price = %no_ask,
             total_price = %total_price,
             net_profit_pct = %net_profit_pct,
+            profit_model = self.profit_model.name(),
             order_size = %final_size,
             "发现套利机会（卖一价）"
         );
 
-        Some(ArbitrageOpportunity {
+        let opp = ArbitrageOpportunity {
             market_id: *market_id,
             yes_token_id: yes_book.asset_id,
             no_token_id: no_book.asset_id,
@@ -141,6 +423,134 @@ impl ArbitrageDetector {
             profit_percentage: net_profit_pct,
             yes_size: final_size,
             no_size: final_size,
-        })
+            category: category.map(str::to_string),
+            book_updated_at: oldest_update,
+            trace_id: new_trace_id(),
+        };
+
+        if !opp.is_profitable(&self.profit_config) {
+            debug!(
+                market_id = %market_id,
+                net_edge = %opp.net_edge(&self.profit_config),
+                net_edge_bps = %opp.net_edge_bps(&self.profit_config).round_dp(2),
+                fee_bps = %self.profit_config.taker_fee_bps(category),
+                min_edge = %self.profit_config.min_edge,
+                min_net_edge_bps = %self.profit_config.min_net_edge_bps,
+                "扣费后不满足盈利条件，不发出套利机会"
+            );
+            return None;
+        }
+        debug!(
+            market_id = %market_id,
+            net_edge_bps = %opp.net_edge_bps(&self.profit_config).round_dp(2),
+            "扣费后净利（按卖一，未含滑点）"
+        );
+
+        if let Some(dedup) = &self.dedup {
+            if dedup.is_duplicate(*market_id, yes_ask, no_ask) {
+                debug!(
+                    market_id = %market_id,
+                    yes_price = %yes_ask,
+                    no_price = %no_ask,
+                    "去重窗口内已发出相同机会，跳过"
+                );
+                return None;
+            }
+        }
+
+        Some(opp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opp(yes_ask: Decimal, no_ask: Decimal) -> ArbitrageOpportunity {
+        ArbitrageOpportunity {
+            market_id: B256::ZERO,
+            yes_token_id: U256::from(1),
+            no_token_id: U256::from(2),
+            yes_ask_price: yes_ask,
+            no_ask_price: no_ask,
+            total_cost: yes_ask + no_ask,
+            profit_percentage: dec!(0),
+            yes_size: dec!(10),
+            no_size: dec!(10),
+            category: None,
+            book_updated_at: Instant::now(),
+            trace_id: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn net_edge_deducts_fee_on_the_sum() {
+        assert_eq!(opp(dec!(0.48), dec!(0.50)).net_edge(&ProfitConfig::new(0.0, 0.0, 1.0).unwrap()), dec!(0.02));
+        // 100 bps × 0.98 = 0.0098
+        assert_eq!(opp(dec!(0.48), dec!(0.50)).net_edge(&ProfitConfig::new(100.0, 0.0, 1.0).unwrap()), dec!(0.0102));
+        assert_eq!(opp(dec!(0.48), dec!(0.50)).net_edge_bps(&ProfitConfig::new(0.0, 0.0, 1.0).unwrap()), dec!(200));
+    }
+
+    #[test]
+    fn min_edge_is_inclusive() {
+        let cfg = ProfitConfig::new(0.0, 0.01, 1.0).unwrap();
+        assert!(opp(dec!(0.49), dec!(0.50)).is_profitable(&cfg));
+        assert!(!opp(dec!(0.491), dec!(0.50)).is_profitable(&cfg));
+    }
+
+    #[test]
+    fn zero_net_edge_is_never_profitable() {
+        let cfg = ProfitConfig::new(0.0, 0.0, 1.0).unwrap();
+        assert!(!opp(dec!(0.50), dec!(0.50)).is_profitable(&cfg));
+        assert!(opp(dec!(0.50), dec!(0.4999)).is_profitable(&cfg));
+    }
+
+    #[test]
+    fn max_slipped_sum_is_inclusive() {
+        let cfg = ProfitConfig::new(0.0, 0.0, 0.97).unwrap();
+        assert!(opp(dec!(0.47), dec!(0.50)).is_profitable(&cfg));
+        assert!(!opp(dec!(0.4701), dec!(0.50)).is_profitable(&cfg));
+    }
+
+    #[test]
+    fn profit_config_rejects_nan_and_out_of_range() {
+        assert!(ProfitConfig::new(f64::NAN, 0.001, 1.0).is_err());
+        assert!(ProfitConfig::new(-5.0, 0.001, 1.0).is_err());
+        assert!(ProfitConfig::new(0.0, f64::NAN, 1.0).is_err());
+        assert!(ProfitConfig::new(0.0, -0.01, 1.0).is_err());
+        assert!(ProfitConfig::new(0.0, 0.001, f64::INFINITY).is_err());
+        assert!(ProfitConfig::new(0.0, 0.001, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn fee_schedule_filters_marginal_opportunity_by_category() {
+        let cfg = ProfitConfig::new(0.0, 0.01, 1.0)
+            .unwrap()
+            .with_fee_schedule(FeeSchedule::parse("btc:0:50,eth:0:200", dec!(0)).unwrap());
+        let marginal = |category: Option<&str>| ArbitrageOpportunity {
+            category: category.map(str::to_string),
+            ..opp(dec!(0.48), dec!(0.50))
+        };
+        // 0.98 × 50 bps = 0.0049 → 净利 0.0151 >= 0.01
+        assert_eq!(marginal(Some("btc")).net_edge(&cfg), dec!(0.0151));
+        assert!(marginal(Some("btc")).is_profitable(&cfg));
+        // 0.98 × 200 bps = 0.0196 → 净利 0.0004 < 0.01
+        assert_eq!(marginal(Some("eth")).net_edge(&cfg), dec!(0.0004));
+        assert!(!marginal(Some("eth")).is_profitable(&cfg));
+        assert!(!marginal(Some("ETH")).is_profitable(&cfg));
+        // 未配置的类别走默认费率
+        assert!(marginal(Some("sol")).is_profitable(&cfg));
+        assert!(marginal(None).is_profitable(&cfg));
+    }
+
+    #[test]
+    fn min_net_edge_bps_is_inclusive() {
+        let cfg = ProfitConfig::new(0.0, 0.0, 1.0).unwrap().with_min_net_edge_bps(250);
+        let at = opp(dec!(0.48), dec!(0.495));
+        assert_eq!(at.net_edge_bps(&cfg), dec!(250));
+        assert!(at.is_profitable(&cfg));
+        let below = opp(dec!(0.48), dec!(0.4951));
+        assert_eq!(below.net_edge_bps(&cfg), dec!(249));
+        assert!(!below.is_profitable(&cfg));
     }
 }
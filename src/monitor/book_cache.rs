@@ -0,0 +1,81 @@
+//! 监控与执行器共享的盘口缓存：监控收到 WS 推送时写入买一/卖一，执行器复核、加价、卖出前
+//! 先读缓存，TTL 内的盘口直接复用，过期或缺失时才走 REST 拉取并回写，减少热路径上的重复请求。
+
+use anyhow::Result;
+use dashmap::DashMap;
+use polymarket_client_sdk::clob::ws::types::response::BookUpdate;
+use polymarket_client_sdk::types::{Decimal, U256};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// 买一/卖一快照；某侧无挂单时为 None
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopOfBook {
+    pub best_ask: Option<Decimal>,
+    pub best_bid: Option<Decimal>,
+}
+
+impl From<&BookUpdate> for TopOfBook {
+    /// 注意：WS 推送中 asks/bids 的最后一个为卖一/买一
+    fn from(book: &BookUpdate) -> Self {
+        Self {
+            best_ask: book.asks.last().map(|l| l.price),
+            best_bid: book.bids.last().map(|l| l.price),
+        }
+    }
+}
+
+/// 按 token 缓存的盘口，条目超过 TTL 即视为过期；TTL 为 0 时不缓存（每次都拉取）
+#[derive(Debug)]
+pub struct BookCache {
+    ttl: Duration,
+    books: DashMap<U256, (TopOfBook, Instant)>,
+}
+
+impl BookCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            books: DashMap::new(),
+        }
+    }
+
+    /// TTL 内的缓存盘口
+    pub fn get(&self, token_id: U256) -> Option<TopOfBook> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        self.books
+            .get(&token_id)
+            .filter(|e| e.1.elapsed() <= self.ttl)
+            .map(|e| e.0)
+    }
+
+    /// 写入最新盘口（覆盖旧条目）
+    pub fn insert(&self, token_id: U256, top: TopOfBook) {
+        if !self.ttl.is_zero() {
+            self.books.insert(token_id, (top, Instant::now()));
+        }
+    }
+
+    /// 命中 TTL 内的缓存则直接返回，否则调用 fetch 拉取并回写
+    pub async fn get_or_fetch<F, Fut>(&self, token_id: U256, fetch: F) -> Result<TopOfBook>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<TopOfBook>>,
+    {
+        if let Some(top) = self.get(token_id) {
+            debug!(token_id = %token_id, "盘口缓存命中");
+            return Ok(top);
+        }
+        let top = fetch().await?;
+        self.insert(token_id, top);
+        Ok(top)
+    }
+
+    /// 清空缓存（新一轮市场开始时调用，旧 token 不再使用）
+    pub fn clear(&self) {
+        self.books.clear();
+    }
+}
@@ -0,0 +1,105 @@
+//! 涨跌方向：决定每腿使用哪一档滑点（仅下降用 second，上涨、持平与未知用 first）。
+//!
+//! 方向由同一市场相邻两拍订单簿的「采样值」比较得出，采样值取决于 `DirectionSource`：
+//!
+//! | 来源 | 采样值 | Up | Down | Flat |
+//! |------|--------|----|------|------|
+//! | `BestAsk`（默认） | 卖一价 | 卖一上移 | 卖一下移 | 不变 |
+//! | `Midpoint` | (买一 + 卖一) / 2 | 中间价上移 | 中间价下移 | 不变 |
+//! | `BookImbalance` | (买一量 − 卖一量) / (买一量 + 卖一量) | 买压增强超过死区 | 卖压增强超过死区 | 变化在死区内 |
+//!
+//! 首拍（无上一拍采样）或任一拍无法采样（如缺买一/卖一）时方向为 Unknown。
+//! WS 订单簿消息不含成交价，因此不提供按最近成交价采样的来源。
+
+use polymarket_client_sdk::clob::ws::types::response::BookUpdate;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::trading::PriceDirection;
+
+/// 盘口失衡度变化小于此值视为持平，避免挂单量的细微抖动频繁翻转方向
+const IMBALANCE_DEADBAND: Decimal = dec!(0.05);
+
+/// 涨跌方向的采样来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectionSource {
+    BestAsk,
+    Midpoint,
+    BookImbalance,
+}
+
+impl DirectionSource {
+    /// 解析配置：best_ask | midpoint | book_imbalance，大小写不敏感；未知值返回 Err，避免静默退回 BestAsk
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "best_ask" | "ask" => Ok(DirectionSource::BestAsk),
+            "midpoint" | "mid" => Ok(DirectionSource::Midpoint),
+            "book_imbalance" | "imbalance" => Ok(DirectionSource::BookImbalance),
+            other => Err(format!("未知来源 {:?}（可用: best_ask, midpoint, book_imbalance）", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectionSource::BestAsk => "best_ask",
+            DirectionSource::Midpoint => "midpoint",
+            DirectionSource::BookImbalance => "book_imbalance",
+        }
+    }
+
+    /// 从单边订单簿取采样值（asks/bids 最后一个为卖一/买一）
+    pub fn sample(&self, book: &BookUpdate) -> Option<Decimal> {
+        let best_ask = book.asks.last();
+        let best_bid = book.bids.last();
+        match self {
+            DirectionSource::BestAsk => best_ask.map(|a| a.price),
+            DirectionSource::Midpoint => Some((best_bid?.price + best_ask?.price) / dec!(2)),
+            DirectionSource::BookImbalance => {
+                let (bid, ask) = (best_bid?.size, best_ask?.size);
+                let total = bid + ask;
+                if total.is_zero() {
+                    None
+                } else {
+                    Some((bid - ask) / total)
+                }
+            }
+        }
+    }
+}
+
+/// 比较上一拍与当前采样值得到涨跌方向（映射规则见模块文档）
+pub fn compute_direction(
+    prev: Option<Decimal>,
+    cur: Option<Decimal>,
+    source: DirectionSource,
+) -> PriceDirection {
+    match (prev, cur) {
+        (Some(prev), Some(cur)) => {
+            if source == DirectionSource::BookImbalance && (cur - prev).abs() < IMBALANCE_DEADBAND {
+                PriceDirection::Flat
+            } else {
+                PriceDirection::from_change(cur, prev)
+            }
+        }
+        _ => PriceDirection::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_known_sources() {
+        assert_eq!(DirectionSource::parse("best_ask"), Ok(DirectionSource::BestAsk));
+        assert_eq!(DirectionSource::parse(" Midpoint "), Ok(DirectionSource::Midpoint));
+        assert_eq!(DirectionSource::parse("book-imbalance"), Ok(DirectionSource::BookImbalance));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_sources() {
+        assert!(DirectionSource::parse("last_trade").is_err());
+        assert!(DirectionSource::parse("bestask_typo").is_err());
+        assert!(DirectionSource::parse("").is_err());
+    }
+}
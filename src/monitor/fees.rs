@@ -0,0 +1,85 @@
+//! 按市场类别的手续费表：类别（如 btc、eth，即市场的 crypto_symbol）→ maker/taker 费率（基点）。
+//!
+//! 配置 `FEE_SCHEDULE` 格式为逗号分隔的 `类别:maker_bps:taker_bps`，如 `btc:0:20,xrp:0:50`；
+//! 未列出的类别使用默认费率（maker 0，taker 为 `FEE_BPS`）。类别不区分大小写。
+
+use polymarket_client_sdk::types::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// 单个类别的费率（基点）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeRates {
+    pub maker_bps: Decimal,
+    pub taker_bps: Decimal,
+}
+
+/// 按类别的手续费表
+#[derive(Debug, Clone)]
+pub struct FeeSchedule {
+    default: FeeRates,
+    by_category: HashMap<String, FeeRates>,
+}
+
+impl FeeSchedule {
+    /// 仅有默认费率的手续费表
+    pub fn flat(taker_bps: Decimal) -> Self {
+        Self {
+            default: FeeRates {
+                maker_bps: dec!(0),
+                taker_bps,
+            },
+            by_category: HashMap::new(),
+        }
+    }
+
+    /// 解析 `类别:maker_bps:taker_bps,...`，空串返回仅默认费率的表；格式错误返回 Err 并指出出错项
+    pub fn parse(spec: &str, default_taker_bps: Decimal) -> Result<Self, String> {
+        let mut schedule = Self::flat(default_taker_bps);
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let parts: Vec<&str> = item.split(':').map(str::trim).collect();
+            if parts.len() != 3 || parts[0].is_empty() {
+                return Err(format!("手续费表项格式应为 类别:maker_bps:taker_bps，实际: {}", item));
+            }
+            let bps = |s: &str| {
+                Decimal::from_str(s)
+                    .ok()
+                    .filter(|d| *d >= dec!(0))
+                    .ok_or_else(|| format!("手续费表项费率无效: {}", item))
+            };
+            schedule.by_category.insert(
+                parts[0].to_lowercase(),
+                FeeRates {
+                    maker_bps: bps(parts[1])?,
+                    taker_bps: bps(parts[2])?,
+                },
+            );
+        }
+        Ok(schedule)
+    }
+
+    /// 类别对应的费率；类别为空或未配置时返回默认费率
+    pub fn rates_for(&self, category: Option<&str>) -> FeeRates {
+        category
+            .and_then(|c| self.by_category.get(&c.to_lowercase()))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// 类别对应的 taker 费率（套利双腿均为吃单）
+    pub fn taker_bps(&self, category: Option<&str>) -> Decimal {
+        self.rates_for(category).taker_bps
+    }
+
+    /// 启动日志用：`默认 maker/taker | btc maker/taker, ...`
+    pub fn describe(&self) -> String {
+        let mut items: Vec<_> = self.by_category.iter().collect();
+        items.sort_by(|a, b| a.0.cmp(b.0));
+        let mut out = format!("默认 {}/{}", self.default.maker_bps, self.default.taker_bps);
+        for (category, rates) in items {
+            out.push_str(&format!(" | {} {}/{}", category, rates.maker_bps, rates.taker_bps));
+        }
+        out
+    }
+}
@@ -1,5 +1,14 @@
 pub mod arbitrage;
+pub mod book_cache;
+pub mod direction;
+pub mod fees;
 pub mod orderbook;
+pub mod profit_model;
+pub mod ws;
 
 pub use arbitrage::*;
+pub use book_cache::*;
+pub use direction::*;
+pub use fees::*;
 pub use orderbook::*;
+pub use profit_model::*;
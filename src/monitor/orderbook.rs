@@ -1,14 +1,17 @@
 use anyhow::Result;
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use futures::Stream;
 use futures::StreamExt;
 use polymarket_client_sdk::clob::ws::{Client as WsClient, types::response::BookUpdate};
 use polymarket_client_sdk::types::{B256, U256};
 use std::collections::HashMap;
 use std::pin::Pin;
-use tracing::{debug, info};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{debug, error, info};
 
 use crate::market::MarketInfo;
+use crate::monitor::book_cache::{BookCache, TopOfBook};
 
 /// 缩短 B256 用于日志：保留 0x + 前 8 位 hex，如 0xb91126b7..
 #[inline]
@@ -31,13 +34,18 @@ fn short_u256(u: &U256) -> String {
 pub struct OrderBookMonitor {
     ws_client: WsClient,
     books: DashMap<U256, BookUpdate>,
+    updated_at: DashMap<U256, Instant>, // token -> 最近一次收到订单簿推送的时间
     market_map: HashMap<B256, (U256, U256)>, // market_id -> (yes_token_id, no_token_id)
+    mispaired: DashSet<B256>, // 配对自检失败的市场，仅首次记录 error 日志
+    book_cache: Option<Arc<BookCache>>, // 与执行器共享的盘口缓存，收到推送即写入
 }
 
 pub struct OrderBookPair {
     pub yes_book: BookUpdate,
     pub no_book: BookUpdate,
     pub market_id: B256,
+    /// 两腿中较旧一侧的最近更新时间（另一侧刚推送，陈旧风险来自这一侧）
+    pub oldest_update: Instant,
 }
 
 impl OrderBookMonitor {
@@ -47,12 +55,77 @@ impl OrderBookMonitor {
             // 只有订阅用户数据（如用户订单、交易等）才需要认证
             ws_client: WsClient::default(),
             books: DashMap::new(),
+            updated_at: DashMap::new(),
             market_map: HashMap::new(),
+            mispaired: DashSet::new(),
+            book_cache: None,
+        }
+    }
+
+    /// 共享盘口缓存：每次推送写入买一/卖一，执行器在 TTL 内直接复用
+    pub fn with_book_cache(mut self, cache: Arc<BookCache>) -> Self {
+        self.book_cache = Some(cache);
+        self
+    }
+
+    /// 配对自检：YES/NO 须为不同 token，且两侧订单簿推送的 market（condition_id）都与订阅的市场一致，
+    /// 否则可能把无关结果的 token 配成一对下单
+    fn check_pairing(market_id: &B256, yes_book: &BookUpdate, no_book: &BookUpdate) -> std::result::Result<(), String> {
+        if yes_book.asset_id == no_book.asset_id {
+            return Err(format!("YES 与 NO 为同一 token {}", short_u256(&yes_book.asset_id)));
+        }
+        for (side, book) in [("YES", yes_book), ("NO", no_book)] {
+            if book.market != *market_id {
+                return Err(format!(
+                    "{} token {} 属于市场 {}，而非 {}",
+                    side,
+                    short_u256(&book.asset_id),
+                    short_b256(&book.market),
+                    short_b256(market_id)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 自检通过则返回订单簿对，否则记录并丢弃（同一市场仅首次记 error）
+    fn checked_pair(&self, market_id: B256, yes_book: BookUpdate, no_book: BookUpdate) -> Option<OrderBookPair> {
+        match Self::check_pairing(&market_id, &yes_book, &no_book) {
+            Ok(()) => {
+                let oldest_update = [yes_book.asset_id, no_book.asset_id]
+                    .iter()
+                    .filter_map(|t| self.updated_at.get(t).map(|r| *r))
+                    .min()
+                    .unwrap_or_else(Instant::now);
+                Some(OrderBookPair { yes_book, no_book, market_id, oldest_update })
+            }
+            Err(reason) => {
+                if self.mispaired.insert(market_id) {
+                    error!(market_id = %market_id, "🚫 YES/NO 配对自检失败，不发出套利机会: {}", reason);
+                } else {
+                    debug!(market_id = short_b256(&market_id), "YES/NO 配对自检失败: {}", reason);
+                }
+                None
+            }
         }
     }
 
     /// 订阅新市场
     pub fn subscribe_market(&mut self, market: &MarketInfo) -> Result<()> {
+        // 配对自检：YES/NO 不同，且 token 未被其他市场占用
+        if market.yes_token_id == market.no_token_id {
+            return Err(anyhow::anyhow!("YES 与 NO token 相同: {}", market.yes_token_id));
+        }
+        for (other_id, (yes, no)) in &self.market_map {
+            if *other_id != market.market_id
+                && [*yes, *no].iter().any(|t| *t == market.yes_token_id || *t == market.no_token_id)
+            {
+                return Err(anyhow::anyhow!(
+                    "token 已属于市场 {}，拒绝重复配对",
+                    short_b256(other_id)
+                ));
+            }
+        }
         // 记录市场映射
         self.market_map.insert(
             market.market_id,
@@ -124,25 +197,21 @@ impl OrderBookMonitor {
         }
 
         // 更新订单簿缓存
+        self.updated_at.insert(book.asset_id, Instant::now());
         self.books.insert(book.asset_id, book.clone());
+        if let Some(cache) = &self.book_cache {
+            cache.insert(book.asset_id, TopOfBook::from(&book));
+        }
 
         // 查找这个 token 属于哪个市场；任一侧（YES 或 NO）更新都返回 OrderBookPair，以便及时反应套利
         for (market_id, (yes_token, no_token)) in &self.market_map {
             if book.asset_id == *yes_token {
                 if let Some(no_book) = self.books.get(no_token) {
-                    return Some(OrderBookPair {
-                        yes_book: book.clone(),
-                        no_book: no_book.clone(),
-                        market_id: *market_id,
-                    });
+                    return self.checked_pair(*market_id, book.clone(), no_book.clone());
                 }
             } else if book.asset_id == *no_token {
                 if let Some(yes_book) = self.books.get(yes_token) {
-                    return Some(OrderBookPair {
-                        yes_book: yes_book.clone(),
-                        no_book: book.clone(),
-                        market_id: *market_id,
-                    });
+                    return self.checked_pair(*market_id, yes_book.clone(), book.clone());
                 }
             }
         }
@@ -158,6 +227,8 @@ impl OrderBookMonitor {
     /// 清除所有订阅
     pub fn clear(&mut self) {
         self.books.clear();
+        self.updated_at.clear();
         self.market_map.clear();
+        self.mispaired.clear();
     }
 }
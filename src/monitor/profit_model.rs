@@ -0,0 +1,72 @@
+//! 机会打分模型：决定 `ArbitrageOpportunity::profit_percentage` 的计算方式（用于日志与排序）。
+//!
+//! 是否执行仍由 `ProfitConfig`（扣费净利、最小边际、总价上限）判定，模型只影响打分。
+//! 内置两种模型，通过 `PROFIT_MODEL` 选择：
+//!
+//! - `spread`（默认）：价差百分比 `(1 - (YES + NO)) × 100`
+//! - `guaranteed_return`：扣费后保证收益率 `(1 - (YES + NO) - 手续费) / (YES + NO) × 100`，
+//!   即每投入 1 USDC 在结算（或 merge）时确定获得的收益百分比
+
+use polymarket_client_sdk::types::Decimal;
+use rust_decimal_macros::dec;
+
+/// 打分输入：选中的卖一价与可成交数量
+#[derive(Debug, Clone)]
+#[allow(dead_code)] // size 供自定义模型按深度打分，内置模型未使用
+pub struct RawBook {
+    pub yes_ask: Decimal,
+    pub no_ask: Decimal,
+    pub size: Decimal,
+}
+
+/// 机会打分模型
+pub trait ProfitModel: Send + Sync {
+    /// 模型名（日志用）
+    fn name(&self) -> &'static str;
+    /// 打分，单位为百分比
+    fn score(&self, book: &RawBook) -> Decimal;
+}
+
+/// 价差百分比：(1 - 总价) × 100
+#[derive(Debug, Clone, Default)]
+pub struct SpreadModel;
+
+impl ProfitModel for SpreadModel {
+    fn name(&self) -> &'static str {
+        "spread"
+    }
+
+    fn score(&self, book: &RawBook) -> Decimal {
+        (dec!(1.0) - (book.yes_ask + book.no_ask)) * dec!(100.0)
+    }
+}
+
+/// 扣费后保证收益率：净利 / 成本 × 100
+#[derive(Debug, Clone)]
+pub struct GuaranteedReturnModel {
+    /// 手续费（基点，按 YES+NO 总价计）
+    pub fee_bps: Decimal,
+}
+
+impl ProfitModel for GuaranteedReturnModel {
+    fn name(&self) -> &'static str {
+        "guaranteed_return"
+    }
+
+    fn score(&self, book: &RawBook) -> Decimal {
+        let cost = book.yes_ask + book.no_ask;
+        if cost.is_zero() {
+            return dec!(0);
+        }
+        let fees = cost * self.fee_bps / dec!(10000);
+        (dec!(1.0) - cost - fees) / cost * dec!(100.0)
+    }
+}
+
+/// 按名称构建内置模型：spread | guaranteed_return，未知值默认 spread
+pub fn profit_model_from_name(name: &str, fee_bps: Decimal) -> Box<dyn ProfitModel> {
+    match name.trim().to_lowercase().as_str() {
+        "guaranteed_return" | "guaranteed" => Box::new(GuaranteedReturnModel { fee_bps }),
+        _ => Box::new(SpreadModel),
+    }
+}
@@ -0,0 +1,167 @@
+//! 用户频道（认证 WebSocket）：订阅本账户的订单推送，实时获知挂单成交与撤单，
+//! 不必等提交后按 trades 轮询对账（RECONCILE_FILLS_DELAY_MS）。
+//!
+//! 订单推送中的 `size_matched` 是累计成交，按订单 ID 取最大值记入 [`OrderFills`]，重复或乱序推送不会重复计数；
+//! 执行器对账与风险管理器（更新持仓、订单对成交数量）都读同一份记录。
+
+use anyhow::Result;
+use dashmap::DashMap;
+use futures::StreamExt;
+use polymarket_client_sdk::auth::Credentials;
+use polymarket_client_sdk::clob::ws::types::response::{OrderMessageType, WsMessage};
+use polymarket_client_sdk::clob::ws::Client as WsClient;
+use polymarket_client_sdk::types::{Address, Decimal, U256};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+/// 断线后重连的初始等待，之后按倍数退避
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+/// 重连等待上限
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// 本账户订单的状态变化
+#[derive(Debug, Clone)]
+pub enum OrderEvent {
+    /// 订单累计成交增加
+    Fill {
+        order_id: String,
+        /// 累计成交份数（非本次增量）
+        matched: Decimal,
+        price: Decimal,
+    },
+    /// 订单已撤销（手动撤单或到期），matched 为撤销时的累计成交
+    Cancelled {
+        order_id: String,
+        asset_id: U256,
+        matched: Decimal,
+    },
+}
+
+impl OrderEvent {
+    pub fn order_id(&self) -> &str {
+        match self {
+            OrderEvent::Fill { order_id, .. } | OrderEvent::Cancelled { order_id, .. } => order_id,
+        }
+    }
+
+    pub fn matched(&self) -> Decimal {
+        match self {
+            OrderEvent::Fill { matched, .. } | OrderEvent::Cancelled { matched, .. } => *matched,
+        }
+    }
+}
+
+/// 单个订单的推送状态
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderFillState {
+    /// 累计成交份数
+    pub matched: Decimal,
+    /// 已撤销：此后不会再有成交
+    pub cancelled: bool,
+}
+
+/// 按订单 ID 记录用户频道推送的累计成交（执行器与风险管理器共享）
+#[derive(Default)]
+pub struct OrderFills {
+    orders: DashMap<String, OrderFillState>,
+}
+
+impl OrderFills {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记入一条推送，返回是否有变化（累计成交增加或首次标记撤销）
+    pub fn apply(&self, event: &OrderEvent) -> bool {
+        let mut state = self.orders.entry(event.order_id().to_string()).or_default();
+        let mut changed = false;
+        if event.matched() > state.matched {
+            state.matched = event.matched();
+            changed = true;
+        }
+        if matches!(event, OrderEvent::Cancelled { .. }) && !state.cancelled {
+            state.cancelled = true;
+            changed = true;
+        }
+        changed
+    }
+
+    /// 订单累计成交；未收到过推送时为 None
+    pub fn matched(&self, order_id: &str) -> Option<Decimal> {
+        self.orders.get(order_id).map(|s| s.matched)
+    }
+}
+
+/// 把 SDK 推送转成订单事件：挂单（PLACEMENT）与成交记录（trade）不产生事件，成交以订单的累计成交为准
+fn to_event(msg: WsMessage) -> Option<OrderEvent> {
+    let WsMessage::Order(order) = msg else {
+        return None;
+    };
+    match order.msg_type {
+        OrderMessageType::Update => Some(OrderEvent::Fill {
+            order_id: order.id,
+            matched: order.size_matched,
+            price: order.price,
+        }),
+        OrderMessageType::Cancellation => Some(OrderEvent::Cancelled {
+            order_id: order.id,
+            asset_id: order.asset_id,
+            matched: order.size_matched,
+        }),
+        _ => None,
+    }
+}
+
+/// 后台订阅用户频道（全部市场），每条有变化的推送先记入 fills 再交给 on_event；
+/// 用户频道需 API 凭证认证，断线或认证失败后按 1s、2s、4s…（上限 30s）退避重连
+pub fn spawn_order_updates<F>(credentials: Credentials, address: Address, fills: std::sync::Arc<OrderFills>, on_event: F)
+where
+    F: Fn(&OrderEvent) + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        let mut backoff = RECONNECT_BACKOFF;
+        loop {
+            match run_once(&credentials, address, &fills, &on_event).await {
+                Ok(()) => {
+                    // 连接曾正常建立，从初始等待重新退避
+                    backoff = RECONNECT_BACKOFF;
+                    warn!("用户频道推送流已结束，{}s 后重连", backoff.as_secs());
+                }
+                Err(e) => {
+                    warn!(error = %e, "用户频道订阅失败，{}s 后重连", backoff.as_secs());
+                }
+            }
+            sleep(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+        }
+    });
+}
+
+/// 建立一次认证连接并处理推送，直到流结束或出错
+async fn run_once<F>(credentials: &Credentials, address: Address, fills: &OrderFills, on_event: &F) -> Result<()>
+where
+    F: Fn(&OrderEvent),
+{
+    // 用户频道为私有数据，需用 API 凭证认证（订单簿订阅用的是未认证客户端）
+    let client = WsClient::default()
+        .authenticate(credentials.clone(), address)
+        .map_err(|e| anyhow::anyhow!("用户频道认证失败: {}", e))?;
+    // 空列表表示订阅本账户全部市场，跨 5 分钟窗口无需重新订阅
+    let mut stream = client
+        .subscribe_user_events(Vec::new())
+        .map_err(|e| anyhow::anyhow!("订阅用户频道失败: {}", e))?;
+    info!(address = %address, "📡 已订阅用户频道（订单成交/撤单推送）");
+    while let Some(msg) = stream.next().await {
+        let msg = msg.map_err(|e| anyhow::anyhow!("用户频道推送错误: {}", e))?;
+        let Some(event) = to_event(msg) else {
+            continue;
+        };
+        if fills.apply(&event) {
+            on_event(&event);
+        } else {
+            debug!(order_id = %event.order_id(), matched = %event.matched(), "用户频道重复推送，忽略");
+        }
+    }
+    Ok(())
+}
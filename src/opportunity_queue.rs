@@ -0,0 +1,239 @@
+//! 套利机会队列：监控端把机会序列化为 JSON 发出，执行端订阅后下单，使监控与执行可分进程部署。
+//!
+//! | 实现 | OPPORTUNITY_SINK | 说明 |
+//! |------|------------------|------|
+//! | [`ChannelSink`] / [`ChannelSource`] | —（进程内） | tokio mpsc 通道 |
+//! | `RedisSink` / `RedisSource` | `redis` | LPUSH 到 Redis 列表，执行端 BRPOP；需 `--features redis-queue` |
+//!
+//! ## 消息格式（JSON）
+//!
+//! ```text
+//! {
+//!   "ts_ms": 1770972301234,          // 发出时间，Unix 毫秒
+//!   "market_id": "0xb911...",
+//!   "yes_token_id": "7160...3653",   // 十进制字符串
+//!   "no_token_id": "4521...0917",
+//!   "yes_ask_price": "0.47", "no_ask_price": "0.50",
+//!   "total_cost": "0.97", "profit_percentage": "3.09",
+//!   "yes_size": "120", "no_size": "120",
+//!   "category": "btc",
+//!   "book_age_ms": 35,               // 发出时较旧一侧订单簿的陈旧时长
+//!   "yes_dir": "↓", "no_dir": "↑",   // 涨跌方向（↑ ↓ −，未知为空串）
+//!   "trace_id": "9f0c...e41a"        // 机会追踪 ID，执行端沿用以串联两端的 span（旧消息缺省为空）
+//! }
+//! ```
+//!
+//! 价格与数量均为十进制字符串，避免浮点精度损失。执行端按 `book_age_ms + (收到时间 − ts_ms)` 还原陈旧度。
+
+use anyhow::Result;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+/// 队列中的一条套利机会
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpportunityMessage {
+    pub ts_ms: i64,
+    pub market_id: String,
+    pub yes_token_id: String,
+    pub no_token_id: String,
+    pub yes_ask_price: String,
+    pub no_ask_price: String,
+    pub total_cost: String,
+    pub profit_percentage: String,
+    pub yes_size: String,
+    pub no_size: String,
+    pub category: Option<String>,
+    pub book_age_ms: u64,
+    pub yes_dir: String,
+    pub no_dir: String,
+    #[serde(default)]
+    pub trace_id: String,
+}
+
+impl OpportunityMessage {
+    /// 收到时的订单簿陈旧时长（毫秒）：发出时的陈旧度 + 队列中经过的时间（跨机器时钟偏差按 0 截断）
+    pub fn age_ms_now(&self) -> u64 {
+        let in_queue = (Utc::now().timestamp_millis() - self.ts_ms).max(0) as u64;
+        self.book_age_ms.saturating_add(in_queue)
+    }
+}
+
+/// 机会发布端（监控进程）
+pub trait OpportunitySink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 发布一条机会；失败只影响本条
+    fn publish(&self, msg: &OpportunityMessage) -> BoxFuture<'_, Result<()>>;
+}
+
+/// 机会订阅端（执行进程）
+pub trait OpportunitySource: Send {
+    fn name(&self) -> &'static str;
+
+    /// 等待下一条机会；发布端全部关闭时返回 None
+    fn next(&mut self) -> BoxFuture<'_, Result<Option<OpportunityMessage>>>;
+}
+
+/// 进程内通道：发布端满时直接丢弃（机会过时即无价值，不阻塞监控）
+#[derive(Clone)]
+pub struct ChannelSink {
+    tx: mpsc::Sender<OpportunityMessage>,
+}
+
+pub struct ChannelSource {
+    rx: mpsc::Receiver<OpportunityMessage>,
+}
+
+impl ChannelSink {
+    pub fn new(capacity: usize) -> (Self, ChannelSource) {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        (Self { tx }, ChannelSource { rx })
+    }
+}
+
+impl OpportunitySink for ChannelSink {
+    fn name(&self) -> &'static str {
+        "channel"
+    }
+
+    fn publish(&self, msg: &OpportunityMessage) -> BoxFuture<'_, Result<()>> {
+        let msg = msg.clone();
+        Box::pin(async move {
+            self.tx
+                .try_send(msg)
+                .map_err(|e| anyhow::anyhow!("机会通道发送失败: {}", e))
+        })
+    }
+}
+
+impl OpportunitySource for ChannelSource {
+    fn name(&self) -> &'static str {
+        "channel"
+    }
+
+    fn next(&mut self) -> BoxFuture<'_, Result<Option<OpportunityMessage>>> {
+        Box::pin(async move { Ok(self.rx.recv().await) })
+    }
+}
+
+#[cfg(feature = "redis-queue")]
+pub use redis_queue::{RedisSink, RedisSource};
+
+#[cfg(feature = "redis-queue")]
+mod redis_queue {
+    use super::*;
+    use anyhow::Context;
+    use redis::aio::MultiplexedConnection;
+
+    /// BRPOP 单次阻塞超时（秒），超时后继续等待
+    const POP_TIMEOUT_SECS: u64 = 5;
+
+    async fn connect(url: &str) -> Result<MultiplexedConnection> {
+        redis::Client::open(url)
+            .with_context(|| format!("Redis 地址无效: {}", url))?
+            .get_multiplexed_async_connection()
+            .await
+            .with_context(|| format!("连接 Redis 失败: {}", url))
+    }
+
+    /// LPUSH 到 Redis 列表
+    pub struct RedisSink {
+        conn: MultiplexedConnection,
+        key: String,
+    }
+
+    impl RedisSink {
+        pub async fn connect(url: &str, key: &str) -> Result<Self> {
+            Ok(Self {
+                conn: connect(url).await?,
+                key: key.to_string(),
+            })
+        }
+    }
+
+    impl OpportunitySink for RedisSink {
+        fn name(&self) -> &'static str {
+            "redis"
+        }
+
+        fn publish(&self, msg: &OpportunityMessage) -> BoxFuture<'_, Result<()>> {
+            let payload = serde_json::to_string(msg);
+            Box::pin(async move {
+                let mut conn = self.conn.clone();
+                let _: i64 = redis::cmd("LPUSH")
+                    .arg(&self.key)
+                    .arg(payload?)
+                    .query_async(&mut conn)
+                    .await?;
+                Ok(())
+            })
+        }
+    }
+
+    /// BRPOP 消费 Redis 列表（与 LPUSH 组成先进先出）
+    pub struct RedisSource {
+        conn: MultiplexedConnection,
+        key: String,
+    }
+
+    impl RedisSource {
+        pub async fn connect(url: &str, key: &str) -> Result<Self> {
+            Ok(Self {
+                conn: connect(url).await?,
+                key: key.to_string(),
+            })
+        }
+    }
+
+    impl OpportunitySource for RedisSource {
+        fn name(&self) -> &'static str {
+            "redis"
+        }
+
+        fn next(&mut self) -> BoxFuture<'_, Result<Option<OpportunityMessage>>> {
+            Box::pin(async move {
+                loop {
+                    let popped: Option<(String, String)> = redis::cmd("BRPOP")
+                        .arg(&self.key)
+                        .arg(POP_TIMEOUT_SECS)
+                        .query_async(&mut self.conn)
+                        .await?;
+                    if let Some((_, payload)) = popped {
+                        return Ok(Some(serde_json::from_str(&payload).context("机会消息解析失败")?));
+                    }
+                }
+            })
+        }
+    }
+}
+
+/// 按配置构建发布端：none（不发布）| redis
+pub async fn sink_from_config(backend: &str, url: &str, key: &str) -> Result<Option<Box<dyn OpportunitySink>>> {
+    match backend.trim().to_lowercase().as_str() {
+        "" | "none" | "off" => Ok(None),
+        #[cfg(feature = "redis-queue")]
+        "redis" => Ok(Some(Box::new(RedisSink::connect(url, key).await?))),
+        #[cfg(not(feature = "redis-queue"))]
+        "redis" => {
+            let _ = (url, key);
+            anyhow::bail!("OPPORTUNITY_SINK=redis 需以 --features redis-queue 构建")
+        }
+        other => anyhow::bail!("未知 OPPORTUNITY_SINK: {}（可用: none, redis）", other),
+    }
+}
+
+/// 按配置构建订阅端（执行进程）：redis
+pub async fn source_from_config(backend: &str, url: &str, key: &str) -> Result<Box<dyn OpportunitySource>> {
+    match backend.trim().to_lowercase().as_str() {
+        #[cfg(feature = "redis-queue")]
+        "redis" => Ok(Box::new(RedisSource::connect(url, key).await?)),
+        #[cfg(not(feature = "redis-queue"))]
+        "redis" => {
+            let _ = (url, key);
+            anyhow::bail!("OPPORTUNITY_SINK=redis 需以 --features redis-queue 构建")
+        }
+        other => anyhow::bail!("订阅端需要外部队列，OPPORTUNITY_SINK={} 不支持（可用: redis）", other),
+    }
+}
@@ -3,7 +3,8 @@
 use anyhow::{Context, Result};
 use polymarket_client_sdk::data::types::request::PositionsRequest;
 use polymarket_client_sdk::data::Client;
-use polymarket_client_sdk::types::Address;
+use polymarket_client_sdk::types::{Address, Decimal, B256, U256};
+use std::collections::HashMap;
 
 /// Data API 返回的持仓结构，重新导出便于调用方使用
 pub use polymarket_client_sdk::data::types::response::Position;
@@ -41,3 +42,97 @@ pub async fn get_positions() -> Result<Vec<Position>> {
     let req = PositionsRequest::builder().user(user).build();
     client.positions(&req).await.context("获取持仓失败")
 }
+
+/// 按 condition 分组的持仓快照，区分可 merge 的双边平衡部分与需卖出的净持仓
+#[derive(Debug, Clone, Default)]
+pub struct PositionBook {
+    by_condition: HashMap<B256, Vec<(U256, Decimal)>>,
+}
+
+impl PositionBook {
+    /// 由 Data API 持仓构建（数量非正的记录忽略）
+    pub fn from_positions(positions: &[Position]) -> Self {
+        Self::from_holdings(positions.iter().map(|p| (p.condition_id, p.asset, p.size)))
+    }
+
+    /// 由 (condition_id, token_id, 数量) 构建，数量非正的记录忽略
+    pub fn from_holdings(holdings: impl IntoIterator<Item = (B256, U256, Decimal)>) -> Self {
+        let mut by_condition: HashMap<B256, Vec<(U256, Decimal)>> = HashMap::new();
+        for (condition_id, token_id, size) in holdings {
+            if size > Decimal::ZERO {
+                by_condition.entry(condition_id).or_default().push((token_id, size));
+            }
+        }
+        Self { by_condition }
+    }
+
+    /// 持仓 token 数
+    pub fn len(&self) -> usize {
+        self.by_condition.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_condition.is_empty()
+    }
+
+    /// 每个 condition 可 merge 的平衡数量：双边都持仓时为 min(YES, NO)
+    pub fn balanced(&self) -> Vec<(B256, Decimal)> {
+        self.by_condition
+            .iter()
+            .filter_map(|(condition_id, legs)| {
+                let amount = Self::merge_amount(legs);
+                (amount > Decimal::ZERO).then_some((*condition_id, amount))
+            })
+            .collect()
+    }
+
+    /// 每个 token 扣除平衡部分后的净持仓 (condition_id, token_id, 数量)；单边持仓全部为净持仓
+    pub fn net_holdings(&self) -> Vec<(B256, U256, Decimal)> {
+        self.by_condition
+            .iter()
+            .flat_map(|(condition_id, legs)| {
+                let amount = Self::merge_amount(legs);
+                legs.iter()
+                    .map(move |(token_id, size)| (*condition_id, *token_id, *size - amount))
+                    .filter(|(_, _, excess)| *excess > Decimal::ZERO)
+            })
+            .collect()
+    }
+
+    fn merge_amount(legs: &[(U256, Decimal)]) -> Decimal {
+        match legs {
+            [a, b] => a.1.min(b.1),
+            _ => Decimal::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn position_book_splits_balanced_and_net() {
+        let (c1, c2) = (B256::repeat_byte(1), B256::repeat_byte(2));
+        let book = PositionBook::from_holdings([
+            (c1, U256::from(11), dec!(10)),
+            (c1, U256::from(12), dec!(4)),
+            (c2, U256::from(21), dec!(3)),
+            (c2, U256::from(22), dec!(0)),
+        ]);
+        assert_eq!(book.len(), 3);
+        assert_eq!(book.balanced(), vec![(c1, dec!(4))]);
+        let mut net = book.net_holdings();
+        net.sort();
+        assert_eq!(net, vec![(c1, U256::from(11), dec!(6)), (c2, U256::from(21), dec!(3))]);
+    }
+
+    #[test]
+    fn empty_position_book_has_nothing_to_flatten() {
+        let book = PositionBook::from_holdings([(B256::ZERO, U256::from(1), dec!(-1))]);
+        assert!(book.is_empty());
+        assert!(book.balanced().is_empty());
+        assert!(book.net_holdings().is_empty());
+    }
+}
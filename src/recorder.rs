@@ -0,0 +1,291 @@
+//! 订单簿快照记录器：将实时订单簿按时间戳写入 JSONL，供回测重放。
+//!
+//! 记录在独立的写盘线程中执行（std::fs 为阻塞 IO，不占用 tokio 工作线程），主循环只做一次非阻塞 `try_send`，通道满时直接丢弃该快照，
+//! 保证记录永远不会拖慢交易。文件按大小轮转。
+//!
+//! ## 快照格式（每行一个 JSON 对象）
+//!
+//! ```text
+//! {
+//!   "ts_ms": 1770972301234,            // 本地接收时间，Unix 毫秒
+//!   "asset_id": "7160...3653",         // token_id（十进制字符串）
+//!   "bids": [{"price": "0.48", "size": "120.5"}, ...],
+//!   "asks": [{"price": "0.52", "size": "80"}, ...]
+//! }
+//! ```
+//!
+//! `bids`/`asks` 保持 WS 推送的原始顺序（asks 最后一个为卖一价，bids 最后一个为买一价）。
+//! 价格与数量均为十进制字符串，避免浮点精度损失。
+//!
+//! 文件命名：`books-YYYYmmdd-HHMMSS.jsonl`；同一秒内再次轮转时为 `books-YYYYmmdd-HHMMSS-N.jsonl`（N 从 1 递增）。
+//! 读取时按（时间戳, 轮转序号）排序，不按原始文件名字典序（`-N` 会排到不带序号的首个文件之前）。
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use polymarket_client_sdk::clob::ws::types::response::BookUpdate;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// 通道容量：写盘跟不上时最多缓存的快照数，超出则丢弃
+const CHANNEL_CAPACITY: usize = 4096;
+
+/// 单档价格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotLevel {
+    pub price: String,
+    pub size: String,
+}
+
+/// 单条订单簿快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    pub ts_ms: i64,
+    pub asset_id: String,
+    pub bids: Vec<SnapshotLevel>,
+    pub asks: Vec<SnapshotLevel>,
+}
+
+impl BookSnapshot {
+    pub fn from_book(book: &BookUpdate) -> Self {
+        Self {
+            ts_ms: Utc::now().timestamp_millis(),
+            asset_id: book.asset_id.to_string(),
+            bids: book
+                .bids
+                .iter()
+                .map(|l| SnapshotLevel { price: l.price.to_string(), size: l.size.to_string() })
+                .collect(),
+            asks: book
+                .asks
+                .iter()
+                .map(|l| SnapshotLevel { price: l.price.to_string(), size: l.size.to_string() })
+                .collect(),
+        }
+    }
+}
+
+/// 记录器句柄：clone 后可在多处发送快照
+#[derive(Clone)]
+pub struct BookRecorder {
+    tx: mpsc::Sender<BookSnapshot>,
+}
+
+impl BookRecorder {
+    /// 启动写盘线程。`dir` 不存在时自动创建；`max_file_bytes` 为单文件轮转阈值。
+    pub fn spawn(dir: impl Into<PathBuf>, max_file_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("创建记录目录失败: {}", dir.display()))?;
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let writer_dir = dir.clone();
+        std::thread::Builder::new()
+            .name("book-recorder".to_string())
+            .spawn(move || run_writer(writer_dir, max_file_bytes, rx))
+            .context("启动订单簿记录线程失败")?;
+        info!(dir = %dir.display(), max_file_bytes, "📼 订单簿记录器已启动");
+        Ok(Self { tx })
+    }
+
+    /// 非阻塞记录一条订单簿；通道已满或写盘线程已退出时丢弃
+    pub fn record(&self, book: &BookUpdate) {
+        if let Err(e) = self.tx.try_send(BookSnapshot::from_book(book)) {
+            debug!(error = %e, "订单簿快照丢弃（记录器繁忙或已关闭）");
+        }
+    }
+}
+
+struct RotatingWriter {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    writer: Option<BufWriter<File>>,
+    written: u64,
+}
+
+impl RotatingWriter {
+    fn rotate(&mut self) -> Result<()> {
+        if let Some(mut w) = self.writer.take() {
+            w.flush()?;
+        }
+        let mut path = self.dir.join(format!("books-{}.jsonl", Utc::now().format("%Y%m%d-%H%M%S")));
+        // 同一秒内多次轮转时追加序号，避免覆盖
+        let mut seq = 1;
+        while path.exists() {
+            path = self.dir.join(format!("books-{}-{}.jsonl", Utc::now().format("%Y%m%d-%H%M%S"), seq));
+            seq += 1;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        info!(file = %path.display(), "📼 订单簿记录切换到新文件");
+        self.writer = Some(BufWriter::new(file));
+        self.written = 0;
+        Ok(())
+    }
+
+    fn write(&mut self, snap: &BookSnapshot) -> Result<()> {
+        if self.writer.is_none() || (self.max_file_bytes > 0 && self.written >= self.max_file_bytes) {
+            self.rotate()?;
+        }
+        let line = serde_json::to_string(snap)?;
+        if let Some(w) = self.writer.as_mut() {
+            writeln!(w, "{}", line)?;
+            self.written += line.len() as u64 + 1;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        if let Some(w) = self.writer.as_mut() {
+            if let Err(e) = w.flush() {
+                warn!(error = %e, "订单簿记录刷盘失败");
+            }
+        }
+    }
+}
+
+/// 写盘线程主循环：阻塞等待快照，所有发送端释放后刷盘退出
+fn run_writer(dir: PathBuf, max_file_bytes: u64, mut rx: mpsc::Receiver<BookSnapshot>) {
+    let mut writer = RotatingWriter { dir, max_file_bytes, writer: None, written: 0 };
+    while let Some(snap) = rx.blocking_recv() {
+        if let Err(e) = writer.write(&snap) {
+            error!(error = %e, "写入订单簿快照失败");
+        }
+        // 通道暂时为空时刷盘，减少异常退出丢数据
+        if rx.is_empty() {
+            writer.flush();
+        }
+    }
+    writer.flush();
+    info!("📼 订单簿记录器已停止");
+}
+
+/// 快照文件排序键：(时间戳, 轮转序号)。`books-TS.jsonl` 序号为 0，`books-TS-N.jsonl` 序号为 N
+fn snapshot_order_key(path: &Path) -> (String, u32) {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let name = stem.strip_prefix("books-").unwrap_or(stem);
+    match name.rsplit_once('-') {
+        // 时间戳本身形如 YYYYmmdd-HHMMSS，只有第三段才是轮转序号
+        Some((base, seq)) if base.contains('-') => match seq.parse() {
+            Ok(n) => (base.to_string(), n),
+            Err(_) => (name.to_string(), 0),
+        },
+        _ => (name.to_string(), 0),
+    }
+}
+
+/// 列出目录下所有快照文件，按时间戳与轮转序号升序
+pub fn list_snapshot_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("读取记录目录失败: {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension().map(|x| x == "jsonl").unwrap_or(false)
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| n.starts_with("books-"))
+                    .unwrap_or(false)
+        })
+        .collect();
+    files.sort_by_cached_key(|p| snapshot_order_key(p));
+    Ok(files)
+}
+
+/// 读取单个快照文件；损坏的行（如进程被杀时写了一半）跳过并告警
+pub fn read_snapshots(path: &Path) -> Result<Vec<BookSnapshot>> {
+    let file = File::open(path).with_context(|| format!("打开快照文件失败: {}", path.display()))?;
+    let mut out = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<BookSnapshot>(&line) {
+            Ok(s) => out.push(s),
+            Err(e) => warn!(file = %path.display(), line = i + 1, error = %e, "跳过无法解析的快照行"),
+        }
+    }
+    Ok(out)
+}
+
+/// 读取目录下全部快照，按时间戳排序，供回测重放
+pub fn read_snapshot_dir(dir: &Path) -> Result<Vec<BookSnapshot>> {
+    let mut all = Vec::new();
+    for f in list_snapshot_files(dir)? {
+        all.extend(read_snapshots(&f)?);
+    }
+    all.sort_by_key(|s| s.ts_ms);
+    Ok(all)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 临时目录下的唯一记录目录，测试结束时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("recorder-test-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn snapshot(ts_ms: i64) -> BookSnapshot {
+        BookSnapshot { ts_ms, asset_id: "1".to_string(), bids: Vec::new(), asks: Vec::new() }
+    }
+
+    #[test]
+    fn rotated_files_are_listed_after_their_base() {
+        let tmp = TempDir::new();
+        for name in [
+            "books-20260101-000001.jsonl",
+            "books-20260101-000000-10.jsonl",
+            "books-20260101-000000-2.jsonl",
+            "books-20260101-000000.jsonl",
+            "books-20260101-000000-1.jsonl",
+            "other.jsonl",
+        ] {
+            File::create(tmp.0.join(name)).unwrap();
+        }
+        let names: Vec<String> = list_snapshot_files(&tmp.0)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "books-20260101-000000.jsonl",
+                "books-20260101-000000-1.jsonl",
+                "books-20260101-000000-2.jsonl",
+                "books-20260101-000000-10.jsonl",
+                "books-20260101-000001.jsonl",
+            ]
+        );
+    }
+
+    #[test]
+    fn writer_rotates_by_size_and_files_read_back_in_order() {
+        let tmp = TempDir::new();
+        // 每条快照都超过 1 字节阈值：每次写入前轮转，同一秒内产生 books-TS、books-TS-1、books-TS-2
+        let mut writer = RotatingWriter { dir: tmp.0.clone(), max_file_bytes: 1, writer: None, written: 0 };
+        for ts in 1..=3 {
+            writer.write(&snapshot(ts)).unwrap();
+        }
+        writer.flush();
+        let files = list_snapshot_files(&tmp.0).unwrap();
+        assert_eq!(files.len(), 3);
+        let per_file: Vec<Vec<i64>> =
+            files.iter().map(|f| read_snapshots(f).unwrap().iter().map(|s| s.ts_ms).collect()).collect();
+        assert_eq!(per_file, vec![vec![1], vec![2], vec![3]]);
+    }
+}
@@ -6,7 +6,6 @@ use polymarket_client_sdk::clob::Client;
 use polymarket_client_sdk::clob::types::{OrderType, Side};
 use polymarket_client_sdk::clob::ws::types::response::BookUpdate;
 use polymarket_client_sdk::types::{Address, Decimal, U256};
-use polymarket_client_sdk::POLYGON;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
 use std::str::FromStr;
@@ -34,6 +33,7 @@ pub struct HedgeMonitor {
     client: Client<polymarket_client_sdk::auth::state::Authenticated<polymarket_client_sdk::auth::Normal>>,
     private_key: String,
     proxy_address: Option<Address>,
+    chain_id: u64, // 签名用链 ID
     positions: DashMap<String, HedgePosition>, // pair_id -> position
     position_tracker: Arc<PositionTracker>, // 用于更新风险敞口
 }
@@ -43,12 +43,14 @@ impl HedgeMonitor {
         client: Client<polymarket_client_sdk::auth::state::Authenticated<polymarket_client_sdk::auth::Normal>>,
         private_key: String,
         proxy_address: Option<Address>,
+        chain_id: u64,
         position_tracker: Arc<PositionTracker>,
     ) -> Self {
         Self {
             client,
             private_key,
             proxy_address,
+            chain_id,
             positions: DashMap::new(),
             position_tracker,
         }
@@ -222,6 +224,7 @@ impl HedgeMonitor {
                 let positions = self.positions.clone();
                 let client = self.client.clone();
                 let private_key = self.private_key.clone();
+                let chain_id = self.chain_id;
                 
                 // 先标记为正在处理，避免重复下单（使用remove+insert避免阻塞）
                 if let Some((_, mut pos)) = self.positions.remove(&pair_id) {
@@ -232,7 +235,7 @@ impl HedgeMonitor {
                 tokio::spawn(async move {
                     // 重新创建 signer（因为不能在 spawn 中直接使用 self）
                     let signer = match LocalSigner::from_str(&private_key) {
-                        Ok(s) => s.with_chain_id(Some(POLYGON)),
+                        Ok(s) => s.with_chain_id(Some(chain_id)),
                         Err(e) => {
                             error!(
                                 "❌ 创建signer失败 | 市场:{} | 错误:{}",
@@ -487,7 +490,7 @@ impl HedgeMonitor {
         size: Option<Decimal>,
     ) -> Result<(String, Decimal, Decimal)> {
         let signer = LocalSigner::from_str(&self.private_key)?
-            .with_chain_id(Some(POLYGON));
+            .with_chain_id(Some(self.chain_id));
 
         // 计算手续费
         // 公式: fee = c * fee_rate * (p * (1-p))^exponent
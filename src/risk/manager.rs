@@ -8,7 +8,9 @@ use tracing::{debug, error, info};
 
 use super::positions::PositionTracker;
 use super::recovery::{RecoveryAction, RecoveryStrategy};
-use crate::config::Config as BotConfig;
+use crate::config::{decimal_setting, Config as BotConfig};
+use crate::monitor::ws::{OrderEvent, OrderFills};
+use crate::trading::clock::short_id;
 use crate::trading::executor::OrderPairResult;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,6 +35,9 @@ pub struct OrderPair {
     pub no_size: Decimal,
     pub yes_filled: Decimal,
     pub no_filled: Decimal,
+    /// 下单时的卖一价（买入价），单边卖出时作为保本价
+    pub yes_price: Decimal,
+    pub no_price: Decimal,
     pub status: PairStatus,
     pub created_at: DateTime<Utc>,
 }
@@ -42,24 +47,103 @@ pub struct RiskManager {
     pending_pairs: DashMap<String, OrderPair>,
     position_tracker: std::sync::Arc<PositionTracker>,
     recovery_strategy: RecoveryStrategy,
+    order_fills: Option<std::sync::Arc<OrderFills>>, // 用户频道推送的订单累计成交，注册订单对时补上已推送的成交
+}
+
+/// 按两腿成交与下单数量判定订单对状态
+fn pair_status(yes_filled: Decimal, yes_size: Decimal, no_filled: Decimal, no_size: Decimal) -> PairStatus {
+    if yes_filled == yes_size && no_filled == no_size {
+        PairStatus::BothFilled
+    } else if yes_filled > dec!(0) && no_filled > dec!(0) {
+        PairStatus::PartiallyFilled
+    } else if yes_filled > dec!(0) || no_filled > dec!(0) {
+        PairStatus::OneFailed
+    } else {
+        PairStatus::BothFailed
+    }
 }
 
 impl RiskManager {
     pub fn new(
         clob_client: Client<polymarket_client_sdk::auth::state::Authenticated<polymarket_client_sdk::auth::Normal>>,
         config: &BotConfig,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        Ok(Self {
             clob_client,
             pending_pairs: DashMap::new(),
-            position_tracker: std::sync::Arc::new(PositionTracker::new(
-                Decimal::try_from(config.risk_max_exposure_usdc).unwrap_or(dec!(1000.0)),
-            )),
+            position_tracker: std::sync::Arc::new(PositionTracker::new(decimal_setting(
+                "RISK_MAX_EXPOSURE_USDC",
+                config.risk_max_exposure_usdc,
+                dec!(0),
+                None,
+            )?)),
             recovery_strategy: RecoveryStrategy::new(
                 config.risk_imbalance_threshold,
                 config.hedge_take_profit_pct,
                 config.hedge_stop_loss_pct,
+                config.unwind_style,
+                config.on_imbalance,
+            )?,
+            order_fills: None,
+        })
+    }
+
+    /// 共享用户频道的订单成交记录（与执行器同一份），注册订单对时补上已推送的成交
+    pub fn with_order_fills(mut self, fills: std::sync::Arc<OrderFills>) -> Self {
+        self.order_fills = Some(fills);
+        self
+    }
+
+    /// 处理用户频道的订单推送：累计成交超过订单对已记录的部分计入持仓并更新订单对状态；
+    /// 撤单只记日志（未成交部分不再成交）。不属于已注册订单对的订单（如单边卖出）忽略
+    pub fn apply_order_event(&self, event: &OrderEvent) {
+        let order_id = event.order_id();
+        let Some(mut pair) = self
+            .pending_pairs
+            .iter_mut()
+            .find(|p| p.yes_order_id == order_id || p.no_order_id == order_id)
+        else {
+            return;
+        };
+        let is_yes = pair.yes_order_id == order_id;
+        let (token_id, filled) = if is_yes {
+            (pair.yes_token_id, pair.yes_filled)
+        } else {
+            (pair.no_token_id, pair.no_filled)
+        };
+        let delta = event.matched() - filled;
+        if delta > dec!(0) {
+            if is_yes {
+                pair.yes_filled = event.matched();
+            } else {
+                pair.no_filled = event.matched();
+            }
+            pair.status = pair_status(pair.yes_filled, pair.yes_size, pair.no_filled, pair.no_size);
+            self.position_tracker.update_position(token_id, delta);
+        }
+        match event {
+            OrderEvent::Fill { price, .. } if delta > dec!(0) => info!(
+                "📬 推送成交 | {} | {} +{}@{} | YES {}/{} NO {}/{}",
+                short_id(&pair.pair_id),
+                if is_yes { "YES" } else { "NO" },
+                delta,
+                price,
+                pair.yes_filled,
+                pair.yes_size,
+                pair.no_filled,
+                pair.no_size
+            ),
+            OrderEvent::Cancelled { asset_id, .. } => info!(
+                "📭 推送撤单 | {} | {} token={} | 最终成交 YES {}/{} NO {}/{}",
+                short_id(&pair.pair_id),
+                if is_yes { "YES" } else { "NO" },
+                asset_id,
+                pair.yes_filled,
+                pair.yes_size,
+                pair.no_filled,
+                pair.no_size
             ),
+            _ => {}
         }
     }
 
@@ -75,17 +159,16 @@ impl RiskManager {
         yes_price: Decimal,
         no_price: Decimal,
     ) {
-        let status = if result.yes_filled == result.yes_size && result.no_filled == result.no_size {
-            PairStatus::BothFilled
-        } else if result.yes_filled > dec!(0) && result.no_filled > dec!(0) {
-            PairStatus::PartiallyFilled
-        } else if result.yes_filled > dec!(0) && result.no_filled == dec!(0) {
-            PairStatus::OneFailed
-        } else if result.yes_filled == dec!(0) && result.no_filled > dec!(0) {
-            PairStatus::OneFailed
-        } else {
-            PairStatus::BothFailed
+        // 提交后、注册前已由用户频道推送的成交一并计入
+        let pushed = |order_id: &str| {
+            self.order_fills
+                .as_ref()
+                .and_then(|f| f.matched(order_id))
+                .unwrap_or(dec!(0))
         };
+        let yes_filled = result.yes_filled.max(pushed(&result.yes_order_id));
+        let no_filled = result.no_filled.max(pushed(&result.no_order_id));
+        let status = pair_status(yes_filled, result.yes_size, no_filled, result.no_size);
 
         let pair = OrderPair {
             pair_id: result.pair_id.clone(),
@@ -96,8 +179,10 @@ impl RiskManager {
             no_token_id: no_token,
             yes_size: result.yes_size,
             no_size: result.no_size,
-            yes_filled: result.yes_filled,
-            no_filled: result.no_filled,
+            yes_filled,
+            no_filled,
+            yes_price,
+            no_price,
             status: status.clone(),
             created_at: Utc::now(),
         };
@@ -109,6 +194,9 @@ impl RiskManager {
         // 这个日志已经在executor中打印了，这里不再重复打印
         debug!(
             pair_id = %pair.pair_id,
+            strategy_tag = %result.strategy_tag,
+            expected_edge = %result.expected_edge,
+            realized_edge = ?result.realized_edge,
             status = ?status,
             yes_filled = %pair.yes_filled,
             no_filled = %pair.no_filled,
@@ -128,14 +216,24 @@ impl RiskManager {
             .clone();
 
         match pair.status {
+            PairStatus::BothFilled if pair.yes_filled != pair.no_filled => {
+                // 两腿下单数量不同时，各自完全成交仍会留下差额
+                Ok(self.recovery_strategy.handle_filled_imbalance(&pair))
+            }
             PairStatus::BothFilled => {
                 info!(pair_id = %pair.pair_id, "两个订单都完全成交，无需恢复");
                 Ok(RecoveryAction::None)
             }
             PairStatus::PartiallyFilled => {
-                self.recovery_strategy
+                let action = self
+                    .recovery_strategy
                     .handle_partial_fill(&pair, &self.position_tracker)
-                    .await
+                    .await?;
+                // 不平衡未超过阈值（或未配置卖出）时，剩余差额按 ON_IMBALANCE 处理
+                match action {
+                    RecoveryAction::None => Ok(self.recovery_strategy.handle_filled_imbalance(&pair)),
+                    action => Ok(action),
+                }
             }
             PairStatus::OneFailed => {
                 self.recovery_strategy
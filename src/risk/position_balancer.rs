@@ -10,7 +10,7 @@ use std::collections::HashMap;
 use tracing::{debug, error, info, warn};
 
 use super::positions::PositionTracker;
-use crate::config::Config as BotConfig;
+use crate::config::{decimal_setting, Config as BotConfig};
 use poly_5min_bot::positions::get_positions;
 
 /// 仓位平衡器
@@ -27,14 +27,14 @@ impl PositionBalancer {
         clob_client: Client<polymarket_client_sdk::auth::state::Authenticated<polymarket_client_sdk::auth::Normal>>,
         position_tracker: std::sync::Arc<PositionTracker>,
         config: &BotConfig,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        Ok(Self {
             clob_client,
             position_tracker,
-            threshold: Decimal::try_from(config.position_balance_threshold).unwrap_or(dec!(2.0)),
-            min_total: Decimal::try_from(config.position_balance_min_total).unwrap_or(dec!(5.0)),
-            max_order_size: Decimal::try_from(config.max_order_size_usdc).unwrap_or(dec!(5.0)),
-        }
+            threshold: decimal_setting("POSITION_BALANCE_THRESHOLD", config.position_balance_threshold, dec!(0), None)?,
+            min_total: decimal_setting("POSITION_BALANCE_MIN_TOTAL", config.position_balance_min_total, dec!(0), None)?,
+            max_order_size: decimal_setting("MAX_ORDER_SIZE_USDC", config.max_order_size_usdc, dec!(0), None)?,
+        })
     }
 
     /// 检查并平衡仓位：获取持仓和挂单，分析每个市场的YES/NO平衡情况，取消多余挂单
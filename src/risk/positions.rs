@@ -1,15 +1,39 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use dashmap::DashMap;
-use polymarket_client_sdk::types::{Decimal, U256};
+use polymarket_client_sdk::types::{Address, Decimal, U256};
 use rust_decimal_macros::dec;
-use tracing::{debug, info, trace};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{debug, info, trace, warn};
 
+use poly_5min_bot::merge;
 use poly_5min_bot::positions::{get_positions, Position};
 
+/// 持仓快照落盘格式：token_id 与数量均以十进制字符串保存
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SnapshotFile {
+    saved_at: i64,
+    positions: HashMap<String, String>,
+    exposure_costs: HashMap<String, String>,
+}
+
+/// 持仓快照配置：文件路径、链上核对用的持仓地址、是否有未落盘的变更
+struct Snapshot {
+    path: PathBuf,
+    owner: Option<Address>,
+    dirty: AtomicBool,
+}
+
 pub struct PositionTracker {
     positions: DashMap<U256, Decimal>, // token_id -> 数量（正数=持有多头，负数=持有空头）
     exposure_costs: DashMap<U256, Decimal>, // token_id -> 成本（USD），用于跟踪风险敞口
     max_exposure: Decimal,
+    snapshot: OnceLock<Snapshot>,
 }
 
 impl PositionTracker {
@@ -18,7 +42,143 @@ impl PositionTracker {
             positions: DashMap::new(),
             exposure_costs: DashMap::new(),
             max_exposure,
+            snapshot: OnceLock::new(),
+        }
+    }
+
+    /// 启用持仓快照：若文件已存在则加载到内存（用于崩溃重启后恢复持仓与敞口），返回加载的持仓条数。
+    /// `owner` 为持仓所在地址（proxy），供 [`Self::reconcile_with_chain`] 读取链上余额。
+    pub fn enable_snapshot(&self, path: PathBuf, owner: Option<Address>) -> Result<usize> {
+        let loaded = if path.exists() {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("读取持仓快照失败: {}", path.display()))?;
+            let file: SnapshotFile = serde_json::from_str(&text)
+                .with_context(|| format!("解析持仓快照失败: {}", path.display()))?;
+            let parse = |k: &str, v: &str| -> Option<(U256, Decimal)> {
+                Some((U256::from_str(k).ok()?, Decimal::from_str(v).ok()?))
+            };
+            for (k, v) in &file.positions {
+                match parse(k, v) {
+                    Some((token, size)) => {
+                        self.positions.insert(token, size);
+                    }
+                    None => warn!(token_id = %k, size = %v, "持仓快照条目无效，已忽略"),
+                }
+            }
+            for (token, cost) in file.exposure_costs.iter().filter_map(|(k, v)| parse(k, v)) {
+                self.exposure_costs.insert(token, cost);
+            }
+            info!(
+                "📂 已加载持仓快照 | 文件:{} | 持仓:{}条 | 保存于:{}",
+                path.display(),
+                self.positions.len(),
+                chrono::DateTime::from_timestamp(file.saved_at, 0)
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_else(|| file.saved_at.to_string())
+            );
+            self.positions.len()
+        } else {
+            info!("持仓快照文件不存在，将在首次持仓变更后创建: {}", path.display());
+            0
+        };
+        let _ = self.snapshot.set(Snapshot {
+            path,
+            owner,
+            dirty: AtomicBool::new(false),
+        });
+        Ok(loaded)
+    }
+
+    /// 标记快照待写入（由后台任务去抖后落盘）
+    fn mark_dirty(&self) {
+        if let Some(s) = self.snapshot.get() {
+            s.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// 立即写入快照（先写临时文件再 rename，避免写到一半崩溃留下损坏文件）
+    pub fn save_snapshot(&self) -> Result<()> {
+        let Some(s) = self.snapshot.get() else {
+            return Ok(());
+        };
+        let file = SnapshotFile {
+            saved_at: chrono::Utc::now().timestamp(),
+            positions: self
+                .positions
+                .iter()
+                .filter(|e| !e.value().is_zero())
+                .map(|e| (e.key().to_string(), e.value().to_string()))
+                .collect(),
+            exposure_costs: self
+                .exposure_costs
+                .iter()
+                .map(|e| (e.key().to_string(), e.value().to_string()))
+                .collect(),
+        };
+        let tmp = s.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&file)?)
+            .with_context(|| format!("写入持仓快照失败: {}", tmp.display()))?;
+        std::fs::rename(&tmp, &s.path)
+            .with_context(|| format!("替换持仓快照失败: {}", s.path.display()))?;
+        trace!("持仓快照已写入: {}", s.path.display());
+        Ok(())
+    }
+
+    /// 后台去抖落盘：每 `debounce` 检查一次，有变更才写入，避免每次持仓更新都写文件
+    pub fn spawn_snapshot_writer(self: Arc<Self>, debounce: Duration) {
+        if self.snapshot.get().is_none() {
+            return;
         }
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(debounce).await;
+                let dirty = self
+                    .snapshot
+                    .get()
+                    .map(|s| s.dirty.swap(false, Ordering::Relaxed))
+                    .unwrap_or(false);
+                if dirty {
+                    if let Err(e) = self.save_snapshot() {
+                        warn!(error = %e, "持仓快照写入失败，下次变更时重试");
+                        self.mark_dirty();
+                    }
+                }
+            }
+        });
+    }
+
+    /// 用链上 ERC1155 余额校正本地持仓：对本地记录的每个 token 读取 `balanceOf(owner, token_id)`，
+    /// 与本地数量不一致时以链上为准。需在 [`Self::enable_snapshot`] 时提供 owner。
+    pub async fn reconcile_with_chain(&self, rpc_url: Option<&str>, chain_id: u64) -> Result<()> {
+        let owner = self
+            .snapshot
+            .get()
+            .and_then(|s| s.owner)
+            .ok_or_else(|| anyhow::anyhow!("未配置持仓地址（POLYMARKET_PROXY_ADDRESS），无法与链上核对"))?;
+        let tokens: Vec<U256> = self.positions.iter().map(|e| *e.key()).collect();
+        if tokens.is_empty() {
+            return Ok(());
+        }
+        let balances = merge::ctf_balances(owner, &tokens, rpc_url, chain_id).await?;
+        let mut corrected = 0usize;
+        for (token, bal) in tokens.into_iter().zip(balances) {
+            let on_chain = merge::units_to_usdc(bal);
+            let local = self.get_position(token);
+            if (on_chain - local).abs() >= dec!(0.0001) {
+                warn!(
+                    "🔧 持仓与链上不一致，已校正 | token_id={} | 本地:{} | 链上:{}",
+                    token, local, on_chain
+                );
+                // 敞口按数量比例随 update_position 清理；先调敞口再调持仓，与 merge 扣减顺序一致
+                if on_chain < local {
+                    self.update_exposure_cost(token, dec!(0), on_chain - local);
+                }
+                self.update_position(token, on_chain - local);
+                corrected += 1;
+            }
+        }
+        info!("🔗 持仓链上核对完成 | 校正:{}条", corrected);
+        Ok(())
     }
 
     pub fn update_position(&self, token_id: U256, delta: Decimal) {
@@ -50,6 +210,7 @@ impl PositionTracker {
             trace!("update_position: exposure_costs已remove");
         }
         
+        self.mark_dirty();
         trace!("update_position: 完成");
     }
 
@@ -123,6 +284,7 @@ impl PositionTracker {
             drop(entry); // 显式释放写锁
         }
         
+        self.mark_dirty();
         trace!("update_exposure_cost: 完成");
     }
 
@@ -134,6 +296,7 @@ impl PositionTracker {
     /// 重置风险敞口（新一轮开始时调用，清空成本缓存，使本轮从 0 敞口重新累计）
     pub fn reset_exposure(&self) {
         self.exposure_costs.clear();
+        self.mark_dirty();
         info!("🔄 风险敞口已重置（新一轮）");
     }
 
@@ -144,6 +307,13 @@ impl PositionTracker {
             .unwrap_or(dec!(0))
     }
 
+    /// 每份持仓成本（敞口成本 / 持仓数量）；无持仓或本轮未记录成本时为 None
+    pub fn average_cost(&self, token_id: U256) -> Option<Decimal> {
+        let position = self.get_position(token_id);
+        let cost = self.exposure_costs.get(&token_id).map(|v| *v.value())?;
+        (position > dec!(0) && cost > dec!(0)).then(|| cost / position)
+    }
+
     /// 计算持仓不平衡度（0.0 = 完全平衡，1.0 = 完全不平衡）
     pub fn calculate_imbalance(&self, yes_token: U256, no_token: U256) -> Decimal {
         let yes_pos = self.get_position(yes_token);
@@ -193,7 +363,6 @@ impl PositionTracker {
     /// 这个方法会从API获取最新持仓，清空并重建本地positions map
     /// 用于定时同步任务，确保本地缓存与链上实际持仓一致
     pub async fn sync_from_api(&self) -> Result<Vec<Position>> {
-        use std::collections::HashMap;
         use polymarket_client_sdk::types::B256;
         
         let positions = get_positions().await?;
@@ -213,6 +382,7 @@ impl PositionTracker {
                 updated_count += 1;
             }
         }
+        self.mark_dirty();
         
         // 按市场分组打印持仓
         if !valid_positions.is_empty() {
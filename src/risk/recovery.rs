@@ -1,10 +1,13 @@
 use anyhow::Result;
-use polymarket_client_sdk::types::{Decimal, U256};
+use polymarket_client_sdk::types::{B256, Decimal, U256};
 use rust_decimal_macros::dec;
-use tracing::debug;
+use tracing::{debug, info};
 
 use super::manager::OrderPair;
 use super::positions::PositionTracker;
+use crate::config::decimal_setting;
+use crate::trading::clock::short_id;
+use crate::trading::UnwindStyle;
 
 #[derive(Debug, Clone)]
 pub enum RecoveryAction {
@@ -21,28 +24,147 @@ pub enum RecoveryAction {
         market_display: String, // 市场显示名称（例如"btc预测市场"）
     },
     ManualIntervention { reason: String },
+    /// 按配置的方式卖出多余腿
+    Unwind {
+        market_id: B256,
+        token_id: U256,
+        amount: Decimal,
+        entry_price: Decimal, // 该腿买入价，保本挂单与无买单时分档的起点
+        style: UnwindStyle,
+        pair_id: String,
+        cancel_order_ids: Vec<String>, // 卖出前先撤掉的挂单余量（两腿成交不等时，避免撤单前继续成交）
+    },
+    /// 补买短腿，使两腿成交数量相等
+    TopUp {
+        market_id: B256,
+        token_id: U256,
+        amount: Decimal,
+        max_price: Decimal, // 补买限价上限：1 − 长腿买入价 − 1 tick，保证补齐后每对成本 < 1
+        pair_id: String,
+        cancel_order_ids: Vec<String>, // 补买前先撤掉的挂单余量
+    },
+}
+
+/// 双边都有成交但数量不等时的处理（ON_IMBALANCE）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnImbalance {
+    /// 不处理，差额留作敞口（默认，与原行为一致）
+    Ignore,
+    /// 按 UNWIND_STYLE 卖出长腿多余部分（未配置时保本挂单）
+    Unwind,
+    /// 补买短腿差额
+    TopUp,
+}
+
+impl OnImbalance {
+    /// 解析配置：ignore | unwind | top_up，大小写不敏感，未知值默认 Ignore
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "unwind" | "sell" => OnImbalance::Unwind,
+            "top_up" | "topup" => OnImbalance::TopUp,
+            _ => OnImbalance::Ignore,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OnImbalance::Ignore => "ignore",
+            OnImbalance::Unwind => "unwind",
+            OnImbalance::TopUp => "top_up",
+        }
+    }
 }
 
 pub struct RecoveryStrategy {
     imbalance_threshold: Decimal,
     take_profit_pct: Decimal, // 止盈百分比
     stop_loss_pct: Decimal,   // 止损百分比
+    unwind_style: Option<UnwindStyle>, // None 时单边/不平衡成交不处理
+    on_imbalance: OnImbalance, // 双边成交数量不等时的处理
 }
 
 impl RecoveryStrategy {
-    pub fn new(imbalance_threshold: f64, take_profit_pct: f64, stop_loss_pct: f64) -> Self {
-        Self {
-            imbalance_threshold: Decimal::try_from(imbalance_threshold)
-                .unwrap_or(dec!(0.1)),
-            take_profit_pct: Decimal::try_from(take_profit_pct)
-                .unwrap_or(dec!(0.05)), // 默认5%止盈
-            stop_loss_pct: Decimal::try_from(stop_loss_pct)
-                .unwrap_or(dec!(0.05)), // 默认5%止损
+    pub fn new(
+        imbalance_threshold: f64,
+        take_profit_pct: f64,
+        stop_loss_pct: f64,
+        unwind_style: Option<UnwindStyle>,
+        on_imbalance: OnImbalance,
+    ) -> Result<Self> {
+        Ok(Self {
+            imbalance_threshold: decimal_setting("RISK_IMBALANCE_THRESHOLD", imbalance_threshold, dec!(0), None)?,
+            take_profit_pct: decimal_setting("HEDGE_TAKE_PROFIT_PCT", take_profit_pct, dec!(0), None)?,
+            stop_loss_pct: decimal_setting("HEDGE_STOP_LOSS_PCT", stop_loss_pct, dec!(0), None)?,
+            unwind_style,
+            on_imbalance,
+        })
+    }
+
+    /// 双边都有成交但数量不等（如 GTD 成交 YES 98 / NO 100）：按 on_imbalance 卖出长腿多余部分或补买短腿；
+    /// 仍有挂单余量的腿先撤单，避免处理后余量继续成交又产生新的差额
+    pub fn handle_filled_imbalance(&self, pair: &OrderPair) -> RecoveryAction {
+        if pair.yes_filled <= dec!(0) || pair.no_filled <= dec!(0) || pair.yes_filled == pair.no_filled {
+            return RecoveryAction::None;
+        }
+        let amount = (pair.yes_filled - pair.no_filled).abs();
+        let ((long_side, long_token, long_price), short_token) = if pair.yes_filled > pair.no_filled {
+            (("YES", pair.yes_token_id, pair.yes_price), pair.no_token_id)
+        } else {
+            (("NO", pair.no_token_id, pair.no_price), pair.yes_token_id)
+        };
+        let cancel_order_ids: Vec<String> = [
+            (&pair.yes_order_id, pair.yes_filled < pair.yes_size),
+            (&pair.no_order_id, pair.no_filled < pair.no_size),
+        ]
+        .into_iter()
+        .filter(|(id, working)| *working && !id.is_empty())
+        .map(|(id, _)| id.clone())
+        .collect();
+
+        match self.on_imbalance {
+            OnImbalance::Ignore => {
+                debug!(
+                    pair_id = %pair.pair_id,
+                    long_side,
+                    imbalance_amount = %amount,
+                    "双边成交数量不等，ON_IMBALANCE=ignore，不处理"
+                );
+                RecoveryAction::None
+            }
+            OnImbalance::Unwind => {
+                info!(
+                    "⚖️ 双边成交不等 | {} | YES {} NO {} | 卖出 {} 多余 {} 份",
+                    short_id(&pair.pair_id), pair.yes_filled, pair.no_filled, long_side, amount
+                );
+                RecoveryAction::Unwind {
+                    market_id: pair.market_id,
+                    token_id: long_token,
+                    amount,
+                    entry_price: long_price,
+                    style: self.unwind_style.unwrap_or(UnwindStyle::LimitAtBreakeven),
+                    pair_id: pair.pair_id.clone(),
+                    cancel_order_ids,
+                }
+            }
+            OnImbalance::TopUp => {
+                info!(
+                    "⚖️ 双边成交不等 | {} | YES {} NO {} | 补买短腿 {} 份",
+                    short_id(&pair.pair_id), pair.yes_filled, pair.no_filled, amount
+                );
+                RecoveryAction::TopUp {
+                    market_id: pair.market_id,
+                    token_id: short_token,
+                    amount,
+                    max_price: dec!(1) - long_price - dec!(0.01),
+                    pair_id: pair.pair_id.clone(),
+                    cancel_order_ids,
+                }
+            }
         }
     }
 
     /// 处理部分成交（GTC订单的情况）
-    /// 对冲策略已暂时关闭，部分成交不平衡不做任何处理
+    /// 对冲策略已暂时关闭；配置了 UNWIND_STYLE 时不平衡超过阈值则卖出多余部分，否则不做任何处理
     pub async fn handle_partial_fill(
         &self,
         pair: &OrderPair,
@@ -59,7 +181,7 @@ impl RecoveryStrategy {
             dec!(0)
         };
 
-        // 对冲策略已关闭，部分成交不平衡不做任何处理
+        // 对冲策略已关闭，部分成交不平衡只在配置了 UNWIND_STYLE 时卖出多余部分
         if imbalance_ratio > self.imbalance_threshold {
             let (side, amount) = if pair.yes_filled > pair.no_filled {
                 // YES成交多
@@ -69,6 +191,23 @@ impl RecoveryStrategy {
                 ("NO", pair.no_filled - pair.yes_filled)
             };
 
+            if let Some(style) = self.unwind_style {
+                let (token_id, entry_price) = if side == "YES" {
+                    (pair.yes_token_id, pair.yes_price)
+                } else {
+                    (pair.no_token_id, pair.no_price)
+                };
+                return Ok(RecoveryAction::Unwind {
+                    market_id: pair.market_id,
+                    token_id,
+                    amount,
+                    entry_price,
+                    style,
+                    pair_id: pair.pair_id.clone(),
+                    cancel_order_ids: Vec::new(),
+                });
+            }
+
             debug!(
                 pair_id = %pair.pair_id,
                 side = side,
@@ -110,7 +249,7 @@ impl RecoveryStrategy {
     }
 
     /// 处理只购买一边成功（GTC订单的情况）
-    /// 对冲策略已暂时关闭，单边成交不做任何处理
+    /// 对冲策略已暂时关闭；配置了 UNWIND_STYLE 时按该方式卖出成交的一腿，否则不做任何处理
     pub async fn handle_one_sided_fill(
         &self,
         pair: &OrderPair,
@@ -128,6 +267,23 @@ impl RecoveryStrategy {
                 return Ok(RecoveryAction::None);
             };
 
+        if let Some(style) = self.unwind_style {
+            let (token_id, entry_price) = if side == "YES" {
+                (pair.yes_token_id, pair.yes_price)
+            } else {
+                (pair.no_token_id, pair.no_price)
+            };
+            return Ok(RecoveryAction::Unwind {
+                market_id: pair.market_id,
+                token_id,
+                amount: filled_amount,
+                entry_price,
+                style,
+                pair_id: pair.pair_id.clone(),
+                cancel_order_ids: Vec::new(),
+            });
+        }
+
         // 对冲策略已关闭，单边成交不做任何处理（详情由 executor 的 ⚠️ 单边成交 已记录）
         debug!(
             "单边成交 | {} 成交 {} 份 | 对冲已关，不处理",
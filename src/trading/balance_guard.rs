@@ -0,0 +1,132 @@
+//! USDC 余额下限保护：后台任务周期读取链上 USDC 余额，低于 `MIN_TRADING_BALANCE` 时自动暂停下单，
+//! 回升（如 merge 回收资金后）到下限以上时自动恢复。与管理命令 `pause` 的人工暂停相互独立。
+
+use poly_5min_bot::merge;
+use polymarket_client_sdk::types::Address;
+use rust_decimal::Decimal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::trading::clob_api::ClobApi;
+use crate::trading::executor::TradingExecutor;
+
+/// 提交前余额核对方式（PRE_SUBMIT_BALANCE_CHECK）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceCheckMode {
+    /// 不核对（默认），余额不足由交易所拒单后按 BALANCE_REJECT_RETRY 处理
+    Off,
+    /// 先读余额，通过后再构建、签名
+    Serial,
+    /// 余额读取与构建、签名并行，两者都完成且余额足够才提交；余额不足时丢弃已签名订单
+    Concurrent,
+}
+
+impl BalanceCheckMode {
+    /// 解析配置：off | serial | concurrent，大小写不敏感；未知值按 off
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "serial" => BalanceCheckMode::Serial,
+            "concurrent" | "parallel" => BalanceCheckMode::Concurrent,
+            _ => BalanceCheckMode::Off,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BalanceCheckMode::Off => "off",
+            BalanceCheckMode::Serial => "serial",
+            BalanceCheckMode::Concurrent => "concurrent",
+        }
+    }
+}
+
+/// 余额状态快照
+#[derive(Debug, Clone)]
+pub struct BalanceState {
+    /// 最近一次读取到的 USDC 余额；尚未读取成功为 None
+    pub balance: Option<Decimal>,
+    /// 下限；None 表示未启用
+    pub floor: Option<Decimal>,
+    /// 是否因余额不足自动暂停
+    pub low: bool,
+}
+
+impl std::fmt::Display for BalanceState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let balance = self.balance.map(|b| format!("{:.2}", b)).unwrap_or_else(|| "未知".to_string());
+        match self.floor {
+            Some(floor) if self.low => write!(f, "USDC 余额 {}（下限 {}），余额不足已自动暂停", balance, floor),
+            Some(floor) => write!(f, "USDC 余额 {}（下限 {}），正常", balance, floor),
+            None => write!(f, "USDC 余额 {}（未设置下限）", balance),
+        }
+    }
+}
+
+/// 余额下限判定：只由后台任务调用 update，执行器读取 is_low
+#[derive(Debug, Default)]
+pub struct BalanceGuard {
+    floor: Option<Decimal>,
+    balance: Mutex<Option<Decimal>>,
+    low: AtomicBool,
+}
+
+impl BalanceGuard {
+    /// floor ≤ 0 时不启用
+    pub fn new(floor: Decimal) -> Self {
+        Self {
+            floor: (floor > Decimal::ZERO).then_some(floor),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.floor.is_some()
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.low.load(Ordering::SeqCst)
+    }
+
+    pub fn state(&self) -> BalanceState {
+        BalanceState {
+            balance: *self.balance.lock().unwrap(),
+            floor: self.floor,
+            low: self.is_low(),
+        }
+    }
+
+    /// 记录最新余额；跨越下限时切换暂停状态并记录日志
+    pub fn update(&self, balance: Decimal) {
+        *self.balance.lock().unwrap() = Some(balance);
+        let Some(floor) = self.floor else {
+            return;
+        };
+        let low = balance < floor;
+        match (self.low.swap(low, Ordering::SeqCst), low) {
+            (false, true) => warn!("💸 USDC 余额 {:.2} 低于下限 {}，自动暂停下单（回升后自动恢复）", balance, floor),
+            (true, false) => info!("💰 USDC 余额 {:.2} 已回升至下限 {} 以上，自动恢复下单", balance, floor),
+            _ => debug!(balance = %balance, floor = %floor, low, "USDC 余额检查"),
+        }
+    }
+}
+
+/// 启动余额检查任务：每 interval 读取一次 owner 的 USDC 余额（读取失败只记日志，保持上次状态）
+pub fn spawn<C: ClobApi + 'static>(
+    executor: Arc<TradingExecutor<C>>,
+    owner: Address,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    info!(owner = %owner, interval_secs = interval.as_secs(), "💰 USDC 余额检查任务已启动");
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match merge::usdc_balance(owner, None, executor.chain_id()).await {
+                Ok(balance) => executor.balance_guard().update(balance),
+                Err(e) => warn!(error = %e, "读取 USDC 余额失败，保持上次状态"),
+            }
+        }
+    })
+}
@@ -0,0 +1,878 @@
+//! CLOB 下单接口抽象：executor 只依赖 `ClobApi`，实盘用 `SdkClobApi`（SDK 客户端），
+//! 影子配置执行器用 `OfflineClobApi`（不联网，任何调用都报错），
+//! 单元测试中换成 `MockClobApi`（按脚本返回结果）。
+
+use alloy::signers::Signer;
+use alloy::signers::local::LocalSigner;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use polymarket_client_sdk::clob::types::request::{OrdersRequest, TradesRequest};
+use polymarket_client_sdk::clob::types::response::{CancelOrdersResponse, PostOrderResponse};
+use polymarket_client_sdk::clob::types::{Amount, OrderType, Side, SignableOrder, SignatureType, SignedOrder};
+use polymarket_client_sdk::clob::{Client, Config};
+use polymarket_client_sdk::types::{Address, B256, Decimal, U256};
+use polymarket_client_sdk::{derive_proxy_wallet, derive_safe_wallet};
+use std::collections::HashMap;
+#[cfg(test)]
+use std::collections::VecDeque;
+use std::future::Future;
+use std::str::FromStr;
+#[cfg(test)]
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::monitor::TopOfBook;
+
+pub type AuthClient = Client<polymarket_client_sdk::auth::state::Authenticated<polymarket_client_sdk::auth::Normal>>;
+
+/// 限价单参数；expiration 仅 GTD 时设置（SDK 规定非 GTD 不可设过期）
+#[derive(Debug, Clone)]
+pub struct LimitOrderParams {
+    pub token_id: U256,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub order_type: OrderType,
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+/// 市价单参数：按 USDC 金额下单（买入时为最多花费的 USDC），订单类型仅 FOK/FAK
+#[derive(Debug, Clone)]
+pub struct MarketOrderParams {
+    pub token_id: U256,
+    pub side: Side,
+    pub usdc_amount: Decimal,
+    /// 最差成交价（买入时为含滑点的报价上限），不按订单簿深度推算
+    pub price: Decimal,
+    pub order_type: OrderType,
+}
+
+/// 单笔下单结果（executor 关心的字段）
+#[derive(Debug, Clone)]
+pub struct OrderPostResult {
+    pub order_id: String,
+    pub success: bool,
+    pub error_msg: Option<String>,
+    /// 即时成交中收到的数量：买单为份数，卖单为 USDC
+    pub taking_amount: Decimal,
+    /// 即时成交中付出的数量：买单为 USDC，卖单为份数
+    pub making_amount: Decimal,
+    /// 原始响应（逐字保留，用于排查简化错误信息未覆盖的拒单原因）
+    pub raw: String,
+}
+
+impl OrderPostResult {
+    /// 原始错误：下单未成功或带错误信息时，返回 error_msg 与完整响应原文
+    pub fn raw_error(&self) -> Option<String> {
+        let has_error = self.error_msg.as_deref().map(|m| !m.is_empty()).unwrap_or(false);
+        if self.success && !has_error {
+            return None;
+        }
+        Some(format!(
+            "error_msg={} | response={}",
+            self.error_msg.as_deref().unwrap_or(""),
+            self.raw
+        ))
+    }
+}
+
+impl From<&PostOrderResponse> for OrderPostResult {
+    fn from(r: &PostOrderResponse) -> Self {
+        Self {
+            order_id: r.order_id.clone(),
+            success: r.success,
+            error_msg: r.error_msg.clone(),
+            taking_amount: r.taking_amount,
+            making_amount: r.making_amount,
+            raw: format!("{:?}", r),
+        }
+    }
+}
+
+/// 按订单 ID 查询到的订单状态
+#[derive(Debug, Clone)]
+pub struct OrderSnapshot {
+    pub order_id: String,
+    /// 交易所状态原文（如 LIVE / MATCHED / CANCELED），统一为大写
+    pub status: String,
+    /// 已成交数量（份）
+    pub size_matched: Decimal,
+    /// 订单所属 token（asset_id）；未知时为 None（不参与返回结果校验）
+    pub token_id: Option<U256>,
+}
+
+impl OrderSnapshot {
+    /// 已终结（不会再成交）：撤销或过期
+    pub fn is_dead(&self) -> bool {
+        self.status.contains("CANCEL") || self.status.contains("EXPIRE") || self.status.contains("UNMATCHED")
+    }
+}
+
+/// 撤单结果汇总：requested = 撤销成功 + 失败，failed 为「order_id: 原因」
+#[derive(Debug, Clone, Default)]
+pub struct CancelSummary {
+    pub requested: usize,
+    pub cancelled: usize,
+    pub failed: Vec<String>,
+    /// 原始响应（逐字保留，便于排查）
+    pub raw: String,
+}
+
+impl From<&CancelOrdersResponse> for CancelSummary {
+    fn from(r: &CancelOrdersResponse) -> Self {
+        let mut failed: Vec<String> = r
+            .not_canceled
+            .iter()
+            .map(|(id, reason)| format!("{}: {}", id, reason))
+            .collect();
+        failed.sort();
+        Self {
+            requested: r.canceled.len() + failed.len(),
+            cancelled: r.canceled.len(),
+            failed,
+            raw: format!("{:?}", r),
+        }
+    }
+}
+
+impl std::fmt::Display for CancelSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "已撤销 {}/{}", self.cancelled, self.requested)?;
+        if !self.failed.is_empty() {
+            write!(f, "，失败 {}: {}", self.failed.len(), self.failed.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// 市场交易参数（CLOB /markets/{condition_id}）；读取失败时用 Default 的保守值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketMeta {
+    /// 价格最小变动单位
+    pub tick_size: Decimal,
+    /// 最小下单数量（份）
+    pub min_order_size: Decimal,
+    /// 最小下单金额（USD，金额须严格大于此值）
+    pub min_order_amount: Decimal,
+    /// 吃单费率（基点）
+    pub taker_fee_bps: Decimal,
+}
+
+impl Default for MarketMeta {
+    fn default() -> Self {
+        Self {
+            tick_size: Decimal::new(1, 2),
+            min_order_size: Decimal::ZERO,
+            min_order_amount: Decimal::ONE,
+            taker_fee_bps: Decimal::ZERO,
+        }
+    }
+}
+
+impl MarketMeta {
+    /// 可报的最高价（1 − tick）
+    pub fn max_price(&self) -> Decimal {
+        Decimal::ONE - self.tick_size
+    }
+
+    /// 从 /markets 响应解析；字段缺失或无效时保留默认值（交易所未单独返回最小金额，固定为 $1）
+    fn from_market_json(market: &serde_json::Value) -> Self {
+        let num = |key: &str| match &market[key] {
+            serde_json::Value::String(s) => Decimal::from_str(s).ok(),
+            serde_json::Value::Number(n) => Decimal::from_str(&n.to_string()).ok(),
+            _ => None,
+        };
+        let d = Self::default();
+        Self {
+            tick_size: num("minimum_tick_size").filter(|t| *t > Decimal::ZERO).unwrap_or(d.tick_size),
+            min_order_size: num("minimum_order_size").filter(|s| *s >= Decimal::ZERO).unwrap_or(d.min_order_size),
+            min_order_amount: d.min_order_amount,
+            taker_fee_bps: num("taker_base_fee").filter(|f| *f >= Decimal::ZERO).unwrap_or(d.taker_fee_bps),
+        }
+    }
+}
+
+/// 账户挂单（executor 关心的字段）
+#[derive(Debug, Clone)]
+pub struct OpenOrder {
+    pub order_id: String,
+    pub token_id: U256,
+    pub side: Side,
+    pub price: Decimal,
+    pub order_type: OrderType,
+    /// GTD 过期时间；GTC 等无过期的订单为 None
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+impl OpenOrder {
+    /// GTD 订单且过期时间已过（交易所清扫前仍可能出现在挂单列表中）
+    pub fn is_expired_gtd(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.order_type, OrderType::GTD) && self.expiration.is_some_and(|exp| exp <= now)
+    }
+}
+
+/// executor 使用的 CLOB 操作集合
+pub trait ClobApi: Send + Sync {
+    /// 构建后、签名前的订单
+    type Order: Send;
+    /// 已签名订单；凭证过期重试时需要 clone 重发
+    type Signed: Clone + Send;
+
+    fn limit_order(&self, params: LimitOrderParams) -> impl Future<Output = Result<Self::Order>> + Send;
+    /// SDK 市价单构建器：按 USDC 金额定量，以 `params.price` 为显式最差成交价（买入即含滑点的价格上限），
+    /// 不按盘口深度自动推算
+    fn market_order(&self, params: MarketOrderParams) -> impl Future<Output = Result<Self::Order>> + Send;
+    fn sign(&self, order: Self::Order) -> impl Future<Output = Result<Self::Signed>> + Send;
+    /// 批量提交，返回顺序与提交顺序一致
+    fn post_orders(&self, orders: Vec<Self::Signed>) -> impl Future<Output = Result<Vec<OrderPostResult>>> + Send;
+    fn post_order(&self, order: Self::Signed) -> impl Future<Output = Result<OrderPostResult>> + Send;
+    fn cancel_all_orders(&self) -> impl Future<Output = Result<CancelSummary>> + Send;
+    /// 按订单 ID 撤单
+    fn cancel_order(&self, order_id: &str) -> impl Future<Output = Result<()>> + Send;
+    /// 按订单 ID 批量撤单
+    fn cancel_orders(&self, order_ids: &[String]) -> impl Future<Output = Result<CancelSummary>> + Send;
+    /// 账户全部挂单（自动翻页）
+    fn open_orders(&self) -> impl Future<Output = Result<Vec<OpenOrder>>> + Send;
+    /// 按订单 ID 查询订单状态与已成交数量
+    fn get_order(&self, order_id: &str) -> impl Future<Output = Result<OrderSnapshot>> + Send;
+    /// 从账户成交记录（trades）汇总各订单的实际成交份数（吃单按 taker_order_id，挂单按 maker_orders）；
+    /// 未出现在成交记录中的订单不在返回值中
+    fn traded_sizes(&self, order_ids: &[String]) -> impl Future<Output = Result<HashMap<String, Decimal>>> + Send;
+    /// 读取最新卖一价（REST 订单簿），无卖单时返回 None
+    fn best_ask(&self, token_id: U256) -> impl Future<Output = Result<Option<Decimal>>> + Send;
+    /// 读取最新买一价（REST 订单簿），无买单时返回 None
+    fn best_bid(&self, token_id: U256) -> impl Future<Output = Result<Option<Decimal>>> + Send;
+    /// 读取买一与卖一；默认分别调用 best_ask / best_bid，实盘实现一次请求取两侧
+    fn top_of_book(&self, token_id: U256) -> impl Future<Output = Result<TopOfBook>> + Send {
+        async move {
+            let (best_ask, best_bid) = futures::future::try_join(self.best_ask(token_id), self.best_bid(token_id)).await?;
+            Ok(TopOfBook { best_ask, best_bid })
+        }
+    }
+    /// 市场交易参数（tick、最小数量/金额、费率）
+    fn market_meta(&self, condition_id: B256) -> impl Future<Output = Result<MarketMeta>> + Send;
+    /// 用于验证认证状态
+    fn api_keys(&self) -> impl Future<Output = Result<()>> + Send;
+    /// API 凭证过期后重新认证
+    fn reauthenticate(&self) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// CLOB 连接参数：REST 请求的连接/总超时，以及认证超时后的重试次数
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectOptions {
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    /// 认证超时后的重试次数（不含首次），非超时错误不重试
+    pub auth_retries: u32,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(15),
+            auth_retries: 2,
+        }
+    }
+}
+
+/// 核对私钥与配置的 proxy 地址：由私钥得到 EOA，推导其 Polymarket Proxy（Email/Magic）与 Safe（浏览器钱包）地址，
+/// 两者都不等于配置值时返回说明文字；一致时返回 None
+pub fn proxy_mismatch(private_key: &str, proxy_address: Address, chain_id: u64) -> Result<Option<String>> {
+    let eoa = LocalSigner::from_str(private_key)
+        .map_err(|e| anyhow::anyhow!("私钥格式无效: {}", e))?
+        .address();
+    let derived_proxy = derive_proxy_wallet(eoa, chain_id);
+    let derived_safe = derive_safe_wallet(eoa, chain_id);
+    if derived_proxy == Some(proxy_address) || derived_safe == Some(proxy_address) {
+        return Ok(None);
+    }
+    let show = |a: Option<Address>| a.map(|a| a.to_string()).unwrap_or_else(|| "-".to_string());
+    Ok(Some(format!(
+        "POLYMARKET_PROXY_ADDRESS={} 与私钥不匹配：EOA={}，推导的 Proxy={}，Safe={}（下单与 Merge 将失败，请检查私钥或代理地址）",
+        proxy_address,
+        eoa,
+        show(derived_proxy),
+        show(derived_safe)
+    )))
+}
+
+/// 基于 polymarket_client_sdk 的实盘实现
+pub struct SdkClobApi {
+    client: tokio::sync::RwLock<AuthClient>, // 凭证过期时 reauthenticate 会整体替换
+    private_key: String,
+    proxy_address: Option<Address>,
+    chain_id: u64, // 签名用链 ID（EIP-712 domain）
+    http: reqwest::Client,
+    connect: ConnectOptions,
+}
+
+const CLOB_HOST: &str = "https://clob.polymarket.com";
+/// 成交对账最多翻阅的成交记录页数（最新在前）
+const TRADES_MAX_PAGES: usize = 3;
+
+impl SdkClobApi {
+    pub async fn new(
+        private_key: String,
+        proxy_address: Option<Address>,
+        chain_id: u64,
+        connect: ConnectOptions,
+    ) -> Result<Self> {
+        anyhow::ensure!(chain_id != 0, "chain_id 不能为 0");
+        let client = Self::authenticate_with_retry(&private_key, proxy_address, chain_id, connect).await?;
+        let http = reqwest::Client::builder()
+            .connect_timeout(connect.connect_timeout)
+            .timeout(connect.request_timeout)
+            .build()
+            .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))?;
+        Ok(Self {
+            client: tokio::sync::RwLock::new(client),
+            private_key,
+            proxy_address,
+            chain_id,
+            http,
+            connect,
+        })
+    }
+
+    /// 认证整体受 request_timeout 限制；超时按 1s、2s、4s… 退避重试 auth_retries 次，
+    /// 耗尽后返回明确错误。非超时错误（私钥无效、被拒等）直接返回。
+    async fn authenticate_with_retry(
+        private_key: &str,
+        proxy_address: Option<Address>,
+        chain_id: u64,
+        connect: ConnectOptions,
+    ) -> Result<AuthClient> {
+        let attempts = connect.auth_retries + 1;
+        let mut backoff = Duration::from_secs(1);
+        for attempt in 1..=attempts {
+            match tokio::time::timeout(connect.request_timeout, Self::authenticate(private_key, proxy_address, chain_id)).await {
+                Ok(result) => return result,
+                Err(_) if attempt < attempts => {
+                    warn!(
+                        attempt,
+                        attempts,
+                        "CLOB 认证超时（{}ms），{}s 后重试",
+                        connect.request_timeout.as_millis(),
+                        backoff.as_secs()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(_) => break,
+            }
+        }
+        Err(anyhow::anyhow!(
+            "CLOB 认证超时：{} 次尝试均未在 {}ms 内完成，请检查网络或 Polymarket API 状态（CLOB_REQUEST_TIMEOUT_MS / CLOB_AUTH_RETRIES）",
+            attempts,
+            connect.request_timeout.as_millis()
+        ))
+    }
+
+    /// 用私钥（及可选 proxy）创建并认证 CLOB 客户端
+    async fn authenticate(private_key: &str, proxy_address: Option<Address>, chain_id: u64) -> Result<AuthClient> {
+        // 验证私钥格式
+        let signer = LocalSigner::from_str(private_key)
+            .map_err(|e| anyhow::anyhow!("私钥格式无效: {}. 请确保私钥是64字符的十六进制字符串（不带0x前缀）", e))?
+            .with_chain_id(Some(chain_id));
+
+        let config = Config::builder().use_server_time(false).build();
+        let mut auth_builder = Client::new(CLOB_HOST, config)
+            .map_err(|e| anyhow::anyhow!("创建CLOB客户端失败: {}", e))?
+            .authentication_builder(&signer);
+
+        // 如果提供了proxy_address，设置funder和signature_type（按照Python SDK模式）
+        if let Some(funder) = proxy_address {
+            auth_builder = auth_builder
+                .funder(funder)
+                .signature_type(SignatureType::Proxy);
+        }
+
+        auth_builder
+            .authenticate()
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "API认证失败: {}. 可能的原因：1) 私钥无效 2) 网络问题 3) Polymarket API服务不可用",
+                    e
+                )
+            })
+    }
+
+    /// 拉取 REST 订单簿原始 JSON
+    async fn fetch_book(&self, token_id: U256) -> Result<serde_json::Value> {
+        Ok(self
+            .http
+            .get(format!("{}/book", CLOB_HOST))
+            .query(&[("token_id", token_id.to_string())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// 订单簿某一侧（"asks"/"bids"）的全部价格
+    fn book_prices<'a>(book: &'a serde_json::Value, side: &str) -> impl Iterator<Item = Decimal> + 'a {
+        book[side]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|l| l["price"].as_str().and_then(|p| Decimal::from_str(p).ok()))
+    }
+
+    /// 当前客户端快照（Client 可廉价 clone，避免跨 await 持有读锁）
+    pub async fn client(&self) -> AuthClient {
+        self.client.read().await.clone()
+    }
+}
+
+impl ClobApi for SdkClobApi {
+    type Order = SignableOrder;
+    type Signed = SignedOrder;
+
+    async fn limit_order(&self, params: LimitOrderParams) -> Result<SignableOrder> {
+        let client = self.client().await;
+        let b = client
+            .limit_order()
+            .token_id(params.token_id)
+            .side(params.side)
+            .price(params.price)
+            .size(params.size)
+            .order_type(params.order_type);
+        let order = match params.expiration {
+            Some(expiration) => b.expiration(expiration).build().await?,
+            None => b.build().await?,
+        };
+        Ok(order)
+    }
+
+    async fn market_order(&self, params: MarketOrderParams) -> Result<SignableOrder> {
+        let client = self.client().await;
+        let order = client
+            .market_order()
+            .token_id(params.token_id)
+            .side(params.side)
+            .amount(Amount::usdc(params.usdc_amount)?)
+            .price(params.price)
+            .order_type(params.order_type)
+            .build()
+            .await?;
+        Ok(order)
+    }
+
+    async fn sign(&self, order: SignableOrder) -> Result<SignedOrder> {
+        let signer = LocalSigner::from_str(&self.private_key)?
+            .with_chain_id(Some(self.chain_id));
+        Ok(self.client().await.sign(&signer, order).await?)
+    }
+
+    async fn post_orders(&self, orders: Vec<SignedOrder>) -> Result<Vec<OrderPostResult>> {
+        let results = self
+            .client()
+            .await
+            .post_orders(orders)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(results.iter().map(OrderPostResult::from).collect())
+    }
+
+    async fn post_order(&self, order: SignedOrder) -> Result<OrderPostResult> {
+        let result = self
+            .client()
+            .await
+            .post_order(order)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(OrderPostResult::from(&result))
+    }
+
+    async fn cancel_all_orders(&self) -> Result<CancelSummary> {
+        let resp = self
+            .client()
+            .await
+            .cancel_all_orders()
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(CancelSummary::from(&resp))
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        self.client()
+            .await
+            .cancel_order(order_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(())
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> Result<CancelSummary> {
+        let ids: Vec<&str> = order_ids.iter().map(|s| s.as_str()).collect();
+        let resp = self
+            .client()
+            .await
+            .cancel_orders(&ids)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(CancelSummary::from(&resp))
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>> {
+        let client = self.client().await;
+        let mut out = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = client
+                .orders(&OrdersRequest::default(), cursor)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            out.extend(page.data.into_iter().map(|o| OpenOrder {
+                order_id: o.id,
+                token_id: o.asset_id,
+                side: o.side,
+                price: o.price,
+                order_type: o.order_type,
+                // 交易所以 0 表示无过期
+                expiration: Some(o.expiration).filter(|exp| exp.timestamp() > 0),
+            }));
+            if page.next_cursor.is_empty() || page.next_cursor == "LTE=" {
+                break;
+            }
+            cursor = Some(page.next_cursor);
+        }
+        Ok(out)
+    }
+
+    async fn get_order(&self, order_id: &str) -> Result<OrderSnapshot> {
+        let order = self
+            .client()
+            .await
+            .order(order_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(OrderSnapshot {
+            order_id: order_id.to_string(),
+            status: format!("{:?}", order.status).to_uppercase(),
+            size_matched: order.size_matched,
+            token_id: Some(order.asset_id),
+        })
+    }
+
+    async fn traded_sizes(&self, order_ids: &[String]) -> Result<HashMap<String, Decimal>> {
+        let client = self.client().await;
+        let mut out: HashMap<String, Decimal> = HashMap::new();
+        let mut cursor: Option<String> = None;
+        // 只看最近几页：对账在提交后数秒内进行，目标成交必在最新记录中
+        for _ in 0..TRADES_MAX_PAGES {
+            let page = client
+                .trades(&TradesRequest::default(), cursor)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            for trade in page.data {
+                if order_ids.contains(&trade.taker_order_id) {
+                    *out.entry(trade.taker_order_id.clone()).or_default() += trade.size;
+                }
+                for maker in trade.maker_orders {
+                    if order_ids.contains(&maker.order_id) {
+                        *out.entry(maker.order_id).or_default() += maker.matched_amount;
+                    }
+                }
+            }
+            if page.next_cursor.is_empty() || page.next_cursor == "LTE=" {
+                break;
+            }
+            cursor = Some(page.next_cursor);
+        }
+        Ok(out)
+    }
+
+    async fn best_ask(&self, token_id: U256) -> Result<Option<Decimal>> {
+        // asks 顺序不作假设，直接取最低价
+        Ok(Self::book_prices(&self.fetch_book(token_id).await?, "asks").min())
+    }
+
+    async fn best_bid(&self, token_id: U256) -> Result<Option<Decimal>> {
+        // bids 顺序不作假设，直接取最高价
+        Ok(Self::book_prices(&self.fetch_book(token_id).await?, "bids").max())
+    }
+
+    async fn top_of_book(&self, token_id: U256) -> Result<TopOfBook> {
+        let book = self.fetch_book(token_id).await?;
+        Ok(TopOfBook {
+            best_ask: Self::book_prices(&book, "asks").min(),
+            best_bid: Self::book_prices(&book, "bids").max(),
+        })
+    }
+
+    async fn market_meta(&self, condition_id: B256) -> Result<MarketMeta> {
+        let market: serde_json::Value = self
+            .http
+            .get(format!("{}/markets/{:#x}", CLOB_HOST, condition_id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(MarketMeta::from_market_json(&market))
+    }
+
+    async fn api_keys(&self) -> Result<()> {
+        self.client()
+            .await
+            .api_keys()
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(())
+    }
+
+    async fn reauthenticate(&self) -> Result<()> {
+        warn!("🔑 检测到API凭证失效，正在重新认证...");
+        let client = Self::authenticate_with_retry(&self.private_key, self.proxy_address, self.chain_id, self.connect).await?;
+        *self.client.write().await = client;
+        info!("🔑 重新认证成功");
+        Ok(())
+    }
+}
+
+/// 不联网的 CLOB 实现：影子配置执行器只做下单前决策，从不应调用 api；
+/// 万一被调用则一律报错，而不是返回伪造的成交或盘口
+#[derive(Default)]
+pub struct OfflineClobApi;
+
+impl OfflineClobApi {
+    fn refuse<T>(call: &str) -> Result<T> {
+        anyhow::bail!("离线 ClobApi 不联网，拒绝调用 {}", call)
+    }
+}
+
+impl ClobApi for OfflineClobApi {
+    type Order = LimitOrderParams;
+    type Signed = LimitOrderParams;
+
+    async fn limit_order(&self, _params: LimitOrderParams) -> Result<LimitOrderParams> {
+        Self::refuse("limit_order")
+    }
+
+    async fn market_order(&self, _params: MarketOrderParams) -> Result<LimitOrderParams> {
+        Self::refuse("market_order")
+    }
+
+    async fn sign(&self, _order: LimitOrderParams) -> Result<LimitOrderParams> {
+        Self::refuse("sign")
+    }
+
+    async fn post_orders(&self, _orders: Vec<LimitOrderParams>) -> Result<Vec<OrderPostResult>> {
+        Self::refuse("post_orders")
+    }
+
+    async fn post_order(&self, _order: LimitOrderParams) -> Result<OrderPostResult> {
+        Self::refuse("post_order")
+    }
+
+    async fn cancel_all_orders(&self) -> Result<CancelSummary> {
+        Self::refuse("cancel_all_orders")
+    }
+
+    async fn cancel_order(&self, _order_id: &str) -> Result<()> {
+        Self::refuse("cancel_order")
+    }
+
+    async fn cancel_orders(&self, _order_ids: &[String]) -> Result<CancelSummary> {
+        Self::refuse("cancel_orders")
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>> {
+        Self::refuse("open_orders")
+    }
+
+    async fn get_order(&self, _order_id: &str) -> Result<OrderSnapshot> {
+        Self::refuse("get_order")
+    }
+
+    async fn traded_sizes(&self, _order_ids: &[String]) -> Result<HashMap<String, Decimal>> {
+        Self::refuse("traded_sizes")
+    }
+
+    async fn best_ask(&self, _token_id: U256) -> Result<Option<Decimal>> {
+        Self::refuse("best_ask")
+    }
+
+    async fn best_bid(&self, _token_id: U256) -> Result<Option<Decimal>> {
+        Self::refuse("best_bid")
+    }
+
+    async fn market_meta(&self, _condition_id: B256) -> Result<MarketMeta> {
+        Self::refuse("market_meta")
+    }
+
+    async fn api_keys(&self) -> Result<()> {
+        Self::refuse("api_keys")
+    }
+
+    async fn reauthenticate(&self) -> Result<()> {
+        Self::refuse("reauthenticate")
+    }
+}
+
+/// 脚本化的 CLOB 实现：不联网，按预设队列依次返回结果，并记录提交过的订单，
+/// 用于离线验证 executor 的分支（$1 最小金额、单边成交、提交顺序、凭证过期重试等）。
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockClobApi {
+    /// post_orders 依次返回的结果；队列为空时返回与提交数量相同的「未成交」结果
+    pub post_orders_script: Mutex<VecDeque<Result<Vec<OrderPostResult>>>>,
+    /// post_order 依次返回的结果；队列为空时返回「未成交」
+    pub post_order_script: Mutex<VecDeque<Result<OrderPostResult>>>,
+    /// 每次 post_orders 提交的订单（按提交顺序）
+    pub posted_batches: Mutex<Vec<Vec<LimitOrderParams>>>,
+    /// get_order 依次返回的结果（按订单 ID）；队列为空时返回 LIVE、未成交
+    pub order_script: Mutex<HashMap<String, VecDeque<OrderSnapshot>>>,
+    /// traded_sizes 返回的成交份数（按订单 ID）
+    pub traded: Mutex<HashMap<String, Decimal>>,
+    /// best_ask 返回的卖一价（按 token）；未设置的 token 返回 None
+    pub best_asks: Mutex<HashMap<U256, Decimal>>,
+    /// best_bid 返回的买一价（按 token）；未设置的 token 返回 None
+    pub best_bids: Mutex<HashMap<U256, Decimal>>,
+    /// market_meta 返回的市场参数（按 condition_id）；未设置的市场返回默认值
+    pub market_metas: Mutex<HashMap<B256, MarketMeta>>,
+    /// reauthenticate 调用次数
+    pub reauth_count: Mutex<u32>,
+    /// reauthenticate 是否失败
+    pub reauth_fails: bool,
+    /// cancel_order / cancel_orders 收到的订单 ID
+    pub cancelled: Mutex<Vec<String>>,
+    /// open_orders 返回的挂单
+    pub open_orders: Mutex<Vec<OpenOrder>>,
+}
+
+#[cfg(test)]
+impl MockClobApi {
+    fn unfilled(id: usize) -> OrderPostResult {
+        OrderPostResult {
+            order_id: format!("mock-{}", id),
+            success: false,
+            error_msg: Some("no orders found to match".to_string()),
+            taking_amount: Decimal::ZERO,
+            making_amount: Decimal::ZERO,
+            raw: "mock: unfilled".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ClobApi for MockClobApi {
+    type Order = LimitOrderParams;
+    type Signed = LimitOrderParams;
+
+    async fn limit_order(&self, params: LimitOrderParams) -> Result<LimitOrderParams> {
+        Ok(params)
+    }
+
+    /// 市价单记录为 price=显式最差成交价、size=USDC 金额的订单，便于断言价格上限与金额
+    async fn market_order(&self, params: MarketOrderParams) -> Result<LimitOrderParams> {
+        Ok(LimitOrderParams {
+            token_id: params.token_id,
+            side: params.side,
+            price: params.price,
+            size: params.usdc_amount,
+            order_type: params.order_type,
+            expiration: None,
+        })
+    }
+
+    async fn sign(&self, order: LimitOrderParams) -> Result<LimitOrderParams> {
+        Ok(order)
+    }
+
+    async fn post_orders(&self, orders: Vec<LimitOrderParams>) -> Result<Vec<OrderPostResult>> {
+        let n = orders.len();
+        self.posted_batches.lock().unwrap().push(orders);
+        let scripted = self.post_orders_script.lock().unwrap().pop_front();
+        scripted.unwrap_or_else(|| Ok((0..n).map(Self::unfilled).collect()))
+    }
+
+    async fn post_order(&self, order: LimitOrderParams) -> Result<OrderPostResult> {
+        self.posted_batches.lock().unwrap().push(vec![order]);
+        let scripted = self.post_order_script.lock().unwrap().pop_front();
+        scripted.unwrap_or_else(|| Ok(Self::unfilled(0)))
+    }
+
+    async fn cancel_all_orders(&self) -> Result<CancelSummary> {
+        let ids: Vec<String> = self.open_orders.lock().unwrap().drain(..).map(|o| o.order_id).collect();
+        self.cancelled.lock().unwrap().extend(ids.iter().cloned());
+        Ok(CancelSummary {
+            requested: ids.len(),
+            cancelled: ids.len(),
+            failed: Vec::new(),
+            raw: "mock: cancel_all".to_string(),
+        })
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        self.cancelled.lock().unwrap().push(order_id.to_string());
+        Ok(())
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> Result<CancelSummary> {
+        self.cancelled.lock().unwrap().extend(order_ids.iter().cloned());
+        self.open_orders.lock().unwrap().retain(|o| !order_ids.contains(&o.order_id));
+        Ok(CancelSummary {
+            requested: order_ids.len(),
+            cancelled: order_ids.len(),
+            failed: Vec::new(),
+            raw: "mock: cancel_orders".to_string(),
+        })
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>> {
+        Ok(self.open_orders.lock().unwrap().clone())
+    }
+
+    async fn get_order(&self, order_id: &str) -> Result<OrderSnapshot> {
+        let scripted = self
+            .order_script
+            .lock()
+            .unwrap()
+            .get_mut(order_id)
+            .and_then(|q| q.pop_front());
+        Ok(scripted.unwrap_or_else(|| OrderSnapshot {
+            order_id: order_id.to_string(),
+            status: "LIVE".to_string(),
+            size_matched: Decimal::ZERO,
+            token_id: None,
+        }))
+    }
+
+    async fn traded_sizes(&self, order_ids: &[String]) -> Result<HashMap<String, Decimal>> {
+        let traded = self.traded.lock().unwrap();
+        Ok(order_ids
+            .iter()
+            .filter_map(|id| traded.get(id).map(|s| (id.clone(), *s)))
+            .collect())
+    }
+
+    async fn best_ask(&self, token_id: U256) -> Result<Option<Decimal>> {
+        Ok(self.best_asks.lock().unwrap().get(&token_id).copied())
+    }
+
+    async fn best_bid(&self, token_id: U256) -> Result<Option<Decimal>> {
+        Ok(self.best_bids.lock().unwrap().get(&token_id).copied())
+    }
+
+    async fn market_meta(&self, condition_id: B256) -> Result<MarketMeta> {
+        Ok(self.market_metas.lock().unwrap().get(&condition_id).copied().unwrap_or_default())
+    }
+
+    async fn api_keys(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn reauthenticate(&self) -> Result<()> {
+        *self.reauth_count.lock().unwrap() += 1;
+        if self.reauth_fails {
+            anyhow::bail!("mock: 重新认证失败");
+        }
+        Ok(())
+    }
+}
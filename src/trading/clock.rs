@@ -0,0 +1,104 @@
+//! 可注入的时钟与 ID 生成器：执行器默认使用系统时间与 UUID v4，离线验证时可替换为固定值，
+//! 使 GTD 过期时间、窗口计数与 pair_id 可复现。
+
+use chrono::{DateTime, Utc};
+#[cfg(test)]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(test)]
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// 墙钟时间来源
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// 系统时间（默认）
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// 固定时间（离线验证用）
+#[cfg(test)]
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// 日志中显示的 ID 前缀（前 8 个字符）；不足 8 个字符时原样返回，按字符截取不会切断多字节字符
+pub fn short_id(id: &str) -> &str {
+    id.char_indices().nth(8).map_or(id, |(i, _)| &id[..i])
+}
+
+/// 订单对 ID 生成器。日志显示前 8 个字符（[`short_id`]）。
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> String;
+}
+
+/// UUID v4（默认）
+#[derive(Debug, Default)]
+pub struct UuidGenerator;
+
+impl IdGenerator for UuidGenerator {
+    fn next_id(&self) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// 递增序号：`{prefix}-00000001`、`{prefix}-00000002`…（离线验证用）
+#[cfg(test)]
+#[derive(Debug)]
+pub struct SequentialIds {
+    prefix: String,
+    next: AtomicU64,
+}
+
+#[cfg(test)]
+impl SequentialIds {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            next: AtomicU64::new(1),
+        }
+    }
+}
+
+#[cfg(test)]
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> String {
+        format!("{}-{:08}", self.prefix, self.next.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_id_handles_short_and_multibyte_ids() {
+        assert_eq!(short_id(""), "");
+        assert_eq!(short_id("abc"), "abc");
+        assert_eq!(short_id("12345678"), "12345678");
+        assert_eq!(short_id("3f2a9c1e-77b0-4d2e"), "3f2a9c1e");
+        assert_eq!(short_id("订单对编号一二三四五"), "订单对编号一二三");
+    }
+}
@@ -0,0 +1,90 @@
+//! 窗口收尾时双边持仓的退出方式：先 Merge 回收 1 USDC/份，或把两腿卖给盘口。
+
+use polymarket_client_sdk::types::U256;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tracing::{info, warn};
+
+use crate::trading::clob_api::ClobApi;
+use crate::trading::executor::TradingExecutor;
+
+/// 收尾策略（CLOSE_STRATEGY），逐市场决定双边平衡部分走 Merge 还是卖出
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseStrategy {
+    /// 先 Merge 双边平衡部分，剩余单腿再卖出（默认，即原有行为）
+    MergeFirst,
+    /// 不 Merge，双边持仓全部在卖出步骤卖给盘口
+    SellFirst,
+    /// YES 买一 + NO 买一 > 1 − 手续费 时卖出，否则 Merge
+    SellIfBidAboveMergeValue,
+}
+
+impl CloseStrategy {
+    /// 解析配置：merge_first | sell_first | sell_if_bid_above_merge_value，大小写不敏感，未知值默认 MergeFirst
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "sell_first" | "sell" => CloseStrategy::SellFirst,
+            "sell_if_bid_above_merge_value" | "sell_if_bid_above" | "auto" => CloseStrategy::SellIfBidAboveMergeValue,
+            _ => CloseStrategy::MergeFirst,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseStrategy::MergeFirst => "merge_first",
+            CloseStrategy::SellFirst => "sell_first",
+            CloseStrategy::SellIfBidAboveMergeValue => "sell_if_bid_above_merge_value",
+        }
+    }
+
+    /// 给定两腿买一与 taker 费率（bps），判断是否卖出优于 Merge。任一腿无买单时视为不优。
+    pub fn prefers_sell(&self, yes_bid: Option<Decimal>, no_bid: Option<Decimal>, fee_bps: Decimal) -> bool {
+        match self {
+            CloseStrategy::MergeFirst => false,
+            CloseStrategy::SellFirst => true,
+            CloseStrategy::SellIfBidAboveMergeValue => match (yes_bid, no_bid) {
+                (Some(y), Some(n)) => y + n > dec!(1) - fee_bps / dec!(10000),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl<C: ClobApi> TradingExecutor<C> {
+    /// 收尾时该市场的双边平衡部分是否应 Merge（false 表示留给卖出步骤）。
+    /// 仅 `SellIfBidAboveMergeValue` 需要读取买一；读取失败时保守地 Merge。
+    /// `category` 为市场类别（crypto_symbol），按类别取 taker 费率，未知时用默认费率。
+    pub async fn close_should_merge(
+        &self,
+        strategy: CloseStrategy,
+        yes_token: U256,
+        no_token: U256,
+        category: Option<&str>,
+    ) -> bool {
+        let (yes_bid, no_bid) = match strategy {
+            CloseStrategy::SellIfBidAboveMergeValue => {
+                match futures::future::try_join(self.best_bid(yes_token), self.best_bid(no_token)).await {
+                    Ok(bids) => bids,
+                    Err(e) => {
+                        warn!(error = %e, "收尾：读取买一失败，按 Merge 处理");
+                        return true;
+                    }
+                }
+            }
+            _ => (None, None),
+        };
+        let fee_bps = self.taker_fee_bps(category);
+        let sell = strategy.prefers_sell(yes_bid, no_bid, fee_bps);
+        if strategy == CloseStrategy::SellIfBidAboveMergeValue {
+            info!(
+                "收尾策略 {} | YES买一:{} NO买一:{} | Merge价值:{} | {}",
+                strategy.as_str(),
+                yes_bid.map(|b| b.to_string()).unwrap_or_else(|| "无".to_string()),
+                no_bid.map(|b| b.to_string()).unwrap_or_else(|| "无".to_string()),
+                dec!(1) - fee_bps / dec!(10000),
+                if sell { "卖出" } else { "Merge" }
+            );
+        }
+        !sell
+    }
+}
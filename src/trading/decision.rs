@@ -0,0 +1,126 @@
+//! 下单前决策记录：每个机会在 `execute_arbitrage_pair` 开头生成一条 `PreTradeDecision`，
+//! 汇总毛利、手续费、含滑点总价、每腿金额、深度/余额/冷却检查与最终结论，作为结构化 tracing 事件输出，
+//! 便于统一分析机器人为何执行或放弃机会（target = `pre_trade`）。
+
+use polymarket_client_sdk::types::{Decimal, B256};
+use std::fmt;
+use tracing::{debug, info};
+
+/// 决策结论
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreTradeVerdict {
+    Execute,
+    Skip(String),
+}
+
+impl fmt::Display for PreTradeVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreTradeVerdict::Execute => f.write_str("execute"),
+            PreTradeVerdict::Skip(reason) => write!(f, "skip: {}", reason),
+        }
+    }
+}
+
+/// 单个机会的下单前决策
+#[derive(Debug, Clone)]
+pub struct PreTradeDecision {
+    pub market_id: B256,
+    /// 含滑点报价
+    pub yes_price: Decimal,
+    pub no_price: Decimal,
+    /// 实际下单数量（取整、扣除库存后）
+    pub yes_size: Decimal,
+    pub no_size: Decimal,
+    /// 毛利：1 − (YES 卖一 + NO 卖一)，不含滑点与手续费
+    pub gross_edge: Decimal,
+    /// 适用的 taker 费率（基点）
+    pub fee_bps: Decimal,
+    /// 每份手续费（按含滑点总价）
+    pub fee: Decimal,
+    /// 含滑点 YES+NO 总价
+    pub slipped_sum: Decimal,
+    /// 扣费后每份净利（含滑点）
+    pub net_edge: Decimal,
+    /// net_edge 的基点表示，与 MIN_NET_EDGE_BPS 比较
+    pub net_edge_bps: Decimal,
+    /// 扣除按腿取整的手续费后净利为正的最小数量；任何数量都不盈利时为 None
+    pub min_profitable_size: Option<Decimal>,
+    pub yes_amount_usd: Decimal,
+    pub no_amount_usd: Decimal,
+    /// 已有库存覆盖该腿缺口（该腿不下单）
+    pub skip_yes: bool,
+    pub skip_no: bool,
+    /// 下单数量为正且不超过卖一深度
+    pub depth_ok: bool,
+    /// 余额检查结果；执行端未做余额检查时为 None
+    pub balance_ok: Option<bool>,
+    /// 两腿中较旧一侧订单簿的陈旧时长（毫秒）
+    pub book_age_ms: u64,
+    /// 冷却/限流状态（token 最小间隔、窗口上限等），无则 None
+    pub cooldown: Option<String>,
+    pub verdict: PreTradeVerdict,
+}
+
+impl PreTradeDecision {
+    /// 输出结构化事件（target = pre_trade）
+    pub fn emit(&self) {
+        info!(
+            target: "pre_trade",
+            market_id = %self.market_id,
+            yes_price = %self.yes_price,
+            no_price = %self.no_price,
+            yes_size = %self.yes_size,
+            no_size = %self.no_size,
+            gross_edge = %self.gross_edge,
+            fee_bps = %self.fee_bps,
+            fee = %self.fee,
+            slipped_sum = %self.slipped_sum,
+            net_edge = %self.net_edge,
+            net_edge_bps = %self.net_edge_bps.round_dp(2),
+            min_profitable_size = ?self.min_profitable_size,
+            yes_amount_usd = %self.yes_amount_usd,
+            no_amount_usd = %self.no_amount_usd,
+            skip_yes = self.skip_yes,
+            skip_no = self.skip_no,
+            depth_ok = self.depth_ok,
+            balance_ok = ?self.balance_ok,
+            book_age_ms = self.book_age_ms,
+            cooldown = self.cooldown.as_deref().unwrap_or("-"),
+            verdict = %self.verdict,
+            "📝 下单前决策 | 净利 {:.2}bps | {}",
+            self.net_edge_bps,
+            self.verdict
+        );
+    }
+
+    /// 与影子配置的决策对比：结论、报价或数量不同则输出差异事件（target = shadow），相同只记 debug
+    pub fn log_divergence(&self, shadow: &PreTradeDecision) {
+        let same = self.verdict == shadow.verdict
+            && (self.yes_price, self.no_price, self.yes_size, self.no_size)
+                == (shadow.yes_price, shadow.no_price, shadow.yes_size, shadow.no_size);
+        if same {
+            debug!(target: "shadow", market_id = %self.market_id, "影子配置决策一致");
+            return;
+        }
+        info!(
+            target: "shadow",
+            market_id = %self.market_id,
+            live_verdict = %self.verdict,
+            shadow_verdict = %shadow.verdict,
+            live_yes_price = %self.yes_price,
+            shadow_yes_price = %shadow.yes_price,
+            live_no_price = %self.no_price,
+            shadow_no_price = %shadow.no_price,
+            live_yes_size = %self.yes_size,
+            shadow_yes_size = %shadow.yes_size,
+            live_no_size = %self.no_size,
+            shadow_no_size = %shadow.no_size,
+            live_net_edge_bps = %self.net_edge_bps.round_dp(2),
+            shadow_net_edge_bps = %shadow.net_edge_bps.round_dp(2),
+            "👥 影子配置决策不同 | 实盘 {} | 影子 {}",
+            self.verdict,
+            shadow.verdict
+        );
+    }
+}
@@ -1,19 +1,42 @@
 use anyhow::Result;
-use alloy::signers::Signer;
-use alloy::signers::local::LocalSigner;
 use chrono::Utc;
-use polymarket_client_sdk::clob::{Client, Config};
-use polymarket_client_sdk::clob::types::{OrderType, Side, SignatureType};
-use polymarket_client_sdk::types::{Address, Decimal, U256};
-use polymarket_client_sdk::POLYGON;
+use dashmap::DashMap;
+use poly_5min_bot::leader::LeaderState;
+use poly_5min_bot::merge;
+use polymarket_client_sdk::clob::types::{OrderType, Side};
+use polymarket_client_sdk::types::{Address, B256, Decimal, U256};
 use rust_decimal_macros::dec;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Instant;
-use tracing::{debug, error, info, warn};
-use uuid::Uuid;
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, info_span, trace, warn, Instrument};
 
-use crate::monitor::arbitrage::ArbitrageOpportunity;
+use crate::config::decimal_setting;
+use crate::market::discoverer::FIVE_MIN_SECS;
+use crate::market::{MarketDiscoverer, MarketInfo};
+use crate::monitor::arbitrage::{ArbitrageOpportunity, ProfitConfig};
+use crate::monitor::book_cache::{BookCache, TopOfBook};
+use crate::monitor::ws::OrderFills;
+use crate::risk::positions::PositionTracker;
+use crate::trading::balance_guard::{BalanceCheckMode, BalanceGuard};
+use crate::trading::decision::{PreTradeDecision, PreTradeVerdict};
+use crate::trading::clock::{short_id, Clock, IdGenerator, SystemClock, UuidGenerator};
+use crate::trading::expiry_sweeper::ExpirySweeper;
+use crate::trading::ledger::{LedgerEntry, NullLedger, TradeLedger};
+use crate::trading::clob_api::{
+    proxy_mismatch, CancelSummary, ClobApi, ConnectOptions, LimitOrderParams, MarketMeta, MarketOrderParams,
+    OfflineClobApi, OrderPostResult, SdkClobApi,
+};
+use crate::trading::market_gate::{MarketFilter, MarketGate};
+use crate::trading::orders::{
+    DirectionMismatchAction, OnPartialError, PriceDirection, SelfTradePrevention, SizeMode, SlippageTable,
+};
+use crate::trading::slippage::SlippageRamp;
+use crate::utils::errors::{is_auth_error, is_balance_error, AuthExpired, GuaranteedLoss, NotLeader, OrderResponseMismatch, Paused, RepriceGaveUp};
 
+#[derive(Clone)]
 pub struct OrderPairResult {
     pub pair_id: String,
     pub yes_order_id: String,
@@ -23,129 +46,1936 @@ pub struct OrderPairResult {
     pub yes_size: Decimal,
     pub no_size: Decimal,
     pub success: bool,
+    /// 下单时执行器的策略变体标签
+    pub strategy_tag: String,
+    /// 检测时的预期每份毛利：1 − YES 卖一 − NO 卖一
+    pub expected_edge: Decimal,
+    /// 按实际提交的含滑点报价算出的每份毛利（1 − YES 报价 − NO 报价），仅双边都成交时有；
+    /// 与 expected_edge 之差即滑点让出的部分
+    pub realized_edge: Option<Decimal>,
+    /// YES 腿原始错误（error_msg 与响应原文），无错误时为 None
+    pub yes_raw_error: Option<String>,
+    /// NO 腿原始错误（error_msg 与响应原文），无错误时为 None
+    pub no_raw_error: Option<String>,
 }
 
-pub struct TradingExecutor {
-    client: Client<polymarket_client_sdk::auth::state::Authenticated<polymarket_client_sdk::auth::Normal>>,
-    private_key: String,
+/// 启动校验通过的市场：token 与交易参数均已就绪
+#[derive(Debug, Clone)]
+pub struct ValidatedMarket {
+    pub info: MarketInfo,
+    pub meta: MarketMeta,
+}
+
+/// 交易执行器参数（由 Config::executor_settings 生成）
+#[derive(Debug, Clone)]
+pub struct ExecutorSettings {
+    pub max_order_size_usdc: f64,
+    /// 按涨跌方向的滑点表（默认仅下降侧用 second，上涨与持平用 first）
+    pub slippage: SlippageTable,
+    pub min_slippage: f64,
+    /// 卖一档深度覆盖整腿数量时以卖一价报单（不加滑点）
+    pub capture_improvement: bool,
+    pub gtd_expiration_secs: u64,
+    pub arbitrage_order_type: OrderType,
+    pub profit_config: ProfitConfig,
+    /// 无匹配卖单时加价重试的最大 tick 数，0=不重试
+    pub reprice_max_ticks: u32,
+    /// 双边均挂单未成交时撤单并按最新卖一重报一次（reprice_pair）
+    pub reprice_resting_pair: bool,
+    /// 批量下单后按返回的订单 ID 查询所属 token，核对与提交顺序一致（防止返回乱序导致成交记错腿）
+    pub verify_order_responses: bool,
+    /// 两腿都因余额不足被拒时按最新余额缩量重试一次
+    pub balance_reject_retry: bool,
+    /// 一对订单的构建/签名失败重试预算（两腿共用），0=不重试
+    pub build_sign_retries: u32,
+    /// 提交前 USDC 余额核对：off | serial（先核对再构建签名）| concurrent（与构建签名并行）
+    pub balance_check: BalanceCheckMode,
+    /// 提交后多久按成交记录（trades）对账成交数量（毫秒），0=不对账
+    pub reconcile_fills_delay_ms: u64,
+    /// 同时在途（构建→提交→重试）的订单对上限，0=不限制
+    pub max_concurrent_pairs: usize,
+    /// 使用 SDK 市价单（按 USDC 金额、FOK/FAK）代替限价+滑点
+    pub use_market_orders: bool,
+    /// 下单数量最小变动单位，数量按此向下取整，0=不取整
+    pub size_increment: f64,
+    /// 每个5分钟窗口最多提交的订单对数，0=不限制
+    pub max_pairs_per_window: u32,
+    /// 提交成功但成交量为 0 且无错误（状态不明）时，按订单 ID 轮询的次数，0=不轮询
+    pub fill_poll_count: u32,
+    /// 上述轮询间隔（毫秒）
+    pub fill_poll_interval_ms: u64,
+    /// verify_authentication 成功结果的缓存时长（秒），0=不缓存
+    pub auth_cache_ttl_secs: u64,
+    /// 已持有单边多余库存时，从该腿目标数量中扣除，缺口不足 $1 的腿不下单（需 attach_inventory）
+    pub use_existing_inventory: bool,
+    /// 同一 token 两次提交的最小间隔（毫秒），间隔内再次出现则跳过该订单对，0=不限制
+    pub min_interval_per_token_ms: u64,
+    /// GTC 订单的客户端逻辑过期时间（秒），到期由清扫任务撤单，0=不过期
+    pub gtc_ttl_secs: u64,
+    /// 按窗口剩余时间放大滑点，None=不启用
+    pub slippage_ramp: Option<SlippageRamp>,
+    /// max_order_size_usdc 的口径：Shares=份数上限，Usdc=每腿美元预算
+    pub size_mode: SizeMode,
+    /// 两腿涨跌方向相同时告警或跳过
+    pub direction_mismatch: DirectionMismatchAction,
+    /// 一腿提交失败、另一腿有成交时的处理
+    pub on_partial_error: OnPartialError,
+    /// 提交买单前检查本账户同 token 的卖单，避免自成交
+    pub self_trade_prevention: SelfTradePrevention,
+    /// CLOB 连接/请求超时与认证超时重试次数
+    pub connect: ConnectOptions,
+    /// 策略变体标签，写入订单对结果与台账
+    pub strategy_tag: String,
+    /// 单腿最大成交价相对卖一的让价上限，0=不限制
+    pub max_edge_giveback: f64,
+    /// 私钥与 proxy 地址不匹配时拒绝启动（否则只告警）
+    pub strict_proxy_check: bool,
+    /// 机会的订单簿快照最大陈旧时长（毫秒），下单前复核，0=不检查
+    pub max_book_staleness_ms: u64,
+    /// 结算前最后 N 秒不提交新订单对（按5分钟窗口结束即结算时间计算），0=不限制
+    pub resolution_blackout_secs: u64,
+    /// 单市场未 merge 的平衡持仓上限（对，1 对 = YES+NO 各 1 份），达到后跳过该市场新订单对，0=不限制（需 attach_inventory）
+    pub max_open_pairs_per_market: f64,
+    /// USDC 余额下限，低于时自动暂停下单、回升后恢复（由 balance_guard::spawn 更新余额），0=不启用
+    pub min_trading_balance: f64,
+    /// 与监控共享的盘口缓存 TTL（毫秒），TTL 内复用监控推送的买一/卖一而不走 REST，0=不缓存
+    pub book_cache_ttl_ms: u64,
+    /// 市场白名单（condition_id 或 slug），非空时只交易名单内市场
+    pub market_allowlist: Vec<String>,
+    /// 市场黑名单（condition_id 或 slug），白名单优先
+    pub market_denylist: Vec<String>,
+    /// 影子配置的执行参数：每个机会按它再做一次下单前决策并记录差异，不下单
+    pub shadow: Option<Box<ExecutorSettings>>,
+}
+
+/// 价格最小变动单位
+pub(crate) const TICK_SIZE: Decimal = dec!(0.01);
+
+/// 手续费计费精度（USDC）：每腿手续费按此向上取整，小数量时相当于固定成本
+const FEE_UNIT: Decimal = dec!(0.0001);
+
+pub struct TradingExecutor<C: ClobApi = SdkClobApi> {
+    api: C,
     max_order_size: Decimal,
-    slippage: [Decimal; 2], // [first, second]，仅下降侧用 second，上涨与持平用 first
+    size_mode: SizeMode, // Usdc 时 max_order_size 为每腿美元预算，份数按含滑点价格换算
+    direction_mismatch: DirectionMismatchAction,
+    on_partial_error: OnPartialError, // 默认 LeaveForRisk：两腿原样交风险管理器
+    self_trade_prevention: SelfTradePrevention, // 默认 Off：不查询挂单
+    slippage: SlippageTable, // 按涨跌方向取滑点，默认仅下降侧用 second，上涨与持平用 first
+    min_slippage: Decimal,  // 滑点下限：按方向取得的滑点低于此值时抬到此值（-1 = 不限制）
+    slippage_ramp: Option<SlippageRamp>, // 临近窗口结束时放大滑点
+    capture_improvement: bool, // 卖一档深度足够时不让出滑点
+    max_edge_giveback: Option<Decimal>, // 单腿报价上限 = 卖一 + 此值，None=不限制
+    max_book_staleness: std::time::Duration, // 下单前复核订单簿快照陈旧度，0=不检查
+    resolution_blackout_secs: u64, // 结算前禁止新订单对的秒数，0=不限制
+    blackout_logged_window: std::sync::atomic::AtomicI64, // 已记录过禁入日志的窗口起点，每窗口只记一次
     gtd_expiration_secs: u64,
     arbitrage_order_type: OrderType,
+    profit_config: ProfitConfig, // 与监控端共用的盈利判定，下单前按含滑点价格再判一次
+    reprice_max_ticks: u32,
+    reprice_resting_pair: bool, // 双边挂单未成交时撤单重报
+    verify_order_responses: bool,
+    balance_reject_retry: bool,
+    build_sign_retries: u32, // 构建/签名失败重试预算，一对订单两腿共用
+    balance_check: BalanceCheckMode, // 提交前余额核对方式，仅首次双边提交时生效
+    reconcile_fills_delay: Option<std::time::Duration>, // 提交后按 trades 对账的延迟，None=不对账
+    funder: Option<Address>, // 下单资金地址（proxy 或 EOA），缩量重试时读取 USDC 余额；with_api 构造时为 None
+    pair_permits: Semaphore, // 在途订单对许可，满额时 execute_arbitrage_pair 排队等待
+    use_market_orders: bool,
+    size_increment: Decimal,
+    market_gate: MarketGate, // 运行时禁用的市场，execute_arbitrage_pair 开头检查
+    market_filter: MarketFilter, // 配置的白名单/黑名单，监控发出机会前与下单前各检查一次
+    max_pairs_per_window: u32,
+    window_pairs: std::sync::Mutex<(i64, u32)>, // (窗口开始时间戳, 本窗口已提交订单对数)，换窗口时归零
+    fill_poll_count: u32,
+    fill_poll_interval: std::time::Duration,
+    auth_cache_ttl: std::time::Duration,
+    auth_verified_at: std::sync::Mutex<Option<Instant>>, // 上次 api_keys() 验证成功的时间，下单失败或认证错误时清空
+    use_existing_inventory: bool,
+    inventory: OnceLock<Arc<PositionTracker>>, // 本地持仓簿，执行器创建后由 attach_inventory 设置
+    max_open_pairs_per_market: Option<Decimal>, // 单市场平衡持仓（min(YES, NO)）上限，None=不限制
+    chain_id: u64, // 签名用链 ID，with_api 默认 Polygon 主网
+    min_interval_per_token: std::time::Duration,
+    token_last_submit: DashMap<U256, Instant>, // token -> 上次提交时间
+    gtc_ttl: std::time::Duration,
+    expiry_sweeper: ExpirySweeper, // 已提交 GTC 订单的逻辑过期登记，由 expiry_sweeper::spawn 的任务撤单
+    ledger: Box<dyn TradeLedger>, // 成交台账后端，默认 NullLedger
+    clock: Box<dyn Clock>, // 墙钟（GTD 过期、窗口计数、滑点时间放大），默认系统时间
+    ids: Box<dyn IdGenerator>, // 订单对 ID，默认 UUID v4
+    strategy_tag: String, // 策略变体标签，随订单对结果与台账记录一起保存
+    paused: Arc<AtomicBool>, // 暂停下单（维护用），execute_arbitrage_pair 最先检查；行情与监控不受影响
+    balance_guard: BalanceGuard, // USDC 余额下限，余额不足时与 paused 一样跳过下单
+    leader: Arc<LeaderState>, // 主备选举结果（租约到期时刻），未启用选举时恒为 leader；备机只校验认证、不下单
+    book_cache: Arc<BookCache>, // 与监控共享的盘口缓存，加价/卖出前读取
+    order_fills: Arc<OrderFills>, // 用户频道推送的订单累计成交，未订阅时为空，对账时优先使用
+    market_metas: DashMap<B256, MarketMeta>, // condition_id -> 市场交易参数，首次下单时拉取
+    shadow: Option<Box<TradingExecutor<OfflineClobApi>>>, // 影子配置的执行器（不联网），只用于对比下单前决策
+    merge_lock: tokio::sync::Mutex<()>, // 同一 proxy 的 Safe nonce 只能串行使用：所有 merge 调用点先取得此锁
 }
 
-impl TradingExecutor {
+impl TradingExecutor<SdkClobApi> {
     pub async fn new(
         private_key: String,
-        max_order_size_usdc: f64,
         proxy_address: Option<Address>,
-        slippage: [f64; 2],
-        gtd_expiration_secs: u64,
-        arbitrage_order_type: OrderType,
+        chain_id: u64,
+        settings: ExecutorSettings,
     ) -> Result<Self> {
-        // 验证私钥格式
-        let signer = LocalSigner::from_str(&private_key)
-            .map_err(|e| anyhow::anyhow!("私钥格式无效: {}. 请确保私钥是64字符的十六进制字符串（不带0x前缀）", e))?
-            .with_chain_id(Some(POLYGON));
-
-        let config = Config::builder().use_server_time(false).build();
-        let mut auth_builder = Client::new("https://clob.polymarket.com", config)
-            .map_err(|e| anyhow::anyhow!("创建CLOB客户端失败: {}", e))?
-            .authentication_builder(&signer);
-        
-        // 如果提供了proxy_address，设置funder和signature_type（按照Python SDK模式）
-        if let Some(funder) = proxy_address {
-            auth_builder = auth_builder
-                .funder(funder)
-                .signature_type(SignatureType::Proxy);
+        // 启动时核对私钥与 proxy 地址，避免到首次 Merge 才发现不匹配
+        if let Some(proxy) = proxy_address {
+            match proxy_mismatch(&private_key, proxy, chain_id) {
+                Ok(None) => info!("✅ 私钥与代理地址匹配 | proxy={}", proxy),
+                Ok(Some(msg)) if settings.strict_proxy_check => anyhow::bail!("{}", msg),
+                Ok(Some(msg)) => warn!("⚠️ {}", msg),
+                Err(e) => warn!(error = %e, "无法核对私钥与代理地址"),
+            }
         }
-        
-        let client = auth_builder
-            .authenticate()
-            .await
-            .map_err(|e| {
-                anyhow::anyhow!(
-                    "API认证失败: {}. 可能的原因：1) 私钥无效 2) 网络问题 3) Polymarket API服务不可用",
-                    e
-                )
-            })?;
+        let funder = match proxy_address {
+            Some(proxy) => proxy,
+            None => alloy::signers::local::LocalSigner::from_str(&private_key)
+                .map_err(|e| anyhow::anyhow!("私钥格式无效: {}", e))?
+                .address(),
+        };
+        let api = SdkClobApi::new(private_key, proxy_address, chain_id, settings.connect).await?;
+        let mut executor = Self::with_api(api, settings)?;
+        executor.chain_id = chain_id;
+        executor.funder = Some(funder);
+        Ok(executor)
+    }
+}
 
+impl<C: ClobApi> TradingExecutor<C> {
+    /// 使用任意 ClobApi 实现构造（实盘用 SdkClobApi，单元测试用 MockClobApi，影子配置用 OfflineClobApi）；
+    /// 数值配置为 NaN、无穷或超出合理范围时报错，不再静默回退默认值
+    pub fn with_api(api: C, settings: ExecutorSettings) -> Result<Self> {
+        let max_order_size = decimal_setting("MAX_ORDER_SIZE_USDC", settings.max_order_size_usdc, dec!(0), None)?;
+        let min_slippage = decimal_setting("MIN_SLIPPAGE", settings.min_slippage, dec!(-1), Some(dec!(1)))?;
+        let max_edge_giveback = decimal_setting("MAX_EDGE_GIVEBACK", settings.max_edge_giveback, dec!(0), Some(dec!(1)))?;
+        let size_increment = decimal_setting("SIZE_INCREMENT", settings.size_increment, dec!(0), None)?;
+        let max_open_pairs_per_market =
+            decimal_setting("MAX_OPEN_PAIRS_PER_MARKET", settings.max_open_pairs_per_market, dec!(0), None)?;
+        let min_trading_balance = decimal_setting("MIN_TRADING_BALANCE", settings.min_trading_balance, dec!(0), None)?;
+        let shadow = match settings.shadow {
+            Some(s) => Some(Box::new(
+                TradingExecutor::with_api(OfflineClobApi, *s)
+                    .map_err(|e| anyhow::anyhow!("影子配置无效: {}", e))?,
+            )),
+            None => None,
+        };
         Ok(Self {
-            client,
-            private_key,
-            max_order_size: Decimal::try_from(max_order_size_usdc)
-                .unwrap_or(rust_decimal_macros::dec!(100.0)),
-            slippage: [
-                Decimal::try_from(slippage[0]).unwrap_or(dec!(0.0)),
-                Decimal::try_from(slippage[1]).unwrap_or(dec!(0.01)),
-            ],
-            gtd_expiration_secs,
-            arbitrage_order_type,
+            api,
+            max_order_size,
+            size_mode: settings.size_mode,
+            direction_mismatch: settings.direction_mismatch,
+            on_partial_error: settings.on_partial_error,
+            self_trade_prevention: settings.self_trade_prevention,
+            slippage: settings.slippage,
+            min_slippage,
+            capture_improvement: settings.capture_improvement,
+            slippage_ramp: settings.slippage_ramp,
+            max_book_staleness: std::time::Duration::from_millis(settings.max_book_staleness_ms),
+            resolution_blackout_secs: settings.resolution_blackout_secs,
+            blackout_logged_window: std::sync::atomic::AtomicI64::new(0),
+            max_edge_giveback: Some(max_edge_giveback).filter(|g| *g > dec!(0)),
+            gtd_expiration_secs: settings.gtd_expiration_secs,
+            arbitrage_order_type: settings.arbitrage_order_type,
+            profit_config: settings.profit_config,
+            reprice_max_ticks: settings.reprice_max_ticks,
+            reprice_resting_pair: settings.reprice_resting_pair,
+            verify_order_responses: settings.verify_order_responses,
+            balance_reject_retry: settings.balance_reject_retry,
+            build_sign_retries: settings.build_sign_retries,
+            balance_check: settings.balance_check,
+            reconcile_fills_delay: (settings.reconcile_fills_delay_ms > 0)
+                .then(|| std::time::Duration::from_millis(settings.reconcile_fills_delay_ms)),
+            funder: None,
+            pair_permits: Semaphore::new(if settings.max_concurrent_pairs == 0 {
+                Semaphore::MAX_PERMITS
+            } else {
+                settings.max_concurrent_pairs
+            }),
+            use_market_orders: settings.use_market_orders,
+            size_increment,
+            market_gate: MarketGate::default(),
+            market_filter: MarketFilter::new(&settings.market_allowlist, &settings.market_denylist),
+            max_pairs_per_window: settings.max_pairs_per_window,
+            window_pairs: std::sync::Mutex::new((0, 0)),
+            fill_poll_count: settings.fill_poll_count,
+            fill_poll_interval: std::time::Duration::from_millis(settings.fill_poll_interval_ms),
+            auth_cache_ttl: std::time::Duration::from_secs(settings.auth_cache_ttl_secs),
+            auth_verified_at: std::sync::Mutex::new(None),
+            use_existing_inventory: settings.use_existing_inventory,
+            inventory: OnceLock::new(),
+            max_open_pairs_per_market: Some(max_open_pairs_per_market).filter(|c| *c > dec!(0)),
+            chain_id: polymarket_client_sdk::POLYGON,
+            min_interval_per_token: std::time::Duration::from_millis(settings.min_interval_per_token_ms),
+            token_last_submit: DashMap::new(),
+            gtc_ttl: std::time::Duration::from_secs(settings.gtc_ttl_secs),
+            expiry_sweeper: ExpirySweeper::default(),
+            ledger: Box::new(NullLedger),
+            clock: Box::new(SystemClock),
+            ids: Box::new(UuidGenerator),
+            strategy_tag: settings.strategy_tag,
+            paused: Arc::new(AtomicBool::new(false)),
+            leader: Arc::new(LeaderState::always()),
+            balance_guard: BalanceGuard::new(min_trading_balance),
+            book_cache: Arc::new(BookCache::new(std::time::Duration::from_millis(settings.book_cache_ttl_ms))),
+            order_fills: Arc::new(OrderFills::new()),
+            market_metas: DashMap::new(),
+            shadow,
+            merge_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// 替换时钟（离线验证用固定时间）
+    #[cfg(test)]
+    pub fn with_clock(mut self, clock: Box<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 替换订单对 ID 生成器（离线验证用递增序号）
+    #[cfg(test)]
+    pub fn with_id_generator(mut self, ids: Box<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// 设置成交台账后端（由 LEDGER_BACKEND 选择）
+    pub fn with_ledger(mut self, ledger: Box<dyn TradeLedger>) -> Self {
+        self.ledger = ledger;
+        self
+    }
+
+    pub fn ledger(&self) -> &dyn TradeLedger {
+        self.ledger.as_ref()
+    }
+
+    /// 暂停下单：之后的机会直接以 Paused 跳过，已在途的订单对照常完成；返回此前是否已暂停
+    pub fn pause(&self) -> bool {
+        self.paused.swap(true, Ordering::SeqCst)
+    }
+
+    /// 恢复下单；返回此前是否处于暂停
+    pub fn resume(&self) -> bool {
+        self.paused.swap(false, Ordering::SeqCst)
+    }
+
+    /// 人工暂停或余额不足自动暂停
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst) || self.balance_guard.is_low()
+    }
+
+    /// 链上 merge 锁：proxy 的 Safe nonce 全局唯一，定时、阈值、收尾与紧急平仓的 merge 都须持锁提交
+    pub fn merge_lock(&self) -> &tokio::sync::Mutex<()> {
+        &self.merge_lock
+    }
+
+    /// 是否持有未过期的 leader 租约（未启用主备选举时恒为 true）
+    pub fn is_leader(&self) -> bool {
+        self.leader.is_leader()
+    }
+
+    /// 记录竞选/续约结果（requested_at 为发起请求的时刻）；返回此前是否为 leader
+    pub fn renew_leader(&self, held: bool, requested_at: Instant, ttl: std::time::Duration) -> bool {
+        self.leader.renew(held, requested_at, ttl)
+    }
+
+    pub fn balance_guard(&self) -> &BalanceGuard {
+        &self.balance_guard
+    }
+
+    /// 成交对账延迟；None 表示不对账
+    pub fn reconcile_fills_delay(&self) -> Option<std::time::Duration> {
+        self.reconcile_fills_delay
+    }
+
+    /// 按账户成交记录（trades）汇总订单对两腿的实际成交份数，更新 yes_filled / no_filled。
+    /// 提交响应的 taking_amount 只含即时成交，GTD/GTC 挂单随后成交的部分需在此补上；
+    /// 成交记录可能滞后，取两者较大值，不会把已确认的成交调小。
+    /// 两腿都已收到用户频道推送时直接用推送的累计成交，不再请求 trades
+    pub async fn reconcile_fills(&self, result: &OrderPairResult) -> Result<OrderPairResult> {
+        let ids: Vec<String> = [&result.yes_order_id, &result.no_order_id]
+            .into_iter()
+            .filter(|id| !id.is_empty())
+            .cloned()
+            .collect();
+        if ids.is_empty() {
+            return Ok(result.clone());
+        }
+        let traded = if ids.iter().all(|id| self.order_fills.matched(id).is_some()) {
+            std::collections::HashMap::new()
+        } else {
+            self.api.traded_sizes(&ids).await?
+        };
+        let traded_of = |id: &String| {
+            let pushed = self.order_fills.matched(id).unwrap_or(dec!(0));
+            traded.get(id).copied().unwrap_or(dec!(0)).max(pushed)
+        };
+        let yes_filled = result.yes_filled.max(traded_of(&result.yes_order_id));
+        let no_filled = result.no_filled.max(traded_of(&result.no_order_id));
+        if yes_filled != result.yes_filled || no_filled != result.no_filled {
+            info!(
+                "🧾 成交对账 | {} | YES {}→{} NO {}→{}",
+                short_id(&result.pair_id), result.yes_filled, yes_filled, result.no_filled, no_filled
+            );
+        } else {
+            debug!(pair_id = %result.pair_id, "成交对账一致");
+        }
+        Ok(OrderPairResult {
+            yes_filled,
+            no_filled,
+            ..result.clone()
+        })
+    }
+
+    /// 用户频道订单成交记录（交给 monitor::ws 写入推送，风险管理器共用）
+    pub fn order_fills(&self) -> Arc<OrderFills> {
+        self.order_fills.clone()
+    }
+
+    /// 共享盘口缓存（交给 OrderBookMonitor 写入 WS 推送）
+    pub fn book_cache(&self) -> Arc<BookCache> {
+        self.book_cache.clone()
+    }
+
+    /// 买一/卖一：优先用 TTL 内的缓存（监控推送或上次拉取），否则 REST 拉取并回写
+    pub async fn top_of_book(&self, token_id: U256) -> Result<TopOfBook> {
+        self.book_cache
+            .get_or_fetch(token_id, || self.api.top_of_book(token_id))
+            .await
+    }
+
+    /// 市场交易参数（tick、最小数量/金额、费率），按 condition_id 缓存，每个市场只拉取一次
+    pub async fn market_metadata(&self, condition_id: B256) -> Result<MarketMeta> {
+        if let Some(meta) = self.market_metas.get(&condition_id) {
+            return Ok(*meta);
+        }
+        let meta = self.api.market_meta(condition_id).await?;
+        info!(
+            "📏 市场参数 | {:#x} | tick:{} | 最小数量:{} | 最小金额:${} | 吃单费率:{}bps",
+            condition_id, meta.tick_size, meta.min_order_size, meta.min_order_amount, meta.taker_fee_bps
+        );
+        self.market_metas.insert(condition_id, meta);
+        Ok(meta)
+    }
+
+    /// 启动校验：按 slug 解析市场与 token、检查市场可交易并预取交易参数（写入缓存）；
+    /// 收集全部问题后一并返回错误，而不是在第一个问题处中止
+    pub async fn validate_markets(&self, discoverer: &MarketDiscoverer, slugs: &[String]) -> Result<Vec<ValidatedMarket>> {
+        let mut validated = Vec::with_capacity(slugs.len());
+        let mut problems = Vec::new();
+        for (slug, checked) in discoverer.probe_markets(slugs).await? {
+            let info = match checked {
+                Ok(info) => info,
+                Err(reason) => {
+                    problems.push(format!("{}: {}", slug, reason));
+                    continue;
+                }
+            };
+            match self.market_metadata(info.market_id).await {
+                Ok(meta) => validated.push(ValidatedMarket { info, meta }),
+                Err(e) => problems.push(format!("{}: 读取市场参数失败: {}", slug, e)),
+            }
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("{} 个市场校验失败:\n  {}", problems.len(), problems.join("\n  "));
+        }
+        Ok(validated)
+    }
+
+    /// 把已成交的腿写入台账（按报价记录，买单实际成交价不高于报价），附上预期/实际每份毛利；写入失败只告警
+    async fn record_fills(
+        &self,
+        opp: &ArbitrageOpportunity,
+        pair_id: &str,
+        legs: &[(U256, Decimal, Decimal)],
+        realized_edge: Option<Decimal>,
+    ) {
+        let fee_bps = self.profit_config.taker_fee_bps(opp.category.as_deref());
+        let expected_edge = dec!(1) - opp.yes_ask_price - opp.no_ask_price;
+        for &(token_id, price, filled) in legs.iter().filter(|l| l.2 > dec!(0)) {
+            let entry = LedgerEntry::buy(opp.market_id, pair_id, &self.strategy_tag, token_id, price, filled, fee_bps)
+                .with_edges(expected_edge, realized_edge);
+            if let Err(e) = self.ledger.record(entry).await {
+                warn!(error = %e, ledger = self.ledger.name(), "写入成交台账失败");
+            }
+        }
+    }
+
+    /// 数量按 size_increment 向下取整（交易所拒绝小数位过多或不足一个增量的数量）
+    pub fn round_size(&self, size: Decimal) -> Decimal {
+        if self.size_increment <= dec!(0) {
+            return size;
+        }
+        (size / self.size_increment).floor() * self.size_increment
+    }
+
+    /// 每腿实际下单数量（按卖一价估算，未含滑点）：各自受上限限制后按 size_increment 向下取整
+    pub fn leg_sizes(&self, opp: &ArbitrageOpportunity) -> (Decimal, Decimal) {
+        self.leg_sizes_at(opp, opp.yes_ask_price, opp.no_ask_price)
+    }
+
+    /// 两腿含滑点报价（不超过 max_price）：滑点按涨跌方向分配，仅下降=second，上涨/持平=first
+    fn slipped_prices(
+        &self,
+        opp: &ArbitrageOpportunity,
+        yes_dir: PriceDirection,
+        no_dir: PriceDirection,
+        max_price: Decimal,
+    ) -> (Decimal, Decimal) {
+        (
+            (opp.yes_ask_price + self.slippage_for_direction(yes_dir)).min(max_price),
+            (opp.no_ask_price + self.slippage_for_direction(no_dir)).min(max_price),
+        )
+    }
+
+    /// 监控端准入：按执行端的滑点与盈利判定复核机会，不通过的机会执行端必然跳过，不必发布或执行。
+    /// 启用价格改善时执行端可能以卖一报价，此处不加滑点，只按卖一判定
+    pub fn admits_after_slippage(&self, opp: &ArbitrageOpportunity, yes_dir: PriceDirection, no_dir: PriceDirection) -> bool {
+        if self.capture_improvement {
+            return opp.is_profitable(&self.profit_config);
+        }
+        let (yes_price, no_price) = self.slipped_prices(opp, yes_dir, no_dir, MarketMeta::default().max_price());
+        ArbitrageOpportunity {
+            yes_ask_price: yes_price,
+            no_ask_price: no_price,
+            ..opp.clone()
+        }
+        .is_profitable(&self.profit_config)
+    }
+
+    /// 满足交易所最小金额（金额严格大于 min_amount，通常为 $1）的最小份数，按 size_increment 对齐
+    fn min_size_for_notional(&self, price: Decimal, min_amount: Decimal) -> Option<Decimal> {
+        if price <= dec!(0) {
+            return None;
+        }
+        let step = if self.size_increment > dec!(0) { self.size_increment } else { dec!(0.01) };
+        Some(((min_amount / price) / step).floor() * step + step)
+    }
+
+    /// 最小盈利数量：不低于两腿最小金额所需份数（按 size_increment 对齐），且扣除按腿向上取整的手续费后
+    /// 总净利为正的最小数量；按当前价格每份净利不为正（任何数量都不盈利）时返回 None
+    fn min_profitable_size(&self, opp: &ArbitrageOpportunity, fee_bps: Decimal, min_amount: Decimal) -> Option<Decimal> {
+        let (yes, no) = (opp.yes_ask_price, opp.no_ask_price);
+        let rate = fee_bps / dec!(10000);
+        let edge = dec!(1) - (yes + no) * (dec!(1) + rate);
+        if edge <= dec!(0) {
+            return None;
+        }
+        let step = if self.size_increment > dec!(0) { self.size_increment } else { dec!(0.01) };
+        let fee = |price: Decimal, size: Decimal| (price * size * rate / FEE_UNIT).ceil() * FEE_UNIT;
+        let net = |size: Decimal| size * (dec!(1) - yes - no) - fee(yes, size) - fee(no, size);
+        let floor = self
+            .min_size_for_notional(yes, min_amount)?
+            .max(self.min_size_for_notional(no, min_amount)?);
+        // 取整误差每腿至多 1 个计费单位，upper 处必然盈利，从 floor 逐步向上找第一个净利为正的数量
+        let upper = ((dec!(2) * FEE_UNIT / edge) / step).ceil() * step;
+        let mut size = floor;
+        while size < upper && net(size) <= dec!(0) {
+            size += step;
+        }
+        Some(size)
+    }
+
+    /// 任一腿金额 ≤ min_amount 时，把两腿上调到同一目标数量（取两腿所需最小份数的较大者）；
+    /// 已满足或任一腿目标超过其上限时返回 None
+    fn lift_to_min_notional(
+        &self,
+        yes_price: Decimal,
+        no_price: Decimal,
+        yes_size: Decimal,
+        no_size: Decimal,
+        min_amount: Decimal,
+    ) -> Option<(Decimal, Decimal)> {
+        if yes_price * yes_size > min_amount && no_price * no_size > min_amount {
+            return None;
+        }
+        let target = self
+            .min_size_for_notional(yes_price, min_amount)?
+            .max(self.min_size_for_notional(no_price, min_amount)?);
+        let (yes_new, no_new) = (yes_size.max(target), no_size.max(target));
+        if yes_new > self.leg_cap(yes_price) || no_new > self.leg_cap(no_price) {
+            return None;
+        }
+        Some((yes_new, no_new))
+    }
+
+    /// 每腿份数上限：Shares 模式为 max_order_size；Usdc 模式为 max_order_size / 下单价
+    fn leg_cap(&self, price: Decimal) -> Decimal {
+        match self.size_mode {
+            SizeMode::Shares => self.max_order_size,
+            SizeMode::Usdc if price > dec!(0) => self.max_order_size / price,
+            SizeMode::Usdc => dec!(0),
+        }
+    }
+
+    /// 按给定下单价计算每腿数量（Usdc 模式下两腿份数可能不同）
+    fn leg_sizes_at(&self, opp: &ArbitrageOpportunity, yes_price: Decimal, no_price: Decimal) -> (Decimal, Decimal) {
+        (
+            self.round_size(opp.yes_size.min(self.leg_cap(yes_price))),
+            self.round_size(opp.no_size.min(self.leg_cap(no_price))),
+        )
+    }
+
+    /// 签名与 Merge 使用的链 ID
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// 接入本地持仓簿（用于扣除已有库存与单市场平衡持仓上限）；仅首次调用生效
+    pub fn attach_inventory(&self, tracker: Arc<PositionTracker>) {
+        if self.inventory.set(tracker).is_err() {
+            warn!("持仓簿已接入，忽略重复设置");
+        }
+    }
+
+    /// 扣除已有单边多余库存后的每腿数量：多余部分 = 本腿持仓 − 另一腿持仓（正数部分）。
+    /// 双边平衡的库存不扣除（再买一对仍是完整套利）。返回 (YES数量, NO数量, 是否有腿被扣减)
+    fn net_of_inventory(
+        &self,
+        yes_token_id: U256,
+        no_token_id: U256,
+        yes_size: Decimal,
+        no_size: Decimal,
+    ) -> (Decimal, Decimal, bool) {
+        let tracker = match self.inventory.get() {
+            Some(t) if self.use_existing_inventory => t,
+            _ => return (yes_size, no_size, false),
+        };
+        let (yes_held, no_held) = tracker.get_pair_positions(yes_token_id, no_token_id);
+        let yes_excess = (yes_held - no_held).max(dec!(0));
+        let no_excess = (no_held - yes_held).max(dec!(0));
+        if yes_excess == dec!(0) && no_excess == dec!(0) {
+            return (yes_size, no_size, false);
+        }
+        let yes_net = self.round_size((yes_size - yes_excess).max(dec!(0)));
+        let no_net = self.round_size((no_size - no_excess).max(dec!(0)));
+        info!(
+            "📦 扣除已有库存 | 持仓 YES {} NO {} | 下单数量 YES {}→{} NO {}→{}",
+            yes_held, no_held, yes_size, yes_net, no_size, no_net
+        );
+        (yes_net, no_net, true)
+    }
+
+    /// 含滑点报价之和 ≥ 1 时返回 [`GuaranteedLoss`]；不读取任何配置，无法关闭
+    fn ensure_no_guaranteed_loss(yes_price: Decimal, no_price: Decimal) -> Result<()> {
+        let sum = yes_price + no_price;
+        if sum >= dec!(1) {
+            error!("🛑 硬止损：含滑点报价之和 {} ≥ 1（YES {} + NO {}），拒绝下单", sum, yes_price, no_price);
+            return Err(anyhow::Error::new(GuaranteedLoss { yes_price, no_price, sum }));
+        }
+        Ok(())
+    }
+
+    /// 已持有一腿的每份成本（持仓簿记录的敞口成本 / 持仓）；未记录成本时按该腿当前含滑点报价计
+    fn held_leg_cost(&self, token_id: U256, quoted: Decimal) -> Decimal {
+        self.inventory
+            .get()
+            .and_then(|tracker| tracker.average_cost(token_id))
+            .unwrap_or(quoted)
+    }
+
+    /// 单市场平衡持仓上限：已持有的 min(YES, NO) 达到上限时返回 (当前平衡持仓, 上限)，merge 减仓后自动解除
+    fn open_pairs_capped(&self, yes_token_id: U256, no_token_id: U256) -> Option<(Decimal, Decimal)> {
+        let cap = self.max_open_pairs_per_market?;
+        let tracker = self.inventory.get()?;
+        let (yes_held, no_held) = tracker.get_pair_positions(yes_token_id, no_token_id);
+        let balanced = yes_held.min(no_held).max(dec!(0));
+        (balanced >= cap).then_some((balanced, cap))
+    }
+
+    /// 每 token 最小提交间隔：任一 token 距上次提交不足 min_interval_per_token 时返回 (token, 已过去时间)
+    fn token_throttled(&self, tokens: &[U256]) -> Option<(U256, std::time::Duration)> {
+        if self.min_interval_per_token.is_zero() {
+            return None;
+        }
+        tokens.iter().find_map(|t| {
+            let elapsed = self.token_last_submit.get(t)?.elapsed();
+            (elapsed < self.min_interval_per_token).then_some((*t, elapsed))
+        })
+    }
+
+    /// 记录 token 提交时间（用于每 token 最小间隔）
+    fn mark_submitted(&self, tokens: &[U256]) {
+        if self.min_interval_per_token.is_zero() {
+            return;
+        }
+        let now = Instant::now();
+        for t in tokens {
+            self.token_last_submit.insert(*t, now);
+        }
+    }
+
+    /// 本窗口订单对是否已达上限（只查看，不占用名额）
+    fn window_pairs_full(&self) -> bool {
+        if self.max_pairs_per_window == 0 {
+            return false;
+        }
+        let window = MarketDiscoverer::calculate_current_window_timestamp(self.clock.now());
+        let guard = self.window_pairs.lock().unwrap();
+        guard.0 == window && guard.1 >= self.max_pairs_per_window
+    }
+
+    /// 占用本窗口的一个订单对名额；已达 max_pairs_per_window 时返回 Err(已提交数)
+    /// 窗口与调度器一致：按 UTC 5 分钟整点对齐，进入新窗口时计数归零
+    fn take_window_slot(&self) -> std::result::Result<u32, u32> {
+        let window = MarketDiscoverer::calculate_current_window_timestamp(self.clock.now());
+        let mut guard = self.window_pairs.lock().unwrap();
+        if guard.0 != window {
+            *guard = (window, 0);
+        }
+        if self.max_pairs_per_window > 0 && guard.1 >= self.max_pairs_per_window {
+            return Err(guard.1);
+        }
+        guard.1 += 1;
+        if guard.1 == self.max_pairs_per_window {
+            warn!(
+                "🧮 本窗口订单对已达上限 {}，本窗口剩余机会将跳过 | 窗口:{}",
+                self.max_pairs_per_window, window
+            );
+        }
+        Ok(guard.1)
+    }
+
+    /// 运行时市场开关（clone 后与执行器共享状态）
+    pub fn market_gate(&self) -> &MarketGate {
+        &self.market_gate
+    }
+
+    /// 配置的市场白名单/黑名单（监控端发出机会前同样检查）
+    pub fn market_filter(&self) -> &MarketFilter {
+        &self.market_filter
+    }
+
+    /// 当前剩余的在途订单对许可数
+    pub fn available_permits(&self) -> usize {
+        self.pair_permits.available_permits()
+    }
+
+    /// 重新认证并替换客户端（API 凭证过期/轮换后调用）
+    pub async fn reauthenticate(&self) -> Result<()> {
+        self.api.reauthenticate().await
+    }
+
+    /// 认证类错误：重新认证，成功返回 Ok(())（调用方应重试一次），失败返回 AuthExpired
+    async fn reauth_after(&self, original: &str) -> Result<()> {
+        self.invalidate_auth_cache();
+        self.reauthenticate().await.map_err(|e| {
+            error!(error = %e, "❌ 重新认证失败");
+            anyhow::Error::new(AuthExpired {
+                original: original.to_string(),
+                reauth_error: e.to_string(),
+            })
         })
     }
 
     /// 验证认证是否真的成功 - 按照官方示例使用 api_keys() 来验证
+    /// 上次成功验证在 auth_cache_ttl 内且之后没有下单失败时直接返回 Ok，不调用 API（供健康检查高频调用）
     pub async fn verify_authentication(&self) -> Result<()> {
+        if !self.auth_cache_ttl.is_zero() {
+            let verified_at = *self.auth_verified_at.lock().unwrap();
+            if verified_at.is_some_and(|t| t.elapsed() < self.auth_cache_ttl) {
+                trace!("认证验证命中缓存");
+                return Ok(());
+            }
+        }
         // 按照官方示例，使用 api_keys() 来验证认证状态
-        self.client.api_keys().await
-            .map_err(|e| anyhow::anyhow!("认证验证失败: API调用返回错误: {}", e))?;
-        Ok(())
+        let result = self.api.api_keys().await;
+        let mut verified_at = self.auth_verified_at.lock().unwrap();
+        match result {
+            Ok(_) => {
+                *verified_at = Some(Instant::now());
+                Ok(())
+            }
+            Err(e) => {
+                *verified_at = None;
+                Err(anyhow::anyhow!("认证验证失败: API调用返回错误: {}", e))
+            }
+        }
+    }
+
+    /// 清空认证验证缓存，下次 verify_authentication 强制调用 API
+    fn invalidate_auth_cache(&self) {
+        *self.auth_verified_at.lock().unwrap() = None;
+    }
+
+    /// GTC 订单过期登记表（清扫任务读取）
+    pub fn expiry_sweeper(&self) -> &ExpirySweeper {
+        &self.expiry_sweeper
+    }
+
+    /// 按订单 ID 撤单
+    pub async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        self.api
+            .cancel_order(order_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("撤单失败: {}", e))
+    }
+
+    /// 只撤销 GTD 过期时间已过、但交易所仍列为挂单的订单，返回撤单成功数量（无过期订单时不发撤单请求）
+    pub async fn cancel_expired_orders(&self) -> Result<usize> {
+        let now = self.clock.now();
+        let expired: Vec<String> = self
+            .api
+            .open_orders()
+            .await
+            .map_err(|e| anyhow::anyhow!("查询挂单失败: {}", e))?
+            .into_iter()
+            .filter(|o| o.is_expired_gtd(now))
+            .map(|o| o.order_id)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+        let summary = self
+            .api
+            .cancel_orders(&expired)
+            .await
+            .map_err(|e| anyhow::anyhow!("撤销过期 GTD 订单失败: {}", e))?;
+        if !summary.failed.is_empty() {
+            warn!("撤销过期 GTD 订单部分失败 | {}", summary);
+        }
+        Ok(summary.cancelled)
+    }
+
+    /// 自成交保护：查询本账户挂单，找出 legs（token, 买价）上报价 ≤ 买价的卖单；
+    /// CancelResting 时先撤掉这些卖单（有撤单失败则放弃下单），SkipOrder 时直接放弃下单
+    async fn prevent_self_trade(&self, legs: &[(U256, Decimal)]) -> Result<()> {
+        if self.self_trade_prevention == SelfTradePrevention::Off {
+            return Ok(());
+        }
+        let crossing: Vec<String> = self
+            .api
+            .open_orders()
+            .await
+            .map_err(|e| anyhow::anyhow!("自成交检查查询挂单失败: {}", e))?
+            .into_iter()
+            .filter(|o| {
+                matches!(o.side, Side::Sell)
+                    && legs.iter().any(|(token_id, price)| o.token_id == *token_id && o.price <= *price)
+            })
+            .map(|o| o.order_id)
+            .collect();
+        if crossing.is_empty() {
+            return Ok(());
+        }
+        match self.self_trade_prevention {
+            SelfTradePrevention::SkipOrder => {
+                anyhow::bail!("自成交保护：本账户有 {} 笔卖单会与本次买单成交，跳过", crossing.len())
+            }
+            _ => {
+                let summary = self
+                    .api
+                    .cancel_orders(&crossing)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("自成交保护撤单失败: {}", e))?;
+                if !summary.failed.is_empty() {
+                    anyhow::bail!("自成交保护撤单部分失败，跳过下单 | {}", summary);
+                }
+                info!("🛡️ 自成交保护：已撤掉 {} 笔会被本次买单吃到的卖单", summary.cancelled);
+                Ok(())
+            }
+        }
+    }
+
+    /// 挂单中的 GTC 限价单登记逻辑过期时间（未启用 TTL、市价模式或非 GTC 时不登记）
+    fn register_gtc_expiry(&self, result: &OrderPostResult, size: Decimal) {
+        if self.gtc_ttl.is_zero()
+            || self.use_market_orders
+            || !matches!(self.arbitrage_order_type, OrderType::GTC)
+            || !result.success
+            || result.order_id.is_empty()
+            || result.taking_amount >= size
+        {
+            return;
+        }
+        self.expiry_sweeper.register(&result.order_id, self.gtc_ttl);
+        debug!("🧹 登记 GTC 过期 | order_id={} | {}s 后撤单", result.order_id, self.gtc_ttl.as_secs());
+    }
+
+    /// 读取买一价（无买单时为 None）
+    pub async fn best_bid(&self, token_id: U256) -> Result<Option<Decimal>> {
+        Ok(self.top_of_book(token_id).await?.best_bid)
+    }
+
+    /// 按市场类别取 taker 费率（bps），None 时为默认费率
+    pub fn taker_fee_bps(&self, category: Option<&str>) -> Decimal {
+        self.profit_config.taker_fee_bps(category)
     }
 
-    /// 取消该账户所有挂单（收尾时使用）
-    pub async fn cancel_all_orders(&self) -> Result<polymarket_client_sdk::clob::types::response::CancelOrdersResponse> {
-        self.client
+    /// 取消该账户所有挂单（收尾时使用），返回撤销成功/失败的汇总
+    pub async fn cancel_all_orders(&self) -> Result<CancelSummary> {
+        let summary = self
+            .api
             .cancel_all_orders()
             .await
-            .map_err(|e| anyhow::anyhow!("取消所有挂单失败: {}", e))
+            .map_err(|e| anyhow::anyhow!("取消所有挂单失败: {}", e))?;
+        debug!(raw = %summary.raw, "取消所有挂单响应");
+        Ok(summary)
+    }
+
+    /// 以指定价格下 GTC 卖单（收尾时市价意图卖出单腿持仓）；立即成交部分写入台账
+    pub async fn sell_at_price(
+        &self,
+        market_id: B256,
+        token_id: U256,
+        price: Decimal,
+        size: Decimal,
+    ) -> Result<OrderPostResult> {
+        let order = self
+            .api
+            .limit_order(LimitOrderParams {
+                token_id,
+                side: Side::Sell,
+                price,
+                size,
+                order_type: OrderType::GTC,
+                expiration: None,
+            })
+            .await?;
+        let signed = self.api.sign(order).await?;
+        let resp = match self.api.post_order(signed.clone()).await {
+            Ok(resp) => resp,
+            Err(e) if is_auth_error(&e.to_string()) => {
+                self.reauth_after(&e.to_string()).await?;
+                self.api
+                    .post_order(signed)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("卖出订单提交失败（重新认证后重试）: {}", e))?
+            }
+            Err(e) => {
+                self.invalidate_auth_cache();
+                return Err(anyhow::anyhow!("卖出订单提交失败: {}", e));
+            }
+        };
+        self.record_sell(market_id, token_id, &resp).await;
+        Ok(resp)
+    }
+
+    /// 卖单立即成交部分写入台账：数量为付出的份数（making），成交均价 = 收到 USDC / 份数，
+    /// 手续费按该市场的吃单费率；GTC 挂单随后成交的部分不在此记录。写入失败只告警
+    async fn record_sell(&self, market_id: B256, token_id: U256, resp: &OrderPostResult) {
+        if resp.making_amount <= dec!(0) {
+            return;
+        }
+        let price = resp.taking_amount / resp.making_amount;
+        let fee_bps = match self.market_metadata(market_id).await {
+            Ok(meta) => meta.taker_fee_bps,
+            Err(_) => self.profit_config.taker_fee_bps(None),
+        };
+        let entry = LedgerEntry::sell(market_id, "", &self.strategy_tag, token_id, price, resp.making_amount, fee_bps);
+        if let Err(e) = self.ledger.record(entry).await {
+            warn!(error = %e, ledger = self.ledger.name(), "写入卖出台账失败");
+        }
+    }
+
+    /// Merge 回收写入台账（每份 1 USDC）；写入失败只告警
+    pub async fn record_merge(&self, condition_id: B256, merged_usdc: Decimal) {
+        if let Err(e) = self.ledger.record(LedgerEntry::merge(condition_id, &self.strategy_tag, merged_usdc)).await {
+            warn!(error = %e, ledger = self.ledger.name(), "写入 Merge 台账失败");
+        }
+    }
+
+    /// 状态不明：提交成功、无错误信息、但成交量为 0（可能挂单中，也可能已失效）
+    fn is_ambiguous(r: &OrderPostResult) -> bool {
+        r.success
+            && r.taking_amount == dec!(0)
+            && r.error_msg.as_deref().map(|m| m.is_empty()).unwrap_or(true)
+            && !r.order_id.is_empty()
+    }
+
+    /// 对状态不明的腿按订单 ID 轮询，观察成交量是否更新；有成交时回写 taking_amount。
+    /// 结果分三类记录日志：已成交 / 挂单中 / 已失效（失效时写入 error_msg 并置 success=false）。
+    async fn resolve_ambiguous_fill(&self, pair_id: &str, leg: &str, result: &mut OrderPostResult) {
+        if self.fill_poll_count == 0 || !Self::is_ambiguous(result) {
+            return;
+        }
+        let mut last_status = String::new();
+        for i in 1..=self.fill_poll_count {
+            tokio::time::sleep(self.fill_poll_interval).await;
+            match self.api.get_order(&result.order_id).await {
+                Ok(snap) => {
+                    last_status = snap.status.clone();
+                    if snap.size_matched > dec!(0) {
+                        info!(
+                            "🔎 成交确认 | {} | {} 轮询#{} 成交 {} 份 | 状态:{}",
+                            short_id(&pair_id), leg, i, snap.size_matched, snap.status
+                        );
+                        result.taking_amount = snap.size_matched;
+                        return;
+                    }
+                    if snap.is_dead() {
+                        warn!("🔎 订单已失效 | {} | {} 轮询#{} | 状态:{}", short_id(&pair_id), leg, i, snap.status);
+                        result.success = false;
+                        result.error_msg = Some(format!("order {}", snap.status.to_lowercase()));
+                        return;
+                    }
+                }
+                Err(e) => debug!("🔎 查询订单失败 | {} | {} 轮询#{} | {}", short_id(&pair_id), leg, i, e),
+            }
+        }
+        info!(
+            "🔎 仍在挂单 | {} | {} 轮询{}次未成交 | 状态:{}",
+            short_id(&pair_id), leg, self.fill_poll_count,
+            if last_status.is_empty() { "未知" } else { last_status.as_str() }
+        );
+    }
+
+    /// 一腿失败、另一腿有成交时按 on_partial_error 处理，返回每腿（YES, NO）已卖出的份数。
+    /// 撤单针对提交成功但未全部成交的腿（其余量仍在挂单）；卖出只卖多于另一腿成交的部分，平衡部分留待 merge。
+    async fn handle_partial_error(
+        &self,
+        pair_id: &str,
+        market_id: B256,
+        legs: [(&str, U256, &OrderPostResult, Decimal); 2],
+    ) -> [Decimal; 2] {
+        let mut sold = [dec!(0); 2];
+        if self.on_partial_error == OnPartialError::LeaveForRisk {
+            return sold;
+        }
+        for (leg, _, result, size) in &legs {
+            if result.success && !result.order_id.is_empty() && result.taking_amount < *size {
+                match self.api.cancel_order(&result.order_id).await {
+                    Ok(()) => info!("🧹 撤掉挂单余量 | {} | {} | order_id={} | 已成交 {}/{}", short_id(&pair_id), leg, result.order_id, result.taking_amount, size),
+                    Err(e) => warn!(pair_id = %pair_id, leg = %leg, order_id = %result.order_id, error = %e, "撤掉挂单余量失败"),
+                }
+            }
+        }
+        if let OnPartialError::UnwindFilled { max_slippage } = self.on_partial_error {
+            let (yes_filled, no_filled) = (legs[0].2.taking_amount, legs[1].2.taking_amount);
+            let (i, excess) = if yes_filled > no_filled { (0, yes_filled - no_filled) } else { (1, no_filled - yes_filled) };
+            let (leg, token_id, _, _) = legs[i];
+            let excess = (excess * dec!(100)).floor() / dec!(100);
+            if excess >= dec!(0.01) {
+                match self.sell_market(market_id, token_id, excess, max_slippage).await {
+                    Ok(r) => {
+                        // 卖单付出的是份数（making），收到的是 USDC（taking）
+                        info!("🔻 卖出多余成交 | {} | {} | 数量:{} | 立即成交:{} 份（收到 {} USDC）", short_id(&pair_id), leg, excess, r.making_amount, r.taking_amount);
+                        sold[i] = r.making_amount;
+                    }
+                    Err(e) => warn!(pair_id = %pair_id, leg = %leg, error = %e, "卖出多余成交失败，交由风险管理器"),
+                }
+            }
+        }
+        sold
+    }
+
+    /// 两腿都未成交，且至少一腿因余额/授权不足被拒（未挂单，可安全重报）
+    fn rejected_for_balance(yes: &OrderPostResult, no: &OrderPostResult) -> bool {
+        yes.taking_amount == dec!(0)
+            && no.taking_amount == dec!(0)
+            && [yes, no]
+                .iter()
+                .any(|r| !r.success && r.error_msg.as_deref().is_some_and(is_balance_error))
+    }
+
+    /// 按最新 USDC 余额同比例缩小两腿数量（按 size_increment 向下取整，不超过原数量）；
+    /// 未知资金地址、余额读取失败或缩量后任一腿不足最小金额时返回 None
+    async fn sizes_within_balance(
+        &self,
+        yes_price: Decimal,
+        no_price: Decimal,
+        yes_size: Decimal,
+        no_size: Decimal,
+        min_amount: Decimal,
+    ) -> Option<(Decimal, Decimal)> {
+        let owner = self.funder?;
+        let balance = match merge::usdc_balance(owner, None, self.chain_id).await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!(error = %e, "读取 USDC 余额失败，不缩量重试");
+                return None;
+            }
+        };
+        let cost = yes_price * yes_size + no_price * no_size;
+        if cost <= dec!(0) {
+            return None;
+        }
+        let ratio = (balance / cost).min(dec!(1));
+        let shrink = |size: Decimal| (size * ratio / self.size_increment).floor() * self.size_increment;
+        let (yes, no) = (shrink(yes_size), shrink(no_size));
+        if yes_price * yes <= min_amount || no_price * no <= min_amount {
+            info!(
+                "💸 余额 {:.2} 不足以缩量重试 | 缩量后 YES {}×{:.4} NO {}×{:.4} 不满足 ${} 最小金额",
+                balance, yes, yes_price, no, no_price, min_amount
+            );
+            return None;
+        }
+        Some((yes, no))
+    }
+
+    /// 双边都因订单簿无匹配卖单而未成交（未挂单，可安全重报）
+    fn both_no_match(yes: &OrderPostResult, no: &OrderPostResult) -> bool {
+        let no_match = |r: &OrderPostResult| {
+            r.taking_amount == dec!(0)
+                && r.error_msg
+                    .as_deref()
+                    .map(|m| m.contains("no orders found to match"))
+                    .unwrap_or(false)
+        };
+        no_match(yes) && no_match(no)
+    }
+
+    /// 撤单重报：撤掉订单对两腿的挂单，按撤单后的实际成交量算出剩余数量，重读盘口以最新卖一重报剩余部分；
+    /// 改价后不再盈利、剩余数量不足最小金额或撤单未全部成功时放弃重报（已完成的撤单不回滚）。
+    /// 返回的结果中订单 ID 为新订单，成交量与数量均累计撤单前的部分
+    pub async fn reprice_pair(&self, result: &OrderPairResult, opp: &ArbitrageOpportunity) -> Result<OrderPairResult> {
+        let pair_id = &result.pair_id;
+        let short = short_id(pair_id);
+        let ids: Vec<String> = [&result.yes_order_id, &result.no_order_id]
+            .into_iter()
+            .filter(|id| !id.is_empty())
+            .cloned()
+            .collect();
+        anyhow::ensure!(ids.len() == 2, "订单对缺少订单 ID，无法撤单重报");
+        let summary = self.api.cancel_orders(&ids).await?;
+        info!("🔄 撤单重报 | {} | {}", short, summary);
+        anyhow::ensure!(summary.failed.is_empty(), "撤单未全部成功，放弃重报: {}", summary.failed.join("; "));
+
+        // 撤单前可能又有成交：按订单状态刷新成交量
+        let (yes_snap, no_snap) = tokio::join!(
+            self.api.get_order(&result.yes_order_id),
+            self.api.get_order(&result.no_order_id)
+        );
+        let yes_filled = yes_snap.map(|s| s.size_matched).unwrap_or(dec!(0)).max(result.yes_filled);
+        let no_filled = no_snap.map(|s| s.size_matched).unwrap_or(dec!(0)).max(result.no_filled);
+        let yes_size = self.round_size(result.yes_size - yes_filled);
+        let no_size = self.round_size(result.no_size - no_filled);
+
+        // 重读盘口（绕过缓存，撤单后需要最新卖一）并回写缓存
+        let (yes_book, no_book) = tokio::join!(
+            self.api.top_of_book(opp.yes_token_id),
+            self.api.top_of_book(opp.no_token_id)
+        );
+        let (yes_book, no_book) = (yes_book?, no_book?);
+        self.book_cache.insert(opp.yes_token_id, yes_book);
+        self.book_cache.insert(opp.no_token_id, no_book);
+        let (yes_ask, no_ask) = match (yes_book.best_ask, no_book.best_ask) {
+            (Some(y), Some(n)) => (y, n),
+            _ => anyhow::bail!("订单簿无卖单，已撤单、不重报"),
+        };
+        let meta = self.market_metadata(opp.market_id).await.unwrap_or_default();
+        let yes_price = yes_ask.min(self.max_fill_price(opp.yes_ask_price)).min(meta.max_price());
+        let no_price = no_ask.min(self.max_fill_price(opp.no_ask_price)).min(meta.max_price());
+        let repriced_opp = ArbitrageOpportunity {
+            yes_ask_price: yes_price,
+            no_ask_price: no_price,
+            ..opp.clone()
+        };
+        if !repriced_opp.is_profitable(&self.profit_config) {
+            warn!(
+                "🛑 放弃重报 | {} | 卖一 YES {:.4} NO {:.4} | 总价 {:.4}，扣费后每份净利 {:.4}",
+                short, yes_ask, no_ask, yes_price + no_price, repriced_opp.net_edge(&self.profit_config)
+            );
+            anyhow::bail!("改价后不再盈利，已撤单、不重报");
+        }
+        if yes_price * yes_size <= meta.min_order_amount || no_price * no_size <= meta.min_order_amount {
+            anyhow::bail!(
+                "剩余数量不足最小金额（YES {}×{:.4} NO {}×{:.4}），已撤单、不重报",
+                yes_size, yes_price, no_size, no_price
+            );
+        }
+
+        info!(
+            "🔁 重报 | {} | YES {:.4}×{} NO {:.4}×{} | 撤单前已成交 YES {} NO {}",
+            short, yes_price, yes_size, no_price, no_size, yes_filled, no_filled
+        );
+        let expiration = (!self.use_market_orders && matches!(self.arbitrage_order_type, OrderType::GTD))
+            .then(|| self.clock.now() + chrono::Duration::seconds(self.gtd_expiration_secs as i64));
+        self.mark_submitted(&[opp.yes_token_id, opp.no_token_id]);
+        let (mut yes_result, mut no_result) = self
+            .submit_pair(
+                pair_id,
+                Instant::now(),
+                (opp.yes_token_id, yes_price, yes_size),
+                (opp.no_token_id, no_price, no_size),
+                expiration,
+                false,
+            )
+            .await?;
+        tokio::join!(
+            self.resolve_ambiguous_fill(pair_id, "YES", &mut yes_result),
+            self.resolve_ambiguous_fill(pair_id, "NO", &mut no_result)
+        );
+        self.register_gtc_expiry(&yes_result, yes_size);
+        self.register_gtc_expiry(&no_result, no_size);
+        let (yes_new, no_new) = (yes_result.taking_amount, no_result.taking_amount);
+        let realized_edge = (yes_new > dec!(0) && no_new > dec!(0)).then(|| dec!(1) - yes_price - no_price);
+        self.record_fills(
+            opp,
+            pair_id,
+            &[(opp.yes_token_id, yes_price, yes_new), (opp.no_token_id, no_price, no_new)],
+            realized_edge,
+        )
+        .await;
+        info!("🔁 重报结果 | {} | YES 成交 {} NO 成交 {}", short, yes_new, no_new);
+
+        Ok(OrderPairResult {
+            pair_id: pair_id.clone(),
+            yes_order_id: yes_result.order_id.clone(),
+            no_order_id: no_result.order_id.clone(),
+            yes_filled: yes_filled + yes_new,
+            no_filled: no_filled + no_new,
+            yes_size: yes_filled + yes_size,
+            no_size: no_filled + no_size,
+            success: yes_result.success && no_result.success,
+            strategy_tag: self.strategy_tag.clone(),
+            expected_edge: result.expected_edge,
+            realized_edge: realized_edge.or(result.realized_edge),
+            yes_raw_error: yes_result.raw_error(),
+            no_raw_error: no_result.raw_error(),
+        })
+    }
+
+    /// 市价模式下的订单类型：配置为 FAK 时用 FAK，否则 FOK（市价单不支持 GTC/GTD）
+    fn market_order_type(&self) -> OrderType {
+        if matches!(self.arbitrage_order_type, OrderType::FAK) {
+            OrderType::FAK
+        } else {
+            OrderType::FOK
+        }
+    }
+
+    /// 构建单腿买单：市价模式按 价格×数量 的 USDC 金额下市价单（以含滑点的 price 为最差成交价），否则下限价单
+    /// （仅 GTD 时设置 expiration，SDK 规定非 GTD 不可设过期）
+    async fn build_buy_leg(
+        &self,
+        token_id: U256,
+        price: Decimal,
+        size: Decimal,
+        expiration: Option<chrono::DateTime<Utc>>,
+    ) -> Result<C::Order> {
+        if self.use_market_orders {
+            self.api
+                .market_order(MarketOrderParams {
+                    token_id,
+                    side: Side::Buy,
+                    usdc_amount: price * size,
+                    price,
+                    order_type: self.market_order_type(),
+                })
+                .await
+        } else {
+            self.api
+                .limit_order(LimitOrderParams {
+                    token_id,
+                    side: Side::Buy,
+                    price,
+                    size,
+                    order_type: self.arbitrage_order_type.clone(),
+                    expiration,
+                })
+                .await
+        }
+    }
+
+    /// 构建并签名单腿买单，返回 (已签名订单, 构建耗时ms, 签名耗时ms)；构建或签名失败时从 budget 扣一次重试，
+    /// budget 由同一对订单的两腿共用，耗尽后返回最后一次错误（签名会消耗订单，重试需重新构建）
+    async fn build_and_sign_leg(
+        &self,
+        leg: &'static str,
+        (token_id, price, size): (U256, Decimal, Decimal),
+        expiration: Option<chrono::DateTime<Utc>>,
+        budget: &std::sync::atomic::AtomicU32,
+    ) -> Result<(C::Signed, u128, u128)> {
+        let (mut build_ms, mut sign_ms) = (0u128, 0u128);
+        loop {
+            let build_start = Instant::now();
+            let built = self.build_buy_leg(token_id, price, size, expiration).await;
+            build_ms += build_start.elapsed().as_millis();
+            let attempt = match built {
+                Ok(order) => {
+                    let sign_start = Instant::now();
+                    let signed = self.api.sign(order).instrument(info_span!("sign", leg = leg)).await;
+                    sign_ms += sign_start.elapsed().as_millis();
+                    signed.map_err(|e| ("签名", e))
+                }
+                Err(e) => Err(("构建", e)),
+            };
+            match attempt {
+                Ok(signed) => return Ok((signed, build_ms, sign_ms)),
+                Err((stage, e)) => {
+                    let left = budget.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+                    match left {
+                        Ok(n) => warn!(leg, stage, error = %e, remaining = n - 1, "订单{}失败，重试", stage),
+                        Err(_) => return Err(e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// 提交前核对 USDC 余额不少于 required；未知资金地址时跳过。读到的余额同时写入 BalanceGuard
+    async fn verify_balance(&self, required: Decimal) -> Result<()> {
+        let Some(owner) = self.funder else {
+            return Ok(());
+        };
+        let balance = merge::usdc_balance(owner, None, self.chain_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("提交前读取 USDC 余额失败: {}", e))?;
+        self.balance_guard.update(balance);
+        if balance < required {
+            anyhow::bail!("USDC 余额 {:.2} 不足以支付本对订单 {:.2}", balance, required);
+        }
+        Ok(())
+    }
+
+    /// 构建、签名并批量提交一对买单，返回 (YES结果, NO结果)
+    /// 每腿参数为 (token_id, 含滑点价格, 数量)；expiration 仅 GTD 时为 Some；
+    /// check_balance 时按 balance_check 方式核对余额（并行模式下与构建签名重叠），不足则不提交
+    async fn submit_pair(
+        &self,
+        pair_id: &str,
+        total_start: Instant,
+        yes_leg: (U256, Decimal, Decimal),
+        no_leg: (U256, Decimal, Decimal),
+        expiration: Option<chrono::DateTime<Utc>>,
+        check_balance: bool,
+    ) -> Result<(OrderPostResult, OrderPostResult)> {
+        let (yes_token_id, yes_price_with_slippage, yes_order_size) = yes_leg;
+        let (no_token_id, no_price_with_slippage, no_order_size) = no_leg;
+        // 硬止损：所有双边提交（含加价重试）都经过此处，报价之和 ≥ 1 一律拒绝
+        Self::ensure_no_guaranteed_loss(yes_price_with_slippage, no_price_with_slippage)?;
+
+        // 并行构建并签名YES和NO订单（纯本地操作），瞬时失败在两腿共用的预算内重试；post_orders 的重试另行处理
+        let budget = std::sync::atomic::AtomicU32::new(self.build_sign_retries);
+        let sign_both = async {
+            tokio::join!(
+                self.build_and_sign_leg("YES", yes_leg, expiration, &budget),
+                self.build_and_sign_leg("NO", no_leg, expiration, &budget)
+            )
+        };
+        let required = yes_price_with_slippage * yes_order_size + no_price_with_slippage * no_order_size;
+        let mode = if check_balance { self.balance_check } else { BalanceCheckMode::Off };
+        let ((signed_yes_result, signed_no_result), balance_result) = match mode {
+            BalanceCheckMode::Off => (sign_both.await, Ok(())),
+            BalanceCheckMode::Serial => {
+                self.verify_balance(required).await?;
+                (sign_both.await, Ok(()))
+            }
+            BalanceCheckMode::Concurrent => tokio::join!(sign_both, self.verify_balance(required)),
+        };
+        if let Err(e) = balance_result {
+            // 已签名订单尚未提交，直接丢弃即可
+            warn!("💸 余额核对未通过，丢弃已签名订单 | {} | {}", short_id(&pair_id), e);
+            return Err(e);
+        }
+
+        let (signed_yes, yes_build_ms, yes_sign_ms) = signed_yes_result?;
+        let (signed_no, no_build_ms, no_sign_ms) = signed_no_result?;
+        // 两腿并行，按较慢一腿计
+        let build_elapsed = yes_build_ms.max(no_build_ms);
+        let sign_elapsed = yes_sign_ms.max(no_sign_ms);
+
+        // 性能计时：发送订单开始
+        let send_start = Instant::now();
+        
+        // 单价高的排前面发送；提交后需按相同顺序从 results 中解析 yes_result / no_result
+        let yes_first = yes_price_with_slippage >= no_price_with_slippage;
+        let orders_to_send: Vec<_> = if yes_first {
+            vec![signed_yes, signed_no]
+        } else {
+            vec![signed_no, signed_yes]
+        };
+        // 凭证过期：重新认证后用同一批已签名订单重试一次（订单由 EOA 签名，与 API 凭证无关）
+        let post_result = match self
+            .api
+            .post_orders(orders_to_send.clone())
+            .instrument(info_span!("post_orders", pair_id = %pair_id))
+            .await
+        {
+            Err(e) if is_auth_error(&e.to_string()) => {
+                self.reauth_after(&e.to_string()).await?;
+                self.api
+                    .post_orders(orders_to_send)
+                    .instrument(info_span!("post_orders", pair_id = %pair_id, retry = true))
+                    .await
+            }
+            other => other,
+        };
+        let results = match post_result {
+            Ok(results) => {
+                let send_elapsed = send_start.elapsed().as_millis();
+                let total_elapsed = total_start.elapsed().as_millis();
+                
+                info!(
+                    "⏱️ 耗时 | {} | 构建{}ms 签名{}ms 发送{}ms 总{}ms",
+                    short_id(&pair_id), build_elapsed, sign_elapsed, send_elapsed, total_elapsed
+                );
+                
+                results
+            }
+            Err(e) => {
+                self.invalidate_auth_cache();
+                let send_elapsed = send_start.elapsed().as_millis();
+                let total_elapsed = total_start.elapsed().as_millis();
+                
+                error!(
+                    "❌ 批量下单API调用失败 | 订单对ID:{} | YES价格:{} (含滑点) | NO价格:{} (含滑点) | 数量:YES {} NO {} | 构建耗时:{}ms | 签名耗时:{}ms | 发送耗时:{}ms | 总耗时:{}ms | 错误:{}",
+                    short_id(&pair_id),
+                    yes_price_with_slippage,
+                    no_price_with_slippage,
+                    yes_order_size,
+                    no_order_size,
+                    build_elapsed,
+                    sign_elapsed,
+                    send_elapsed,
+                    total_elapsed,
+                    e
+                );
+                return Err(anyhow::anyhow!("批量下单API调用失败: {}", e));
+            }
+        };
+        
+        // 验证返回结果数量
+        if results.len() != 2 {
+            error!(
+                "❌ 批量下单返回结果数量不正确 | 订单对ID:{} | 期望:2 | 实际:{}",
+                short_id(&pair_id),
+                results.len()
+            );
+            return Err(anyhow::anyhow!(
+                "批量下单返回结果数量不正确 | 期望:2 | 实际:{}",
+                results.len()
+            ));
+        }
+        
+        // 提取YES和NO订单的结果（提交顺序为单价高者在前，需按 yes_first 映射）
+        let mut results = results.into_iter();
+        let (first, second) = (results.next().unwrap(), results.next().unwrap());
+        let (yes_result, no_result) = if yes_first { (first, second) } else { (second, first) };
+        if self.verify_order_responses {
+            self.verify_pair_response(pair_id, (&yes_result, yes_token_id), (&no_result, no_token_id))
+                .await?;
+        }
+        Ok((yes_result, no_result))
+    }
+
+    /// 按返回的订单 ID 查询所属 token，与该位置提交的 token 比对；不一致返回 [`OrderResponseMismatch`]。
+    /// 无订单 ID（被拒）、查询失败或 token 未知的腿跳过，不阻断后续处理
+    async fn verify_pair_response(
+        &self,
+        pair_id: &str,
+        yes: (&OrderPostResult, U256),
+        no: (&OrderPostResult, U256),
+    ) -> Result<()> {
+        let lookup = |result: &OrderPostResult| {
+            let order_id = result.order_id.clone();
+            async move {
+                if order_id.is_empty() {
+                    return None;
+                }
+                match self.api.get_order(&order_id).await {
+                    Ok(snapshot) => snapshot.token_id,
+                    Err(e) => {
+                        debug!(order_id = %order_id, error = %e, "核对下单返回结果：查询订单失败，跳过");
+                        None
+                    }
+                }
+            }
+        };
+        let (yes_actual, no_actual) = tokio::join!(lookup(yes.0), lookup(no.0));
+        for (leg, (result, expected), actual) in [("YES", yes, yes_actual), ("NO", no, no_actual)] {
+            if let Some(actual) = actual.filter(|a| *a != expected) {
+                error!(
+                    "❌ 下单返回结果与提交订单不匹配 | {} | {} 腿订单 {} 属于 token {:#x}，期望 {:#x}",
+                    short_id(&pair_id), leg, result.order_id, actual, expected
+                );
+                return Err(anyhow::Error::new(OrderResponseMismatch {
+                    leg,
+                    order_id: result.order_id.clone(),
+                    expected_token: format!("{:#x}", expected),
+                    actual_token: format!("{:#x}", actual),
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// 构建、签名并提交单腿买单（已有库存覆盖另一腿时使用），认证错误时重新认证后重试一次
+    async fn submit_single_leg(
+        &self,
+        token_id: U256,
+        price: Decimal,
+        size: Decimal,
+        expiration: Option<chrono::DateTime<Utc>>,
+    ) -> Result<OrderPostResult> {
+        let order = self.build_buy_leg(token_id, price, size, expiration).await?;
+        let signed = self.api.sign(order).await?;
+        match self.api.post_order(signed.clone()).await {
+            Ok(resp) => Ok(resp),
+            Err(e) if is_auth_error(&e.to_string()) => {
+                self.reauth_after(&e.to_string()).await?;
+                self.api
+                    .post_order(signed)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("单腿下单提交失败（重新认证后重试）: {}", e))
+            }
+            Err(e) => {
+                self.invalidate_auth_cache();
+                Err(anyhow::anyhow!("单腿下单提交失败: {}", e))
+            }
+        }
+    }
+
+    /// 激进卖出：以 (买一 − max_slippage) 下 FAK 卖单，立即成交能成交的部分，其余取消不挂单；
+    /// 无买单时以最低价 0.01 报出。价格按 tick 向下取整。立即成交部分写入台账。
+    pub async fn sell_market(
+        &self,
+        market_id: B256,
+        token_id: U256,
+        size: Decimal,
+        max_slippage: Decimal,
+    ) -> Result<OrderPostResult> {
+        let best_bid = self.best_bid(token_id).await?;
+        let price = best_bid
+            .map(|b| ((b - max_slippage) / TICK_SIZE).floor() * TICK_SIZE)
+            .unwrap_or(TICK_SIZE)
+            .max(TICK_SIZE);
+        info!(
+            "🔻 激进卖出 | token_id={:#x} | 数量:{} | 买一:{} | 报价:{:.2}",
+            token_id,
+            size,
+            best_bid.map(|b| b.to_string()).unwrap_or_else(|| "无".to_string()),
+            price
+        );
+        let order = self
+            .api
+            .limit_order(LimitOrderParams {
+                token_id,
+                side: Side::Sell,
+                price,
+                size,
+                order_type: OrderType::FAK,
+                expiration: None,
+            })
+            .await?;
+        let signed = self.api.sign(order).await?;
+        let resp = match self.api.post_order(signed.clone()).await {
+            Ok(resp) => resp,
+            Err(e) if is_auth_error(&e.to_string()) => {
+                self.reauth_after(&e.to_string()).await?;
+                self.api
+                    .post_order(signed)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("激进卖出提交失败（重新认证后重试）: {}", e))?
+            }
+            Err(e) => {
+                self.invalidate_auth_cache();
+                return Err(anyhow::anyhow!("激进卖出提交失败: {}", e));
+            }
+        };
+        self.record_sell(market_id, token_id, &resp).await;
+        Ok(resp)
+    }
+
+    /// 补买短腿：以不超过 max_price 的限价（按 tick 向下取整）下 FAK 买单，立即成交能成交的部分；
+    /// 无卖单、卖一高于上限或金额不足市场最小金额时不下单，返回 None
+    pub async fn top_up(
+        &self,
+        market_id: B256,
+        token_id: U256,
+        size: Decimal,
+        max_price: Decimal,
+    ) -> Result<Option<OrderPostResult>> {
+        let meta = self.market_metadata(market_id).await.unwrap_or_default();
+        let size = (size * dec!(100)).floor() / dec!(100);
+        let price = ((max_price / meta.tick_size).floor() * meta.tick_size).min(meta.max_price());
+        let best_ask = self.top_of_book(token_id).await?.best_ask;
+        match best_ask {
+            Some(ask) if ask <= price && price * size > meta.min_order_amount => {}
+            _ => {
+                info!(
+                    "⏭️ 不补买 | token_id={:#x} | 数量:{} | 卖一:{} | 上限:{:.2}（无卖单、卖一超过上限或金额不足 ${}）",
+                    token_id,
+                    size,
+                    best_ask.map(|a| a.to_string()).unwrap_or_else(|| "无".to_string()),
+                    price,
+                    meta.min_order_amount
+                );
+                return Ok(None);
+            }
+        }
+        info!("🔺 补买短腿 | token_id={:#x} | 数量:{} | 报价上限:{:.2}", token_id, size, price);
+        let order = self
+            .api
+            .limit_order(LimitOrderParams {
+                token_id,
+                side: Side::Buy,
+                price,
+                size,
+                order_type: OrderType::FAK,
+                expiration: None,
+            })
+            .await?;
+        let signed = self.api.sign(order).await?;
+        match self.api.post_order(signed.clone()).await {
+            Ok(resp) => Ok(Some(resp)),
+            Err(e) if is_auth_error(&e.to_string()) => {
+                self.reauth_after(&e.to_string()).await?;
+                self.api
+                    .post_order(signed)
+                    .await
+                    .map(Some)
+                    .map_err(|e| anyhow::anyhow!("补买提交失败（重新认证后重试）: {}", e))
+            }
+            Err(e) => {
+                self.invalidate_auth_cache();
+                Err(anyhow::anyhow!("补买提交失败: {}", e))
+            }
+        }
+    }
+
+    /// 按方向查滑点表（默认仅下降(↓)用 second，上涨(↑)、持平(−)与未知用 first）
+    /// 结果不低于 min_slippage（下限生效时记录 debug 日志，每腿报价都会经过这里）
+    fn slippage_for_direction(&self, dir: PriceDirection) -> Decimal {
+        let raw = self.slippage.get(dir);
+        let base = if raw < self.min_slippage {
+            debug!(
+                "🔧 滑点下限生效 | 方向:{} | 配置滑点:{} → {}",
+                if dir == PriceDirection::Unknown { "?" } else { dir.symbol() },
+                raw,
+                self.min_slippage
+            );
+            self.min_slippage
+        } else {
+            raw
+        };
+        self.time_scaled(base)
+    }
+
+    /// 单腿最大可接受成交价：卖一 + max_edge_giveback，不超过1；未配置时为1
+    fn max_fill_price(&self, ask: Decimal) -> Decimal {
+        match self.max_edge_giveback {
+            Some(giveback) => (ask + giveback).min(dec!(1.0)),
+            None => dec!(1.0),
+        }
+    }
+
+    /// 当前5分钟窗口距结束（即结算）的秒数，以及窗口起点
+    fn seconds_to_resolution(&self) -> (u64, i64) {
+        let now = self.clock.now();
+        let window_start = MarketDiscoverer::calculate_current_window_timestamp(now);
+        (((window_start + FIVE_MIN_SECS) - now.timestamp()).max(0) as u64, window_start)
+    }
+
+    /// 处于结算前禁入期时返回距结算的秒数；每个窗口首次进入时记录日志
+    fn in_resolution_blackout(&self) -> Option<u64> {
+        if self.resolution_blackout_secs == 0 {
+            return None;
+        }
+        let (left, window_start) = self.seconds_to_resolution();
+        if left > self.resolution_blackout_secs {
+            return None;
+        }
+        if self.blackout_logged_window.swap(window_start, Ordering::SeqCst) != window_start {
+            info!(
+                "🌑 进入结算前禁入期 | 距结算 {}s ≤ {}s，本窗口不再提交新订单对",
+                left, self.resolution_blackout_secs
+            );
+        }
+        Some(left)
+    }
+
+    /// 启用 SLIPPAGE_TIME_RAMP 时按当前5分钟窗口剩余秒数放大滑点
+    fn time_scaled(&self, base: Decimal) -> Decimal {
+        let Some(ramp) = self.slippage_ramp else {
+            return base;
+        };
+        let (seconds_remaining, _) = self.seconds_to_resolution();
+        let scaled = ramp.time_scaled_slippage(base, seconds_remaining, FIVE_MIN_SECS as u64);
+        if scaled != base {
+            debug!(
+                "⏱️ 滑点随时间放大 | 剩余:{}s | 曲线:{} | {} → {}",
+                seconds_remaining,
+                ramp.curve.as_str(),
+                base,
+                scaled
+            );
+        }
+        scaled
     }
 
-    /// 以指定价格下 GTC 卖单（收尾时市价意图卖出单腿持仓）
-    pub async fn sell_at_price(
+    /// 下单前决策：依次检查市场开关、token 限流、深度、含滑点盈利、库存覆盖、最小数量/金额与窗口上限，
+    /// 汇总为 PreTradeDecision（不占用许可与窗口名额，实际占用在提交前）；价格上限与最小数量/金额取自 meta
+    fn pre_trade_decision(
         &self,
-        token_id: U256,
-        price: Decimal,
-        size: Decimal,
-    ) -> Result<polymarket_client_sdk::clob::types::response::PostOrderResponse> {
-        let signer = LocalSigner::from_str(&self.private_key)?
-            .with_chain_id(Some(POLYGON));
-        let order = self
-            .client
-            .limit_order()
-            .token_id(token_id)
-            .side(Side::Sell)
-            .price(price)
-            .size(size)
-            .order_type(OrderType::GTC)
-            .build()
-            .await?;
-        let signed = self.client.sign(&signer, order).await?;
-        self.client
-            .post_order(signed)
-            .await
-            .map_err(|e| anyhow::anyhow!("卖出订单提交失败: {}", e))
-    }
+        opp: &ArbitrageOpportunity,
+        yes_dir: PriceDirection,
+        no_dir: PriceDirection,
+        meta: &MarketMeta,
+    ) -> PreTradeDecision {
+        let yes_token_id = opp.yes_token_id;
+        let no_token_id = opp.no_token_id;
+
+        let (yes_price, no_price) = self.slipped_prices(opp, yes_dir, no_dir, meta.max_price());
+
+        // 每腿独立定量（各自受上限限制，再按 size_increment 向下取整）；Usdc 模式按含滑点价格把预算换算为份数
+        let (yes_order_size, no_order_size) = self.leg_sizes_at(opp, yes_price, no_price);
+        let (yes_raw_size, no_raw_size) = (opp.yes_size.min(self.leg_cap(yes_price)), opp.no_size.min(self.leg_cap(no_price)));
+        if yes_raw_size != yes_order_size || no_raw_size != no_order_size {
+            info!(
+                "📐 数量取整 | 口径:{} | 增量:{} | YES {}→{} NO {}→{}",
+                self.size_mode.as_str(), self.size_increment, yes_raw_size, yes_order_size, no_raw_size, no_order_size
+            );
+        }
+        let (yes_size, no_size, inventory_adjusted) =
+            self.net_of_inventory(yes_token_id, no_token_id, yes_order_size, no_order_size);
+        // 任一腿不足最小金额时尝试把两腿上调到同一数量（不超过每腿上限），使双边都满足最小金额；
+        // 上调后的数量可超过卖一档深度，超出部分按含滑点限价吃更深档位或挂单
+        let (yes_size, no_size, lifted) = if inventory_adjusted {
+            (yes_size, no_size, false)
+        } else {
+            match self.lift_to_min_notional(yes_price, no_price, yes_size, no_size, meta.min_order_amount) {
+                Some((y, n)) => {
+                    info!(
+                        "⬆️ 上调数量以满足 ${} 最小金额 | YES {}→{} NO {}→{} | 价格 YES {:.4} NO {:.4}",
+                        meta.min_order_amount, yes_size, y, no_size, n, yes_price, no_price
+                    );
+                    (y, n, true)
+                }
+                None => (yes_size, no_size, false),
+            }
+        };
+        // 价格改善：卖一档深度已覆盖整腿数量时，以卖一价报单即可全部成交，不必让出滑点
+        let (yes_price, no_price) = if self.capture_improvement {
+            let improve = |leg: &str, ask: Decimal, price: Decimal, size: Decimal, depth: Decimal| {
+                if price > ask && size > dec!(0) && size <= depth {
+                    info!(
+                        "💰 价格改善 | {} 卖一档深度 {} 覆盖数量 {} | 报价 {:.4}→{:.4} | 每份少付 {:.4}，共 {:.4} USD",
+                        leg, depth, size, price, ask, price - ask, (price - ask) * size
+                    );
+                    ask
+                } else {
+                    price
+                }
+            };
+            (
+                improve("YES", opp.yes_ask_price, yes_price, yes_size, opp.yes_size),
+                improve("NO", opp.no_ask_price, no_price, no_size, opp.no_size),
+            )
+        } else {
+            (yes_price, no_price)
+        };
+        // 按含滑点价格复核盈利（与监控端同一判定）
+        let slipped_opp = ArbitrageOpportunity {
+            yes_ask_price: yes_price,
+            no_ask_price: no_price,
+            ..opp.clone()
+        };
+        let slipped_sum = yes_price + no_price;
+        let fee_bps = self.profit_config.taker_fee_bps(opp.category.as_deref());
+        let net_edge = slipped_opp.net_edge(&self.profit_config);
+        let net_edge_bps = slipped_opp.net_edge_bps(&self.profit_config);
+        let min_profitable_size = self.min_profitable_size(&slipped_opp, fee_bps, meta.min_order_amount);
+
+        // 最小金额（通常 $1）；已扣除库存时，缺口不足最小金额的腿不下单，只下另一腿
+        let min_amount = meta.min_order_amount;
+        let yes_amount_usd = yes_price * yes_size;
+        let no_amount_usd = no_price * no_size;
+        let skip_yes = inventory_adjusted && yes_amount_usd <= min_amount;
+        let skip_no = inventory_adjusted && no_amount_usd <= min_amount;
+        let (yes_depth, no_depth) = if lifted {
+            (opp.yes_size.max(yes_size), opp.no_size.max(no_size))
+        } else {
+            (opp.yes_size, opp.no_size)
+        };
+        let depth_ok = (skip_yes || (yes_size > dec!(0) && yes_size <= yes_depth))
+            && (skip_no || (no_size > dec!(0) && no_size <= no_depth));
+        let (yes_max_fill, no_max_fill) = (self.max_fill_price(opp.yes_ask_price), self.max_fill_price(opp.no_ask_price));
+
+        let condition_id = format!("{:#x}", opp.market_id);
+        let throttled = self.token_throttled(&[yes_token_id, no_token_id]);
+        let window_full = self.window_pairs_full();
+        let cooldown = match (&throttled, window_full) {
+            (Some((token, elapsed)), _) => Some(format!(
+                "token {:#x} 距上次提交 {}ms < {}ms",
+                token,
+                elapsed.as_millis(),
+                self.min_interval_per_token.as_millis()
+            )),
+            (None, true) => Some(format!("本窗口订单对已达上限 {}", self.max_pairs_per_window)),
+            (None, false) => None,
+        };
 
-    /// 按方向取滑点：仅下降(↓)用 second，上涨(↑)和持平(−/空)用 first
-    fn slippage_for_direction(&self, dir: &str) -> Decimal {
-        if dir == "↓" {
-            self.slippage[1]
+        let book_age = opp.book_age();
+        let open_pairs_capped = self.open_pairs_capped(yes_token_id, no_token_id);
+        let verdict = if self.market_gate.is_disabled(&condition_id) {
+            PreTradeVerdict::Skip(format!("市场已禁用: {}", condition_id))
+        } else if let Some(reason) = self.market_filter.rejection(&condition_id) {
+            debug!("🚫 {}（下单前复核）", reason);
+            PreTradeVerdict::Skip(reason)
+        } else if let Some(left) = self.in_resolution_blackout() {
+            PreTradeVerdict::Skip(format!("结算前禁入期: 距结算 {}s（RESOLUTION_BLACKOUT_SECS={}）", left, self.resolution_blackout_secs))
+        } else if let Some((balanced, cap)) = open_pairs_capped {
+            warn!("🧺 单市场平衡持仓已达上限，跳过新订单对 | {} | 持有 {} 对（上限 {}）", condition_id, balanced, cap);
+            PreTradeVerdict::Skip(format!("未 merge 的平衡持仓 {} 对已达上限 {}", balanced, cap))
+        } else if !self.max_book_staleness.is_zero() && book_age > self.max_book_staleness {
+            PreTradeVerdict::Skip(format!(
+                "订单簿快照陈旧: {}ms（上限 {}ms）",
+                book_age.as_millis(),
+                self.max_book_staleness.as_millis()
+            ))
+        } else if let Some(c) = &cooldown {
+            PreTradeVerdict::Skip(c.clone())
+        } else if !depth_ok {
+            PreTradeVerdict::Skip(format!("下单数量无效或超过深度: YES {} NO {}", yes_size, no_size))
+        } else if (!skip_yes && yes_price > yes_max_fill) || (!skip_no && no_price > no_max_fill) {
+            PreTradeVerdict::Skip(format!(
+                "含滑点报价超过最大成交价: YES {:.4}（上限 {:.4}）NO {:.4}（上限 {:.4}）",
+                yes_price, yes_max_fill, no_price, no_max_fill
+            ))
+        } else if !slipped_opp.is_profitable(&self.profit_config) {
+            PreTradeVerdict::Skip(format!(
+                "含滑点后不满足盈利条件: 总价 {:.4}（上限 {:.4}）, 扣费后每份净利 {:.4}（最低 {:.4}）= {:.2}bps（最低 {}bps）",
+                slipped_sum,
+                self.profit_config.max_slipped_sum,
+                net_edge,
+                self.profit_config.min_edge,
+                net_edge_bps,
+                self.profit_config.min_net_edge_bps
+            ))
+        } else if let Some(need) = min_profitable_size
+            .filter(|n| !skip_yes && !skip_no && *n > self.leg_cap(yes_price).min(self.leg_cap(no_price)))
+        {
+            PreTradeVerdict::Skip(format!(
+                "最小盈利数量 {} 超过单腿上限（YES {} NO {}）",
+                need,
+                self.leg_cap(yes_price),
+                self.leg_cap(no_price)
+            ))
+        } else if let Some(need) = min_profitable_size.filter(|n| !skip_yes && !skip_no && yes_size.min(no_size) < *n) {
+            PreTradeVerdict::Skip(format!(
+                "下单数量低于最小盈利数量 {}（YES {} NO {}），扣除手续费后不盈利",
+                need, yes_size, no_size
+            ))
+        } else if skip_yes && skip_no {
+            PreTradeVerdict::Skip("已有库存覆盖双边缺口，无需下单".to_string())
+        } else if (yes_amount_usd <= min_amount && !skip_yes) || (no_amount_usd <= min_amount && !skip_no) {
+            PreTradeVerdict::Skip(format!(
+                "下单金额不满足交易所最小要求（上调至单腿上限仍不足）: YES {:.2} USD, NO {:.2} USD，双边均须 > ${}",
+                yes_amount_usd, no_amount_usd, min_amount
+            ))
+        } else if (!skip_yes && yes_size < meta.min_order_size) || (!skip_no && no_size < meta.min_order_size) {
+            PreTradeVerdict::Skip(format!(
+                "下单数量低于市场最小数量 {}: YES {} NO {}",
+                meta.min_order_size, yes_size, no_size
+            ))
         } else {
-            self.slippage[0]
+            PreTradeVerdict::Execute
+        };
+
+        PreTradeDecision {
+            market_id: opp.market_id,
+            yes_price,
+            no_price,
+            yes_size,
+            no_size,
+            gross_edge: dec!(1.0) - (opp.yes_ask_price + opp.no_ask_price),
+            fee_bps,
+            fee: slipped_sum * fee_bps / dec!(10000),
+            slipped_sum,
+            net_edge,
+            net_edge_bps,
+            min_profitable_size,
+            yes_amount_usd,
+            no_amount_usd,
+            skip_yes,
+            skip_no,
+            depth_ok,
+            balance_ok: None,
+            book_age_ms: book_age.as_millis() as u64,
+            cooldown,
+            verdict,
         }
     }
 
     /// 执行套利交易（使用post_orders批量提交YES和NO订单；订单类型由 arbitrage_order_type 配置，GTD 时配合 gtd_expiration_secs）
-    /// yes_dir / no_dir：涨跌方向 "↑" "↓" "−" 或 ""，用于按方向分配滑点（仅下降=second，上涨与持平=first）
+    /// yes_dir / no_dir：涨跌方向，用于按方向分配滑点（仅下降=second，上涨、持平与未知=first）
+    #[tracing::instrument(
+        name = "execute_arbitrage_pair",
+        skip_all,
+        fields(trace_id = %opp.trace_id, market_id = %opp.market_id, pair_id = tracing::field::Empty)
+    )]
     pub async fn execute_arbitrage_pair(
         &self,
         opp: &ArbitrageOpportunity,
-        yes_dir: &str,
-        no_dir: &str,
+        yes_dir: PriceDirection,
+        no_dir: PriceDirection,
     ) -> Result<OrderPairResult> {
+        if self.is_paused() {
+            return Err(anyhow::Error::new(Paused));
+        }
+        if !self.is_leader() {
+            return Err(anyhow::Error::new(NotLeader));
+        }
+
+        // 方向一致性：本项目只交易 Up/Down 二元市场（非 neg-risk），两腿不应同涨同跌
+        if self.direction_mismatch != DirectionMismatchAction::Off
+            && DirectionMismatchAction::is_mismatch(yes_dir, no_dir)
+        {
+            warn!(
+                "⚠️ 两腿方向相同 | {:#x} | YES {} NO {}，方向数据可能有误（DIRECTION_MISMATCH={}）",
+                opp.market_id,
+                yes_dir,
+                no_dir,
+                self.direction_mismatch.as_str()
+            );
+            if self.direction_mismatch == DirectionMismatchAction::Skip {
+                return Err(anyhow::anyhow!("两腿方向相同（YES {} NO {}），跳过", yes_dir, no_dir));
+            }
+        }
+
         // 性能计时：总开始时间
         let total_start = Instant::now();
-        
+
+        // 市场交易参数（tick、最小数量/金额）；拉取失败时按默认值（0.01 tick、$1 最小金额）继续
+        let meta = match self.market_metadata(opp.market_id).await {
+            Ok(meta) => meta,
+            Err(e) => {
+                warn!(error = %e, "读取市场参数失败，使用默认值 | {:#x}", opp.market_id);
+                MarketMeta::default()
+            }
+        };
+
+        // 下单前决策：所有执行/跳过条件汇总为一条结构化记录
+        let decision = self.pre_trade_decision(opp, yes_dir, no_dir, &meta);
+        decision.emit();
+        // 影子配置：同一机会按另一套参数再决策一次，只记录差异，不下单
+        if let Some(shadow) = &self.shadow {
+            let shadow_decision =
+                info_span!("shadow").in_scope(|| shadow.pre_trade_decision(opp, yes_dir, no_dir, &meta));
+            decision.log_divergence(&shadow_decision);
+        }
+        // 决策为跳过时直接返回跳过原因，硬止损只检查真正要下单的价格
+        if let PreTradeVerdict::Skip(reason) = &decision.verdict {
+            return Err(anyhow::anyhow!("{}", reason));
+        }
+        // 硬止损：只下缺口腿时，另一腿按已持有库存的每份成本计
+        match (decision.skip_yes, decision.skip_no) {
+            (false, false) => Self::ensure_no_guaranteed_loss(decision.yes_price, decision.no_price)?,
+            (true, false) => Self::ensure_no_guaranteed_loss(
+                self.held_leg_cost(opp.yes_token_id, decision.yes_price),
+                decision.no_price,
+            )?,
+            (false, true) => Self::ensure_no_guaranteed_loss(
+                decision.yes_price,
+                self.held_leg_cost(opp.no_token_id, decision.no_price),
+            )?,
+            (true, true) => {}
+        }
+        let PreTradeDecision {
+            yes_price: yes_price_with_slippage,
+            no_price: no_price_with_slippage,
+            yes_size: mut yes_order_size,
+            no_size: mut no_order_size,
+            skip_yes,
+            skip_no,
+            ..
+        } = decision;
+        let yes_token_id = opp.yes_token_id;
+        let no_token_id = opp.no_token_id;
+
         // 这个日志已经在main.rs中打印了，这里不再重复打印
         let expiry_info = if matches!(self.arbitrage_order_type, OrderType::GTD) {
             format!("过期:{}秒", self.gtd_expiration_secs)
@@ -161,174 +1991,235 @@ impl TradingExecutor {
             expiry_info
         );
 
-        // 计算实际下单数量（考虑最大订单限制）
-        let yes_token_id = U256::from_str(&opp.yes_token_id.to_string())?;
-        let no_token_id = U256::from_str(&opp.no_token_id.to_string())?;
-
-        let order_size = opp.yes_size.min(opp.no_size).min(self.max_order_size);
-
         // 生成订单对ID
-        let pair_id = Uuid::new_v4().to_string();
+        let pair_id = self.ids.next_id();
+        tracing::Span::current().record("pair_id", pair_id.as_str());
 
         // 计算过期时间：当前时间 + 配置的过期时间
-        let expiration = Utc::now() + chrono::Duration::seconds(self.gtd_expiration_secs as i64);
+        let expiration = self.clock.now() + chrono::Duration::seconds(self.gtd_expiration_secs as i64);
 
-        // 滑点按涨跌方向分配：上涨=first，下降/持平=second
-        let yes_slippage_apply = self.slippage_for_direction(yes_dir);
-        let no_slippage_apply = self.slippage_for_direction(no_dir);
-        let yes_price_with_slippage = (opp.yes_ask_price + yes_slippage_apply).min(dec!(1.0));
-        let no_price_with_slippage = (opp.no_ask_price + no_slippage_apply).min(dec!(1.0));
-        
         // 打印选档信息（加滑点后的价格）
         info!(
             "📋 选档 | YES {:.4}×{:.2} NO {:.4}×{:.2}",
-            yes_price_with_slippage, order_size,
-            no_price_with_slippage, order_size
+            yes_price_with_slippage, yes_order_size,
+            no_price_with_slippage, no_order_size
         );
         
-        let expiry_suffix = if matches!(self.arbitrage_order_type, OrderType::GTD) {
+        let expiry_suffix = if self.use_market_orders {
+            " | 市价单(按USDC金额)".to_string()
+        } else if matches!(self.arbitrage_order_type, OrderType::GTD) {
             format!(" | GTD {}s", self.gtd_expiration_secs)
         } else {
             String::new()
         };
+        let order_type_shown = if self.use_market_orders {
+            self.market_order_type()
+        } else {
+            self.arbitrage_order_type.clone()
+        };
         info!(
             "📤 下单 | YES {:.4}→{:.4}×{} NO {:.4}→{:.4}×{} | {}{}",
-            opp.yes_ask_price, yes_price_with_slippage, order_size,
-            opp.no_ask_price, no_price_with_slippage, order_size,
-            self.arbitrage_order_type, expiry_suffix
+            opp.yes_ask_price, yes_price_with_slippage, yes_order_size,
+            opp.no_ask_price, no_price_with_slippage, no_order_size,
+            order_type_shown, expiry_suffix
         );
 
-        // 下单前检查：双边金额均须 > $1（交易所最小下单金额）
-        let yes_amount_usd = yes_price_with_slippage * order_size;
-        let no_amount_usd = no_price_with_slippage * order_size;
-        if yes_amount_usd <= dec!(1) || no_amount_usd <= dec!(1) {
-            warn!(
-                "⏭️ 跳过下单 | YES金额:{:.2} USD NO金额:{:.2} USD | 双边均须 > $1",
-                yes_amount_usd, no_amount_usd
-            );
-            return Err(anyhow::anyhow!(
-                "下单金额不满足交易所最小要求: YES {:.2} USD, NO {:.2} USD，双边均须 > $1",
-                yes_amount_usd, no_amount_usd
-            ));
+        // 在途订单对限流：许可持有到本函数返回（含加价重试）
+        let wait_start = Instant::now();
+        let _permit = self
+            .pair_permits
+            .acquire()
+            .await
+            .map_err(|e| anyhow::anyhow!("在途订单对许可已关闭: {}", e))?;
+        let waited = wait_start.elapsed().as_millis();
+        if waited > 0 {
+            debug!("⏳ 等待在途许可 {}ms | {} | 剩余许可:{}", waited, short_id(&pair_id), self.available_permits());
         }
 
-        // 性能计时：并行构建YES和NO订单开始
-        let build_start = Instant::now();
-        
-        // 并行构建YES和NO订单；仅 GTD 时设置 expiration（SDK 规定非 GTD 不可设过期）
-        let (yes_order, no_order) = tokio::join!(
-            async {
-                let b = self.client
-                    .limit_order()
-                    .token_id(yes_token_id)
-                    .side(Side::Buy)
-                    .price(yes_price_with_slippage)
-                    .size(order_size)
-                    .order_type(self.arbitrage_order_type.clone());
-                if matches!(&self.arbitrage_order_type, OrderType::GTD) {
-                    b.expiration(expiration).build().await
-                } else {
-                    b.build().await
-                }
-            },
-            async {
-                let b = self.client
-                    .limit_order()
-                    .token_id(no_token_id)
-                    .side(Side::Buy)
-                    .price(no_price_with_slippage)
-                    .size(order_size)
-                    .order_type(self.arbitrage_order_type.clone());
-                if matches!(&self.arbitrage_order_type, OrderType::GTD) {
-                    b.expiration(expiration).build().await
-                } else {
-                    b.build().await
-                }
-            }
-        );
-        
-        let yes_order = yes_order?;
-        let no_order = no_order?;
-        let build_elapsed = build_start.elapsed().as_millis();
-
-        // 性能计时：并行签名开始
-        let sign_start = Instant::now();
-        
-        // 创建signer
-        let signer = LocalSigner::from_str(&self.private_key)?
-            .with_chain_id(Some(POLYGON));
-        
-        // 并行签名YES和NO订单
-        let (signed_yes_result, signed_no_result) = tokio::join!(
-            self.client.sign(&signer, yes_order),
-            self.client.sign(&signer, no_order)
-        );
-        
-        let signed_yes = signed_yes_result?;
-        let signed_no = signed_no_result?;
-        let sign_elapsed = sign_start.elapsed().as_millis();
+        // 每窗口订单对上限：占用名额后才提交（提交失败也计入，避免失败重试刷单）
+        if let Err(count) = self.take_window_slot() {
+            debug!("⏭️ 本窗口已提交 {} 对，达到上限，跳过 | {}", count, short_id(&pair_id));
+            return Err(anyhow::anyhow!("本窗口订单对已达上限: {}", self.max_pairs_per_window));
+        }
 
-        // 性能计时：发送订单开始
-        let send_start = Instant::now();
-        
-        // 单价高的排前面发送；提交后需按相同顺序从 results 中解析 yes_result / no_result
-        let yes_first = yes_price_with_slippage >= no_price_with_slippage;
-        let orders_to_send: Vec<_> = if yes_first {
-            vec![signed_yes, signed_no]
+        let order_expiration = if !self.use_market_orders && matches!(&self.arbitrage_order_type, OrderType::GTD) {
+            Some(expiration)
         } else {
-            vec![signed_no, signed_yes]
+            None
         };
-        let results = match self.client.post_orders(orders_to_send).await {
-            Ok(results) => {
-                let send_elapsed = send_start.elapsed().as_millis();
-                let total_elapsed = total_start.elapsed().as_millis();
-                
-                info!(
-                    "⏱️ 耗时 | {} | 构建{}ms 签名{}ms 发送{}ms 总{}ms",
-                    &pair_id[..8], build_elapsed, sign_elapsed, send_elapsed, total_elapsed
-                );
-                
-                results
+        let stp_legs: Vec<(U256, Decimal)> = [
+            (!skip_yes).then_some((yes_token_id, yes_price_with_slippage)),
+            (!skip_no).then_some((no_token_id, no_price_with_slippage)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        self.prevent_self_trade(&stp_legs).await?;
+        if skip_yes || skip_no {
+            let (token_id, price, size, leg) = if skip_yes {
+                (no_token_id, no_price_with_slippage, no_order_size, "NO")
+            } else {
+                (yes_token_id, yes_price_with_slippage, yes_order_size, "YES")
+            };
+            info!("📦 仅下缺口腿 | {} | {} {:.4}×{}", short_id(&pair_id), leg, price, size);
+            self.mark_submitted(&[token_id]);
+            let mut result = self.submit_single_leg(token_id, price, size, order_expiration).await?;
+            self.resolve_ambiguous_fill(&pair_id, leg, &mut result).await;
+            self.register_gtc_expiry(&result, size);
+            let filled = result.taking_amount;
+            let raw_error = result.raw_error();
+            if filled == dec!(0) {
+                warn!("❌ 缺口腿未成交 | {} | {} | {}", short_id(&pair_id), leg, result.error_msg.as_deref().unwrap_or("未知错误"));
+            } else {
+                info!("✅ 缺口腿成交 | {} | {} 成交 {} 份", short_id(&pair_id), leg, filled);
             }
-            Err(e) => {
-                let send_elapsed = send_start.elapsed().as_millis();
-                let total_elapsed = total_start.elapsed().as_millis();
-                
-                error!(
-                    "❌ 批量下单API调用失败 | 订单对ID:{} | YES价格:{} (含滑点) | NO价格:{} (含滑点) | 数量:{} | 构建耗时:{}ms | 签名耗时:{}ms | 发送耗时:{}ms | 总耗时:{}ms | 错误:{}",
-                    &pair_id[..8],
+            self.record_fills(opp, &pair_id, &[(token_id, price, filled)], None).await;
+            let (yes_order_id, no_order_id, yes_filled, no_filled, yes_size, no_size, yes_raw_error, no_raw_error) = if skip_yes {
+                (String::new(), result.order_id, dec!(0), filled, dec!(0), size, None, raw_error)
+            } else {
+                (result.order_id, String::new(), filled, dec!(0), size, dec!(0), raw_error, None)
+            };
+            return Ok(OrderPairResult {
+                pair_id,
+                yes_order_id,
+                no_order_id,
+                yes_filled,
+                no_filled,
+                yes_size,
+                no_size,
+                success: filled > dec!(0),
+                strategy_tag: self.strategy_tag.clone(),
+                expected_edge: dec!(1) - opp.yes_ask_price - opp.no_ask_price,
+                realized_edge: None,
+                yes_raw_error,
+                no_raw_error,
+            });
+        }
+
+        self.mark_submitted(&[yes_token_id, no_token_id]);
+        let (mut yes_result, mut no_result) = self
+            .submit_pair(
+                &pair_id,
+                total_start,
+                (yes_token_id, yes_price_with_slippage, yes_order_size),
+                (no_token_id, no_price_with_slippage, no_order_size),
+                order_expiration,
+                true,
+            )
+            .await?;
+
+        // 余额不足被拒（并发订单对抢占了同一账户资金）：按最新余额缩量重试一次
+        if self.balance_reject_retry && Self::rejected_for_balance(&yes_result, &no_result) {
+            if let Some((y, n)) = self
+                .sizes_within_balance(
                     yes_price_with_slippage,
                     no_price_with_slippage,
-                    order_size,
-                    build_elapsed,
-                    sign_elapsed,
-                    send_elapsed,
-                    total_elapsed,
-                    e
+                    yes_order_size,
+                    no_order_size,
+                    meta.min_order_amount,
+                )
+                .await
+            {
+                info!(
+                    "💸 余额不足被拒，缩量重试 | {} | YES {}→{} NO {}→{}",
+                    short_id(&pair_id), yes_order_size, y, no_order_size, n
                 );
-                return Err(anyhow::anyhow!("批量下单API调用失败: {}", e));
+                yes_order_size = y;
+                no_order_size = n;
+                (yes_result, no_result) = self
+                    .submit_pair(
+                        &pair_id,
+                        Instant::now(),
+                        (yes_token_id, yes_price_with_slippage, yes_order_size),
+                        (no_token_id, no_price_with_slippage, no_order_size),
+                        order_expiration,
+                        false,
+                    )
+                    .await?;
             }
-        };
-        
-        // 验证返回结果数量
-        if results.len() != 2 {
-            error!(
-                "❌ 批量下单返回结果数量不正确 | 订单对ID:{} | 期望:2 | 实际:{}",
-                &pair_id[..8],
-                results.len()
+        }
+
+        // 双边均无匹配卖单（盘口跳档）：按最新卖一价逐 tick 加价重试，加价后仍须满足盈利条件
+        let mut yes_price = yes_price_with_slippage;
+        let mut no_price = no_price_with_slippage;
+        let mut attempts = 0u32;
+        while self.reprice_max_ticks > 0 && Self::both_no_match(&yes_result, &no_result) {
+            let give_up = |reason: String| {
+                warn!("🛑 放弃加价重试 | {} | 已重试{}次 | {}", short_id(&pair_id), attempts, reason);
+                anyhow::Error::new(RepriceGaveUp { attempts, reason })
+            };
+            if attempts >= self.reprice_max_ticks {
+                return Err(give_up(format!("已达最大加价 {} tick", self.reprice_max_ticks)));
+            }
+            let (yes_book, no_book) = tokio::join!(
+                self.top_of_book(yes_token_id),
+                self.top_of_book(no_token_id)
+            );
+            let (yes_ask, no_ask) = match (yes_book?.best_ask, no_book?.best_ask) {
+                (Some(y), Some(n)) => (y, n),
+                _ => return Err(give_up("订单簿无卖单".to_string())),
+            };
+            // 新价 = max(最新卖一, 上次报价) + 1 tick，累计加价不超过 reprice_max_ticks 个 tick，且不超过最大成交价
+            let max_bump = meta.tick_size * Decimal::from(self.reprice_max_ticks);
+            let new_yes = (yes_ask.max(yes_price) + meta.tick_size)
+                .min(yes_price_with_slippage + max_bump)
+                .min(self.max_fill_price(opp.yes_ask_price))
+                .min(meta.max_price());
+            let new_no = (no_ask.max(no_price) + meta.tick_size)
+                .min(no_price_with_slippage + max_bump)
+                .min(self.max_fill_price(opp.no_ask_price))
+                .min(meta.max_price());
+            if new_yes <= yes_price && new_no <= no_price {
+                return Err(give_up(format!("已达最大加价 {} tick", self.reprice_max_ticks)));
+            }
+            let repriced_opp = ArbitrageOpportunity {
+                yes_ask_price: new_yes,
+                no_ask_price: new_no,
+                ..opp.clone()
+            };
+            if !repriced_opp.is_profitable(&self.profit_config) {
+                return Err(give_up(format!(
+                    "加价后不再盈利: 总价 {:.4}, 扣费后每份净利 {:.4}",
+                    new_yes + new_no,
+                    repriced_opp.net_edge(&self.profit_config)
+                )));
+            }
+            attempts += 1;
+            info!(
+                "🔁 加价重试 #{} | {} | 卖一 YES {:.4} NO {:.4} | 报价 YES {:.4}→{:.4} NO {:.4}→{:.4}",
+                attempts, short_id(&pair_id), yes_ask, no_ask, yes_price, new_yes, no_price, new_no
+            );
+            yes_price = new_yes;
+            no_price = new_no;
+            (yes_result, no_result) = self
+                .submit_pair(
+                    &pair_id,
+                    Instant::now(),
+                    (yes_token_id, yes_price, yes_order_size),
+                    (no_token_id, no_price, no_order_size),
+                    order_expiration,
+                    false,
+                )
+                .await?;
+        }
+        // 状态不明（成功但成交 0、无错误）的腿：按订单 ID 轮询确认成交/挂单/失效
+        tokio::join!(
+            self.resolve_ambiguous_fill(&pair_id, "YES", &mut yes_result),
+            self.resolve_ambiguous_fill(&pair_id, "NO", &mut no_result)
+        );
+        let (yes_result, no_result) = (&yes_result, &no_result);
+        self.register_gtc_expiry(yes_result, yes_order_size);
+        self.register_gtc_expiry(no_result, no_order_size);
+        let yes_raw_error = yes_result.raw_error();
+        let no_raw_error = no_result.raw_error();
+        if yes_raw_error.is_some() || no_raw_error.is_some() {
+            trace!(
+                pair_id = %pair_id,
+                yes_raw_error = ?yes_raw_error,
+                no_raw_error = ?no_raw_error,
+                "下单原始错误响应"
             );
-            return Err(anyhow::anyhow!(
-                "批量下单返回结果数量不正确 | 期望:2 | 实际:{}",
-                results.len()
-            ));
         }
-        
-        // 提取YES和NO订单的结果（提交顺序为单价高者在前，需按 yes_first 映射）
-        let (yes_result, no_result) = if yes_first {
-            (&results[0], &results[1])
-        } else {
-            (&results[1], &results[0])
-        };
 
         // 订单返回结果详情已移除，只保留关键信息在后续日志中
 
@@ -336,6 +2227,35 @@ impl TradingExecutor {
         let yes_filled = yes_result.taking_amount;
         let no_filled = no_result.taking_amount;
 
+        // 双边均挂单未成交（盘口已移动）：撤单后按最新卖一重报一次
+        if self.reprice_resting_pair
+            && yes_filled == dec!(0)
+            && no_filled == dec!(0)
+            && Self::is_ambiguous(yes_result)
+            && Self::is_ambiguous(no_result)
+        {
+            let resting = OrderPairResult {
+                pair_id: pair_id.clone(),
+                yes_order_id: yes_result.order_id.clone(),
+                no_order_id: no_result.order_id.clone(),
+                yes_filled,
+                no_filled,
+                yes_size: yes_order_size,
+                no_size: no_order_size,
+                success: false,
+                strategy_tag: self.strategy_tag.clone(),
+                expected_edge: dec!(1) - opp.yes_ask_price - opp.no_ask_price,
+                realized_edge: None,
+                yes_raw_error,
+                no_raw_error,
+            };
+            let reposted = self.reprice_pair(&resting, opp).await?;
+            if reposted.yes_filled == dec!(0) && reposted.no_filled == dec!(0) {
+                return Err(anyhow::anyhow!("套利失败: 撤单重报后YES和NO订单仍未成交"));
+            }
+            return Ok(reposted);
+        }
+
         // 对于GTD订单，如果无法在90秒内全部成交，订单会在过期后取消
         // 我们应该检查实际的成交数量，而不是 success 字段
         // 只有在两个订单都完全没有成交时，才返回错误
@@ -369,7 +2289,7 @@ impl TradingExecutor {
 
             error!(
                 "❌ 套利交易失败 | 订单对ID:{} | YES订单:{} | NO订单:{}",
-                &pair_id[..8], // 只显示前8个字符
+                short_id(&pair_id), // 只显示前8个字符
                 yes_error_simple,
                 no_error_simple
             );
@@ -394,8 +2314,10 @@ impl TradingExecutor {
         }
 
         // 如果至少有一个订单成交了，记录警告但不返回错误
-        // 让后续的风险管理器来处理单边成交的情况
+        // 默认让后续的风险管理器来处理单边成交的情况，ON_PARTIAL_ERROR 可改为立即撤单/卖出
+        let mut sold = [dec!(0); 2];
         if !yes_result.success || !no_result.success {
+            self.invalidate_auth_cache();
             let yes_error_msg = yes_result
                 .error_msg
                 .as_deref()
@@ -424,7 +2346,7 @@ impl TradingExecutor {
 
             warn!(
                 "⚠️ 部分订单状态异常 | 订单对ID:{} | YES:{} (成交:{}份) | NO:{} (成交:{}份) | 已启动风险管理",
-                &pair_id[..8],
+                short_id(&pair_id),
                 yes_error_simple,
                 yes_filled,
                 no_error_simple,
@@ -442,13 +2364,44 @@ impl TradingExecutor {
                 no_error = %no_error_msg,
                 "订单提交状态异常详情"
             );
+
+            sold = self
+                .handle_partial_error(
+                    &pair_id,
+                    opp.market_id,
+                    [
+                        ("YES", yes_token_id, yes_result, yes_order_size),
+                        ("NO", no_token_id, no_result, no_order_size),
+                    ],
+                )
+                .await;
+        }
+
+        // 预期 vs 实际：检测时卖一毛利 vs 实际提交报价毛利，差值即滑点让出部分（买单成交价不高于报价，实际值为下限）
+        let expected_edge = dec!(1) - opp.yes_ask_price - opp.no_ask_price;
+        let realized_edge = (yes_filled > dec!(0) && no_filled > dec!(0)).then(|| dec!(1) - yes_price - no_price);
+        if let Some(realized) = realized_edge {
+            info!(
+                "📏 预期 vs 实际毛利 | {} | 预期:{:.4} | 实际:{:.4} | 滑点让出:{:.4}/份",
+                short_id(&pair_id),
+                expected_edge,
+                realized,
+                expected_edge - realized
+            );
         }
+        self.record_fills(
+            opp,
+            &pair_id,
+            &[(yes_token_id, yes_price, yes_filled), (no_token_id, no_price, no_filled)],
+            realized_edge,
+        )
+        .await;
 
         // 根据成交情况打印不同的日志
         if yes_filled > dec!(0) && no_filled > dec!(0) {
             info!(
                 "✅ 套利交易成功 | 订单对ID:{} | YES成交:{}份 | NO成交:{}份 | 总成交:{}份",
-                &pair_id[..8],
+                short_id(&pair_id),
                 yes_filled,
                 no_filled,
                 yes_filled.min(no_filled)
@@ -459,12 +2412,12 @@ impl TradingExecutor {
             let other_side = if yes_filled > dec!(0) { "NO" } else { "YES" };
             warn!(
                 "⚠️ 单边成交 | {} | {} 成交 {} 份，{} 未成交（已交风控）",
-                &pair_id[..8], side, filled, other_side
+                short_id(&pair_id), side, filled, other_side
             );
         } else {
             warn!(
                 "❌ 套利失败 | 订单对ID:{} | YES和NO都未成交",
-                &pair_id[..8]
+                short_id(&pair_id)
             );
         }
 
@@ -472,11 +2425,509 @@ impl TradingExecutor {
             pair_id,
             yes_order_id: yes_result.order_id.clone(),
             no_order_id: no_result.order_id.clone(),
-            yes_filled,
-            no_filled,
-            yes_size: order_size,
-            no_size: order_size,
+            // 已卖出的多余成交不再计入持仓（ON_PARTIAL_ERROR=unwind_filled）
+            yes_filled: yes_filled - sold[0],
+            no_filled: no_filled - sold[1],
+            yes_size: yes_order_size,
+            no_size: no_order_size,
             success: true,
+            strategy_tag: self.strategy_tag.clone(),
+            expected_edge,
+            realized_edge,
+            yes_raw_error,
+            no_raw_error,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::fees::FeeSchedule;
+    use crate::trading::clob_api::MockClobApi;
+    use crate::trading::clock::{FixedClock, SequentialIds};
+
+    const YES: u64 = 1;
+    const NO: u64 = 2;
+
+    /// 离线执行器参数：关闭轮询、加价重试、限流与余额核对，滑点为 0，GTC 限价单
+    fn settings() -> ExecutorSettings {
+        ExecutorSettings {
+            max_order_size_usdc: 100.0,
+            slippage: SlippageTable::from_pair(dec!(0), dec!(0)),
+            min_slippage: 0.0,
+            capture_improvement: false,
+            gtd_expiration_secs: 60,
+            arbitrage_order_type: OrderType::GTC,
+            profit_config: ProfitConfig::new(0.0, 0.001, 1.0).unwrap(),
+            reprice_max_ticks: 0,
+            reprice_resting_pair: false,
+            verify_order_responses: false,
+            balance_reject_retry: false,
+            build_sign_retries: 0,
+            balance_check: BalanceCheckMode::Off,
+            reconcile_fills_delay_ms: 0,
+            max_concurrent_pairs: 0,
+            use_market_orders: false,
+            size_increment: 0.01,
+            max_pairs_per_window: 0,
+            fill_poll_count: 0,
+            fill_poll_interval_ms: 0,
+            auth_cache_ttl_secs: 0,
+            use_existing_inventory: false,
+            min_interval_per_token_ms: 0,
+            gtc_ttl_secs: 0,
+            slippage_ramp: None,
+            size_mode: SizeMode::Shares,
+            direction_mismatch: DirectionMismatchAction::Off,
+            on_partial_error: OnPartialError::LeaveForRisk,
+            self_trade_prevention: SelfTradePrevention::Off,
+            connect: ConnectOptions::default(),
+            strategy_tag: "test".to_string(),
+            max_edge_giveback: 0.0,
+            strict_proxy_check: false,
+            max_book_staleness_ms: 0,
+            resolution_blackout_secs: 0,
+            max_open_pairs_per_market: 0.0,
+            min_trading_balance: 0.0,
+            book_cache_ttl_ms: 0,
+            market_allowlist: Vec::new(),
+            market_denylist: Vec::new(),
+            shadow: None,
+        }
+    }
+
+    fn executor(api: MockClobApi, settings: ExecutorSettings) -> TradingExecutor<MockClobApi> {
+        TradingExecutor::with_api(api, settings).unwrap()
+    }
+
+    fn opp(yes_ask: Decimal, no_ask: Decimal, size: Decimal) -> ArbitrageOpportunity {
+        ArbitrageOpportunity {
+            market_id: B256::ZERO,
+            yes_token_id: U256::from(YES),
+            no_token_id: U256::from(NO),
+            yes_ask_price: yes_ask,
+            no_ask_price: no_ask,
+            total_cost: yes_ask + no_ask,
+            profit_percentage: dec!(0),
+            yes_size: size,
+            no_size: size,
+            category: None,
+            book_updated_at: Instant::now(),
+            trace_id: "test".to_string(),
+        }
+    }
+
+    fn filled(order_id: &str, amount: Decimal) -> OrderPostResult {
+        OrderPostResult {
+            order_id: order_id.to_string(),
+            success: true,
+            error_msg: None,
+            taking_amount: amount,
+            making_amount: amount,
+            raw: "test: filled".to_string(),
+        }
+    }
+
+    fn rejected(order_id: &str) -> OrderPostResult {
+        OrderPostResult {
+            order_id: order_id.to_string(),
+            success: false,
+            error_msg: Some("no orders found to match".to_string()),
+            taking_amount: dec!(0),
+            making_amount: dec!(0),
+            raw: "test: rejected".to_string(),
+        }
+    }
+
+    /// 按提交顺序预置一次 post_orders 的返回
+    fn script_post_orders(api: &MockClobApi, results: Vec<OrderPostResult>) {
+        api.post_orders_script.lock().unwrap().push_back(Ok(results));
+    }
+
+    fn posted(executor: &TradingExecutor<MockClobApi>) -> Vec<Vec<LimitOrderParams>> {
+        executor.api.posted_batches.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn skips_pair_below_min_notional() {
+        // 每腿上限 1.5 份：0.45×1.5 ≈ $0.68，上调到 $1 所需份数超过上限，不提交
+        let ex = executor(MockClobApi::default(), ExecutorSettings { max_order_size_usdc: 1.5, ..settings() });
+        let err = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(1.5)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("单腿上限"), "{}", err);
+        assert!(posted(&ex).is_empty());
+    }
+
+    #[tokio::test]
+    async fn lifts_small_legs_to_min_notional() {
+        let ex = executor(MockClobApi::default(), settings());
+        let _ = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(1.5)), PriceDirection::Flat, PriceDirection::Flat)
+            .await;
+        let batches = posted(&ex);
+        assert_eq!(batches.len(), 1);
+        for order in &batches[0] {
+            assert!(order.price * order.size > dec!(1), "{:?}", order);
+        }
+    }
+
+    #[tokio::test]
+    async fn shadow_executor_only_decides_and_never_calls_api() {
+        let shadow = ExecutorSettings { max_order_size_usdc: 1.5, ..settings() };
+        let ex = executor(MockClobApi::default(), ExecutorSettings { shadow: Some(Box::new(shadow)), ..settings() });
+        let _ = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await;
+        // 只有主执行器提交；影子执行器只做下单前决策，其 OfflineClobApi 不联网
+        assert_eq!(posted(&ex).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn single_sided_fill_is_returned_for_risk_handling() {
+        let api = MockClobApi::default();
+        // YES 报价较高，先提交
+        script_post_orders(&api, vec![filled("yes-1", dec!(10)), rejected("no-1")]);
+        let ex = executor(api, settings());
+        let result = ex
+            .execute_arbitrage_pair(&opp(dec!(0.50), dec!(0.45), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap();
+        assert_eq!(result.yes_order_id, "yes-1");
+        assert_eq!(result.yes_filled, dec!(10));
+        assert_eq!(result.no_filled, dec!(0));
+        assert_eq!(result.realized_edge, None);
+        assert!(result.no_raw_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn both_unfilled_is_an_error() {
+        let ex = executor(MockClobApi::default(), settings());
+        let err = ex
+            .execute_arbitrage_pair(&opp(dec!(0.50), dec!(0.45), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("都未成交"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn higher_priced_leg_is_posted_first() {
+        let api = MockClobApi::default();
+        // NO 报价较高：提交顺序为 [NO, YES]，返回结果按同一顺序映射回两腿
+        script_post_orders(&api, vec![filled("no-1", dec!(5)), filled("yes-1", dec!(7))]);
+        let ex = executor(api, settings());
+        let result = ex
+            .execute_arbitrage_pair(&opp(dec!(0.40), dec!(0.55), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap();
+        let batches = posted(&ex);
+        assert_eq!(batches.len(), 1);
+        let tokens: Vec<U256> = batches[0].iter().map(|o| o.token_id).collect();
+        assert_eq!(tokens, vec![U256::from(NO), U256::from(YES)]);
+        assert_eq!((result.yes_order_id.as_str(), result.yes_filled), ("yes-1", dec!(7)));
+        assert_eq!((result.no_order_id.as_str(), result.no_filled), ("no-1", dec!(5)));
+    }
+
+    #[tokio::test]
+    async fn equal_prices_post_yes_first() {
+        let ex = executor(MockClobApi::default(), settings());
+        let _ = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.45), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await;
+        let tokens: Vec<U256> = posted(&ex)[0].iter().map(|o| o.token_id).collect();
+        assert_eq!(tokens, vec![U256::from(YES), U256::from(NO)]);
+    }
+
+    /// 手续费为返佣（负费率）时，价格之和略超 1 仍可能通过盈利判定，只有硬止损能拦住
+    fn rebate_settings() -> ExecutorSettings {
+        ExecutorSettings {
+            profit_config: ProfitConfig::new(0.0, 0.0, 2.0).unwrap().with_fee_schedule(FeeSchedule::flat(dec!(-500))),
+            ..settings()
+        }
+    }
+
+    fn guaranteed_loss(err: &anyhow::Error) -> &GuaranteedLoss {
+        err.downcast_ref::<GuaranteedLoss>().unwrap_or_else(|| panic!("不是 GuaranteedLoss: {}", err))
+    }
+
+    #[tokio::test]
+    async fn slipped_sum_at_or_above_one_is_never_submitted() {
+        let ex = executor(
+            MockClobApi::default(),
+            ExecutorSettings { slippage: SlippageTable::from_pair(dec!(0.02), dec!(0.02)), ..rebate_settings() },
+        );
+        let err = ex
+            .execute_arbitrage_pair(&opp(dec!(0.49), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap_err();
+        assert_eq!(guaranteed_loss(&err).sum, dec!(1.03));
+        assert!(posted(&ex).is_empty());
+    }
+
+    #[tokio::test]
+    async fn skip_verdict_is_reported_before_hard_stop() {
+        let ex = executor(
+            MockClobApi::default(),
+            ExecutorSettings { slippage: SlippageTable::from_pair(dec!(0.02), dec!(0.02)), ..settings() },
+        );
+        let err = ex
+            .execute_arbitrage_pair(&opp(dec!(0.49), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<GuaranteedLoss>().is_none());
+        assert!(err.to_string().contains("含滑点后不满足盈利条件"), "{}", err);
+        assert!(posted(&ex).is_empty());
+    }
+
+    #[tokio::test]
+    async fn reprice_loop_stops_at_guaranteed_loss() {
+        let api = MockClobApi::default();
+        api.best_asks.lock().unwrap().extend([(U256::from(YES), dec!(0.52)), (U256::from(NO), dec!(0.50))]);
+        let ex = executor(api, ExecutorSettings { reprice_max_ticks: 5, ..rebate_settings() });
+        let err = ex
+            .execute_arbitrage_pair(&opp(dec!(0.48), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap_err();
+        // 加价到 YES 0.53 + NO 0.51 后拒绝，只有首次提交
+        assert_eq!(guaranteed_loss(&err).sum, dec!(1.04));
+        assert_eq!(posted(&ex).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reprice_pair_refuses_guaranteed_loss() {
+        let api = MockClobApi::default();
+        api.best_asks.lock().unwrap().extend([(U256::from(YES), dec!(0.53)), (U256::from(NO), dec!(0.51))]);
+        let ex = executor(api, rebate_settings());
+        let resting = OrderPairResult {
+            pair_id: "pair-0001".to_string(),
+            yes_order_id: "yes-1".to_string(),
+            no_order_id: "no-1".to_string(),
+            yes_filled: dec!(0),
+            no_filled: dec!(0),
+            yes_size: dec!(10),
+            no_size: dec!(10),
+            success: false,
+            strategy_tag: "test".to_string(),
+            expected_edge: dec!(0.02),
+            realized_edge: None,
+            yes_raw_error: None,
+            no_raw_error: None,
+        };
+        let err = ex
+            .reprice_pair(&resting, &opp(dec!(0.48), dec!(0.50), dec!(10)))
+            .await
+            .unwrap_err();
+        assert_eq!(guaranteed_loss(&err).sum, dec!(1.04));
+        assert_eq!(*ex.api.cancelled.lock().unwrap(), vec!["yes-1".to_string(), "no-1".to_string()]);
+        assert!(posted(&ex).is_empty());
+    }
+
+    /// 已持有 10 份 YES（每份成本 yes_cost），机会同为 10 份：只下 NO 缺口腿
+    fn executor_holding_yes(yes_cost: Decimal) -> TradingExecutor<MockClobApi> {
+        let ex = executor(MockClobApi::default(), ExecutorSettings { use_existing_inventory: true, ..settings() });
+        let tracker = Arc::new(PositionTracker::new(dec!(1000)));
+        tracker.update_position(U256::from(YES), dec!(10));
+        tracker.update_exposure_cost(U256::from(YES), yes_cost, dec!(10));
+        ex.attach_inventory(tracker);
+        ex
+    }
+
+    #[tokio::test]
+    async fn single_leg_checks_held_cost_for_guaranteed_loss() {
+        let ex = executor_holding_yes(dec!(0.60));
+        let err = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap_err();
+        let loss = guaranteed_loss(&err);
+        assert_eq!((loss.yes_price, loss.no_price), (dec!(0.60), dec!(0.50)));
+        assert!(posted(&ex).is_empty());
+    }
+
+    #[tokio::test]
+    async fn single_leg_below_one_is_submitted() {
+        let ex = executor_holding_yes(dec!(0.40));
+        let result = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap();
+        assert_eq!(result.yes_size, dec!(0));
+        let batches = posted(&ex);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0][0].token_id, U256::from(NO));
+    }
+
+    #[tokio::test]
+    async fn admission_applies_executor_slippage() {
+        // 执行价差 0.01 放行总价 0.99，但加 0.01×2 滑点后为 1.01，执行端必然跳过
+        let ex = executor(
+            MockClobApi::default(),
+            ExecutorSettings { slippage: SlippageTable::from_pair(dec!(0.01), dec!(0.01)), ..settings() },
+        );
+        assert!(!ex.admits_after_slippage(&opp(dec!(0.49), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Down));
+        assert!(ex.admits_after_slippage(&opp(dec!(0.48), dec!(0.49), dec!(10)), PriceDirection::Flat, PriceDirection::Down));
+    }
+
+    #[tokio::test]
+    async fn gtd_expiration_follows_injected_clock() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:01:30Z").unwrap().with_timezone(&Utc);
+        let ex = executor(
+            MockClobApi::default(),
+            ExecutorSettings { arbitrage_order_type: OrderType::GTD, gtd_expiration_secs: 45, ..settings() },
+        )
+        .with_clock(Box::new(FixedClock::new(now)));
+        let _ = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await;
+        let batches = posted(&ex);
+        assert_eq!(batches.len(), 1);
+        for order in &batches[0] {
+            assert!(matches!(order.order_type, OrderType::GTD));
+            assert_eq!(order.expiration, Some(now + chrono::Duration::seconds(45)));
+        }
+    }
+
+    #[tokio::test]
+    async fn pair_ids_come_from_injected_generator() {
+        let api = MockClobApi::default();
+        script_post_orders(&api, vec![filled("yes-1", dec!(10)), filled("no-1", dec!(10))]);
+        script_post_orders(&api, vec![filled("yes-2", dec!(10)), filled("no-2", dec!(10))]);
+        let ex = executor(api, settings()).with_id_generator(Box::new(SequentialIds::new("pair")));
+        let opp = opp(dec!(0.45), dec!(0.50), dec!(10));
+        let first = ex.execute_arbitrage_pair(&opp, PriceDirection::Flat, PriceDirection::Flat).await.unwrap();
+        let second = ex.execute_arbitrage_pair(&opp, PriceDirection::Flat, PriceDirection::Flat).await.unwrap();
+        assert_eq!(first.pair_id, "pair-00000001");
+        assert_eq!(second.pair_id, "pair-00000002");
+    }
+
+    #[tokio::test]
+    async fn auth_error_reauthenticates_and_retries_once() {
+        let api = MockClobApi::default();
+        api.post_orders_script
+            .lock()
+            .unwrap()
+            .push_back(Err(anyhow::anyhow!("error making POST call to /orders: status: 401, body: Unauthorized/Invalid api key")));
+        script_post_orders(&api, vec![filled("yes-1", dec!(10)), filled("no-1", dec!(10))]);
+        let ex = executor(api, settings());
+        let result = ex
+            .execute_arbitrage_pair(&opp(dec!(0.50), dec!(0.45), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await
+            .unwrap();
+        assert_eq!(*ex.api.reauth_count.lock().unwrap(), 1);
+        // 重试提交的是同一批已签名订单
+        let batches = posted(&ex);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(format!("{:?}", batches[0]), format!("{:?}", batches[1]));
+        assert_eq!((result.yes_filled, result.no_filled), (dec!(10), dec!(10)));
+    }
+
+    #[tokio::test]
+    async fn price_containing_401_is_not_an_auth_error() {
+        let api = MockClobApi::default();
+        api.post_orders_script
+            .lock()
+            .unwrap()
+            .push_back(Err(anyhow::anyhow!("order rejected: invalid price 0.401")));
+        let ex = executor(api, settings());
+        let _ = ex
+            .execute_arbitrage_pair(&opp(dec!(0.50), dec!(0.45), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await;
+        assert_eq!(*ex.api.reauth_count.lock().unwrap(), 0);
+        assert_eq!(posted(&ex).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn negative_slippage_quotes_below_the_ask() {
+        let negative = SlippageTable::from_pair(dec!(-0.02), dec!(-0.02));
+        // 滑点下限 -1：不抬高负滑点，挂在卖一之下
+        let ex = executor(MockClobApi::default(), ExecutorSettings { slippage: negative, min_slippage: -1.0, ..settings() });
+        let _ = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await;
+        let prices: Vec<Decimal> = posted(&ex)[0].iter().map(|o| o.price).collect();
+        assert_eq!(prices, vec![dec!(0.48), dec!(0.43)]);
+
+        // 滑点下限 0：负滑点被抬到 0，按卖一报价
+        let ex = executor(MockClobApi::default(), ExecutorSettings { slippage: negative, min_slippage: 0.0, ..settings() });
+        let _ = ex
+            .execute_arbitrage_pair(&opp(dec!(0.45), dec!(0.50), dec!(10)), PriceDirection::Flat, PriceDirection::Flat)
+            .await;
+        let prices: Vec<Decimal> = posted(&ex)[0].iter().map(|o| o.price).collect();
+        assert_eq!(prices, vec![dec!(0.50), dec!(0.45)]);
+    }
+
+    #[tokio::test]
+    async fn nan_setting_fails_construction() {
+        let err = TradingExecutor::with_api(MockClobApi::default(), ExecutorSettings { min_slippage: f64::NAN, ..settings() })
+            .err()
+            .expect("NaN 配置应报错");
+        assert!(err.to_string().contains("MIN_SLIPPAGE"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn market_buy_leg_caps_price_at_slipped_quote() {
+        let ex = executor(MockClobApi::default(), ExecutorSettings { use_market_orders: true, ..settings() });
+        let order = ex.build_buy_leg(U256::from(1), dec!(0.52), dec!(10), None).await.unwrap();
+        assert_eq!(order.price, dec!(0.52));
+        assert_eq!(order.size, dec!(5.2));
+        assert!(matches!(order.order_type, OrderType::FOK));
+    }
+
+    #[tokio::test]
+    async fn partial_error_unwind_counts_sold_shares_not_usdc() {
+        let api = MockClobApi::default();
+        api.best_bids.lock().unwrap().insert(U256::from(1), dec!(0.45));
+        // 卖出 10 份 @0.40：付出 10 份，收到 4 USDC
+        api.post_order_script.lock().unwrap().push_back(Ok(OrderPostResult {
+            taking_amount: dec!(4),
+            making_amount: dec!(10),
+            ..filled("sell", dec!(0))
+        }));
+        let ex = executor(
+            api,
+            ExecutorSettings { on_partial_error: OnPartialError::UnwindFilled { max_slippage: dec!(0.05) }, ..settings() },
+        );
+        let yes = filled("yes", dec!(10));
+        let no = rejected("no");
+        let sold = ex
+            .handle_partial_error("pair", B256::ZERO, [("YES", U256::from(1), &yes, dec!(10)), ("NO", U256::from(2), &no, dec!(10))])
+            .await;
+        assert_eq!(sold, [dec!(10), dec!(0)]);
+        let batches = posted(&ex);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0][0].size, dec!(10));
+        assert_eq!(batches[0][0].price, dec!(0.40));
+    }
+
+    #[tokio::test]
+    async fn sells_and_merges_are_recorded_in_ledger() {
+        use crate::trading::ledger::{JsonlLedger, LedgerSide};
+
+        let path = std::env::temp_dir().join(format!("executor-ledger-{}.jsonl", uuid::Uuid::new_v4()));
+        let api = MockClobApi::default();
+        api.best_bids.lock().unwrap().insert(U256::from(1), dec!(0.45));
+        // 第一笔卖出 10 份收到 4 USDC；第二笔未成交，不写台账
+        api.post_order_script.lock().unwrap().push_back(Ok(OrderPostResult {
+            taking_amount: dec!(4),
+            making_amount: dec!(10),
+            ..filled("sell", dec!(0))
+        }));
+        let ex = executor(api, settings()).with_ledger(Box::new(JsonlLedger::new(&path)));
+        let market = B256::repeat_byte(7);
+        ex.sell_market(market, U256::from(1), dec!(10), dec!(0.05)).await.unwrap();
+        ex.sell_at_price(market, U256::from(1), dec!(0.5), dec!(3)).await.unwrap();
+        ex.record_merge(market, dec!(5)).await;
+
+        let entries = ex.ledger().entries().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].side, LedgerSide::Sell);
+        assert_eq!(entries[0].market_id, market);
+        assert_eq!(entries[0].size, dec!(10));
+        assert_eq!(entries[0].price, dec!(0.4));
+        assert_eq!(entries[1].side, LedgerSide::Merge);
+        assert_eq!(entries[1].size, dec!(5));
+    }
+}
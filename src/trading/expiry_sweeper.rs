@@ -0,0 +1,101 @@
+//! GTC 订单的客户端过期清扫：SDK 不允许非 GTD 订单设置 expiration，GTC 订单会一直挂到成交或手动取消。
+//! 执行器提交 GTC 订单后登记逻辑过期时间，后台任务按 `EXPIRY_SWEEP_INTERVAL_SECS` 周期扫描，
+//! 对超时的订单按 ID 撤单，使 GTC 订单有一个实际的 TTL。
+//!
+//! GTD 订单过期后交易所清扫前仍可能列为挂单：[`spawn_gtd_cleanup`] 周期查询挂单，只撤销已过期的那些，
+//! 避免收尾时用 `cancel_all_orders` 连带处理。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::trading::clob_api::ClobApi;
+use crate::trading::executor::TradingExecutor;
+
+/// 已登记的 GTC 订单：order_id → 逻辑过期时刻
+#[derive(Debug, Default)]
+pub struct ExpirySweeper {
+    orders: Mutex<HashMap<String, Instant>>,
+}
+
+impl ExpirySweeper {
+    /// 登记订单，ttl 后到期
+    pub fn register(&self, order_id: &str, ttl: Duration) {
+        if order_id.is_empty() {
+            return;
+        }
+        self.orders
+            .lock()
+            .unwrap()
+            .insert(order_id.to_string(), Instant::now() + ttl);
+    }
+
+    /// 取出所有已到期的订单（从登记表中移除）
+    pub fn take_due(&self, now: Instant) -> Vec<String> {
+        let mut orders = self.orders.lock().unwrap();
+        let due: Vec<String> = orders
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &due {
+            orders.remove(id);
+        }
+        due
+    }
+
+    /// 当前登记中的订单数
+    pub fn len(&self) -> usize {
+        self.orders.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 启动后台清扫任务：每 interval 撤销一次已到期的 GTC 订单（撤单失败只记日志，订单可能已成交或已撤）
+pub fn spawn<C: ClobApi + 'static>(
+    executor: Arc<TradingExecutor<C>>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    info!(interval_secs = interval.as_secs(), "🧹 GTC 过期清扫任务已启动");
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let due = executor.expiry_sweeper().take_due(Instant::now());
+            if due.is_empty() {
+                continue;
+            }
+            debug!(due = due.len(), pending = executor.expiry_sweeper().len(), "GTC 过期清扫");
+            for order_id in due {
+                match executor.cancel_order(&order_id).await {
+                    Ok(()) => info!("🧹 GTC 订单已过期撤单 | order_id={}", order_id),
+                    Err(e) => warn!(order_id = %order_id, error = %e, "GTC 过期撤单失败（可能已成交或已撤）"),
+                }
+            }
+        }
+    })
+}
+
+/// 启动 GTD 过期清理任务：每 interval 查询一次挂单，撤销过期时间已过的 GTD 订单（失败只记日志）
+pub fn spawn_gtd_cleanup<C: ClobApi + 'static>(
+    executor: Arc<TradingExecutor<C>>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    info!(interval_secs = interval.as_secs(), "🧹 GTD 过期清理任务已启动");
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match executor.cancel_expired_orders().await {
+                Ok(0) => debug!("GTD 过期清理：无过期挂单"),
+                Ok(n) => info!("🧹 已撤销 {} 个过期 GTD 订单", n),
+                Err(e) => warn!(error = %e, "GTD 过期清理失败"),
+            }
+        }
+    })
+}
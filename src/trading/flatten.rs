@@ -0,0 +1,126 @@
+//! 紧急平仓（kill-switch）：取消全部挂单 → 激进卖出每个市场的净持仓 → 对双边平衡部分执行 Merge。
+
+use anyhow::Result;
+use poly_5min_bot::merge;
+use poly_5min_bot::positions::PositionBook;
+use polymarket_client_sdk::types::{Address, B256, U256};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::fmt;
+use tracing::{error, info, warn};
+
+use crate::trading::clob_api::{CancelSummary, ClobApi};
+use crate::trading::executor::TradingExecutor;
+
+/// 一次紧急平仓的结果
+#[derive(Debug, Default)]
+pub struct FlattenReport {
+    /// 取消全部挂单的结果；请求失败时为 None
+    pub cancelled: Option<CancelSummary>,
+    /// 已提交卖出：(token_id, 卖出数量, 立即成交份数)
+    pub sold: Vec<(U256, Decimal, Decimal)>,
+    /// 卖出失败：(token_id, 数量, 错误)
+    pub sell_failures: Vec<(U256, Decimal, String)>,
+    /// Merge 成功：(condition_id, 数量, tx_hash)
+    pub merged: Vec<(B256, Decimal, String)>,
+    /// Merge 失败：(condition_id, 错误)
+    pub merge_failures: Vec<(B256, String)>,
+}
+
+impl fmt::Display for FlattenReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "取消挂单:{} | 卖出:{}笔(失败{}) | Merge:{}个市场(失败{})",
+            self.cancelled.as_ref().map(|s| s.to_string()).unwrap_or_else(|| "失败".to_string()),
+            self.sold.len(),
+            self.sell_failures.len(),
+            self.merged.len(),
+            self.merge_failures.len()
+        )
+    }
+}
+
+impl<C: ClobApi> TradingExecutor<C> {
+    /// 紧急平仓：取消全部挂单，激进卖出每个净持仓（买一 − max_slippage，FAK），
+    /// 最后对双边平衡部分强制 merge（忽略 gas 经济性与 MERGE_MIN_AMOUNT，需提供 proxy 与私钥）。单步失败记录在报告中并继续后续步骤。
+    pub async fn panic_flatten(
+        &self,
+        book: &PositionBook,
+        max_slippage: Decimal,
+        merge_with: Option<(Address, &str)>,
+    ) -> Result<FlattenReport> {
+        warn!("🚨 紧急平仓开始 | 持仓记录:{}条", book.len());
+        let mut report = FlattenReport::default();
+
+        // 1. 取消全部挂单
+        match self.cancel_all_orders().await {
+            Ok(summary) => {
+                info!("✅ 紧急平仓：取消挂单 | {}", summary);
+                report.cancelled = Some(summary);
+            }
+            Err(e) => error!(error = %e, "紧急平仓：取消所有挂单失败，继续卖出"),
+        }
+
+        // 2. 激进卖出净持仓（数量按 0.01 向下取整）
+        let balanced = book.balanced();
+        for (condition_id, token_id, size) in book.net_holdings() {
+            let size_floor = (size * dec!(100)).floor() / dec!(100);
+            if size_floor < dec!(0.01) {
+                continue;
+            }
+            match self.sell_market(condition_id, token_id, size_floor, max_slippage).await {
+                // 卖单付出的是份数（making），收到的是 USDC（taking）
+                Ok(resp) => report.sold.push((token_id, size_floor, resp.making_amount)),
+                Err(e) => {
+                    warn!(token_id = %token_id, size = %size_floor, error = %e, "紧急平仓：卖出失败");
+                    report.sell_failures.push((token_id, size_floor, e.to_string()));
+                }
+            }
+        }
+
+        // 3. Merge 双边平衡部分
+        match merge_with {
+            Some((proxy, private_key)) => {
+                // 紧急平仓不按经济性或最小数量跳过：平衡持仓留在账上同样是未平的敞口
+                let opts = merge::MergeOptions::from_env().map(|opts| merge::MergeOptions {
+                    chain_id: self.chain_id(),
+                    force: true,
+                    min_merge_amount: U256::ZERO,
+                    ..opts
+                });
+                for (condition_id, amount) in balanced {
+                    let outcome = match &opts {
+                        Ok(opts) => {
+                            let _merge_guard = self.merge_lock().lock().await;
+                            merge::merge_max_with(condition_id, proxy, private_key, None, opts).await
+                        }
+                        Err(e) => Err(anyhow::anyhow!("读取 Merge 配置失败: {}", e)),
+                    };
+                    match outcome {
+                        Ok(skipped @ (merge::MergeOutcome::SkippedUneconomical { .. } | merge::MergeOutcome::BelowThreshold { .. })) => {
+                            warn!(condition_id = %condition_id, "紧急平仓：Merge 跳过: {}", skipped);
+                            report.merge_failures.push((condition_id, skipped.to_string()));
+                        }
+                        Ok(merge::MergeOutcome::Merged(res)) => {
+                            info!("✅ 紧急平仓：Merge 完成 | condition_id={:#x} | 数量:{} | tx={}", condition_id, amount, res.tx_hash);
+                            self.record_merge(condition_id, merge::units_to_usdc(res.merge_amount)).await;
+                            report.merged.push((condition_id, amount, res.tx_hash));
+                        }
+                        Err(e) => {
+                            warn!(condition_id = %condition_id, error = %e, "紧急平仓：Merge 失败");
+                            report.merge_failures.push((condition_id, e.to_string()));
+                        }
+                    }
+                }
+            }
+            None if !balanced.is_empty() => {
+                warn!("紧急平仓：未配置 POLYMARKET_PROXY_ADDRESS，{} 个市场的平衡持仓未 Merge", balanced.len());
+            }
+            None => {}
+        }
+
+        warn!("🚨 紧急平仓结束 | {}", report);
+        Ok(report)
+    }
+}
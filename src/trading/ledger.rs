@@ -0,0 +1,515 @@
+//! 成交台账：执行器把每笔成交写入可插拔的后端，按部署选择轻量或完整的持久化方式。
+//!
+//! | 后端 | LEDGER_BACKEND | 说明 |
+//! |------|----------------|------|
+//! | [`NullLedger`] | `null`（默认） | 不记录 |
+//! | [`JsonlLedger`] | `jsonl` | 追加写 JSONL 文件，每行一条 |
+//! | `SqliteLedger` | `sqlite` | SQLite 表 `trades`，需 `--features sqlite-ledger` |
+//!
+//! 盈亏按现金流计算：买入 −价格×数量，卖出 +价格×数量，Merge +数量（每份 1 USDC），均再扣手续费。
+//! 买入在订单对成交后记录；卖出（单边退出、紧急平仓、收尾、多余成交）在执行器的卖出入口按立即成交部分记录；
+//! 定时、盘中阈值、收尾与紧急平仓的 Merge 成功后记录。
+//! 每条记录带策略标签（STRATEGY_TAG），可按 (市场, 标签) 拆分盈亏以对比参数变体。
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use polymarket_client_sdk::types::{Decimal, B256, U256};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tokio::io::AsyncWriteExt;
+
+/// 台账条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerSide {
+    Buy,
+    Sell,
+    Merge,
+}
+
+impl LedgerSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerSide::Buy => "BUY",
+            LedgerSide::Sell => "SELL",
+            LedgerSide::Merge => "MERGE",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "BUY" => Some(LedgerSide::Buy),
+            "SELL" => Some(LedgerSide::Sell),
+            "MERGE" => Some(LedgerSide::Merge),
+            _ => None,
+        }
+    }
+}
+
+/// 单条成交记录。Merge 时 token_id 为 0、price 为 1。
+#[derive(Debug, Clone)]
+pub struct LedgerEntry {
+    pub ts_ms: i64,
+    pub market_id: B256,
+    pub pair_id: String,
+    pub strategy_tag: String,
+    pub token_id: U256,
+    pub side: LedgerSide,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub fee: Decimal,
+    /// 检测时的预期每份毛利（1 − YES 卖一 − NO 卖一），仅订单对的买入记录有
+    pub expected_edge: Option<Decimal>,
+    /// 按实际报价与成交算出的每份毛利，仅双边都成交时有
+    pub realized_edge: Option<Decimal>,
+}
+
+impl LedgerEntry {
+    /// 买入成交（手续费 = 价格 × 数量 × fee_bps / 10000）
+    pub fn buy(
+        market_id: B256,
+        pair_id: &str,
+        strategy_tag: &str,
+        token_id: U256,
+        price: Decimal,
+        size: Decimal,
+        fee_bps: Decimal,
+    ) -> Self {
+        Self {
+            ts_ms: Utc::now().timestamp_millis(),
+            market_id,
+            pair_id: pair_id.to_string(),
+            strategy_tag: strategy_tag.to_string(),
+            token_id,
+            side: LedgerSide::Buy,
+            price,
+            size,
+            fee: price * size * fee_bps / dec!(10000),
+            expected_edge: None,
+            realized_edge: None,
+        }
+    }
+
+    /// 卖出成交（手续费 = 价格 × 数量 × fee_bps / 10000）
+    pub fn sell(
+        market_id: B256,
+        pair_id: &str,
+        strategy_tag: &str,
+        token_id: U256,
+        price: Decimal,
+        size: Decimal,
+        fee_bps: Decimal,
+    ) -> Self {
+        Self {
+            side: LedgerSide::Sell,
+            ..Self::buy(market_id, pair_id, strategy_tag, token_id, price, size, fee_bps)
+        }
+    }
+
+    /// 附上订单对的预期/实际每份毛利
+    pub fn with_edges(mut self, expected: Decimal, realized: Option<Decimal>) -> Self {
+        self.expected_edge = Some(expected);
+        self.realized_edge = realized;
+        self
+    }
+
+    /// Merge 回收（每份 1 USDC，无手续费）
+    pub fn merge(market_id: B256, strategy_tag: &str, size: Decimal) -> Self {
+        Self {
+            ts_ms: Utc::now().timestamp_millis(),
+            market_id,
+            pair_id: String::new(),
+            strategy_tag: strategy_tag.to_string(),
+            token_id: U256::ZERO,
+            side: LedgerSide::Merge,
+            price: dec!(1),
+            size,
+            fee: dec!(0),
+            expected_edge: None,
+            realized_edge: None,
+        }
+    }
+
+    /// 对该条记录的盈亏贡献（USDC）
+    pub fn cash_flow(&self) -> Decimal {
+        let gross = match self.side {
+            LedgerSide::Buy => -self.price * self.size,
+            LedgerSide::Sell => self.price * self.size,
+            LedgerSide::Merge => self.size,
+        };
+        gross - self.fee
+    }
+}
+
+/// 落盘格式：数值均为十进制字符串，避免浮点精度损失
+#[derive(Debug, Serialize, Deserialize)]
+struct LedgerRow {
+    ts_ms: i64,
+    market_id: String,
+    pair_id: String,
+    /// 旧台账无此字段，读入时视为空标签
+    #[serde(default)]
+    strategy_tag: String,
+    token_id: String,
+    side: String,
+    price: String,
+    size: String,
+    fee: String,
+    /// 空串表示无
+    #[serde(default)]
+    expected_edge: String,
+    #[serde(default)]
+    realized_edge: String,
+}
+
+impl From<&LedgerEntry> for LedgerRow {
+    fn from(e: &LedgerEntry) -> Self {
+        Self {
+            ts_ms: e.ts_ms,
+            market_id: format!("{:#x}", e.market_id),
+            pair_id: e.pair_id.clone(),
+            strategy_tag: e.strategy_tag.clone(),
+            token_id: e.token_id.to_string(),
+            side: e.side.as_str().to_string(),
+            price: e.price.to_string(),
+            size: e.size.to_string(),
+            fee: e.fee.to_string(),
+            expected_edge: e.expected_edge.map(|d| d.to_string()).unwrap_or_default(),
+            realized_edge: e.realized_edge.map(|d| d.to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<LedgerRow> for LedgerEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(r: LedgerRow) -> Result<Self> {
+        Ok(Self {
+            ts_ms: r.ts_ms,
+            market_id: B256::from_str(&r.market_id).map_err(|e| anyhow::anyhow!("market_id 无效: {}", e))?,
+            pair_id: r.pair_id,
+            strategy_tag: r.strategy_tag,
+            token_id: U256::from_str(&r.token_id).map_err(|e| anyhow::anyhow!("token_id 无效: {}", e))?,
+            side: LedgerSide::parse(&r.side).ok_or_else(|| anyhow::anyhow!("side 无效: {}", r.side))?,
+            price: Decimal::from_str(&r.price)?,
+            size: Decimal::from_str(&r.size)?,
+            fee: Decimal::from_str(&r.fee)?,
+            expected_edge: parse_optional(&r.expected_edge)?,
+            realized_edge: parse_optional(&r.realized_edge)?,
+        })
+    }
+}
+
+fn parse_optional(s: &str) -> Result<Option<Decimal>> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Decimal::from_str(s)?))
+}
+
+/// 台账后端。方法返回 BoxFuture 以便执行器持有 `Box<dyn TradeLedger>`。
+pub trait TradeLedger: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 追加一条记录
+    fn record(&self, entry: LedgerEntry) -> BoxFuture<'_, Result<()>>;
+
+    /// 读出全部记录（按写入顺序）
+    fn entries(&self) -> BoxFuture<'_, Result<Vec<LedgerEntry>>>;
+
+    /// 按市场汇总现金流盈亏（USDC）
+    fn pnl_by_market(&self) -> BoxFuture<'_, Result<HashMap<B256, Decimal>>> {
+        Box::pin(async move {
+            let mut out: HashMap<B256, Decimal> = HashMap::new();
+            for e in self.entries().await? {
+                *out.entry(e.market_id).or_insert(dec!(0)) += e.cash_flow();
+            }
+            Ok(out)
+        })
+    }
+
+    /// 按 (市场, 策略标签) 汇总现金流盈亏（USDC）
+    fn pnl_by_market_and_tag(&self) -> BoxFuture<'_, Result<HashMap<(B256, String), Decimal>>> {
+        Box::pin(async move {
+            let mut out: HashMap<(B256, String), Decimal> = HashMap::new();
+            for e in self.entries().await? {
+                let flow = e.cash_flow();
+                *out.entry((e.market_id, e.strategy_tag)).or_insert(dec!(0)) += flow;
+            }
+            Ok(out)
+        })
+    }
+}
+
+/// 不记录任何内容
+#[derive(Debug, Default)]
+pub struct NullLedger;
+
+impl TradeLedger for NullLedger {
+    fn name(&self) -> &'static str {
+        "null"
+    }
+
+    fn record(&self, _entry: LedgerEntry) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn entries(&self) -> BoxFuture<'_, Result<Vec<LedgerEntry>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+}
+
+/// JSONL 文件台账：每条记录追加一行；写入串行化，避免并发追加交错
+pub struct JsonlLedger {
+    path: PathBuf,
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl JsonlLedger {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+}
+
+impl TradeLedger for JsonlLedger {
+    fn name(&self) -> &'static str {
+        "jsonl"
+    }
+
+    fn record(&self, entry: LedgerEntry) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let mut line = serde_json::to_string(&LedgerRow::from(&entry))?;
+            line.push('\n');
+            let _guard = self.write_lock.lock().await;
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await
+                .with_context(|| format!("打开台账文件失败: {}", self.path.display()))?;
+            file.write_all(line.as_bytes()).await?;
+            file.flush().await?;
+            Ok(())
+        })
+    }
+
+    fn entries(&self) -> BoxFuture<'_, Result<Vec<LedgerEntry>>> {
+        Box::pin(async move {
+            let text = match tokio::fs::read_to_string(&self.path).await {
+                Ok(t) => t,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => return Err(anyhow::anyhow!("读取台账文件失败 {}: {}", self.path.display(), e)),
+            };
+            let mut entries = Vec::new();
+            for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+                let row: LedgerRow = serde_json::from_str(line)
+                    .with_context(|| format!("台账第 {} 行解析失败", i + 1))?;
+                entries.push(LedgerEntry::try_from(row).with_context(|| format!("台账第 {} 行无效", i + 1))?);
+            }
+            Ok(entries)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite-ledger")]
+pub use sqlite::SqliteLedger;
+
+#[cfg(feature = "sqlite-ledger")]
+mod sqlite {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// SQLite 台账：表 `trades`，阻塞 IO 放到 spawn_blocking 中执行
+    pub struct SqliteLedger {
+        conn: Arc<Mutex<rusqlite::Connection>>,
+    }
+
+    impl SqliteLedger {
+        pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+            let path = path.into();
+            let conn = rusqlite::Connection::open(&path)
+                .with_context(|| format!("打开 SQLite 台账失败: {}", path.display()))?;
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS trades (
+                    ts_ms INTEGER NOT NULL,
+                    market_id TEXT NOT NULL,
+                    pair_id TEXT NOT NULL,
+                    strategy_tag TEXT NOT NULL DEFAULT '',
+                    token_id TEXT NOT NULL,
+                    side TEXT NOT NULL,
+                    price TEXT NOT NULL,
+                    size TEXT NOT NULL,
+                    fee TEXT NOT NULL,
+                    expected_edge TEXT NOT NULL DEFAULT '',
+                    realized_edge TEXT NOT NULL DEFAULT ''
+                );
+                CREATE INDEX IF NOT EXISTS trades_market ON trades(market_id);",
+            )?;
+            // 早期版本建的表缺少后加的列：逐列补上，列已存在时忽略错误
+            for column in ["strategy_tag", "expected_edge", "realized_edge"] {
+                let _ = conn.execute(&format!("ALTER TABLE trades ADD COLUMN {} TEXT NOT NULL DEFAULT ''", column), []);
+            }
+            Ok(Self { conn: Arc::new(Mutex::new(conn)) })
+        }
+    }
+
+    impl TradeLedger for SqliteLedger {
+        fn name(&self) -> &'static str {
+            "sqlite"
+        }
+
+        fn record(&self, entry: LedgerEntry) -> BoxFuture<'_, Result<()>> {
+            let conn = self.conn.clone();
+            Box::pin(async move {
+                tokio::task::spawn_blocking(move || -> Result<()> {
+                    let r = LedgerRow::from(&entry);
+                    conn.lock().unwrap().execute(
+                        "INSERT INTO trades (ts_ms, market_id, pair_id, strategy_tag, token_id, side, price, size, fee,
+                                             expected_edge, realized_edge)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                        rusqlite::params![
+                            r.ts_ms,
+                            r.market_id,
+                            r.pair_id,
+                            r.strategy_tag,
+                            r.token_id,
+                            r.side,
+                            r.price,
+                            r.size,
+                            r.fee,
+                            r.expected_edge,
+                            r.realized_edge
+                        ],
+                    )?;
+                    Ok(())
+                })
+                .await?
+            })
+        }
+
+        fn entries(&self) -> BoxFuture<'_, Result<Vec<LedgerEntry>>> {
+            let conn = self.conn.clone();
+            Box::pin(async move {
+                tokio::task::spawn_blocking(move || -> Result<Vec<LedgerEntry>> {
+                    let conn = conn.lock().unwrap();
+                    let mut stmt = conn.prepare(
+                        "SELECT ts_ms, market_id, pair_id, strategy_tag, token_id, side, price, size, fee,
+                                expected_edge, realized_edge
+                         FROM trades ORDER BY rowid",
+                    )?;
+                    let rows = stmt.query_map([], |row| {
+                        Ok(LedgerRow {
+                            ts_ms: row.get(0)?,
+                            market_id: row.get(1)?,
+                            pair_id: row.get(2)?,
+                            strategy_tag: row.get(3)?,
+                            token_id: row.get(4)?,
+                            side: row.get(5)?,
+                            price: row.get(6)?,
+                            size: row.get(7)?,
+                            fee: row.get(8)?,
+                            expected_edge: row.get(9)?,
+                            realized_edge: row.get(10)?,
+                        })
+                    })?;
+                    let mut entries = Vec::new();
+                    for row in rows {
+                        entries.push(LedgerEntry::try_from(row?)?);
+                    }
+                    Ok(entries)
+                })
+                .await?
+            })
+        }
+    }
+}
+
+/// 按配置构建台账后端：null | jsonl | sqlite
+pub fn ledger_from_config(backend: &str, path: &str) -> Result<Box<dyn TradeLedger>> {
+    match backend.trim().to_lowercase().as_str() {
+        "" | "null" | "none" => Ok(Box::new(NullLedger)),
+        "jsonl" | "file" => Ok(Box::new(JsonlLedger::new(path))),
+        #[cfg(feature = "sqlite-ledger")]
+        "sqlite" => Ok(Box::new(SqliteLedger::open(path)?)),
+        #[cfg(not(feature = "sqlite-ledger"))]
+        "sqlite" => anyhow::bail!("LEDGER_BACKEND=sqlite 需以 --features sqlite-ledger 构建"),
+        other => anyhow::bail!("未知 LEDGER_BACKEND: {}（可用: null, jsonl, sqlite）", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 临时目录下的唯一台账文件，测试结束时删除
+    struct TempLedger(PathBuf);
+
+    impl TempLedger {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("ledger-test-{}.jsonl", uuid::Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempLedger {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_file_has_no_entries() {
+        let tmp = TempLedger::new();
+        let ledger = JsonlLedger::new(&tmp.0);
+        assert!(ledger.entries().await.unwrap().is_empty());
+        assert!(ledger.pnl_by_market().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn jsonl_records_round_trip_into_pnl() {
+        let tmp = TempLedger::new();
+        let ledger = JsonlLedger::new(&tmp.0);
+        let a = B256::repeat_byte(0xaa);
+        let b = B256::repeat_byte(0xbb);
+        let yes = U256::from(1);
+        let no = U256::from(2);
+
+        // 市场 a / 标签 base：0.48 + 0.50 买入 10 份后 merge → +0.2
+        ledger
+            .record(LedgerEntry::buy(a, "p1", "base", yes, dec!(0.48), dec!(10), dec!(0)).with_edges(dec!(0.02), Some(dec!(0.02))))
+            .await
+            .unwrap();
+        ledger.record(LedgerEntry::buy(a, "p1", "base", no, dec!(0.50), dec!(10), dec!(0))).await.unwrap();
+        ledger.record(LedgerEntry::merge(a, "base", dec!(10))).await.unwrap();
+        // 市场 a / 标签 wide：0.40 × 5 买入（100 bps 手续费 0.02），0.45 卖出 → +0.23
+        ledger.record(LedgerEntry::buy(a, "p2", "wide", yes, dec!(0.40), dec!(5), dec!(100))).await.unwrap();
+        ledger.record(LedgerEntry::sell(a, "", "wide", yes, dec!(0.45), dec!(5), dec!(0))).await.unwrap();
+        // 市场 b / 标签 base：只买入 → -1
+        ledger.record(LedgerEntry::buy(b, "p3", "base", yes, dec!(0.5), dec!(2), dec!(0))).await.unwrap();
+
+        let entries = ledger.entries().await.unwrap();
+        assert_eq!(entries.len(), 6);
+        assert_eq!(entries[0].expected_edge, Some(dec!(0.02)));
+        assert_eq!(entries[0].realized_edge, Some(dec!(0.02)));
+        assert_eq!(entries[1].expected_edge, None);
+        assert_eq!(entries[2].side, LedgerSide::Merge);
+        assert_eq!(entries[3].fee, dec!(0.02));
+        assert_eq!(entries[4].side, LedgerSide::Sell);
+
+        let by_market = ledger.pnl_by_market().await.unwrap();
+        assert_eq!(by_market.len(), 2);
+        assert_eq!(by_market[&a], dec!(0.43));
+        assert_eq!(by_market[&b], dec!(-1));
+
+        let by_tag = ledger.pnl_by_market_and_tag().await.unwrap();
+        assert_eq!(by_tag.len(), 3);
+        assert_eq!(by_tag[&(a, "base".to_string())], dec!(0.2));
+        assert_eq!(by_tag[&(a, "wide".to_string())], dec!(0.23));
+        assert_eq!(by_tag[&(b, "base".to_string())], dec!(-1));
+    }
+}
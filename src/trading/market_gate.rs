@@ -0,0 +1,105 @@
+//! 运行时按市场开关：被禁用的 condition_id 上的套利机会直接跳过，无需重启。
+//! 另有配置的白名单/黑名单（[`MarketFilter`]），监控发出机会前与下单前各检查一次。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// 已禁用市场集合（condition_id 统一为小写 0x 十六进制），clone 后共享同一份状态
+#[derive(Debug, Clone, Default)]
+pub struct MarketGate {
+    disabled: Arc<RwLock<HashSet<String>>>,
+}
+
+impl MarketGate {
+    fn normalize(condition_id: &str) -> String {
+        let s = condition_id.trim().to_lowercase();
+        if s.starts_with("0x") { s } else { format!("0x{}", s) }
+    }
+
+    /// 禁用市场，返回此前是否处于启用状态
+    pub fn disable_market(&self, condition_id: &str) -> bool {
+        self.disabled.write().unwrap().insert(Self::normalize(condition_id))
+    }
+
+    /// 重新启用市场，返回此前是否处于禁用状态
+    pub fn enable_market(&self, condition_id: &str) -> bool {
+        self.disabled.write().unwrap().remove(&Self::normalize(condition_id))
+    }
+
+    pub fn is_disabled(&self, condition_id: &str) -> bool {
+        let disabled = self.disabled.read().unwrap();
+        !disabled.is_empty() && disabled.contains(&Self::normalize(condition_id))
+    }
+
+    /// 当前禁用的市场（排序后）
+    pub fn disabled_markets(&self) -> Vec<String> {
+        let mut v: Vec<String> = self.disabled.read().unwrap().iter().cloned().collect();
+        v.sort();
+        v
+    }
+}
+
+/// 配置的市场白名单/黑名单（MARKET_ALLOWLIST / MARKET_DENYLIST），条目为 condition_id 或 slug；
+/// slug 条目也匹配带窗口时间戳后缀的 5 分钟市场（btc-updown-5m 匹配 btc-updown-5m-1770972300）。
+/// 白名单非空时只允许名单内市场；同时出现在两个名单中时以白名单为准
+#[derive(Debug, Clone, Default)]
+pub struct MarketFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+    slugs: Arc<RwLock<HashMap<String, String>>>, // condition_id -> slug，按 condition_id 检查时据此匹配 slug 条目
+}
+
+impl MarketFilter {
+    pub fn new(allow: &[String], deny: &[String]) -> Self {
+        let entries = |list: &[String]| -> Vec<String> {
+            list.iter()
+                .map(|e| e.trim().to_lowercase())
+                .filter(|e| !e.is_empty())
+                .map(|e| if e.starts_with("0x") { MarketGate::normalize(&e) } else { e })
+                .collect()
+        };
+        Self {
+            allow: entries(allow),
+            deny: entries(deny),
+            slugs: Arc::default(),
+        }
+    }
+
+    /// 登记市场 slug（新窗口发现市场时调用），使 slug 条目可按 condition_id 匹配
+    pub fn remember_slug(&self, condition_id: &str, slug: &str) {
+        if self.allow.is_empty() && self.deny.is_empty() {
+            return;
+        }
+        self.slugs
+            .write()
+            .unwrap()
+            .insert(MarketGate::normalize(condition_id), slug.trim().to_lowercase());
+    }
+
+    fn matches(list: &[String], condition_id: &str, slug: Option<&str>) -> bool {
+        list.iter().any(|e| {
+            e == condition_id
+                || slug.is_some_and(|s| s == e || (s.starts_with(e.as_str()) && s[e.len()..].starts_with('-')))
+        })
+    }
+
+    /// 被拒原因；允许时返回 None
+    pub fn rejection(&self, condition_id: &str) -> Option<String> {
+        if self.allow.is_empty() && self.deny.is_empty() {
+            return None;
+        }
+        let condition_id = MarketGate::normalize(condition_id);
+        let slugs = self.slugs.read().unwrap();
+        let slug = slugs.get(&condition_id).map(String::as_str);
+        let shown = slug.unwrap_or(&condition_id);
+        if Self::matches(&self.allow, &condition_id, slug) {
+            None
+        } else if !self.allow.is_empty() {
+            Some(format!("市场不在白名单: {}", shown))
+        } else if Self::matches(&self.deny, &condition_id, slug) {
+            Some(format!("市场在黑名单: {}", shown))
+        } else {
+            None
+        }
+    }
+}
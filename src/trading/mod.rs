@@ -1,4 +1,19 @@
+pub mod balance_guard;
+pub mod clob_api;
+pub mod clock;
+pub mod close_strategy;
+pub mod decision;
 pub mod executor;
+pub mod expiry_sweeper;
+pub mod flatten;
+pub mod ledger;
+pub mod market_gate;
 pub mod orders;
+pub mod slippage;
+pub mod unwind;
 
+pub use close_strategy::CloseStrategy;
 pub use executor::TradingExecutor;
+pub use market_gate::{MarketFilter, MarketGate};
+pub use orders::{DirectionMismatchAction, OnPartialError, PriceDirection, SelfTradePrevention, SizeMode, SlippageTable};
+pub use unwind::UnwindStyle;
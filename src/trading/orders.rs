@@ -1,2 +1,239 @@
 // 订单相关的辅助类型和函数
-// 目前为空，可以后续扩展
+
+use polymarket_client_sdk::types::Decimal;
+use std::fmt;
+
+/// 卖一价相对上一拍的涨跌方向，用于按方向分配滑点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceDirection {
+    /// 上涨 ↑
+    Up,
+    /// 下降 ↓
+    Down,
+    /// 持平 −
+    Flat,
+    /// 首拍无上一价，方向未知
+    Unknown,
+}
+
+impl PriceDirection {
+    /// 比较当前价与上一拍价格
+    pub fn from_change(current: Decimal, previous: Decimal) -> Self {
+        if current > previous {
+            PriceDirection::Up
+        } else if current < previous {
+            PriceDirection::Down
+        } else {
+            PriceDirection::Flat
+        }
+    }
+
+    /// 日志箭头："↑" "↓" "−"，未知为空串
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PriceDirection::Up => "↑",
+            PriceDirection::Down => "↓",
+            PriceDirection::Flat => "−",
+            PriceDirection::Unknown => "",
+        }
+    }
+
+    /// 滑点规则：仅下降用 second，上涨、持平、未知均用 first
+    pub fn uses_second_slippage(&self) -> bool {
+        matches!(self, PriceDirection::Down)
+    }
+}
+
+impl From<&str> for PriceDirection {
+    fn from(s: &str) -> Self {
+        match s.trim() {
+            "↑" => PriceDirection::Up,
+            "↓" => PriceDirection::Down,
+            "−" | "-" => PriceDirection::Flat,
+            _ => PriceDirection::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for PriceDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// 按涨跌方向取滑点的映射表。默认由 SLIPPAGE 的 [first, second] 生成（仅下降用 second），
+/// 可用 SLIPPAGE_BY_DIRECTION 单独覆盖某个方向，例如盘口粘滞的持平市场放宽滑点。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlippageTable {
+    pub up: Decimal,
+    pub down: Decimal,
+    pub flat: Decimal,
+    pub unknown: Decimal,
+}
+
+impl SlippageTable {
+    /// 默认规则：仅下降用 second，上涨、持平、未知均用 first
+    pub fn from_pair(first: Decimal, second: Decimal) -> Self {
+        let pick = |dir: PriceDirection| if dir.uses_second_slippage() { second } else { first };
+        Self {
+            up: pick(PriceDirection::Up),
+            down: pick(PriceDirection::Down),
+            flat: pick(PriceDirection::Flat),
+            unknown: pick(PriceDirection::Unknown),
+        }
+    }
+
+    /// 按 "flat=0.02,down=0" 覆盖指定方向（键：up|down|flat|unknown，也接受 ↑ ↓ −）；空串不覆盖
+    pub fn with_overrides(mut self, spec: &str) -> Result<Self, String> {
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("缺少 '=': {}", item))?;
+            let value: Decimal = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("滑点不是数字: {}", item))?;
+            let slot = match key.trim().to_lowercase().as_str() {
+                "up" | "↑" => &mut self.up,
+                "down" | "↓" => &mut self.down,
+                "flat" | "−" | "-" => &mut self.flat,
+                "unknown" | "?" => &mut self.unknown,
+                other => return Err(format!("未知方向: {}（可用: up, down, flat, unknown）", other)),
+            };
+            *slot = value;
+        }
+        Ok(self)
+    }
+
+    pub fn get(&self, dir: PriceDirection) -> Decimal {
+        match dir {
+            PriceDirection::Up => self.up,
+            PriceDirection::Down => self.down,
+            PriceDirection::Flat => self.flat,
+            PriceDirection::Unknown => self.unknown,
+        }
+    }
+}
+
+/// 两腿涨跌方向相同（同为 ↑ 或同为 ↓）时的处理。二元市场 YES/NO 卖一互补，方向应相反或持平，
+/// 同向说明上游方向计算或行情数据有误，按此方向取滑点会分配错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectionMismatchAction {
+    /// 不检查
+    Off,
+    /// 告警后照常下单（默认）
+    Warn,
+    /// 告警并跳过本次机会
+    Skip,
+}
+
+impl DirectionMismatchAction {
+    /// 解析配置：off | warn | skip，大小写不敏感，未知值默认 Warn
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "none" | "0" => DirectionMismatchAction::Off,
+            "skip" => DirectionMismatchAction::Skip,
+            _ => DirectionMismatchAction::Warn,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectionMismatchAction::Off => "off",
+            DirectionMismatchAction::Warn => "warn",
+            DirectionMismatchAction::Skip => "skip",
+        }
+    }
+
+    /// 两腿同为上涨或同为下降视为不一致；持平、未知不算
+    pub fn is_mismatch(yes_dir: PriceDirection, no_dir: PriceDirection) -> bool {
+        yes_dir == no_dir && matches!(yes_dir, PriceDirection::Up | PriceDirection::Down)
+    }
+}
+
+/// 订单对中一腿提交失败、另一腿有成交时的处理（ON_PARTIAL_ERROR）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnPartialError {
+    /// 两腿原样交给风险管理器（默认）
+    LeaveForRisk,
+    /// 立即撤掉成功一腿未成交的挂单余量，已成交部分仍交风险管理器
+    CancelWorking,
+    /// 立即撤掉挂单余量，并以 买一 − max_slippage 卖出多于另一腿的已成交部分
+    UnwindFilled { max_slippage: Decimal },
+}
+
+impl OnPartialError {
+    /// 解析配置：leave_for_risk | cancel_working | unwind_filled，大小写不敏感，未知值默认 LeaveForRisk
+    pub fn parse(s: &str, max_slippage: Decimal) -> Self {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "cancel_working" | "cancel" => OnPartialError::CancelWorking,
+            "unwind_filled" | "unwind" => OnPartialError::UnwindFilled { max_slippage },
+            _ => OnPartialError::LeaveForRisk,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OnPartialError::LeaveForRisk => "leave_for_risk",
+            OnPartialError::CancelWorking => "cancel_working",
+            OnPartialError::UnwindFilled { .. } => "unwind_filled",
+        }
+    }
+}
+
+/// 自成交保护（SELF_TRADE_PREVENTION）：交易所下单接口不提供 STP 参数，由执行端在提交买单前查询本账户挂单，
+/// 同一 token 上报价 ≤ 本次买价的卖单会与本次买单互相成交
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTradePrevention {
+    /// 不检查（默认，不增加查询挂单的延迟）
+    Off,
+    /// 先撤掉会被吃到的本账户卖单，再提交买单
+    CancelResting,
+    /// 放弃本次下单，保留已有卖单
+    SkipOrder,
+}
+
+impl SelfTradePrevention {
+    /// 解析配置：off | cancel_resting | skip_order，大小写不敏感，未知值默认 Off
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "cancel_resting" | "cancel_maker" => SelfTradePrevention::CancelResting,
+            "skip_order" | "skip" | "cancel_taker" => SelfTradePrevention::SkipOrder,
+            _ => SelfTradePrevention::Off,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SelfTradePrevention::Off => "off",
+            SelfTradePrevention::CancelResting => "cancel_resting",
+            SelfTradePrevention::SkipOrder => "skip_order",
+        }
+    }
+}
+
+/// 下单数量口径：MAX_ORDER_SIZE_USDC 按份数上限还是按每腿美元预算解释
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeMode {
+    /// 每腿最多 max_order_size 份
+    Shares,
+    /// 每腿最多花费 max_order_size USDC，份数 = 预算 / 含滑点价格
+    Usdc,
+}
+
+impl SizeMode {
+    /// 解析配置：shares | usdc，大小写不敏感，未知值默认 Shares
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "usdc" | "usd" | "notional" => SizeMode::Usdc,
+            _ => SizeMode::Shares,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SizeMode::Shares => "shares",
+            SizeMode::Usdc => "usdc",
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! 随窗口剩余时间放大滑点：窗口早期耐心报价，临近结算时更激进地越价，保证收盘前成交。
+//!
+//! 倍数 = 1 + (max_multiplier − 1) × curve(已过比例)，已过比例 = 1 − 剩余秒数 / 窗口长度，
+//! 结果按 tick 向上取整。curve 可选 linear（x）、quadratic（x²，前期平缓、末段陡增）、cubic（x³）。
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::config::decimal_setting;
+
+/// 价格最小变动单位
+const TICK_SIZE: Decimal = dec!(0.01);
+
+/// 放大曲线
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RampCurve {
+    Linear,
+    Quadratic,
+    Cubic,
+}
+
+impl RampCurve {
+    fn apply(&self, x: Decimal) -> Decimal {
+        match self {
+            RampCurve::Linear => x,
+            RampCurve::Quadratic => x * x,
+            RampCurve::Cubic => x * x * x,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RampCurve::Linear => "linear",
+            RampCurve::Quadratic => "quadratic",
+            RampCurve::Cubic => "cubic",
+        }
+    }
+}
+
+/// 按剩余时间放大滑点的配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlippageRamp {
+    pub curve: RampCurve,
+    /// 窗口结束时的最大倍数（≥ 1）
+    pub max_multiplier: Decimal,
+}
+
+impl SlippageRamp {
+    /// 解析配置：off | linear | quadratic | cubic（大小写不敏感），off 或未知值返回 None（不启用）；
+    /// 启用时最大倍数须为 ≥ 1 的有限数值，否则报错
+    pub fn parse(curve: &str, max_multiplier: f64) -> anyhow::Result<Option<Self>> {
+        let curve = match curve.trim().to_lowercase().as_str() {
+            "linear" => RampCurve::Linear,
+            "quadratic" | "quad" => RampCurve::Quadratic,
+            "cubic" => RampCurve::Cubic,
+            _ => return Ok(None),
+        };
+        let max_multiplier = decimal_setting("SLIPPAGE_TIME_RAMP_MAX_MULT", max_multiplier, dec!(1), None)?;
+        Ok(Some(Self { curve, max_multiplier }))
+    }
+
+    /// 按剩余时间放大基础滑点。基础滑点 ≤ 0（挂在卖一之下等待成交）时原样返回，不做放大。
+    pub fn time_scaled_slippage(&self, base: Decimal, seconds_remaining: u64, window_len: u64) -> Decimal {
+        if base <= dec!(0) || window_len == 0 {
+            return base;
+        }
+        let remaining = Decimal::from(seconds_remaining.min(window_len));
+        let elapsed = dec!(1) - remaining / Decimal::from(window_len);
+        let multiplier = dec!(1) + (self.max_multiplier - dec!(1)) * self.curve.apply(elapsed);
+        ((base * multiplier) / TICK_SIZE).ceil() * TICK_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rejects_invalid_multiplier_only_when_enabled() {
+        assert!(SlippageRamp::parse("linear", f64::NAN).is_err());
+        assert!(SlippageRamp::parse("linear", 0.5).is_err());
+        assert!(SlippageRamp::parse("off", f64::NAN).unwrap().is_none());
+        assert_eq!(SlippageRamp::parse("cubic", 3.0).unwrap().unwrap().max_multiplier, dec!(3));
+    }
+
+    #[test]
+    fn negative_slippage_is_not_scaled() {
+        let ramp = SlippageRamp::parse("linear", 3.0).unwrap().unwrap();
+        assert_eq!(ramp.time_scaled_slippage(dec!(-0.02), 0, 300), dec!(-0.02));
+        // 窗口结束时放大到 3 倍
+        assert_eq!(ramp.time_scaled_slippage(dec!(0.02), 0, 300), dec!(0.06));
+    }
+}
@@ -0,0 +1,160 @@
+//! 单边成交后的退出方式：保本挂单、带滑点上限的市价退出、或从买一开始分档挂卖单。
+
+use anyhow::Result;
+use polymarket_client_sdk::types::{B256, U256};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tracing::{info, warn};
+
+use crate::trading::clob_api::{ClobApi, OrderPostResult};
+use crate::trading::executor::{TradingExecutor, TICK_SIZE};
+
+/// 单边卖出方式（UNWIND_STYLE），由风险管理器在单边/不平衡成交时选择
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnwindStyle {
+    /// 以买入价（按 tick 向上取整）挂 GTC 卖单，耐心等待保本成交
+    LimitAtBreakeven,
+    /// 以 买一 − max_slippage 下 FAK 卖单立即退出，未成交部分不挂单
+    MarketWithMaxSlippage { max_slippage: Decimal },
+    /// 从买一（无买单时为买入价）开始分 steps 档、每档降 step_down 挂 GTC 卖单，数量均分
+    Laddered { steps: u32, step_down: Decimal },
+}
+
+impl UnwindStyle {
+    /// 解析配置：off | limit_at_breakeven | market_with_max_slippage | laddered，大小写不敏感；
+    /// off 或未知值返回 None（不卖出，与关闭对冲时一致）
+    pub fn parse(s: &str, max_slippage: Decimal, steps: u32, step_down: Decimal) -> Option<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "limit_at_breakeven" | "limit" | "breakeven" => Some(UnwindStyle::LimitAtBreakeven),
+            "market_with_max_slippage" | "market" => Some(UnwindStyle::MarketWithMaxSlippage { max_slippage }),
+            "laddered" | "ladder" => Some(UnwindStyle::Laddered { steps: steps.max(1), step_down }),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnwindStyle::LimitAtBreakeven => "limit_at_breakeven",
+            UnwindStyle::MarketWithMaxSlippage { .. } => "market_with_max_slippage",
+            UnwindStyle::Laddered { .. } => "laddered",
+        }
+    }
+}
+
+/// 卖单立即成交的份数合计：卖单付出份数（making），收到 USDC（taking）
+pub(crate) fn sold_shares(results: &[OrderPostResult]) -> Decimal {
+    results.iter().map(|r| r.making_amount).sum()
+}
+
+/// 按 tick 向下取整，不低于最小价
+fn floor_tick(price: Decimal) -> Decimal {
+    ((price / TICK_SIZE).floor() * TICK_SIZE).max(TICK_SIZE)
+}
+
+impl<C: ClobApi> TradingExecutor<C> {
+    /// 按 style 卖出单腿多余持仓（数量按 0.01 向下取整），返回每笔卖单的提交结果；
+    /// 分档时单档提交失败只记日志，继续下一档
+    pub async fn unwind(
+        &self,
+        market_id: B256,
+        token_id: U256,
+        size: Decimal,
+        entry_price: Decimal,
+        style: UnwindStyle,
+    ) -> Result<Vec<OrderPostResult>> {
+        let size = (size * dec!(100)).floor() / dec!(100);
+        if size < dec!(0.01) {
+            return Ok(Vec::new());
+        }
+        match style {
+            UnwindStyle::LimitAtBreakeven => {
+                let price = ((entry_price / TICK_SIZE).ceil() * TICK_SIZE).clamp(TICK_SIZE, dec!(0.99));
+                info!("🔻 单边卖出（保本挂单）| token_id={:#x} | 数量:{} | 报价:{:.2}", token_id, size, price);
+                Ok(vec![self.sell_at_price(market_id, token_id, price, size).await?])
+            }
+            UnwindStyle::MarketWithMaxSlippage { max_slippage } => {
+                Ok(vec![self.sell_market(market_id, token_id, size, max_slippage).await?])
+            }
+            UnwindStyle::Laddered { steps, step_down } => {
+                let start = self.best_bid(token_id).await?.unwrap_or(entry_price);
+                self.sell_laddered(market_id, token_id, size, start, steps, step_down).await
+            }
+        }
+    }
+
+    /// 分档卖出：从 start_price 起每档降 step_down（按 tick 向下取整），共 steps 档 GTC 卖单，
+    /// total_size 均分到各档（按 0.01 向下取整，余量并入最后一档）；单档提交失败只记日志，继续下一档。
+    /// 返回各档提交结果，立即成交份数汇总记入日志
+    pub async fn sell_laddered(
+        &self,
+        market_id: B256,
+        token_id: U256,
+        total_size: Decimal,
+        start_price: Decimal,
+        steps: u32,
+        step_down: Decimal,
+    ) -> Result<Vec<OrderPostResult>> {
+        let total_size = (total_size * dec!(100)).floor() / dec!(100);
+        let steps = steps.max(1);
+        let chunk = (total_size / Decimal::from(steps) * dec!(100)).floor() / dec!(100);
+        let mut results = Vec::with_capacity(steps as usize);
+        let mut remaining = total_size;
+        for i in 0..steps {
+            // 最后一档带上取整余量
+            let part = if i + 1 == steps { remaining } else { chunk.min(remaining) };
+            if part < dec!(0.01) {
+                continue;
+            }
+            let price = floor_tick(start_price - step_down * Decimal::from(i));
+            info!(
+                "🔻 单边卖出（分档 {}/{}）| token_id={:#x} | 数量:{} | 报价:{:.2}",
+                i + 1,
+                steps,
+                token_id,
+                part,
+                price
+            );
+            match self.sell_at_price(market_id, token_id, price, part).await {
+                Ok(r) => results.push(r),
+                Err(e) => warn!(token_id = %token_id, price = %price, size = %part, error = %e, "分档卖出提交失败"),
+            }
+            remaining -= part;
+        }
+        let filled = sold_shares(&results);
+        info!(
+            "🔻 分档卖出完成 | token_id={:#x} | 总量:{} | 已提交 {}/{} 档 | 立即成交:{} | 挂单:{}",
+            token_id,
+            total_size,
+            results.len(),
+            steps,
+            filled,
+            total_size - filled
+        );
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sell_fill(shares: Decimal, price: Decimal) -> OrderPostResult {
+        OrderPostResult {
+            order_id: "sell".to_string(),
+            success: true,
+            error_msg: None,
+            taking_amount: shares * price,
+            making_amount: shares,
+            raw: "test: sell".to_string(),
+        }
+    }
+
+    #[test]
+    fn sold_shares_ignores_usdc_received() {
+        // 三档 @0.45/0.44/0.43，只有前两档立即成交
+        let results = [sell_fill(dec!(4), dec!(0.45)), sell_fill(dec!(2.5), dec!(0.44)), sell_fill(dec!(0), dec!(0.43))];
+        assert_eq!(sold_shares(&results), dec!(6.5));
+        assert_eq!(results.iter().map(|r| r.taking_amount).sum::<Decimal>(), dec!(2.90));
+        assert_eq!(sold_shares(&[]), dec!(0));
+    }
+}
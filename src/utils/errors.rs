@@ -1,2 +1,166 @@
 // 简化错误处理，直接使用 anyhow::Error
-// 如果需要更细粒度的错误类型，可以后续扩展
+// 需要调用方区分处理的错误在此定义，通过 anyhow::Error::downcast_ref 识别
+
+use std::fmt;
+
+/// API 凭证过期且重新认证失败（调用方应停止下单并人工处理）
+#[derive(Debug)]
+pub struct AuthExpired {
+    /// 触发重新认证的原始错误
+    pub original: String,
+    /// 重新认证失败的原因
+    pub reauth_error: String,
+}
+
+impl fmt::Display for AuthExpired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "API凭证已过期且重新认证失败: 原始错误: {} | 重新认证: {}",
+            self.original, self.reauth_error
+        )
+    }
+}
+
+impl std::error::Error for AuthExpired {}
+
+/// 无匹配卖单时加价重试仍未成交（或加价后不再满足盈利条件），放弃本次套利
+#[derive(Debug)]
+pub struct RepriceGaveUp {
+    /// 已进行的加价重试次数
+    pub attempts: u32,
+    /// 放弃原因
+    pub reason: String,
+}
+
+impl fmt::Display for RepriceGaveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "加价重试放弃（已重试{}次）: {}", self.attempts, self.reason)
+    }
+}
+
+impl std::error::Error for RepriceGaveUp {}
+
+/// 执行器已暂停下单（管理命令 pause），机会被跳过；行情订阅照常运行
+#[derive(Debug)]
+pub struct Paused;
+
+impl fmt::Display for Paused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("已暂停下单")
+    }
+}
+
+impl std::error::Error for Paused {}
+
+/// 主备部署中本实例不持有 leader 租约（备机），机会被跳过；行情与认证校验照常运行
+#[derive(Debug)]
+pub struct NotLeader;
+
+impl fmt::Display for NotLeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("非 leader（备机），不下单")
+    }
+}
+
+impl std::error::Error for NotLeader {}
+
+/// 含滑点的 YES+NO 报价之和 ≥ 1：双边成交必然亏损，无条件拒绝下单（不受任何配置影响）
+#[derive(Debug)]
+pub struct GuaranteedLoss {
+    pub yes_price: rust_decimal::Decimal,
+    pub no_price: rust_decimal::Decimal,
+    /// yes_price + no_price
+    pub sum: rust_decimal::Decimal,
+}
+
+impl fmt::Display for GuaranteedLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "含滑点报价之和 {} ≥ 1（YES {} + NO {}），成交即锁定亏损，拒绝下单",
+            self.sum, self.yes_price, self.no_price
+        )
+    }
+}
+
+impl std::error::Error for GuaranteedLoss {}
+
+/// 批量下单返回的订单与提交的腿对不上（按订单 ID 查到的 token 不是该位置提交的 token），
+/// 继续按位置映射会把成交记到错误的一腿
+#[derive(Debug)]
+pub struct OrderResponseMismatch {
+    /// 对不上的一腿（YES / NO）
+    pub leg: &'static str,
+    pub order_id: String,
+    /// 该位置提交的 token
+    pub expected_token: String,
+    /// 交易所返回的订单实际所属 token
+    pub actual_token: String,
+}
+
+impl fmt::Display for OrderResponseMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "下单返回结果与提交订单不匹配: {} 腿订单 {} 属于 token {}，期望 {}",
+            self.leg, self.order_id, self.actual_token, self.expected_token
+        )
+    }
+}
+
+impl std::error::Error for OrderResponseMismatch {}
+
+/// 判断下单被拒是否因 USDC 余额/授权不足（如 "not enough balance / allowance"）
+pub fn is_balance_error(msg: &str) -> bool {
+    let m = msg.to_lowercase();
+    m.contains("not enough balance") || m.contains("insufficient balance")
+}
+
+/// 错误信息中是否带 HTTP 状态码 401：只认 `status: 401`、`status code 401`、`HTTP 401`、`(401 Unauthorized)` 等写法，
+/// 价格、数量或订单 ID 中出现的 401（如 0.401、0x401a）不算
+fn has_status_401(m: &str) -> bool {
+    m.match_indices("401").any(|(i, _)| {
+        let (before, after) = (&m[..i], &m[i + 3..]);
+        if before.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '.')
+            || after.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '.')
+        {
+            return false;
+        }
+        let prefix = before.trim_end_matches([' ', ':', '=', '(']);
+        prefix.ends_with("status") || prefix.ends_with("code") || prefix.ends_with("http") || after.trim_start().starts_with("unauthorized")
+    })
+}
+
+/// 判断 SDK 返回的错误是否为认证失效（HTTP 401 / unauthorized / 凭证过期）
+pub fn is_auth_error(msg: &str) -> bool {
+    let m = msg.to_lowercase();
+    has_status_401(&m)
+        || m.contains("unauthorized")
+        || (m.contains("expired") && (m.contains("key") || m.contains("token") || m.contains("credential")))
+        || m.contains("invalid api key")
+        || m.contains("invalid credentials")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_error_matches_http_status() {
+        assert!(is_auth_error("error making POST call to /orders: status: 401, body: {}"));
+        assert!(is_auth_error("Status code 401"));
+        assert!(is_auth_error("HTTP 401"));
+        assert!(is_auth_error("HTTP status client error (401 Unauthorized) for url (https://clob.polymarket.com/order)"));
+        assert!(is_auth_error("API key expired"));
+    }
+
+    #[test]
+    fn auth_error_ignores_401_in_prices_and_ids() {
+        assert!(!is_auth_error("not enough balance: price 0.401 size 10"));
+        assert!(!is_auth_error("order 0x401abc rejected"));
+        assert!(!is_auth_error("size 401 exceeds max"));
+        assert!(!is_auth_error("status: 4010"));
+        assert!(!is_auth_error("no orders found to match with FAK order 401"));
+    }
+}
@@ -1,27 +1,67 @@
 use anyhow::Result;
 use std::fs::File;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
 pub fn init_logger() -> Result<()> {
     // 设置默认日志级别为 info，如果没有设置 RUST_LOG 环境变量
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info"));
     
-    if let Ok(path) = std::env::var("LOG_FILE") {
+    let fmt_layer = if let Ok(path) = std::env::var("LOG_FILE") {
         let file = File::create(path)?;
-        tracing_subscriber::registry()
-            .with(env_filter)
-            .with(
-                tracing_subscriber::fmt::layer()
-                    .with_writer(file)
-                    .with_ansi(false),
-            )
-            .init();
+        tracing_subscriber::fmt::layer()
+            .with_writer(file)
+            .with_ansi(false)
+            .boxed()
     } else {
-        tracing_subscriber::fmt()
-            .with_env_filter(env_filter)
-            .init();
+        tracing_subscriber::fmt::layer().boxed()
+    };
+
+    // OTLP_ENDPOINT 配置时把 span 导出到追踪后端（需 --features otel）
+    let otlp_endpoint = std::env::var("OTLP_ENDPOINT").ok().filter(|e| !e.trim().is_empty());
+    let registry = tracing_subscriber::registry().with(env_filter).with(fmt_layer);
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otlp_endpoint.as_deref().map(otel::layer).transpose()?);
+    registry.init();
+
+    #[cfg(feature = "otel")]
+    if let Some(endpoint) = &otlp_endpoint {
+        tracing::info!(endpoint = %endpoint, "🔭 OpenTelemetry span 导出已启用（OTLP/HTTP）");
+    }
+    #[cfg(not(feature = "otel"))]
+    if otlp_endpoint.is_some() {
+        tracing::warn!("OTLP_ENDPOINT 需以 --features otel 构建，已忽略");
     }
 
     Ok(())
 }
+
+#[cfg(feature = "otel")]
+mod otel {
+    use anyhow::Result;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+    use tracing::Subscriber;
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// OTLP/HTTP span 导出层（批量异步发送），endpoint 如 http://localhost:4318/v1/traces
+    pub fn layer<S>(endpoint: &str) -> Result<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| anyhow::anyhow!("创建 OTLP 导出器失败 endpoint={}: {}", endpoint, e))?;
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new("service.name", env!("CARGO_PKG_NAME"))]))
+            .build();
+        let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+        opentelemetry::global::set_tracer_provider(provider);
+        Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+    }
+}
@@ -0,0 +1,150 @@
+//! Polygon 分叉上的 Merge 端到端检查（需 `--features forked-tests`，普通 `cargo test` 不编译本文件）。
+//!
+//! 在 anvil 分叉链上：从 USDC 大户转入 USDC → 以 Safe 身份 splitPosition 得到等量 YES/NO →
+//! 调用 `merge::merge_max_with` 走 Gnosis Safe 路径 → 校验 Safe 的 USDC 余额增量等于合并数量。
+//!
+//! 用法示例：
+//!   anvil --fork-url https://polygon-bor-rpc.publicnode.com
+//!   FORK_CONDITION_ID=0x... FORK_USDC_WHALE=0x... \
+//!   POLYMARKET_PRIVATE_KEY=0x... POLYMARKET_PROXY_ADDRESS=0x... \
+//!     cargo test --features forked-tests --test fork_merge -- --nocapture
+//!
+//! 环境变量：
+//! - `FORK_RPC_URL`：anvil 地址，默认 http://127.0.0.1:8545
+//! - `POLYMARKET_PRIVATE_KEY` / `POLYMARKET_PROXY_ADDRESS`：Safe owner 私钥与 Safe 地址（须为 Gnosis Safe）
+//! - `FORK_CONDITION_ID`：分叉块上已 prepare 的二元市场 condition ID
+//! - `FORK_USDC_WHALE`：持有足够 USDC.e 的地址（会被 impersonate）
+//! - `FORK_SEED_USDC`：注入并 split 的 USDC 数量，默认 10
+//! - `CHAIN_ID`：分叉节点的链 ID，默认 137（anvil 分叉默认沿用上游链 ID）
+
+#![cfg(feature = "forked-tests")]
+
+use alloy::primitives::{address, Address, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::sol;
+use anyhow::{Context, Result};
+use poly_5min_bot::merge::{self, MergeOptions, MergeOutcome, MergePath};
+use polymarket_client_sdk::contract_config;
+use std::env;
+use std::str::FromStr;
+
+const USDC_POLYGON: Address = address!("0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174");
+/// 给被 impersonate 的地址预置的 POL（支付分叉链 gas）
+const GAS_BALANCE_WEI: u128 = 10_000_000_000_000_000_000;
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+    }
+
+    #[sol(rpc)]
+    interface IConditionalTokens {
+        function splitPosition(
+            address collateralToken,
+            bytes32 parentCollectionId,
+            bytes32 conditionId,
+            uint256[] calldata partition,
+            uint256 amount
+        ) external;
+    }
+}
+
+fn env_required(key: &str) -> Result<String> {
+    env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .with_context(|| format!("缺少环境变量 {}", key))
+}
+
+/// anvil：以 addr 身份发送交易，并预置 gas 余额
+async fn impersonate<P: Provider>(provider: &P, addr: Address) -> Result<()> {
+    provider
+        .raw_request::<_, ()>("anvil_impersonateAccount".into(), (addr,))
+        .await
+        .context("anvil_impersonateAccount 失败（是否连接的是 anvil？）")?;
+    provider
+        .raw_request::<_, ()>("anvil_setBalance".into(), (addr, U256::from(GAS_BALANCE_WEI)))
+        .await
+        .context("anvil_setBalance 失败")?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn safe_merge_on_polygon_fork() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let _ = tracing_subscriber::fmt().with_env_filter("info").try_init();
+
+    let rpc_url = env::var("FORK_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8545".to_string());
+    let private_key = env_required("POLYMARKET_PRIVATE_KEY")?;
+    let safe = Address::from_str(&env_required("POLYMARKET_PROXY_ADDRESS")?).context("POLYMARKET_PROXY_ADDRESS 无效")?;
+    let condition_id = B256::from_str(&env_required("FORK_CONDITION_ID")?).context("FORK_CONDITION_ID 无效")?;
+    let whale = Address::from_str(&env_required("FORK_USDC_WHALE")?).context("FORK_USDC_WHALE 无效")?;
+    let seed_usdc: u64 = env::var("FORK_SEED_USDC").ok().and_then(|s| s.trim().parse().ok()).unwrap_or(10);
+    let seed = U256::from(seed_usdc) * U256::from(1_000_000u64);
+
+    // 读写都走分叉节点
+    env::remove_var("RPC_READ_URLS");
+    env::remove_var("RPC_WRITE_URL");
+
+    let provider = ProviderBuilder::new().connect(&rpc_url).await?;
+    if provider.get_code_at(safe).await?.is_empty() {
+        anyhow::bail!("{} 在分叉链上没有合约代码：本检查只覆盖 Gnosis Safe 路径", safe);
+    }
+    let opts = MergeOptions {
+        force: true, // 分叉链 gas 价格无意义，跳过经济性判断
+        ..MergeOptions::from_env()?
+    };
+    let ctf_address = contract_config(opts.chain_id, false)
+        .context("不支持的 chain_id")?
+        .conditional_tokens;
+    let usdc = IERC20::new(USDC_POLYGON, &provider);
+    let ctf = IConditionalTokens::new(ctf_address, &provider);
+
+    // 1. 大户 → Safe 转入 USDC
+    impersonate(&provider, whale).await?;
+    usdc.transfer(safe, seed).from(whale).send().await?.get_receipt().await?;
+    println!("✅ 已向 Safe 转入 {} USDC", seed_usdc);
+
+    // 2. 以 Safe 身份 split，得到等量 YES/NO
+    impersonate(&provider, safe).await?;
+    usdc.approve(ctf_address, seed).from(safe).send().await?.get_receipt().await?;
+    ctf.splitPosition(USDC_POLYGON, B256::ZERO, condition_id, vec![U256::from(1), U256::from(2)], seed)
+        .from(safe)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    provider
+        .raw_request::<_, ()>("anvil_stopImpersonatingAccount".into(), (safe,))
+        .await?;
+    println!("✅ 已 split {} 份 YES/NO", seed_usdc);
+
+    // 3. 走 Safe 路径 merge，并按 USDC 余额增量核对
+    let before = usdc.balanceOf(safe).call().await?;
+    let outcome = merge::merge_max_with(condition_id, safe, &private_key, Some(&rpc_url), &opts).await?;
+    let res = match outcome {
+        MergeOutcome::Merged(res) => res,
+        other => anyhow::bail!("Merge 未执行: {}", other),
+    };
+    let after = usdc.balanceOf(safe).call().await?;
+
+    anyhow::ensure!(res.path == MergePath::GnosisSafe, "执行路径应为 Gnosis Safe，实际为 {}", res.path);
+    anyhow::ensure!(res.merge_amount >= seed, "合并数量 {} 小于注入的 {}", res.merge_amount, seed);
+    let delta = after.checked_sub(before).context("Merge 后 USDC 余额反而减少")?;
+    anyhow::ensure!(
+        delta == res.merge_amount,
+        "USDC 增量 {} 与合并数量 {} 不一致",
+        delta,
+        res.merge_amount
+    );
+    println!(
+        "✅ Safe Merge 通过 | tx={} | 合并 {} USDC | USDC 增量 {}",
+        res.tx_hash,
+        merge::units_to_usdc(res.merge_amount),
+        merge::units_to_usdc(delta)
+    );
+    Ok(())
+}